@@ -1,8 +1,158 @@
+use std::fmt::Display;
+use std::io::Write;
+use std::path::Path;
+
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use librustbelt::{
-    analyzer::RustAnalyzerish, builder::RustAnalyzerishBuilder, entities::CursorCoordinates,
+    RustAnalyzerUtils,
+    analyzer::RustAnalyzerish,
+    builder::{RustAnalyzerishBuilder, WorkspaceOptions},
+    entities::{
+        ApplyOptions, CompletionOptions, CursorCoordinates, DefinitionOptions, ReferenceOptions,
+        ReferenceScope, TypeHintOptions, WorkspaceSymbolsOptions,
+    },
 };
+use serde::Serialize;
+
+// Options controlling how the workspace is loaded, shared by commands that load one
+#[derive(Args, Debug, Clone, Default)]
+pub struct WorkspaceArgs {
+    /// Enable (`test`, `feature="foo"`) or disable (`-debug_assertions`) a cfg for workspace crates
+    #[arg(long = "cfg", value_name = "CFG", allow_hyphen_values = true)]
+    pub cfgs: Vec<String>,
+    /// Analyze workspace crates with this edition (2015, 2018, 2021, 2024) instead of their own
+    #[arg(long, value_name = "EDITION")]
+    pub edition: Option<String>,
+    /// Analyze `#[cfg(test)]` code, as it compiles under `cargo test`
+    #[arg(long)]
+    pub include_tests: bool,
+    /// Don't load the standard library for faster startup (std types won't resolve)
+    #[arg(long)]
+    pub no_sysroot: bool,
+    /// Use the toolchain at this sysroot, as printed by `rustc --print sysroot`
+    #[arg(long, value_name = "PATH", conflicts_with = "no_sysroot")]
+    pub sysroot: Option<String>,
+    /// Don't query `cargo metadata` for the sysroot's dependencies
+    #[arg(long, conflicts_with = "no_sysroot")]
+    pub no_sysroot_metadata: bool,
+    /// Don't expand proc macros for faster startup (derived items won't resolve)
+    #[arg(long)]
+    pub no_proc_macros: bool,
+    /// Analyze a file outside any cargo project as a standalone crate
+    #[arg(long)]
+    pub allow_detached: bool,
+    /// Cap the threads used to prime caches (defaults to the physical core count)
+    #[arg(long, value_name = "N")]
+    pub prime_threads: Option<usize>,
+    /// Don't prime caches for faster startup (early queries are slower)
+    #[arg(long)]
+    pub no_prime_caches: bool,
+    /// Don't watch this directory or glob for changes, relative to the workspace root (repeatable)
+    #[arg(long = "watch-exclude", value_name = "PATH")]
+    pub watch_excludes: Vec<String>,
+    /// Also watch files with this extension, besides `rs` and `toml` (repeatable)
+    #[arg(long = "watch-extension", value_name = "EXT")]
+    pub watch_extensions: Vec<String>,
+    /// Override analyzer settings with this JSON file, instead of the workspace's `rustbelt.json`
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<String>,
+}
+
+impl WorkspaceArgs {
+    /// Validated workspace options for the builder
+    pub fn options(&self) -> Result<WorkspaceOptions> {
+        let options = WorkspaceOptions {
+            cfgs: self.cfgs.clone(),
+            edition: self.edition.clone(),
+            include_tests: self.include_tests,
+            no_sysroot: self.no_sysroot,
+            sysroot: self.sysroot.clone(),
+            no_sysroot_metadata: self.no_sysroot_metadata,
+            no_proc_macros: self.no_proc_macros,
+            allow_detached: self.allow_detached,
+            prime_threads: self.prime_threads,
+            no_prime_caches: self.no_prime_caches,
+            watch_excludes: self.watch_excludes.clone(),
+            watch_extensions: self.watch_extensions.clone(),
+            config_file: self.config.clone(),
+        };
+        options.cfg_overrides()?;
+        options.edition()?;
+        Ok(options)
+    }
+
+    /// Render the options back into CLI arguments
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = self
+            .cfgs
+            .iter()
+            .map(|cfg| format!("--cfg={}", cfg))
+            .collect();
+        if let Some(edition) = &self.edition {
+            args.push(format!("--edition={}", edition));
+        }
+        if self.include_tests {
+            args.push("--include-tests".to_string());
+        }
+        if self.no_sysroot {
+            args.push("--no-sysroot".to_string());
+        }
+        if let Some(sysroot) = &self.sysroot {
+            args.push(format!("--sysroot={}", sysroot));
+        }
+        if self.no_sysroot_metadata {
+            args.push("--no-sysroot-metadata".to_string());
+        }
+        if self.no_proc_macros {
+            args.push("--no-proc-macros".to_string());
+        }
+        if self.allow_detached {
+            args.push("--allow-detached".to_string());
+        }
+        if let Some(threads) = self.prime_threads {
+            args.push(format!("--prime-threads={}", threads));
+        }
+        if self.no_prime_caches {
+            args.push("--no-prime-caches".to_string());
+        }
+        args.extend(
+            self.watch_excludes
+                .iter()
+                .map(|path| format!("--watch-exclude={}", path)),
+        );
+        args.extend(
+            self.watch_extensions
+                .iter()
+                .map(|extension| format!("--watch-extension={}", extension)),
+        );
+        if let Some(config) = &self.config {
+            args.push(format!("--config={}", config));
+        }
+        args
+    }
+}
+
+// Where references are searched for, see `ReferenceScope`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeArg {
+    /// Only the file of the position, fast for local variables
+    File,
+    /// The crate containing the file
+    Crate,
+    /// Every crate of the workspace
+    Workspace,
+}
+
+impl From<ScopeArg> for ReferenceScope {
+    fn from(scope: ScopeArg) -> Self {
+        match scope {
+            ScopeArg::File => ReferenceScope::File,
+            ScopeArg::Crate => ReferenceScope::Crate,
+            ScopeArg::Workspace => ReferenceScope::Workspace,
+        }
+    }
+}
 
 // Unified command wrapper for both CLI and REPL use
 #[derive(Parser)]
@@ -10,6 +160,9 @@ use librustbelt::{
 pub struct CommandWrapper {
     #[command(subcommand)]
     pub command: AnalyzerCommand,
+    /// Print results as JSON instead of text, for scripting
+    #[arg(long, global = true)]
+    pub json: bool,
 }
 
 // Base commands without workspace path - used by both CLI and REPL
@@ -27,6 +180,12 @@ pub enum AnalyzerCommand {
         /// Optional symbol name to search for near the coordinates
         #[arg(long)]
         symbol: Option<String>,
+        /// Include the size, alignment and field offsets of the type
+        #[arg(long)]
+        include_layout: bool,
+        /// Include whether dropping the type runs any code
+        #[arg(long)]
+        show_drop_glue: bool,
     },
 
     /// Get definition details for a symbol at a specific position
@@ -40,10 +199,28 @@ pub enum AnalyzerCommand {
         /// Optional symbol name to search for near the coordinates
         #[arg(long)]
         symbol: Option<String>,
+        /// Include the end position, symbol kind and module path
+        #[arg(long)]
+        verbose: bool,
+        /// Show the header of the enclosing impl, trait or type around each definition
+        #[arg(long)]
+        include_container: bool,
+        /// Follow re-exports to the original item, listing both
+        #[arg(long)]
+        follow_reexports: bool,
+        /// Show only each item's signature, without attributes, docs or body
+        #[arg(long)]
+        signature_only: bool,
+        /// Retry on the nearest identifier of the line when nothing is found at the position
+        #[arg(long)]
+        fuzzy: bool,
+        /// Print the source lines around definitions outside of the workspace
+        #[arg(long)]
+        include_external_source: bool,
     },
 
-    /// Get completion suggestions at a specific position
-    GetCompletions {
+    /// Print the fully qualified path of the symbol at a specific position
+    QualifiedPath {
         /// Path to the Rust source file
         file_path: String,
         /// Line number (1-based)
@@ -55,8 +232,8 @@ pub enum AnalyzerCommand {
         symbol: Option<String>,
     },
 
-    /// Find all references to a symbol at a specific position
-    FindReferences {
+    /// Show the definitions, hover text and usage count of a symbol at a specific position
+    DescribeSymbol {
         /// Path to the Rust source file
         file_path: String,
         /// Line number (1-based)
@@ -68,20 +245,70 @@ pub enum AnalyzerCommand {
         symbol: Option<String>,
     },
 
-    /// View a Rust file with embedded inlay hints such as types and named arguments
-    ViewInlayHints {
+    /// Show the function, impl, trait or module enclosing a specific position
+    EnclosingScope {
         /// Path to the Rust source file
         file_path: String,
-        /// Starting line number (1-based, optional)
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
         #[arg(long)]
-        start_line: Option<u32>,
-        /// Ending line number (1-based, optional)
+        symbol: Option<String>,
+    },
+
+    /// Show the attributes and derives on the item at a specific position
+    ItemAttributes {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
         #[arg(long)]
-        end_line: Option<u32>,
+        symbol: Option<String>,
     },
 
-    /// Get available code assists (code actions) at a specific position
-    GetAssists {
+    /// List the variants of the enum at a specific position or path, with their fields
+    EnumVariants {
+        /// Path to a Rust source file of the workspace
+        file_path: String,
+        /// Line number (1-based)
+        #[arg(required_unless_present = "item")]
+        line: Option<u32>,
+        /// Column number (1-based)
+        #[arg(required_unless_present = "item")]
+        column: Option<u32>,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Path of the enum instead of a position, e.g. `shapes::Shape` or `Shape`
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["line", "column", "symbol"])]
+        item: Option<String>,
+    },
+
+    /// List the fields of the struct at a specific position or path, with their visibility
+    StructFields {
+        /// Path to a Rust source file of the workspace
+        file_path: String,
+        /// Line number (1-based)
+        #[arg(required_unless_present = "item")]
+        line: Option<u32>,
+        /// Column number (1-based)
+        #[arg(required_unless_present = "item")]
+        column: Option<u32>,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Path of the struct instead of a position, e.g. `shapes::Shape` or `Shape`
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["line", "column", "symbol"])]
+        item: Option<String>,
+    },
+
+    /// Get the return type of the function or method call at a specific position
+    CallReturnType {
         /// Path to the Rust source file
         file_path: String,
         /// Line number (1-based)
@@ -93,48 +320,990 @@ pub enum AnalyzerCommand {
         symbol: Option<String>,
     },
 
-    /// Apply a specific code assist at a position
-    ApplyAssist {
+    /// Compare the function or trait at a specific position with a previous version
+    DiffSignature {
         /// Path to the Rust source file
         file_path: String,
         /// Line number (1-based)
         line: u32,
         /// Column number (1-based)
         column: u32,
-        /// ID of the assist to apply
-        assist_id: String,
+        /// File with the previous version of the item as Rust source
+        #[arg(long, value_name = "PATH")]
+        baseline: String,
         /// Optional symbol name to search for near the coordinates
         #[arg(long)]
         symbol: Option<String>,
     },
 
-    /// Rename a symbol at a specific position
-    RenameSymbol {
+    /// Get the definition of the type of the symbol at a specific position
+    GetTypeDefinition {
         /// Path to the Rust source file
         file_path: String,
         /// Line number (1-based)
         line: u32,
         /// Column number (1-based)
         column: u32,
-        /// New name for the symbol
-        new_name: String,
         /// Optional symbol name to search for near the coordinates
         #[arg(long)]
         symbol: Option<String>,
+        /// Include the end position, symbol kind and module path
+        #[arg(long)]
+        verbose: bool,
     },
-}
 
-// For REPL use - reuses existing analyzer connection
-pub async fn execute_analyzer_command_with_instance(
-    command: AnalyzerCommand,
-    analyzer: &mut RustAnalyzerish,
-) -> Result<()> {
-    match command {
-        AnalyzerCommand::TypeHint {
+    /// Get the declaration of the symbol at a specific position, e.g. the trait
+    /// method rather than the impl method of a call
+    GetDeclaration {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Include the end position, symbol kind and module path
+        #[arg(long)]
+        verbose: bool,
+    },
+
+    /// Find the tests exercising the item at a specific position
+    GetRelatedTests {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Include the end position, symbol kind and module path
+        #[arg(long)]
+        verbose: bool,
+    },
+
+    /// List the tests, benchmarks and binaries runnable at a position, with cargo commands
+    GetRunnables {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+    },
+
+    /// Get completion suggestions at a specific position
+    GetCompletions {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Also complete items not in scope, showing the edit adding each import
+        #[arg(long)]
+        import_edits: bool,
+        /// Show snippet bodies with tabstops and argument placeholders
+        #[arg(long)]
+        snippets: bool,
+        /// Maximum number of completions to compute (default 200, at most 2000)
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Keep rust-analyzer's order instead of sorting by relevance
+        #[arg(long)]
+        unsorted: bool,
+        /// Show each completion's relevance score
+        #[arg(long)]
+        scores: bool,
+        /// Leave out deprecated items
+        #[arg(long)]
+        no_deprecated: bool,
+        /// Leave out items requiring an unstable feature
+        #[arg(long)]
+        no_unstable: bool,
+        /// With --import-edits, leave out items that need an import, such as
+        /// methods of traits not in scope
+        #[arg(long)]
+        in_scope_only: bool,
+    },
+
+    /// Suggest imports for the unresolved name at a specific position
+    ImportSuggestions {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+    },
+
+    /// Import a path for the unresolved name at a specific position
+    ApplyImport {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Path to import, as listed by import-suggestions
+        import_path: String,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+    },
+
+    /// Find all references to a symbol at a specific position
+    FindReferences {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Number of source lines to show before and after each reference
+        #[arg(long, default_value_t = 0)]
+        context_lines: u32,
+        /// Where to search
+        #[arg(long, value_enum, default_value_t = ScopeArg::Workspace)]
+        scope: ScopeArg,
+    },
+
+    /// Count the references to a symbol at a specific position, e.g. to find dead code
+    CountReferences {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Where to search
+        #[arg(long, value_enum, default_value_t = ScopeArg::Workspace)]
+        scope: ScopeArg,
+        /// Stop at the first reference, to only check whether the symbol is used
+        #[arg(long)]
+        stop_at_first: bool,
+    },
+
+    /// View a Rust file with embedded inlay hints such as types and named arguments
+    ViewInlayHints {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Starting line number (1-based, optional)
+        #[arg(long)]
+        start_line: Option<u32>,
+        /// Ending line number (1-based, optional)
+        #[arg(long)]
+        end_line: Option<u32>,
+        /// Print the hints as JSON with their positions instead of spliced into the source
+        #[arg(long)]
+        structured: bool,
+    },
+
+    /// List semantic highlight tokens (functions, types, keywords, ...) of a file
+    SemanticTokens {
+        /// Path to the Rust source file
+        file_path: String,
+    },
+
+    /// Print the syntax tree of a file, optionally only the node covering some lines
+    SyntaxTree {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Starting line number (1-based, optional)
+        #[arg(long)]
+        start_line: Option<u32>,
+        /// Ending line number (1-based, optional)
+        #[arg(long)]
+        end_line: Option<u32>,
+    },
+
+    /// Show the edits rust-analyzer makes after a character is typed at a position
+    OnTypeFormat {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based) of the typed character
+        line: u32,
+        /// Column number (1-based) of the typed character
+        column: u32,
+        /// The character typed, e.g. `.` or `=`
+        typed_char: char,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+    },
+
+    /// Merge and sort the imports at the top of a file
+    OrganizeImports {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Remove unused imports before sorting
+        #[arg(long)]
+        remove_unused: bool,
+        /// Write the result back to the file instead of printing it
+        #[arg(long)]
+        write: bool,
+    },
+
+    /// Get all diagnostics (errors, warnings) across the workspace
+    WorkspaceDiagnostics {
+        /// Path to the workspace directory or any file inside it
+        workspace_path: String,
+    },
+
+    /// List unused imports, variables, functions, types and constants
+    UnusedItems {
+        /// Rust source file, or a directory whose workspace files are all checked
+        path: String,
+    },
+
+    /// Show the project root, crate graph and VFS file count of the loaded workspace
+    WorkspaceInfo {
+        /// Path to the workspace directory or any file inside it
+        workspace_path: String,
+    },
+
+    /// Show the memory allocated by the analyzer and the size of the workspace
+    ///
+    /// The number of cached queries isn't shown, as the rust-analyzer version
+    /// used (ra_ap 0.0.304) doesn't expose it.
+    MemoryUsage {
+        /// Path to the workspace directory or any file inside it
+        workspace_path: String,
+    },
+
+    /// Show the edition of the crate containing a file
+    FileEdition {
+        /// Path to the Rust source file
+        file_path: String,
+    },
+
+    /// Show the resolved version, edition and enabled features of a crate
+    CrateInfo {
+        /// Path to the workspace directory or any file inside it
+        workspace_path: String,
+        /// Crate name, e.g. `serde` or `serde-json`
+        crate_name: String,
+    },
+
+    /// List the impls of a trait across the workspace, by trait name
+    TraitImpls {
+        /// Path to the workspace directory or any file inside it
+        workspace_path: String,
+        /// Trait name, optionally qualified, e.g. `Display` or `fmt::Display`
+        trait_name: String,
+    },
+
+    /// Search the workspace for symbols by name
+    WorkspaceSymbols {
+        /// Path to the workspace directory or any file inside it
+        workspace_path: String,
+        /// Name, prefix, fuzzy pattern or regex to search for. Leave empty or
+        /// use `*` with --kind to list every symbol of that kind
+        #[arg(default_value = "")]
+        query: String,
+        /// How the query is matched: substring, prefix, fuzzy or regex
+        #[arg(long, default_value = "substring")]
+        mode: String,
+        /// Only return symbols of this kind, e.g. `struct` or `function`
+        #[arg(long)]
+        kind: Option<String>,
+        /// Number of matches to skip, sorted by file then line
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+        /// Maximum number of matches to return
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+
+    /// Get available code assists (code actions) at a specific position
+    GetAssists {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+    },
+
+    /// List the code assists available across a file, grouped by position
+    FileAssists {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Starting line number (1-based, optional)
+        #[arg(long)]
+        start_line: Option<u32>,
+        /// Ending line number (1-based, optional)
+        #[arg(long)]
+        end_line: Option<u32>,
+    },
+
+    /// Apply a specific code assist at a position
+    ApplyAssist {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// ID of the assist to apply
+        assist_id: String,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Print a unified diff of every changed file
+        #[arg(long)]
+        return_diff: bool,
+        /// Show the changes without writing them to disk
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Extract the statements of a line range into a new function
+    ExtractFunction {
+        /// Path to the Rust source file
+        file_path: String,
+        /// First line of the statements to extract (1-based)
+        start_line: u32,
+        /// Last line of the statements to extract (1-based, inclusive)
+        end_line: u32,
+        /// Name of the new function
+        new_name: String,
+    },
+
+    /// Rename a symbol at a specific position
+    RenameSymbol {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// New name for the symbol
+        new_name: String,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Print a unified diff of every changed file
+        #[arg(long)]
+        return_diff: bool,
+        /// Print each changed line before and after the rename
+        #[arg(long)]
+        preview: bool,
+    },
+
+    /// Move the item at a specific position to another module
+    MoveItem {
+        /// Path to the Rust source file
+        file_path: String,
+        /// Line number (1-based)
+        line: u32,
+        /// Column number (1-based)
+        column: u32,
+        /// Module to move the item into, e.g. `crate::shapes`
+        target_module: String,
+        /// Optional symbol name to search for near the coordinates
+        #[arg(long)]
+        symbol: Option<String>,
+    },
+}
+
+// For REPL and daemon use - reuses existing analyzer connection
+//
+// Output is written to `out` so the daemon can send it back over its socket.
+// `limit` caps the number of completions, references and symbols printed, with a
+// footer counting the omitted ones. With `json`, results are printed as JSON and
+// not limited, missing results as `null` and errors as `{"error": "..."}`.
+pub async fn execute_analyzer_command_with_instance(
+    command: AnalyzerCommand,
+    analyzer: &mut RustAnalyzerish,
+    limit: Option<usize>,
+    json: bool,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match command {
+        AnalyzerCommand::TypeHint {
+            file_path,
+            line,
+            column,
+            symbol,
+            include_layout,
+            show_drop_glue,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            let options = TypeHintOptions {
+                include_layout,
+                show_drop_glue,
+            };
+            match analyzer.get_type_hint_with_options(&cursor, &options).await {
+                Ok(Some(type_info)) if json => write_json(out, &type_info)?,
+                Ok(Some(type_info)) => {
+                    writeln!(out, "Type Hint:\n-----\n{}\n------", type_info)?;
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(
+                        out,
+                        "No type information available at {}:{}:{}",
+                        file_path, line, column
+                    )?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting type hint: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::GetDefinition {
+            file_path,
+            line,
+            column,
+            symbol,
+            verbose,
+            include_container,
+            follow_reexports,
+            signature_only,
+            fuzzy,
+            include_external_source,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            let options = DefinitionOptions {
+                include_container,
+                follow_reexports,
+                signature_only,
+                fuzzy,
+                include_external_source,
+            };
+            match analyzer.get_definition_report(&cursor, &options).await {
+                Ok(report) if json => write_json(out, &report)?,
+                Ok(report) => {
+                    match report.definitions {
+                        Some(definitions) => {
+                            writeln!(out, "Found {} definition(s):", definitions.len())?;
+                            for def in definitions {
+                                if verbose {
+                                    writeln!(out, "  {:#}", def)?;
+                                } else {
+                                    writeln!(out, "  {}", def)?;
+                                }
+                            }
+                        }
+                        None => {
+                            writeln!(
+                                out,
+                                "No definitions found at {}:{}:{}",
+                                file_path, line, column
+                            )?;
+                        }
+                    }
+                    if let Some(note) = report.note {
+                        writeln!(out, "Note: {}", note)?;
+                    }
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting definitions: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::QualifiedPath {
+            file_path,
+            line,
+            column,
+            symbol,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.get_qualified_path(&cursor).await {
+                Ok(Some(path)) if json => write_json(out, &path)?,
+                Ok(Some(path)) => {
+                    writeln!(out, "{}", path)?;
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(
+                        out,
+                        "No item with a path at {}:{}:{}",
+                        file_path, line, column
+                    )?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting qualified path: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::DescribeSymbol {
+            file_path,
+            line,
+            column,
+            symbol,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.describe_symbol(&cursor).await {
+                Ok(Some(description)) if json => write_json(out, &description)?,
+                Ok(Some(description)) => {
+                    writeln!(out, "{}", description)?;
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(out, "No symbol found at {}:{}:{}", file_path, line, column)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error describing symbol: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::EnclosingScope {
+            file_path,
+            line,
+            column,
+            symbol,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.get_enclosing_scope(&cursor).await {
+                Ok(Some(scope)) if json => write_json(out, &scope)?,
+                Ok(Some(scope)) => {
+                    writeln!(out, "Enclosing scope: {:#}", scope)?;
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(
+                        out,
+                        "{}:{}:{} is at the top level of the file",
+                        file_path, line, column
+                    )?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting enclosing scope: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::ItemAttributes {
+            file_path,
+            line,
+            column,
+            symbol,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.get_item_attributes(&cursor).await {
+                Ok(Some(attributes)) if json => write_json(out, &attributes)?,
+                Ok(Some(attributes)) => {
+                    writeln!(out, "Found {} attribute(s):", attributes.len())?;
+                    for attribute in attributes {
+                        writeln!(out, "  {}", attribute)?;
+                    }
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(out, "No item at {}:{}:{}", file_path, line, column)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting item attributes: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::EnumVariants {
+            file_path,
+            line,
+            column,
+            symbol,
+            item,
+        } => {
+            let target = ItemTarget::new(file_path, line, column, symbol, item);
+            let result = match &target {
+                ItemTarget::Cursor(cursor) => analyzer.get_enum_variants(cursor).await,
+                ItemTarget::Path(path) => analyzer.get_enum_variants_by_path(path).await,
+            };
+
+            match result {
+                Ok(Some(variants)) if json => write_json(out, &variants)?,
+                Ok(Some(variants)) => {
+                    writeln!(out, "Found {} variant(s):", variants.len())?;
+                    for variant in variants {
+                        writeln!(out, "  {}", variant)?;
+                    }
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(out, "No enum at {}", target)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting enum variants: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::StructFields {
+            file_path,
+            line,
+            column,
+            symbol,
+            item,
+        } => {
+            let target = ItemTarget::new(file_path, line, column, symbol, item);
+            let result = match &target {
+                ItemTarget::Cursor(cursor) => analyzer.get_struct_fields(cursor).await,
+                ItemTarget::Path(path) => analyzer.get_struct_fields_by_path(path).await,
+            };
+
+            match result {
+                Ok(Some(fields)) if json => write_json(out, &fields)?,
+                Ok(Some(fields)) => {
+                    writeln!(out, "Found {} field(s):", fields.len())?;
+                    for field in fields {
+                        writeln!(out, "  {}", field)?;
+                    }
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(out, "No struct at {}", target)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting struct fields: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::CallReturnType {
+            file_path,
+            line,
+            column,
+            symbol,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.get_call_return_type(&cursor).await {
+                Ok(Some(ty)) if json => write_json(out, &ty)?,
+                Ok(Some(ty)) => writeln!(out, "{}", ty)?,
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(out, "No call at {}:{}:{}", file_path, line, column)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting call return type: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::DiffSignature {
+            file_path,
+            line,
+            column,
+            baseline,
+            symbol,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            let baseline = match std::fs::read_to_string(&baseline)
+                .map_err(|e| anyhow::anyhow!("Failed to read baseline {}: {}", baseline, e))
+            {
+                Ok(baseline) => baseline,
+                Err(e) if json => return write_json_error(out, &e),
+                Err(e) => return Err(e),
+            };
+            match analyzer.diff_signature(&cursor, &baseline).await {
+                Ok(Some(diff)) if json => write_json(out, &diff)?,
+                Ok(Some(diff)) => writeln!(out, "{}", diff)?,
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(out, "No definition at {}:{}:{}", file_path, line, column)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error comparing signatures: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::GetTypeDefinition {
+            file_path,
+            line,
+            column,
+            symbol,
+            verbose,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.get_type_definition(&cursor).await {
+                Ok(Some(definitions)) if json => write_json(out, &definitions)?,
+                Ok(Some(definitions)) => {
+                    writeln!(out, "Found {} type definition(s):", definitions.len())?;
+                    for def in definitions {
+                        if verbose {
+                            writeln!(out, "  {:#}", def)?;
+                        } else {
+                            writeln!(out, "  {}", def)?;
+                        }
+                    }
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(
+                        out,
+                        "No type definitions found at {}:{}:{}",
+                        file_path, line, column
+                    )?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting type definitions: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::GetDeclaration {
+            file_path,
+            line,
+            column,
+            symbol,
+            verbose,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.get_declaration(&cursor).await {
+                Ok(Some(declarations)) if json => write_json(out, &declarations)?,
+                Ok(Some(declarations)) => {
+                    writeln!(out, "Found {} declaration(s):", declarations.len())?;
+                    for def in declarations {
+                        if verbose {
+                            writeln!(out, "  {:#}", def)?;
+                        } else {
+                            writeln!(out, "  {}", def)?;
+                        }
+                    }
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(
+                        out,
+                        "No declarations found at {}:{}:{}",
+                        file_path, line, column
+                    )?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting declarations: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::GetRelatedTests {
+            file_path,
+            line,
+            column,
+            symbol,
+            verbose,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.get_related_tests(&cursor).await {
+                Ok(tests) if json => write_json(out, &tests)?,
+                Ok(tests) if tests.is_empty() => {
+                    writeln!(
+                        out,
+                        "No related tests found at {}:{}:{}",
+                        file_path, line, column
+                    )?;
+                }
+                Ok(tests) => {
+                    writeln!(out, "Found {} related test(s):", tests.len())?;
+                    for test in tests {
+                        if verbose {
+                            writeln!(out, "  {:#}", test)?;
+                        } else {
+                            writeln!(out, "  {}", test)?;
+                        }
+                    }
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting related tests: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::GetRunnables {
+            file_path,
+            line,
+            column,
+            symbol,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.get_runnables(&cursor).await {
+                Ok(runnables) if json => write_json(out, &runnables)?,
+                Ok(runnables) if runnables.is_empty() => {
+                    writeln!(
+                        out,
+                        "No runnables found at {}:{}:{}",
+                        file_path, line, column
+                    )?;
+                }
+                Ok(runnables) => {
+                    writeln!(out, "Found {} runnable(s):", runnables.len())?;
+                    for runnable in runnables {
+                        writeln!(out, "  {}", runnable)?;
+                    }
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting runnables: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::GetCompletions {
+            file_path,
+            line,
+            column,
+            symbol,
+            import_edits,
+            snippets,
+            limit: completion_limit,
+            unsorted,
+            scores,
+            no_deprecated,
+            no_unstable,
+            in_scope_only,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            let options = CompletionOptions {
+                include_import_edits: import_edits,
+                include_snippets: snippets,
+                limit: completion_limit,
+                unsorted,
+                include_scores: scores,
+                exclude_deprecated: no_deprecated,
+                exclude_unstable: no_unstable,
+                only_in_scope: in_scope_only,
+            };
+            match analyzer
+                .get_completions_with_options(&cursor, &options)
+                .await
+            {
+                Ok(Some(completions)) if json => write_json(out, &completions)?,
+                Ok(Some(completions)) => {
+                    writeln!(
+                        out,
+                        "Available completions at {}:{}:{} ({} items):",
+                        file_path,
+                        line,
+                        column,
+                        completions.len()
+                    )?;
+                    print_limited(out, &completions, limit)?;
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(
+                        out,
+                        "No completions found at {}:{}:{}",
+                        file_path, line, column
+                    )?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting completions: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::FindReferences {
             file_path,
             line,
             column,
             symbol,
+            context_lines,
+            scope,
         } => {
             let cursor = CursorCoordinates {
                 file_path: file_path.clone(),
@@ -142,27 +1311,41 @@ pub async fn execute_analyzer_command_with_instance(
                 column,
                 symbol,
             };
+            let options = ReferenceOptions {
+                context_lines,
+                scope: scope.into(),
+            };
 
-            match analyzer.get_type_hint(&cursor).await {
-                Ok(Some(type_info)) => {
-                    println!("Type Hint:\n-----\n{}\n------", type_info);
+            match analyzer
+                .find_references_with_options(&cursor, &options)
+                .await
+            {
+                Ok(Some(references)) if json => write_json(out, &references)?,
+                Ok(Some(references)) => {
+                    writeln!(out, "Found {} reference(s):", references.len())?;
+                    print_limited(out, &references, limit)?;
                 }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
                 Ok(None) => {
-                    println!(
-                        "No type information available at {}:{}:{}",
+                    writeln!(
+                        out,
+                        "No references found at {}:{}:{}",
                         file_path, line, column
-                    );
+                    )?;
                 }
+                Err(e) if json => write_json_error(out, &e)?,
                 Err(e) => {
-                    println!("Error getting type hint: {}", e);
+                    writeln!(out, "Error finding references: {}", e)?;
                 }
             }
         }
-        AnalyzerCommand::GetDefinition {
+        AnalyzerCommand::CountReferences {
             file_path,
             line,
             column,
             symbol,
+            scope,
+            stop_at_first,
         } => {
             let cursor = CursorCoordinates {
                 file_path: file_path.clone(),
@@ -171,25 +1354,103 @@ pub async fn execute_analyzer_command_with_instance(
                 symbol,
             };
 
-            match analyzer.get_definition(&cursor).await {
-                Ok(Some(definitions)) => {
-                    println!("Found {} definition(s):", definitions.len());
-                    for def in definitions {
-                        println!("  {}", def);
+            match analyzer
+                .count_references(&cursor, scope.into(), stop_at_first)
+                .await
+            {
+                Ok(Some(count)) if json => write_json(out, &count)?,
+                Ok(Some(count)) => writeln!(out, "{}", count)?,
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(out, "No symbol at {}:{}:{}", file_path, line, column)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error counting references: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::ViewInlayHints {
+            file_path,
+            start_line,
+            end_line,
+            structured,
+        } if structured || json => match analyzer
+            .get_inlay_hints(&file_path, start_line, end_line)
+            .await
+        {
+            Ok(hints) => write_json(out, &hints)?,
+            Err(e) if json => write_json_error(out, &e)?,
+            Err(e) => {
+                writeln!(out, "Error getting inlay hints: {}", e)?;
+            }
+        },
+        AnalyzerCommand::ViewInlayHints {
+            file_path,
+            start_line,
+            end_line,
+            structured: _,
+        } => {
+            match analyzer
+                .view_inlay_hints(&file_path, start_line, end_line)
+                .await
+            {
+                Ok(annotated_content) => {
+                    writeln!(out, "File with inlay hints:")?;
+                    writeln!(out, "=====================================")?;
+                    writeln!(out, "{}", annotated_content)?;
+                    writeln!(out, "=====================================")?;
+                }
+                Err(e) => {
+                    writeln!(out, "Error viewing inlay hints: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::SemanticTokens { file_path } => {
+            match analyzer.get_semantic_tokens(&file_path).await {
+                Ok(tokens) if json => write_json(out, &tokens)?,
+                Ok(tokens) => {
+                    writeln!(
+                        out,
+                        "Found {} semantic token(s) in {}:",
+                        tokens.len(),
+                        file_path
+                    )?;
+                    for token in tokens {
+                        writeln!(out, "  {}", token)?;
                     }
                 }
-                Ok(None) => {
-                    println!("No definitions found at {}:{}:{}", file_path, line, column);
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting semantic tokens: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::SyntaxTree {
+            file_path,
+            start_line,
+            end_line,
+        } => {
+            let range = match (start_line, end_line) {
+                (None, None) => None,
+                (start, end) => Some((start.unwrap_or(1), end.unwrap_or(u32::MAX))),
+            };
+            match analyzer.get_syntax_tree(&file_path, range).await {
+                Ok(tree) if json => write_json(out, &tree)?,
+                Ok(tree) => {
+                    writeln!(out, "{}", tree)?;
                 }
+                Err(e) if json => write_json_error(out, &e)?,
                 Err(e) => {
-                    println!("Error getting definitions: {}", e);
+                    writeln!(out, "Error getting syntax tree: {}", e)?;
                 }
             }
         }
-        AnalyzerCommand::GetCompletions {
+        AnalyzerCommand::OnTypeFormat {
             file_path,
             line,
             column,
+            typed_char,
             symbol,
         } => {
             let cursor = CursorCoordinates {
@@ -199,28 +1460,201 @@ pub async fn execute_analyzer_command_with_instance(
                 symbol,
             };
 
-            match analyzer.get_completions(&cursor).await {
-                Ok(Some(completions)) => {
-                    println!(
-                        "Available completions at {}:{}:{} ({} items):",
+            match analyzer.on_type_format(&cursor, typed_char).await {
+                Ok(Some(edits)) if json => write_json(out, &edits)?,
+                Ok(Some(edits)) => {
+                    writeln!(
+                        out,
+                        "Formatting '{}' at {}:{}:{} ({} edit(s)):",
+                        typed_char,
                         file_path,
                         line,
                         column,
-                        completions.len()
-                    );
-                    for completion in completions {
-                        println!("  {}", completion);
+                        edits.len()
+                    )?;
+                    for edit in edits {
+                        writeln!(out, "  {}", edit)?;
                     }
                 }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
                 Ok(None) => {
-                    println!("No completions found at {}:{}:{}", file_path, line, column);
+                    writeln!(
+                        out,
+                        "No formatting edits for '{}' at {}:{}:{}",
+                        typed_char, file_path, line, column
+                    )?;
                 }
+                Err(e) if json => write_json_error(out, &e)?,
                 Err(e) => {
-                    println!("Error getting completions: {}", e);
+                    writeln!(out, "Error formatting on type: {}", e)?;
                 }
             }
         }
-        AnalyzerCommand::FindReferences {
+        AnalyzerCommand::OrganizeImports {
+            file_path,
+            remove_unused,
+            write,
+        } => {
+            let file_path = analyzer.resolve_path(&file_path);
+            match analyzer
+                .organize_imports_with_options(&file_path, remove_unused)
+                .await
+            {
+                Ok(content) if write => {
+                    std::fs::write(&file_path, content)?;
+                    if json {
+                        write_json(out, &file_path)?;
+                    } else {
+                        writeln!(out, "Organized imports of {}", file_path)?;
+                    }
+                }
+                Ok(content) if json => write_json(out, &content)?,
+                Ok(content) => {
+                    write!(out, "{}", content)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error organizing imports: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::WorkspaceDiagnostics { .. } => {
+            match analyzer.get_workspace_diagnostics().await {
+                Ok(diagnostics) if json => write_json(out, &diagnostics)?,
+                Ok(diagnostics) => {
+                    writeln!(
+                        out,
+                        "Found {} diagnostic(s): {}",
+                        diagnostics.len(),
+                        RustAnalyzerUtils::diagnostics_summary(&diagnostics)
+                    )?;
+                    for diagnostic in diagnostics {
+                        writeln!(out, "  {}", diagnostic)?;
+                    }
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting workspace diagnostics: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::UnusedItems { path } => match analyzer.find_unused_items(&path).await {
+            Ok(items) if json => write_json(out, &items)?,
+            Ok(items) => {
+                let public = items.iter().filter(|item| item.is_pub).count();
+                writeln!(
+                    out,
+                    "Found {} unused item(s), {} of them public",
+                    items.len(),
+                    public
+                )?;
+                for item in items {
+                    writeln!(out, "  {}", item)?;
+                }
+            }
+            Err(e) if json => write_json_error(out, &e)?,
+            Err(e) => {
+                writeln!(out, "Error finding unused items: {}", e)?;
+            }
+        },
+        AnalyzerCommand::TraitImpls { trait_name, .. } => {
+            match analyzer.find_trait_impls(&trait_name).await {
+                Ok(traits) if json => write_json(out, &traits)?,
+                Ok(traits) if traits.is_empty() => {
+                    writeln!(out, "No trait named '{}' found", trait_name)?;
+                }
+                Ok(traits) => {
+                    for trait_impls in traits {
+                        writeln!(out, "{}", trait_impls)?;
+                    }
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error finding trait impls: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::FileEdition { file_path } => {
+            match analyzer.get_file_edition(&file_path).await {
+                Ok(edition) if json => write_json(out, &edition)?,
+                Ok(edition) => {
+                    writeln!(out, "{}", edition)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting file edition: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::CrateInfo { crate_name, .. } => {
+            match analyzer.get_crate_info(&crate_name).await {
+                Ok(crates) if json => write_json(out, &crates)?,
+                Ok(crates) => {
+                    for krate in crates {
+                        writeln!(out, "{:#}", krate)?;
+                    }
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting crate info: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::MemoryUsage { .. } => match analyzer.get_memory_usage().await {
+            Ok(usage) if json => write_json(out, &usage)?,
+            Ok(usage) => {
+                writeln!(out, "{}", usage)?;
+            }
+            Err(e) if json => write_json_error(out, &e)?,
+            Err(e) => {
+                writeln!(out, "Error getting memory usage: {}", e)?;
+            }
+        },
+        AnalyzerCommand::WorkspaceInfo { .. } => match analyzer.get_workspace_info().await {
+            Ok(info) if json => write_json(out, &info)?,
+            Ok(info) => {
+                writeln!(out, "{}", info)?;
+            }
+            Err(e) if json => write_json_error(out, &e)?,
+            Err(e) => {
+                writeln!(out, "Error getting workspace info: {}", e)?;
+            }
+        },
+        AnalyzerCommand::WorkspaceSymbols {
+            query,
+            mode,
+            kind,
+            offset,
+            limit: page_limit,
+            ..
+        } => {
+            let mode = match mode.parse() {
+                Ok(mode) => mode,
+                Err(e) if json => return write_json_error(out, &e),
+                Err(e) => return Err(e),
+            };
+            let options = WorkspaceSymbolsOptions {
+                mode,
+                kind,
+                offset,
+                limit: page_limit,
+            };
+            match analyzer.get_workspace_symbols(&query, &options).await {
+                Ok(page) if json => write_json(out, &page)?,
+                Ok(page) if page.total == 0 => {
+                    writeln!(out, "No symbols matching '{}'", query)?;
+                }
+                Ok(page) => {
+                    writeln!(out, "{}:", page.header())?;
+                    print_limited(out, &page.symbols, limit)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error searching workspace symbols: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::GetAssists {
             file_path,
             line,
             column,
@@ -233,42 +1667,70 @@ pub async fn execute_analyzer_command_with_instance(
                 symbol,
             };
 
-            match analyzer.find_references(&cursor).await {
-                Ok(Some(references)) => {
-                    println!("Found {} reference(s):", references.len());
-                    for reference in references {
-                        println!("  {}", reference);
+            match analyzer.get_assists(&cursor).await {
+                Ok(Some(assists)) if json => write_json(out, &assists)?,
+                Ok(Some(assists)) => {
+                    writeln!(
+                        out,
+                        "Available assists at {}:{}:{} ({} items):",
+                        file_path,
+                        line,
+                        column,
+                        assists.len()
+                    )?;
+                    for assist in assists {
+                        writeln!(out, "  {} ({}): {}", assist.label, assist.id, assist.target)?;
                     }
                 }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
                 Ok(None) => {
-                    println!("No references found at {}:{}:{}", file_path, line, column);
+                    writeln!(
+                        out,
+                        "No assists available at {}:{}:{}",
+                        file_path, line, column
+                    )?;
                 }
+                Err(e) if json => write_json_error(out, &e)?,
                 Err(e) => {
-                    println!("Error finding references: {}", e);
+                    writeln!(out, "Error getting assists: {}", e)?;
                 }
             }
         }
-        AnalyzerCommand::ViewInlayHints {
+        AnalyzerCommand::FileAssists {
             file_path,
             start_line,
             end_line,
         } => {
-            match analyzer
-                .view_inlay_hints(&file_path, start_line, end_line)
-                .await
-            {
-                Ok(annotated_content) => {
-                    println!("File with inlay hints:");
-                    println!("=====================================");
-                    println!("{}", annotated_content);
-                    println!("=====================================");
+            let range = match (start_line, end_line) {
+                (None, None) => None,
+                (start, end) => Some((start.unwrap_or(1), end.unwrap_or(u32::MAX))),
+            };
+            match analyzer.get_file_assists(&file_path, range).await {
+                Ok(file_assists) if json => write_json(out, &file_assists)?,
+                Ok(file_assists) => {
+                    writeln!(
+                        out,
+                        "Found {} assist(s) in {}:",
+                        file_assists.count(),
+                        file_path
+                    )?;
+                    for position in &file_assists.positions {
+                        writeln!(out, "{}", position)?;
+                    }
+                    if file_assists.truncated {
+                        writeln!(
+                            out,
+                            "More assists are available, narrow the lines to see them"
+                        )?;
+                    }
                 }
+                Err(e) if json => write_json_error(out, &e)?,
                 Err(e) => {
-                    println!("Error viewing inlay hints: {}", e);
+                    writeln!(out, "Error getting file assists: {}", e)?;
                 }
             }
         }
-        AnalyzerCommand::GetAssists {
+        AnalyzerCommand::ImportSuggestions {
             file_path,
             line,
             column,
@@ -281,24 +1743,59 @@ pub async fn execute_analyzer_command_with_instance(
                 symbol,
             };
 
-            match analyzer.get_assists(&cursor).await {
-                Ok(Some(assists)) => {
-                    println!(
-                        "Available assists at {}:{}:{} ({} items):",
-                        file_path,
-                        line,
-                        column,
-                        assists.len()
-                    );
-                    for assist in assists {
-                        println!("  {} ({}): {}", assist.label, assist.id, assist.target);
+            match analyzer.get_import_suggestions(&cursor).await {
+                Ok(suggestions) if json => write_json(out, &suggestions)?,
+                Ok(suggestions) if suggestions.is_empty() => {
+                    writeln!(
+                        out,
+                        "No import suggestions at {}:{}:{}",
+                        file_path, line, column
+                    )?;
+                }
+                Ok(suggestions) => {
+                    writeln!(out, "Found {} import suggestion(s):", suggestions.len())?;
+                    print_limited(out, &suggestions, limit)?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error getting import suggestions: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::ApplyImport {
+            file_path,
+            line,
+            column,
+            import_path,
+            symbol,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.apply_import(&cursor, &import_path).await {
+                Ok(Some(source_change)) if json => write_json(out, &source_change)?,
+                Ok(Some(source_change)) => {
+                    writeln!(out, "Successfully imported '{}':", import_path)?;
+                    for file_change in &source_change.file_changes {
+                        writeln!(out, "  Modified file: {}", file_change.file_path)?;
+                        writeln!(out, "    {} edits applied", file_change.edits.len())?;
                     }
                 }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
                 Ok(None) => {
-                    println!("No assists available at {}:{}:{}", file_path, line, column);
+                    writeln!(
+                        out,
+                        "'{}' is not an import suggestion at {}:{}:{}",
+                        import_path, file_path, line, column
+                    )?;
                 }
+                Err(e) if json => write_json_error(out, &e)?,
                 Err(e) => {
-                    println!("Error getting assists: {}", e);
+                    writeln!(out, "Error importing '{}': {}", import_path, e)?;
                 }
             }
         }
@@ -308,6 +1805,8 @@ pub async fn execute_analyzer_command_with_instance(
             column,
             assist_id,
             symbol,
+            return_diff,
+            dry_run,
         } => {
             let cursor = CursorCoordinates {
                 file_path: file_path.clone(),
@@ -316,22 +1815,72 @@ pub async fn execute_analyzer_command_with_instance(
                 symbol,
             };
 
-            match analyzer.apply_assist(&cursor, &assist_id).await {
+            let options = ApplyOptions {
+                return_diff,
+                dry_run,
+            };
+            match analyzer
+                .apply_assist_with_options(&cursor, &assist_id, &options)
+                .await
+            {
+                Ok(Some(source_change)) if json => write_json(out, &source_change)?,
                 Ok(Some(source_change)) => {
-                    println!("Successfully applied assist '{}':", assist_id);
+                    if dry_run {
+                        writeln!(out, "Assist '{}' would change (dry run):", assist_id)?;
+                    } else {
+                        writeln!(out, "Successfully applied assist '{}':", assist_id)?;
+                    }
                     for file_change in &source_change.file_changes {
-                        println!("  Modified file: {}", file_change.file_path);
-                        println!("    {} edits applied", file_change.edits.len());
+                        writeln!(out, "  Modified file: {}", file_change.file_path)?;
+                        writeln!(out, "    {} edit(s)", file_change.edits.len())?;
+                        if let Some(diff) = &file_change.diff {
+                            write!(out, "{}", diff)?;
+                        }
                     }
                 }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
                 Ok(None) => {
-                    println!(
+                    writeln!(
+                        out,
                         "Assist '{}' not available at {}:{}:{}",
                         assist_id, file_path, line, column
-                    );
+                    )?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error applying assist '{}': {}", assist_id, e)?;
+                }
+            }
+        }
+        AnalyzerCommand::ExtractFunction {
+            file_path,
+            start_line,
+            end_line,
+            new_name,
+        } => {
+            match analyzer
+                .extract_function(&file_path, start_line, end_line, &new_name)
+                .await
+            {
+                Ok(Some(source_change)) if json => write_json(out, &source_change)?,
+                Ok(Some(source_change)) => {
+                    writeln!(out, "Extracted function '{}':", new_name)?;
+                    for file_change in &source_change.file_changes {
+                        writeln!(out, "  Modified file: {}", file_change.file_path)?;
+                        writeln!(out, "    {} edits applied", file_change.edits.len())?;
+                    }
+                }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
+                Ok(None) => {
+                    writeln!(
+                        out,
+                        "Lines {}-{} of {} cannot be extracted into a function",
+                        start_line, end_line, file_path
+                    )?;
                 }
+                Err(e) if json => write_json_error(out, &e)?,
                 Err(e) => {
-                    println!("Error applying assist '{}': {}", assist_id, e);
+                    writeln!(out, "Error extracting function: {}", e)?;
                 }
             }
         }
@@ -341,6 +1890,8 @@ pub async fn execute_analyzer_command_with_instance(
             column,
             new_name,
             symbol,
+            return_diff,
+            preview,
         } => {
             let cursor = CursorCoordinates {
                 file_path: file_path.clone(),
@@ -349,24 +1900,77 @@ pub async fn execute_analyzer_command_with_instance(
                 symbol,
             };
 
-            match analyzer.rename_symbol(&cursor, &new_name).await {
+            let options = ApplyOptions {
+                return_diff,
+                ..Default::default()
+            };
+            match analyzer
+                .rename_symbol_with_options(&cursor, &new_name, &options)
+                .await
+            {
+                Ok(Some(changes)) if json => write_json(out, &changes)?,
                 Ok(Some(changes)) => {
-                    println!(
+                    writeln!(
+                        out,
                         "Rename successful! {} file(s) changed:",
                         changes.file_changes.len()
-                    );
+                    )?;
                     for change in &changes.file_changes {
-                        println!("  {}: {} edit(s)", change.file_path, change.edits.len());
+                        writeln!(
+                            out,
+                            "  {}: {} edit(s)",
+                            change.file_path,
+                            change.edits.len()
+                        )?;
+                        if let Some(diff) = &change.diff {
+                            write!(out, "{}", diff)?;
+                        }
+                    }
+                    if preview {
+                        for line_preview in &changes.line_previews {
+                            writeln!(out, "  {}", line_preview)?;
+                        }
+                    }
+                    for file_rename in &changes.file_renames {
+                        writeln!(out, "  {}", file_rename)?;
                     }
                 }
+                Ok(None) if json => write_json(out, &serde_json::Value::Null)?,
                 Ok(None) => {
-                    println!(
+                    writeln!(
+                        out,
                         "No symbol found to rename at {}:{}:{}",
                         file_path, line, column
-                    );
+                    )?;
+                }
+                Err(e) if json => write_json_error(out, &e)?,
+                Err(e) => {
+                    writeln!(out, "Error renaming symbol: {}", e)?;
+                }
+            }
+        }
+        AnalyzerCommand::MoveItem {
+            file_path,
+            line,
+            column,
+            target_module,
+            symbol,
+        } => {
+            let cursor = CursorCoordinates {
+                file_path,
+                line,
+                column,
+                symbol,
+            };
+
+            match analyzer.move_item(&cursor, &target_module).await {
+                Ok(result) if json => write_json(out, &result)?,
+                Ok(result) => {
+                    write!(out, "{}", result)?;
                 }
+                Err(e) if json => write_json_error(out, &e)?,
                 Err(e) => {
-                    println!("Error renaming symbol: {}", e);
+                    writeln!(out, "Error moving item: {}", e)?;
                 }
             }
         }
@@ -374,24 +1978,273 @@ pub async fn execute_analyzer_command_with_instance(
     Ok(())
 }
 
+// Query run by `analyze-snippet` at the given position
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum SnippetQuery {
+    /// Type hint of the symbol, like `type-hint`
+    TypeHint,
+    /// Definition of the symbol, like `get-definition`
+    Definition,
+}
+
+// Rust source to analyze without a file on disk, read from stdin
+#[derive(Args, Debug, Clone)]
+pub struct SnippetArgs {
+    /// Query to run at the position
+    pub query: SnippetQuery,
+    /// Line number (1-based)
+    pub line: u32,
+    /// Column number (1-based)
+    pub column: u32,
+    /// Synthetic file name shown in the output
+    #[arg(long, default_value = "snippet.rs")]
+    pub file_name: String,
+    /// Optional symbol name to search for near the coordinates
+    #[arg(long)]
+    pub symbol: Option<String>,
+}
+
+// For CLI use - analyzes a snippet as a detached file in a scratch directory
+//
+// The scratch directory is removed afterwards, and its path is replaced with
+// the synthetic file name in the output.
+pub(crate) async fn execute_snippet_command(
+    args: SnippetArgs,
+    source: &str,
+    options: &WorkspaceOptions,
+) -> Result<()> {
+    let file_name = std::path::Path::new(&args.file_name)
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid snippet file name '{}'", args.file_name))?
+        .to_string_lossy()
+        .to_string();
+    let scratch_dir = std::env::temp_dir().join(format!("rustbelt-snippet-{}", std::process::id()));
+    std::fs::create_dir_all(&scratch_dir)?;
+    let file_path = scratch_dir.join(&file_name);
+
+    let result = async {
+        std::fs::write(&file_path, source)?;
+        let mut analyzer = RustAnalyzerishBuilder::from_detached_file(&file_path)?
+            .with_options(options)?
+            .build()?;
+        // Resolve symlinks such as /tmp on macOS, as the analyzer reports canonical paths
+        let file_path = file_path.canonicalize()?.to_string_lossy().to_string();
+        let command = match args.query {
+            SnippetQuery::TypeHint => AnalyzerCommand::TypeHint {
+                file_path: file_path.clone(),
+                line: args.line,
+                column: args.column,
+                symbol: args.symbol,
+                include_layout: false,
+                show_drop_glue: false,
+            },
+            SnippetQuery::Definition => AnalyzerCommand::GetDefinition {
+                file_path: file_path.clone(),
+                line: args.line,
+                column: args.column,
+                symbol: args.symbol,
+                verbose: false,
+                include_container: false,
+                follow_reexports: false,
+                signature_only: false,
+                fuzzy: false,
+                include_external_source: false,
+            },
+        };
+        let mut output = Vec::new();
+        execute_analyzer_command_with_instance(command, &mut analyzer, None, false, &mut output)
+            .await?;
+        Ok::<_, anyhow::Error>(String::from_utf8_lossy(&output).replace(&file_path, &file_name))
+    }
+    .await;
+
+    std::fs::remove_dir_all(&scratch_dir)?;
+    print!("{}", result?);
+    Ok(())
+}
+
 // For CLI use - creates new analyzer instance for single command
 pub(crate) async fn execute_analyzer_command(
     command: AnalyzerCommand,
     workspace_path: &str,
+    options: &WorkspaceOptions,
+    json: bool,
 ) -> Result<()> {
-    let mut analyzer = RustAnalyzerishBuilder::from_file(workspace_path)?.build()?;
-    execute_analyzer_command_with_instance(command, &mut analyzer).await
+    let mut analyzer =
+        RustAnalyzerishBuilder::from_file_with_options(workspace_path, options)?.build()?;
+    execute_analyzer_command_with_instance(
+        command,
+        &mut analyzer,
+        None,
+        json,
+        &mut std::io::stdout(),
+    )
+    .await
+}
+
+// An item given either by a position or by its path, for `--item`
+enum ItemTarget {
+    Cursor(CursorCoordinates),
+    Path(String),
+}
+
+impl ItemTarget {
+    // Clap requires the line and column without `--item`
+    fn new(
+        file_path: String,
+        line: Option<u32>,
+        column: Option<u32>,
+        symbol: Option<String>,
+        item: Option<String>,
+    ) -> Self {
+        match item {
+            Some(path) => ItemTarget::Path(path),
+            None => ItemTarget::Cursor(CursorCoordinates {
+                file_path,
+                line: line.unwrap_or(1),
+                column: column.unwrap_or(1),
+                symbol,
+            }),
+        }
+    }
+}
+
+impl Display for ItemTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemTarget::Cursor(cursor) => {
+                write!(f, "{}:{}:{}", cursor.file_path, cursor.line, cursor.column)
+            }
+            ItemTarget::Path(path) => write!(f, "path {}", path),
+        }
+    }
+}
+
+// Print a result as pretty-printed JSON, for `--json`
+fn write_json<T: Serialize + ?Sized>(out: &mut dyn Write, value: &T) -> Result<()> {
+    writeln!(out, "{}", serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+// Print an error as a `{"error": "..."}` JSON object, for `--json`
+fn write_json_error(out: &mut dyn Write, error: &anyhow::Error) -> Result<()> {
+    write_json(out, &serde_json::json!({ "error": error.to_string() }))
+}
+
+// Print one item per line, stopping after `limit` items with a "... N more" footer
+fn print_limited<T: Display>(out: &mut dyn Write, items: &[T], limit: Option<usize>) -> Result<()> {
+    let shown = limit.unwrap_or(items.len()).min(items.len());
+    for item in &items[..shown] {
+        writeln!(out, "  {}", item)?;
+    }
+    if shown < items.len() {
+        writeln!(out, "  ... {} more", items.len() - shown)?;
+    }
+    Ok(())
 }
 
 pub(crate) fn extract_workspace_path(command: &AnalyzerCommand) -> String {
+    command_path(command).clone()
+}
+
+// Make the path arguments of a command absolute, resolving relative ones
+// against `base`, e.g. the working directory of a daemon client
+pub(crate) fn resolve_relative_paths(command: &mut AnalyzerCommand, base: &Path) {
+    let resolve = |path: &mut String| {
+        if Path::new(path.as_str()).is_relative() {
+            *path = base.join(path.as_str()).to_string_lossy().into_owned();
+        }
+    };
+    if let AnalyzerCommand::DiffSignature { baseline, .. } = command {
+        resolve(baseline);
+    }
+    resolve(command_path_mut(command));
+}
+
+// The file or workspace path a command operates on
+fn command_path(command: &AnalyzerCommand) -> &String {
+    match command {
+        AnalyzerCommand::TypeHint { file_path, .. }
+        | AnalyzerCommand::GetDefinition { file_path, .. }
+        | AnalyzerCommand::GetTypeDefinition { file_path, .. }
+        | AnalyzerCommand::GetDeclaration { file_path, .. }
+        | AnalyzerCommand::QualifiedPath { file_path, .. }
+        | AnalyzerCommand::DescribeSymbol { file_path, .. }
+        | AnalyzerCommand::EnclosingScope { file_path, .. }
+        | AnalyzerCommand::ItemAttributes { file_path, .. }
+        | AnalyzerCommand::EnumVariants { file_path, .. }
+        | AnalyzerCommand::StructFields { file_path, .. }
+        | AnalyzerCommand::CallReturnType { file_path, .. }
+        | AnalyzerCommand::DiffSignature { file_path, .. }
+        | AnalyzerCommand::GetRelatedTests { file_path, .. }
+        | AnalyzerCommand::GetRunnables { file_path, .. }
+        | AnalyzerCommand::GetCompletions { file_path, .. }
+        | AnalyzerCommand::FindReferences { file_path, .. }
+        | AnalyzerCommand::CountReferences { file_path, .. }
+        | AnalyzerCommand::ViewInlayHints { file_path, .. }
+        | AnalyzerCommand::SemanticTokens { file_path }
+        | AnalyzerCommand::FileEdition { file_path }
+        | AnalyzerCommand::SyntaxTree { file_path, .. }
+        | AnalyzerCommand::OnTypeFormat { file_path, .. }
+        | AnalyzerCommand::OrganizeImports { file_path, .. }
+        | AnalyzerCommand::GetAssists { file_path, .. }
+        | AnalyzerCommand::FileAssists { file_path, .. }
+        | AnalyzerCommand::ApplyAssist { file_path, .. }
+        | AnalyzerCommand::ImportSuggestions { file_path, .. }
+        | AnalyzerCommand::ApplyImport { file_path, .. }
+        | AnalyzerCommand::ExtractFunction { file_path, .. }
+        | AnalyzerCommand::RenameSymbol { file_path, .. }
+        | AnalyzerCommand::MoveItem { file_path, .. } => file_path,
+        AnalyzerCommand::WorkspaceDiagnostics { workspace_path }
+        | AnalyzerCommand::WorkspaceInfo { workspace_path }
+        | AnalyzerCommand::MemoryUsage { workspace_path }
+        | AnalyzerCommand::CrateInfo { workspace_path, .. }
+        | AnalyzerCommand::TraitImpls { workspace_path, .. }
+        | AnalyzerCommand::WorkspaceSymbols { workspace_path, .. } => workspace_path,
+        AnalyzerCommand::UnusedItems { path } => path,
+    }
+}
+
+// Like `command_path`, to rewrite the path
+fn command_path_mut(command: &mut AnalyzerCommand) -> &mut String {
     match command {
         AnalyzerCommand::TypeHint { file_path, .. }
         | AnalyzerCommand::GetDefinition { file_path, .. }
+        | AnalyzerCommand::GetTypeDefinition { file_path, .. }
+        | AnalyzerCommand::GetDeclaration { file_path, .. }
+        | AnalyzerCommand::QualifiedPath { file_path, .. }
+        | AnalyzerCommand::DescribeSymbol { file_path, .. }
+        | AnalyzerCommand::EnclosingScope { file_path, .. }
+        | AnalyzerCommand::ItemAttributes { file_path, .. }
+        | AnalyzerCommand::EnumVariants { file_path, .. }
+        | AnalyzerCommand::StructFields { file_path, .. }
+        | AnalyzerCommand::CallReturnType { file_path, .. }
+        | AnalyzerCommand::DiffSignature { file_path, .. }
+        | AnalyzerCommand::GetRelatedTests { file_path, .. }
+        | AnalyzerCommand::GetRunnables { file_path, .. }
         | AnalyzerCommand::GetCompletions { file_path, .. }
         | AnalyzerCommand::FindReferences { file_path, .. }
+        | AnalyzerCommand::CountReferences { file_path, .. }
         | AnalyzerCommand::ViewInlayHints { file_path, .. }
+        | AnalyzerCommand::SemanticTokens { file_path }
+        | AnalyzerCommand::FileEdition { file_path }
+        | AnalyzerCommand::SyntaxTree { file_path, .. }
+        | AnalyzerCommand::OnTypeFormat { file_path, .. }
+        | AnalyzerCommand::OrganizeImports { file_path, .. }
         | AnalyzerCommand::GetAssists { file_path, .. }
+        | AnalyzerCommand::FileAssists { file_path, .. }
         | AnalyzerCommand::ApplyAssist { file_path, .. }
-        | AnalyzerCommand::RenameSymbol { file_path, .. } => file_path.clone(),
+        | AnalyzerCommand::ImportSuggestions { file_path, .. }
+        | AnalyzerCommand::ApplyImport { file_path, .. }
+        | AnalyzerCommand::ExtractFunction { file_path, .. }
+        | AnalyzerCommand::RenameSymbol { file_path, .. }
+        | AnalyzerCommand::MoveItem { file_path, .. } => file_path,
+        AnalyzerCommand::WorkspaceDiagnostics { workspace_path }
+        | AnalyzerCommand::WorkspaceInfo { workspace_path }
+        | AnalyzerCommand::MemoryUsage { workspace_path }
+        | AnalyzerCommand::CrateInfo { workspace_path, .. }
+        | AnalyzerCommand::TraitImpls { workspace_path, .. }
+        | AnalyzerCommand::WorkspaceSymbols { workspace_path, .. } => workspace_path,
+        AnalyzerCommand::UnusedItems { path } => path,
     }
 }
@@ -0,0 +1,104 @@
+//! Tab completion for the REPL
+//!
+//! Completes command names at the start of the line and workspace `.rs` file
+//! paths for every other argument.
+
+use std::path::Path;
+
+use clap::CommandFactory;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+use crate::command::CommandWrapper;
+
+/// Commands handled by the REPL loop itself rather than by clap
+const REPL_COMMANDS: &[&str] = &["help", "quit", "exit", "reload", "set"];
+
+/// rustyline helper completing command names and workspace file paths
+pub struct ReplHelper {
+    commands: Vec<String>,
+    files: Vec<String>,
+}
+
+impl ReplHelper {
+    /// Create a helper completing the given workspace files
+    ///
+    /// Absolute paths under `workspace_path` are offered relative to it.
+    pub fn new(workspace_path: &str, file_paths: Vec<String>) -> Self {
+        let mut commands: Vec<String> = CommandWrapper::command()
+            .get_subcommands()
+            .map(|subcommand| subcommand.get_name().to_string())
+            .chain(REPL_COMMANDS.iter().map(|command| command.to_string()))
+            .collect();
+        commands.sort();
+
+        let mut helper = Self {
+            commands,
+            files: Vec::new(),
+        };
+        helper.set_files(workspace_path, file_paths);
+        helper
+    }
+
+    /// Replace the completable files, e.g. after reloading the workspace
+    pub fn set_files(&mut self, workspace_path: &str, file_paths: Vec<String>) {
+        let root = Path::new(workspace_path)
+            .canonicalize()
+            .unwrap_or_else(|_| Path::new(workspace_path).to_path_buf());
+        self.files = file_paths
+            .into_iter()
+            .map(|path| match Path::new(&path).strip_prefix(&root) {
+                Ok(relative) => relative.to_string_lossy().to_string(),
+                Err(_) => path,
+            })
+            .collect();
+    }
+
+    /// Options starting with `prefix` as completion candidates
+    fn candidates(options: &[String], prefix: &str) -> Vec<Pair> {
+        options
+            .iter()
+            .filter(|option| option.starts_with(prefix))
+            .map(|option| Pair {
+                display: option.clone(),
+                replacement: option.clone(),
+            })
+            .collect()
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos]
+            .rfind(char::is_whitespace)
+            .map_or(0, |index| index + 1);
+        let prefix = &line[start..pos];
+
+        let candidates = if line[..start].trim().is_empty() {
+            Self::candidates(&self.commands, prefix)
+        } else {
+            Self::candidates(&self.files, prefix)
+        };
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
@@ -0,0 +1,341 @@
+//! Background daemon keeping a workspace loaded across CLI invocations
+//!
+//! Loading a workspace takes seconds, which dominates the runtime of one-off
+//! `rustbelt analyzer` commands. `rustbelt daemon start` spawns a background
+//! process that loads the workspace once and serves analyzer commands over a
+//! Unix socket. CLI invocations forward their arguments to a running daemon
+//! and fall back to an in-process analyzer when none is found.
+
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use librustbelt::{
+    analyzer::RustAnalyzerish,
+    builder::{RustAnalyzerishBuilder, WorkspaceOptions},
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tracing::{info, warn};
+
+use crate::command::{
+    CommandWrapper, WorkspaceArgs, execute_analyzer_command_with_instance, resolve_relative_paths,
+};
+use crate::logging::LogArgs;
+
+/// How long `daemon start` waits for the workspace to finish loading
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long the daemon waits for a client to send its request line
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Subcommand)]
+pub enum DaemonAction {
+    /// Start a daemon holding the workspace loaded in the background
+    Start {
+        /// Path to the workspace directory
+        workspace_path: String,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
+    /// Stop the daemon serving the workspace
+    Stop {
+        /// Path to the workspace directory
+        workspace_path: String,
+    },
+    /// Check whether a daemon is serving the workspace
+    Status {
+        /// Path to the workspace directory
+        workspace_path: String,
+    },
+    /// Run the daemon in the foreground (used by `start`)
+    #[command(hide = true)]
+    Run {
+        /// Path to the workspace directory
+        workspace_path: String,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
+}
+
+/// A request sent from a CLI invocation to the daemon
+#[derive(Debug, Serialize, Deserialize)]
+enum Request {
+    /// Run an analyzer command from `cwd` with the given CLI arguments
+    Run { cwd: PathBuf, args: Vec<String> },
+    /// Shut the daemon down
+    Stop,
+}
+
+pub async fn run_daemon_action(action: DaemonAction, log: &LogArgs) -> Result<()> {
+    match action {
+        DaemonAction::Start {
+            workspace_path,
+            workspace,
+        } => start(&workspace_path, &workspace, log).await,
+        DaemonAction::Stop { workspace_path } => {
+            match send_request(&workspace_path, &Request::Stop).await? {
+                Some(output) => print!("{}", output),
+                None => println!("No daemon running for {}", workspace_path),
+            }
+            Ok(())
+        }
+        DaemonAction::Status { workspace_path } => {
+            match connect(&workspace_path).await {
+                Some((root, _)) => println!("Daemon running for {}", root.display()),
+                None => println!("No daemon running for {}", workspace_path),
+            }
+            Ok(())
+        }
+        DaemonAction::Run {
+            workspace_path,
+            workspace,
+        } => serve(&workspace_path, &workspace.options()?).await,
+    }
+}
+
+/// Forward CLI arguments to a daemon serving `workspace_path`
+///
+/// Returns the command output, or `None` when no daemon is running so the
+/// caller can fall back to an in-process analyzer.
+pub async fn forward(workspace_path: &str, args: Vec<String>) -> Result<Option<String>> {
+    let request = Request::Run {
+        cwd: std::env::current_dir()?,
+        args,
+    };
+    send_request(workspace_path, &request).await
+}
+
+/// Spawn a detached daemon process and wait until it accepts connections
+async fn start(workspace_path: &str, workspace: &WorkspaceArgs, log: &LogArgs) -> Result<()> {
+    if let Some((root, _)) = connect(workspace_path).await {
+        println!("Daemon already running for {}", root.display());
+        return Ok(());
+    }
+
+    let root = project_root(workspace_path)?;
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    command
+        .args(["daemon", "run"])
+        .arg(&root)
+        .args(workspace.to_args())
+        .args(log.to_args())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Detach from the terminal's process group so Ctrl-C doesn't stop the daemon
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command.spawn().context("Failed to spawn daemon process")?;
+
+    println!("Loading workspace {}...", root.display());
+    let start = Instant::now();
+    while start.elapsed() < STARTUP_TIMEOUT {
+        if let Some(status) = child.try_wait()? {
+            anyhow::bail!("Daemon exited during startup: {}", status);
+        }
+        if UnixStream::connect(socket_path(&root)?).await.is_ok() {
+            println!(
+                "Daemon started for {} in {}ms",
+                root.display(),
+                start.elapsed().as_millis()
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    anyhow::bail!("Daemon did not start within {}s", STARTUP_TIMEOUT.as_secs())
+}
+
+/// Load the workspace and serve requests until asked to stop
+async fn serve(workspace_path: &str, options: &WorkspaceOptions) -> Result<()> {
+    let root = project_root(workspace_path)?;
+    let socket = socket_path(&root)?;
+
+    let mut analyzer = RustAnalyzerishBuilder::from_file(&root)?
+        .with_options(options)?
+        .build()?;
+
+    // A leftover socket from a daemon that didn't shut down cleanly
+    if let Ok(metadata) = std::fs::symlink_metadata(&socket) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", socket.display());
+        }
+        if UnixStream::connect(&socket).await.is_ok() {
+            anyhow::bail!("A daemon is already listening on {}", socket.display());
+        }
+        std::fs::remove_file(&socket)?;
+    }
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("Failed to bind {}", socket.display()))?;
+    std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600))?;
+    info!(
+        "Daemon for {} listening on {}",
+        root.display(),
+        socket.display()
+    );
+
+    // A failing client only loses its own request, never the daemon
+    loop {
+        let mut stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                warn!("Failed to accept daemon connection: {}", e);
+                continue;
+            }
+        };
+        let mut line = String::new();
+        let read = BufReader::new(&mut stream).read_line(&mut line);
+        match tokio::time::timeout(REQUEST_TIMEOUT, read).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                warn!("Failed to read daemon request: {}", e);
+                continue;
+            }
+            Err(_) => {
+                warn!(
+                    "No daemon request received within {}s",
+                    REQUEST_TIMEOUT.as_secs()
+                );
+                continue;
+            }
+        }
+
+        let output = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Run { cwd, args }) => run_command(&mut analyzer, &cwd, args).await,
+            Ok(Request::Stop) => {
+                let _ = stream.write_all(b"Daemon stopped\n").await;
+                break;
+            }
+            Err(e) => {
+                warn!("Invalid daemon request: {}", e);
+                format!("Invalid request: {}\n", e).into_bytes()
+            }
+        };
+
+        if let Err(e) = stream.write_all(&output).await {
+            warn!("Failed to send daemon response: {}", e);
+        }
+    }
+
+    std::fs::remove_file(&socket)?;
+    info!("Daemon for {} stopped", root.display());
+    Ok(())
+}
+
+/// Run analyzer command arguments and capture their output
+async fn run_command(analyzer: &mut RustAnalyzerish, cwd: &Path, args: Vec<String>) -> Vec<u8> {
+    let mut wrapper = match CommandWrapper::try_parse_from(args) {
+        Ok(wrapper) => wrapper,
+        Err(e) => return e.to_string().into_bytes(),
+    };
+    // Relative paths are relative to the client's working directory, not the daemon's
+    resolve_relative_paths(&mut wrapper.command, cwd);
+
+    let mut output = Vec::new();
+    if let Err(e) = execute_analyzer_command_with_instance(
+        wrapper.command,
+        analyzer,
+        None,
+        wrapper.json,
+        &mut output,
+    )
+    .await
+    {
+        output.extend_from_slice(format!("Command failed: {}\n", e).as_bytes());
+    }
+    output
+}
+
+/// Send a request to the daemon serving `workspace_path` and read its response
+async fn send_request(workspace_path: &str, request: &Request) -> Result<Option<String>> {
+    let Some((_, mut stream)) = connect(workspace_path).await else {
+        return Ok(None);
+    };
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes()).await?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    Ok(Some(response))
+}
+
+/// Connect to a daemon serving `path` or one of its ancestors
+///
+/// Walking up the ancestors lets commands on files inside a workspace find
+/// the daemon started for the workspace root.
+async fn connect(path: &str) -> Option<(PathBuf, UnixStream)> {
+    let path = Path::new(path).canonicalize().ok()?;
+    for ancestor in path.ancestors() {
+        let socket = socket_path(ancestor).ok()?;
+        if !socket.exists() {
+            continue;
+        }
+        if let Ok(stream) = UnixStream::connect(&socket).await {
+            return Some((ancestor.to_path_buf(), stream));
+        }
+    }
+    None
+}
+
+/// Canonical project root for a workspace or file path
+fn project_root(workspace_path: &str) -> Result<PathBuf> {
+    let builder = RustAnalyzerishBuilder::from_file(workspace_path)?;
+    let root = builder
+        .project_root()
+        .ok_or_else(|| anyhow::anyhow!("No workspace root found for {}", workspace_path))?;
+    Ok(root.canonicalize()?)
+}
+
+/// Socket path of the daemon serving `root`
+///
+/// The name hashes the root with FNV-1a, which unlike `DefaultHasher` gives
+/// the same path whichever toolchain built the client and the daemon.
+fn socket_path(root: &Path) -> Result<PathBuf> {
+    let hash = root
+        .as_os_str()
+        .as_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    Ok(socket_dir()?.join(format!("{:016x}.sock", hash)))
+}
+
+/// Directory of the daemon sockets of the current user
+///
+/// `$XDG_RUNTIME_DIR` is private to the user. Elsewhere a per-user directory
+/// in the shared temp directory is used, which must be owned by the user and
+/// not accessible to others, so nobody else can connect to a daemon or take
+/// over its socket path.
+fn socket_dir() -> Result<PathBuf> {
+    // SAFETY: getuid has no preconditions and always succeeds
+    let uid = unsafe { libc::getuid() };
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) if Path::new(&runtime_dir).is_absolute() => {
+            PathBuf::from(runtime_dir).join("rustbelt")
+        }
+        _ => std::env::temp_dir().join(format!("rustbelt-{}", uid)),
+    };
+
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e).with_context(|| format!("Failed to create {}", dir.display())),
+    }
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        anyhow::bail!(
+            "Refusing to use {} for daemon sockets: it must be a directory owned by the current user with mode 0700",
+            dir.display()
+        );
+    }
+    Ok(dir)
+}
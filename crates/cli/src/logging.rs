@@ -0,0 +1,73 @@
+//! Logging setup shared by all subcommands
+//!
+//! Logs go to stdout by default. In MCP stdio mode stdout carries JSON-RPC, so
+//! logging is disabled there unless `--log-file` redirects it to a file.
+
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use tracing_subscriber::EnvFilter;
+
+// Options controlling the log level and destination
+#[derive(Args, Debug, Clone, Default, PartialEq)]
+pub struct LogArgs {
+    /// Log filter, e.g. `debug` or `librustbelt=trace` (defaults to `RUST_LOG`, then `error`)
+    #[arg(long, value_name = "FILTER", global = true)]
+    pub log_level: Option<String>,
+    /// Append logs to this file instead of stdout, also in MCP stdio mode
+    #[arg(long, value_name = "PATH", global = true)]
+    pub log_file: Option<PathBuf>,
+}
+
+impl LogArgs {
+    /// Render the options back into CLI arguments
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(level) = &self.log_level {
+            args.push(format!("--log-level={}", level));
+        }
+        if let Some(file) = &self.log_file {
+            // The daemon runs from another process, so don't depend on the cwd
+            let file = std::path::absolute(file).unwrap_or_else(|_| file.clone());
+            args.push(format!("--log-file={}", file.display()));
+        }
+        args
+    }
+}
+
+/// Install the global tracing subscriber
+///
+/// With `stdout_reserved`, as in MCP stdio mode, nothing is logged unless a
+/// log file is given.
+pub fn init(args: &LogArgs, stdout_reserved: bool) -> Result<()> {
+    if stdout_reserved && args.log_file.is_none() {
+        return Ok(());
+    }
+
+    let filter = match &args.log_level {
+        Some(level) => {
+            EnvFilter::try_new(level).with_context(|| format!("Invalid log level '{}'", level))?
+        }
+        None => EnvFilter::from_default_env(),
+    };
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match &args.log_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log file {}", path.display()))?;
+            subscriber
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .init();
+        }
+        None => subscriber.init(),
+    }
+    Ok(())
+}
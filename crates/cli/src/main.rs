@@ -4,11 +4,22 @@
 //! functionality and standalone CLI tools.
 
 use clap::{Parser, Subcommand};
-use command::{CommandWrapper, execute_analyzer_command, extract_workspace_path};
+use std::io::Read;
+use std::time::Duration;
+
+use command::{
+    CommandWrapper, SnippetArgs, WorkspaceArgs, execute_analyzer_command, execute_snippet_command,
+    extract_workspace_path,
+};
 use rustbelt_server::VERSION;
 
 mod command;
+mod completer;
+#[cfg(unix)]
+mod daemon;
+mod logging;
 mod repl;
+mod watch;
 
 #[derive(Parser)]
 #[command(name = "rustbelt")]
@@ -17,6 +28,8 @@ mod repl;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    #[command(flatten)]
+    log: logging::LogArgs,
 }
 
 #[derive(Subcommand)]
@@ -32,6 +45,13 @@ enum Commands {
         /// Port for TCP mode
         #[arg(long, default_value = "3001")]
         port: u16,
+        /// Drop the loaded workspace after this many seconds without requests, to free memory
+        ///
+        /// The workspace is loaded again by the next tool call.
+        #[arg(long, value_name = "SECS")]
+        idle_timeout: Option<u64>,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
     },
     /// Connect to a workspace for interactive queries
     Repl {
@@ -39,41 +59,108 @@ enum Commands {
         workspace_path: String,
     },
     /// Run an analyzer task
-    Analyzer(#[command(flatten)] CommandWrapper),
+    ///
+    /// Forwarded to a running daemon for the workspace when there is one.
+    Analyzer {
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+        #[command(flatten)]
+        command: CommandWrapper,
+    },
+    /// Analyze Rust source read from stdin, without a file on disk
+    ///
+    /// The snippet is analyzed as a standalone crate, like `--allow-detached`.
+    AnalyzeSnippet {
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+        #[command(flatten)]
+        snippet: SnippetArgs,
+    },
+    /// Watch a file and print its diagnostics each time it changes
+    Watch {
+        /// Path to the Rust source file
+        file_path: String,
+        /// How often to check for changes, in milliseconds
+        #[arg(long, value_name = "MS", default_value = "500")]
+        interval: u64,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
+    /// Manage a background daemon that keeps a workspace loaded
+    #[cfg(unix)]
+    Daemon {
+        #[command(subcommand)]
+        action: daemon::DaemonAction,
+    },
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    // Stdio mode serves JSON-RPC on stdout, so only log there with --log-file
+    let stdio = matches!(cli.command, Commands::Serve { tcp: false, .. });
+    logging::init(&cli.log, stdio)?;
 
     match cli.command {
-        Commands::Serve { tcp, host, port } => {
+        Commands::Serve {
+            tcp,
+            host,
+            port,
+            idle_timeout,
+            workspace,
+        } => {
+            let options = workspace.options()?;
+            let idle_timeout = idle_timeout.map(Duration::from_secs);
             if tcp {
                 // Run in TCP mode
-                // Only initialize logging for TCP mode
-                tracing_subscriber::fmt::init();
                 let addr = format!("{host}:{port}");
-                rustbelt_server::serve_tcp(addr).await?;
+                rustbelt_server::serve_tcp(addr, options, idle_timeout).await?;
             } else {
                 // Run in stdio mode - recommended for MCP clients (default)
-                // No logging as it would interfere with JSON-RPC communication
-                rustbelt_server::serve_stdio().await?;
+                // Logs only go to --log-file as stdout carries JSON-RPC communication
+                rustbelt_server::serve_stdio(options, idle_timeout).await?;
             }
         }
         Commands::Repl { workspace_path } => {
-            // Initialize logging for debugging
-            tracing_subscriber::fmt::init();
-
             repl::run_repl(&workspace_path).await?;
         }
-        Commands::Analyzer(command_wrapper) => {
-            // Initialize logging for debugging
-            tracing_subscriber::fmt::init();
-
-            let analyzer_command = command_wrapper.command;
+        Commands::Analyzer { workspace, command } => {
+            let options = workspace.options()?;
+            let json = command.json;
+            let analyzer_command = command.command;
             // For analyzer commands, we need to determine the workspace path
             let workspace_path = extract_workspace_path(&analyzer_command);
-            execute_analyzer_command(analyzer_command, &workspace_path).await?;
+
+            // Reuse a loaded workspace when a daemon is running. The daemon
+            // was loaded with its own options, so only forward when none are given.
+            #[cfg(unix)]
+            if options == Default::default() && cli.log == Default::default() {
+                let args = std::env::args().skip(2).collect();
+                if let Some(output) = daemon::forward(&workspace_path, args).await? {
+                    print!("{}", output);
+                    return Ok(());
+                }
+            }
+
+            execute_analyzer_command(analyzer_command, &workspace_path, &options, json).await?;
+        }
+        Commands::AnalyzeSnippet { workspace, snippet } => {
+            let options = workspace.options()?;
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source)?;
+            execute_snippet_command(snippet, &source, &options).await?;
+        }
+        Commands::Watch {
+            file_path,
+            interval,
+            workspace,
+        } => {
+            let options = workspace.options()?;
+            watch::run_watch(&file_path, &options, Duration::from_millis(interval)).await?;
+        }
+        #[cfg(unix)]
+        Commands::Daemon { action } => {
+            daemon::run_daemon_action(action, &cli.log).await?;
         }
     }
 
@@ -1,25 +1,27 @@
 use std::path::Path;
+use std::time::Instant;
 
 use anyhow::Result;
 use clap::Parser;
-use librustbelt::{builder::RustAnalyzerishBuilder, entities::CursorCoordinates};
-use rustyline::{Config, DefaultEditor};
+use librustbelt::{
+    analyzer::RustAnalyzerish, builder::RustAnalyzerishBuilder, entities::CursorCoordinates,
+};
+use rustyline::history::DefaultHistory;
+use rustyline::{Config, Editor};
 
 use crate::command::{CommandWrapper, execute_analyzer_command_with_instance};
+use crate::completer::ReplHelper;
 
 pub async fn run_repl(workspace_path: &str) -> Result<()> {
     println!("Connecting to workspace: {}", workspace_path);
 
-    // Initialize a standalone analyzer for the workspace
-    let mut analyzer = RustAnalyzerishBuilder::from_file(workspace_path)?.build()?;
-
     // Configure rustyline with history support
     let config = Config::builder()
         .history_ignore_space(true)
         .completion_type(rustyline::CompletionType::List)
         .build();
 
-    let mut rl = DefaultEditor::with_config(config)?;
+    let mut rl: Editor<ReplHelper, DefaultHistory> = Editor::with_config(config)?;
 
     // Load history from file if it exists
     let history_file = format!(
@@ -28,24 +30,20 @@ pub async fn run_repl(workspace_path: &str) -> Result<()> {
     );
     let _ = rl.load_history(&history_file); // Ignore errors if file doesn't exist
 
-    // Start loading the project immediately in the background
+    // Start loading the project immediately
     println!("Loading project... This may take a moment on first run.");
-
-    // Prime the analyzer by accessing a dummy file in the workspace to trigger project loading
-    let dummy_file = Path::new(workspace_path).join("Cargo.toml");
-    if dummy_file.exists() {
-        let dummy_cursor = CursorCoordinates {
-            file_path: dummy_file.to_string_lossy().to_string(),
-            line: 1,
-            column: 1,
-            symbol: None,
-        };
-        let _ = analyzer.get_type_hint(&dummy_cursor).await; // This will trigger project loading
-    }
+    let mut analyzer = load_analyzer(workspace_path).await?;
+    rl.set_helper(Some(ReplHelper::new(
+        workspace_path,
+        analyzer.workspace_file_paths(),
+    )));
 
     println!("Connected to workspace.");
     print_repl_help();
 
+    // Maximum number of list results to print, unlimited by default
+    let mut limit: Option<usize> = None;
+
     loop {
         let readline = rl.readline("rustbelt> ");
         match readline {
@@ -71,6 +69,42 @@ pub async fn run_repl(workspace_path: &str) -> Result<()> {
                     "help" => {
                         print_repl_help();
                     }
+                    "set" => match parts.get(1..) {
+                        Some(["limit", "none" | "off"]) => {
+                            limit = None;
+                            println!("Result limit disabled");
+                        }
+                        Some(["limit", value]) => match value.parse::<usize>() {
+                            Ok(value) if value > 0 => {
+                                limit = Some(value);
+                                println!("Result limit set to {}", value);
+                            }
+                            _ => println!("Invalid limit '{}': expected a positive number", value),
+                        },
+                        Some(["limit"]) => match limit {
+                            Some(value) => println!("Result limit: {}", value),
+                            None => println!("Result limit: none"),
+                        },
+                        _ => println!("Usage: set limit <N|none>"),
+                    },
+                    "reload" => {
+                        println!("Reloading workspace: {}", workspace_path);
+                        let start = Instant::now();
+                        match load_analyzer(workspace_path).await {
+                            Ok(reloaded) => {
+                                analyzer = reloaded;
+                                if let Some(helper) = rl.helper_mut() {
+                                    helper
+                                        .set_files(workspace_path, analyzer.workspace_file_paths());
+                                }
+                                println!("Reloaded workspace in {}ms", start.elapsed().as_millis());
+                            }
+                            Err(e) => {
+                                // Keep the previous analyzer so the session stays usable
+                                println!("Reload failed: {}", e);
+                            }
+                        }
+                    }
                     _ => {
                         // Try to parse as an analyzer command using clap
                         match CommandWrapper::try_parse_from(parts) {
@@ -78,6 +112,9 @@ pub async fn run_repl(workspace_path: &str) -> Result<()> {
                                 match execute_analyzer_command_with_instance(
                                     wrapper.command,
                                     &mut analyzer,
+                                    limit,
+                                    wrapper.json,
+                                    &mut std::io::stdout(),
                                 )
                                 .await
                                 {
@@ -115,6 +152,25 @@ pub async fn run_repl(workspace_path: &str) -> Result<()> {
     Ok(())
 }
 
+/// Build an analyzer for the workspace and prime it so project loading happens upfront
+async fn load_analyzer(workspace_path: &str) -> Result<RustAnalyzerish> {
+    let mut analyzer = RustAnalyzerishBuilder::from_file(workspace_path)?.build()?;
+
+    // Prime the analyzer by accessing a dummy file in the workspace to trigger project loading
+    let dummy_file = Path::new(workspace_path).join("Cargo.toml");
+    if dummy_file.exists() {
+        let dummy_cursor = CursorCoordinates {
+            file_path: dummy_file.to_string_lossy().to_string(),
+            line: 1,
+            column: 1,
+            symbol: None,
+        };
+        let _ = analyzer.get_type_hint(&dummy_cursor).await; // This will trigger project loading
+    }
+
+    Ok(analyzer)
+}
+
 fn print_repl_help() {
     println!("Available commands:");
 
@@ -145,10 +201,19 @@ fn print_repl_help() {
         println!("  {:<20} {}", display_name, about);
     }
 
+    println!(
+        "  {:<20} Reload the workspace to pick up new files and Cargo.toml changes",
+        "reload"
+    );
+    println!(
+        "  {:<20} Limit printed completions/references (e.g. set limit 20)",
+        "set limit <N|none>"
+    );
     println!("  {:<20} Show this help message", "help");
     println!("  {:<20} Exit the REPL", "quit/exit");
     println!();
     println!("Note: File paths can be relative to the workspace or absolute");
     println!("      Use --symbol to specify a symbol name when coordinates are ambiguous");
     println!("      Use up/down arrows to navigate command history");
+    println!("      Press Tab to complete command names and workspace file paths");
 }
@@ -0,0 +1,64 @@
+//! Live diagnostics for a single file
+//!
+//! `rustbelt watch <file>` loads the workspace once and keeps the analyzer
+//! alive. The file watcher picks up edits on disk, and the file's diagnostics
+//! are printed again each time a change is applied.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use librustbelt::{
+    analyzer::RustAnalyzerish,
+    builder::{RustAnalyzerishBuilder, WorkspaceOptions},
+    utils::RustAnalyzerUtils,
+};
+
+/// Load the workspace of `file_path` and print its diagnostics on every change
+///
+/// Runs until interrupted with Ctrl-C. The file watcher is polled every `interval`.
+pub async fn run_watch(
+    file_path: &str,
+    options: &WorkspaceOptions,
+    interval: Duration,
+) -> Result<()> {
+    println!("Loading workspace for {}...", file_path);
+    let mut analyzer =
+        RustAnalyzerishBuilder::from_file_with_options(file_path, options)?.build()?;
+
+    print_diagnostics(&mut analyzer, file_path).await;
+    let mut revision = analyzer.apply_file_changes()?;
+    println!("Watching {} for changes, press Ctrl-C to stop", file_path);
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => return Ok(()),
+            _ = tokio::time::sleep(interval) => {}
+        }
+
+        let current = analyzer.apply_file_changes()?;
+        if current != revision {
+            revision = current;
+            print_diagnostics(&mut analyzer, file_path).await;
+        }
+    }
+}
+
+/// Print a summary line followed by one line per diagnostic of the file
+async fn print_diagnostics(analyzer: &mut RustAnalyzerish, file_path: &str) {
+    match analyzer.get_file_diagnostics(file_path).await {
+        Ok(diagnostics) => {
+            println!(
+                "Found {} diagnostic(s): {}",
+                diagnostics.len(),
+                RustAnalyzerUtils::diagnostics_summary(&diagnostics)
+            );
+            for diagnostic in diagnostics {
+                println!("  {}", diagnostic);
+            }
+        }
+        // Keep watching, the file may be fixed by the next save
+        Err(e) => eprintln!("Error getting diagnostics: {}", e),
+    }
+}
@@ -0,0 +1,68 @@
+//! Integration tests for the rustbelt command line
+//!
+//! These tests run the rustbelt binary against the sample project of
+//! librustbelt and check what it prints.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use serde_json::Value;
+
+fn get_sample_project_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent() // crates
+        .unwrap()
+        .join("librustbelt/tests/sample-project")
+}
+
+/// Run `rustbelt analyzer` without the sysroot and return its stdout as JSON
+fn run_analyzer_json(args: &[&str]) -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_rustbelt"))
+        .args(["analyzer", "--no-sysroot", "--no-proc-macros"])
+        .args(args)
+        .arg("--json")
+        .output()
+        .expect("Failed to run rustbelt");
+    assert!(
+        output.status.success(),
+        "rustbelt failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+        panic!(
+            "Output is not JSON ({e}): {}",
+            String::from_utf8_lossy(&output.stdout)
+        )
+    })
+}
+
+#[test]
+fn test_json_output_for_results_and_errors() {
+    let workspace_path = get_sample_project_path();
+    let workspace_path = workspace_path.to_str().unwrap();
+
+    let page = run_analyzer_json(&["workspace-symbols", workspace_path, "Person"]);
+    let symbols = page["symbols"]
+        .as_array()
+        .expect("Expected a symbols array");
+    assert!(
+        symbols.iter().any(|symbol| symbol["name"] == "Person"),
+        "{page}"
+    );
+    assert_eq!(page["total"], symbols.len());
+
+    // Errors are JSON too, including invalid arguments checked by the handler
+    let error = run_analyzer_json(&[
+        "workspace-symbols",
+        workspace_path,
+        "Person",
+        "--mode",
+        "bogus",
+    ]);
+    assert!(
+        error["error"]
+            .as_str()
+            .is_some_and(|message| message.contains("bogus")),
+        "{error}"
+    );
+}
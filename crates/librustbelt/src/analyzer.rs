@@ -4,31 +4,64 @@
 //! making it easy to get type hints, definitions, and other semantic
 //! information.
 
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::Result;
-use ra_ap_hir::ClosureStyle;
+use ra_ap_hir::{ClosureStyle, HirDisplay, Module, ModuleDef, ModuleSource, Semantics};
 use ra_ap_ide::{
     AdjustmentHints, AdjustmentHintsMode, Analysis, AnalysisHost, CallableSnippets,
     ClosureReturnTypeHints, CompletionConfig, CompletionFieldsToResolve,
-    CompletionItemKind as RaCompletionItemKind, DiscriminantHints, FileId, FilePosition, FileRange,
-    FindAllRefsConfig, GenericParameterHints, GotoDefinitionConfig, HoverConfig, HoverDocFormat,
-    InlayFieldsToResolve, InlayHintPosition, InlayHintsConfig, LifetimeElisionHints, LineCol,
-    LineIndex, MonikerResult, RenameConfig, SubstTyLen, TextRange, TextSize,
+    CompletionItemKind as RaCompletionItemKind, DiagnosticsConfig, DiscriminantHints, FileId,
+    FilePosition, FileRange, FindAllRefsConfig, GenericParameterHints, GotoDefinitionConfig,
+    GotoImplementationConfig, HighlightConfig, HoverConfig, HoverDocFormat, InlayFieldsToResolve,
+    InlayHint, InlayHintPosition, InlayHintsConfig, LifetimeElisionHints, LineCol, LineIndex,
+    MemoryLayoutHoverConfig, MemoryLayoutHoverRenderKind, MonikerResult, NavigationTarget,
+    RenameConfig, RunnableKind, SubstTyLen, SymbolKind, TextRange, TextSize,
 };
-use ra_ap_ide_db::MiniCore;
-use ra_ap_ide_assists::{AssistConfig, AssistResolveStrategy, assists};
+use ra_ap_ide_assists::{Assist, AssistConfig, AssistResolveStrategy, assists};
+use ra_ap_ide_db::defs::{Definition, NameClass, NameRefClass};
 use ra_ap_ide_db::imports::insert_use::{ImportGranularity, InsertUseConfig, PrefixKind};
-use ra_ap_ide_db::text_edit::TextEditBuilder;
+use ra_ap_ide_db::search::SearchScope;
+use ra_ap_ide_db::source_change::{FileSystemEdit, SourceChange};
+use ra_ap_ide_db::symbol_index::Query;
+use ra_ap_ide_db::{MiniCore, RootDatabase, SnippetCap};
+use ra_ap_syntax::ast::{HasAttrs, HasName, HasVisibility};
+use ra_ap_syntax::{
+    AstNode, AstToken, Edition, NodeOrToken, SyntaxKind, SyntaxNode, SyntaxToken, ast, match_ast,
+};
+use regex::RegexBuilder;
 use tracing::{debug, trace, warn};
 
+use super::config::AnalyzerConfig;
 use super::entities::{
-    AssistInfo, AssistSourceChange, CompletionItem, CursorCoordinates, DefinitionInfo, FileChange,
-    ReferenceInfo, RenameResult, TextEdit, TypeHint,
+    AnalyzerStatus, ApplyOptions, AssistInfo, AssistSourceChange, CompletionImport, CompletionItem,
+    CompletionOptions, CrateInfo, CursorCoordinates, DefinitionInfo, DefinitionOptions,
+    DefinitionReport, DiagnosticInfo, DiagnosticSeverity, FieldInfo, FileAssists, FileChange,
+    FileRename, HighlightRange, InlayHintInfo, MemoryUsage, MoveItemResult, PositionedAssists,
+    PublicApi, PublicApiItem, ReferenceCategory, ReferenceCount, ReferenceInfo, ReferenceOptions,
+    ReferenceScope, RenameResult, Runnable, SignatureDiff, SourceExcerpt, SymbolDescription,
+    SymbolMatchMode, TextEdit, TraitImpls, TypeHint, TypeHintOptions, UnusedItem, UnusedKind,
+    VariantField, VariantInfo, VariantKind, WorkspaceInfo, WorkspaceSymbol,
+    WorkspaceSymbolsOptions, WorkspaceSymbolsPage,
 };
+use super::error::{self, ErrorKind};
 use super::file_watcher::FileWatcher;
 use super::utils::RustAnalyzerUtils;
 
+/// Maximum number of assists listed for a whole file
+const MAX_FILE_ASSISTS: usize = 200;
+
+/// Lines of context around an external definition when inlining its source
+const EXTERNAL_SOURCE_CONTEXT_LINES: u32 = 10;
+
+/// Maximum number of lines inlined for an external definition
+const MAX_EXTERNAL_SOURCE_LINES: u32 = 500;
+
+/// Upper bound on the symbols fetched from rust-analyzer before filtering
+const MAX_SYMBOL_CANDIDATES: usize = 10_000;
+
 /// Main interface to rust-analyzer functionality
 ///
 /// This struct provides semantic analysis capabilities for Rust code, including:
@@ -42,6 +75,10 @@ use super::utils::RustAnalyzerUtils;
 pub struct RustAnalyzerish {
     host: AnalysisHost,
     file_watcher: FileWatcher,
+    caches_primed: bool,
+    config: AnalyzerConfig,
+    /// Public API index and the file watcher revision it was built at
+    public_api: Option<(u64, PublicApi)>,
 }
 
 impl RustAnalyzerish {
@@ -49,7 +86,80 @@ impl RustAnalyzerish {
     ///
     /// This is called by RustAnalyzerishBuilder after workspace loading.
     pub fn new(host: AnalysisHost, file_watcher: FileWatcher) -> Self {
-        Self { host, file_watcher }
+        Self {
+            host,
+            file_watcher,
+            caches_primed: false,
+            config: AnalyzerConfig::default(),
+            public_api: None,
+        }
+    }
+
+    /// Override the hover, completion and inlay hint settings
+    pub fn set_config(&mut self, config: AnalyzerConfig) {
+        self.config = config;
+    }
+
+    /// The hover, completion and inlay hint settings in use
+    pub fn config(&self) -> &AnalyzerConfig {
+        &self.config
+    }
+
+    /// Record whether caches were primed while loading the workspace
+    pub(crate) fn set_caches_primed(&mut self, caches_primed: bool) {
+        self.caches_primed = caches_primed;
+    }
+
+    /// Readiness of the analyzer: workspace root, file counts, cache priming
+    ///
+    /// Cheap to call, as it doesn't apply pending file changes or run queries.
+    pub fn status(&self) -> AnalyzerStatus {
+        AnalyzerStatus {
+            project_root: self
+                .file_watcher
+                .project_root()
+                .map(|root| root.to_string()),
+            workspace_file_count: self.file_watcher.workspace_files().len(),
+            vfs_file_count: self.file_watcher.vfs().iter().count(),
+            caches_primed: self.caches_primed,
+        }
+    }
+
+    /// Unload the workspace, freeing its memory and stopping the file watcher
+    ///
+    /// The analyzer is left empty, so queries fail until it's replaced by one
+    /// built for another workspace. Returns the root of the unloaded workspace.
+    pub fn unload_workspace(&mut self) -> Option<String> {
+        let project_root = self
+            .file_watcher
+            .project_root()
+            .map(|root| root.to_string());
+
+        // Dropping the file watcher stops its VFS loader thread
+        self.file_watcher = FileWatcher::new();
+        self.host = AnalysisHost::default();
+        self.caches_primed = false;
+        self.public_api = None;
+
+        project_root
+    }
+
+    /// Paths of the Rust source files that belong to the workspace, sorted
+    pub fn workspace_file_paths(&self) -> Vec<String> {
+        self.file_watcher
+            .workspace_files()
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect()
+    }
+
+    /// Apply pending file changes and return the file watcher revision
+    ///
+    /// The revision increases each time changes are applied, so callers
+    /// polling for edits can tell whether anything changed since their last call.
+    pub fn apply_file_changes(&mut self) -> Result<u64> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+        Ok(self.file_watcher.revision())
     }
 
     /// Debug information about the current cursor position
@@ -121,23 +231,19 @@ impl RustAnalyzerish {
     ) -> Result<TextSize> {
         // Validate coordinates before proceeding
         if cursor.line == 0 || cursor.column == 0 {
-            return Err(anyhow::anyhow!(
+            return Err(ErrorKind::InvalidPosition.error(format!(
                 "Invalid coordinates in file '{}': line and column must be >= 1, got {}:{}",
-                cursor.file_path,
-                cursor.line,
-                cursor.column
-            ));
+                cursor.file_path, cursor.line, cursor.column
+            )));
         }
 
         // Convert line/column to text offset from 1-based to 0-based indexing
         let line_col: LineCol = cursor.into();
         line_index.offset(line_col).ok_or_else(|| {
-            anyhow::anyhow!(
+            ErrorKind::InvalidPosition.error(format!(
                 "Coordinates out of bounds in file '{}': {}:{} (file may have changed)",
-                cursor.file_path,
-                cursor.line,
-                cursor.column
-            )
+                cursor.file_path, cursor.line, cursor.column
+            ))
         })
     }
 
@@ -148,6 +254,8 @@ impl RustAnalyzerish {
         &mut self,
         raw_cursor: &CursorCoordinates,
     ) -> Result<(Analysis, FileId, TextSize, CursorCoordinates)> {
+        let raw_cursor = &self.resolve_cursor(raw_cursor);
+
         // Ensure file watcher changes are applied
         self.file_watcher.drain_and_apply_changes(&mut self.host)?;
 
@@ -159,9 +267,10 @@ impl RustAnalyzerish {
         // Resolve coordinates if a symbol is provided
         let resolved_cursor = if raw_cursor.symbol.is_some() {
             // Get file content for symbol resolution
-            let file_content = std::fs::read_to_string(&raw_cursor.file_path)
-                .map_err(|e| anyhow::anyhow!("Failed to read file content: {}", e))?;
-            raw_cursor.resolve_coordinates(&file_content)
+            let file_content = analysis.file_text(file_id).map_err(|_| {
+                anyhow::anyhow!("Failed to get file content for: {}", raw_cursor.file_path)
+            })?;
+            raw_cursor.resolve_coordinates(RustAnalyzerUtils::strip_bom(&file_content))
         } else {
             raw_cursor.clone()
         };
@@ -196,33 +305,92 @@ impl RustAnalyzerish {
         Ok((analysis, file_id, offset, resolved_cursor))
     }
 
+    /// Resolve a file path relative to the workspace root
+    ///
+    /// Lets every tool accept paths such as `src/main.rs` once a workspace is
+    /// loaded. Absolute paths are returned unchanged, and relative paths that
+    /// don't exist under the root are left relative to the current directory.
+    pub fn resolve_path(&self, file_path: &str) -> String {
+        self.file_watcher
+            .resolve_path(Path::new(file_path))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// The cursor with its file path resolved, see [`Self::resolve_path`]
+    fn resolve_cursor(&self, cursor: &CursorCoordinates) -> CursorCoordinates {
+        CursorCoordinates {
+            file_path: self.resolve_path(&cursor.file_path),
+            ..cursor.clone()
+        }
+    }
+
     /// Create a FilePosition from file_id and offset
     fn create_file_position(file_id: FileId, offset: TextSize) -> FilePosition {
         FilePosition { file_id, offset }
     }
 
+    /// Whether nothing touching `offset` can have a definition
+    ///
+    /// Holds for whitespace, comment markers such as `///` and positions in an
+    /// empty file. The text of doc comments is navigable, for intra-doc links.
+    fn is_unnavigable_offset(
+        analysis: &Analysis,
+        file_id: FileId,
+        offset: TextSize,
+    ) -> Result<bool> {
+        let source_file = analysis
+            .parse(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to parse file {:?}", file_id))?;
+        Ok(source_file.syntax().token_at_offset(offset).all(|token| {
+            token.kind() == SyntaxKind::WHITESPACE
+                || ast::Comment::cast(token.clone()).is_some_and(|comment| {
+                    offset - token.text_range().start() <= TextSize::of(comment.prefix())
+                })
+        }))
+    }
+
     /// Get type hint information at the specified cursor position
     pub async fn get_type_hint(
         &mut self,
         raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<TypeHint>> {
+        self.get_type_hint_with_options(raw_cursor, &TypeHintOptions::default())
+            .await
+    }
+
+    /// Get type information, optionally with memory layout and drop glue details
+    ///
+    /// See [`TypeHintOptions`] for the extra information that can be requested.
+    pub async fn get_type_hint_with_options(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        options: &TypeHintOptions,
     ) -> Result<Option<TypeHint>> {
         let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
 
         // Create TextRange for the hover query - use a single point range
         let text_range = TextRange::new(offset, offset);
 
+        let settings = &self.config.hover;
         let hover_config = HoverConfig {
-            links_in_hover: true,
-            memory_layout: None,
-            documentation: true,
-            keywords: true,
+            links_in_hover: settings.links_in_hover,
+            memory_layout: options.include_layout.then_some(MemoryLayoutHoverConfig {
+                size: Some(MemoryLayoutHoverRenderKind::Decimal),
+                offset: Some(MemoryLayoutHoverRenderKind::Decimal),
+                alignment: Some(MemoryLayoutHoverRenderKind::Decimal),
+                padding: Some(MemoryLayoutHoverRenderKind::Decimal),
+                niches: true,
+            }),
+            documentation: settings.documentation,
+            keywords: settings.keywords,
             // TODO Consider using Markdown but figure out how to reliably show symbol names too
             format: HoverDocFormat::PlainText,
-            max_trait_assoc_items_count: Some(10),
-            max_fields_count: Some(10),
-            max_enum_variants_count: Some(10),
+            max_trait_assoc_items_count: settings.max_trait_assoc_items_count,
+            max_fields_count: settings.max_fields_count,
+            max_enum_variants_count: settings.max_enum_variants_count,
             max_subst_ty_len: SubstTyLen::Unlimited,
-            show_drop_glue: false,
+            show_drop_glue: options.show_drop_glue,
             minicore: MiniCore::default(),
         };
 
@@ -257,13 +425,16 @@ impl RustAnalyzerish {
             "Hover result for {}:{}:{}: {:?}",
             cursor.file_path, cursor.line, cursor.column, hover_result
         );
-        // Get the type information from hover
+        // Get the type information from hover, keeping the first occurrence of
+        // types mentioned several times, e.g. in `(Person, Person)`
         let mut canonical_types: Vec<String> = Vec::new();
         for action in hover_result.info.actions {
             match action {
                 ra_ap_ide::HoverAction::GoToType(type_actions) => {
                     for type_action in type_actions {
-                        canonical_types.push(type_action.mod_path);
+                        if !canonical_types.contains(&type_action.mod_path) {
+                            canonical_types.push(type_action.mod_path);
+                        }
                     }
                 }
                 _ => debug!("Unhandled hover action: {:?}", action),
@@ -275,11 +446,13 @@ impl RustAnalyzerish {
             cursor.file_path, cursor.line, cursor.column
         );
 
+        let symbol = hover_result.info.markup.to_string();
         let type_hint = TypeHint {
             file_path: cursor.file_path.clone(),
             line: cursor.line,
             column: cursor.column,
-            symbol: hover_result.info.markup.to_string(),
+            fully_resolved: RustAnalyzerUtils::is_fully_resolved(&symbol),
+            symbol,
             canonical_types,
         };
 
@@ -290,6 +463,28 @@ impl RustAnalyzerish {
     pub async fn get_completions(
         &mut self,
         raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<Vec<CompletionItem>>> {
+        self.get_completions_with_options(raw_cursor, &CompletionOptions::default())
+            .await
+    }
+
+    /// Get completion suggestions, optionally with import edits and snippets
+    ///
+    /// With `include_import_edits`, items that aren't in scope yet are
+    /// completed too, and each import they need comes with the exact edit
+    /// inserting its `use`, ready for [`RustAnalyzerUtils::apply_file_change`].
+    /// With `include_snippets`, completions such as function calls also come
+    /// with a snippet body containing tabstops like `$0` and `${1:arg}`.
+    ///
+    /// Completions are sorted by relevance, most relevant first, before being
+    /// truncated to the limit, unless `unsorted` is set. Deprecated and
+    /// unstable items are dropped before truncating when `exclude_deprecated`
+    /// or `exclude_unstable` are set, as are items needing an import with
+    /// `only_in_scope`.
+    pub async fn get_completions_with_options(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        options: &CompletionOptions,
     ) -> Result<Option<Vec<CompletionItem>>> {
         let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
 
@@ -300,31 +495,32 @@ impl RustAnalyzerish {
 
         let position = Self::create_file_position(file_id, offset);
 
+        // rust-analyzer stops at its limit in its own order, so only pass the
+        // limit down when its items are returned as they are
+        let filtered =
+            options.exclude_deprecated || options.exclude_unstable || options.only_in_scope;
+        let ra_limit = (options.unsorted && !filtered).then(|| options.effective_limit());
+
+        let settings = &self.config.completion;
         let config = CompletionConfig {
-            enable_postfix_completions: true,
-            enable_imports_on_the_fly: false, // Keep simple for now
-            enable_self_on_the_fly: false,
-            enable_auto_iter: true,
-            enable_auto_await: true,
-            enable_private_editable: false,
-            enable_term_search: false,
-            term_search_fuel: 400,
-            full_function_signatures: false,
+            enable_postfix_completions: settings.enable_postfix_completions,
+            enable_imports_on_the_fly: options.include_import_edits,
+            enable_self_on_the_fly: settings.enable_self_on_the_fly,
+            enable_auto_iter: settings.enable_auto_iter,
+            enable_auto_await: settings.enable_auto_await,
+            enable_private_editable: settings.enable_private_editable,
+            enable_term_search: settings.enable_term_search,
+            term_search_fuel: settings.term_search_fuel,
+            full_function_signatures: settings.full_function_signatures,
             callable: Some(CallableSnippets::FillArguments),
-            add_semicolon_to_unit: false,
-            snippet_cap: None, // Disable snippets for simplicity
-            insert_use: InsertUseConfig {
-                granularity: ImportGranularity::Crate,
-                enforce_granularity: true,
-                prefix_kind: PrefixKind::Plain,
-                group: true,
-                skip_glob_imports: true,
-            },
-            prefer_no_std: false,
-            prefer_prelude: true,
-            prefer_absolute: false,
+            add_semicolon_to_unit: settings.add_semicolon_to_unit,
+            snippet_cap: SnippetCap::new(options.include_snippets),
+            insert_use: Self::insert_use_config(),
+            prefer_no_std: settings.prefer_no_std,
+            prefer_prelude: settings.prefer_prelude,
+            prefer_absolute: settings.prefer_absolute,
             snippets: vec![],
-            limit: Some(200), // Limit results for performance
+            limit: ra_limit,
             fields_to_resolve: CompletionFieldsToResolve::empty(),
             exclude_flyimport: vec![],
             exclude_traits: &[],
@@ -332,7 +528,24 @@ impl RustAnalyzerish {
         };
 
         match analysis.completions(&config, position, Some('.')) {
-            Ok(Some(ra_completions)) => {
+            Ok(Some(mut ra_completions)) => {
+                let unstable = if options.exclude_unstable {
+                    self.unstable_names_in_scope(file_id, offset)
+                } else {
+                    HashSet::new()
+                };
+                ra_completions.retain(|item| {
+                    !(options.exclude_deprecated && item.deprecated)
+                        && !(options.only_in_scope && !item.import_to_add.is_empty())
+                        && !unstable.contains(item.lookup())
+                });
+
+                if !options.unsorted {
+                    // Stable, so equally relevant items keep rust-analyzer's order
+                    ra_completions.sort_by_key(|item| std::cmp::Reverse(item.relevance.score()));
+                }
+                ra_completions.truncate(options.effective_limit());
+
                 let mut completions = Vec::new();
 
                 for completion_item in ra_completions {
@@ -364,13 +577,37 @@ impl RustAnalyzerish {
                         Some(completion_item.import_to_add.join(", "))
                     };
 
+                    let imports = if options.include_import_edits {
+                        self.completion_imports(
+                            &analysis,
+                            &config,
+                            position,
+                            &completion_item.import_to_add,
+                        )?
+                    } else {
+                        Vec::new()
+                    };
+
+                    let snippet = completion_item.is_snippet.then(|| {
+                        completion_item
+                            .text_edit
+                            .iter()
+                            .map(|indel| indel.insert.as_str())
+                            .collect::<String>()
+                    });
+
                     let completion = CompletionItem {
                         name,
                         required_import,
+                        imports,
+                        snippet,
                         kind,
                         signature: completion_item.detail,
                         documentation,
                         deprecated: completion_item.deprecated,
+                        relevance: options
+                            .include_scores
+                            .then(|| completion_item.relevance.score()),
                     };
 
                     completions.push(completion);
@@ -405,6 +642,162 @@ impl RustAnalyzerish {
         &mut self,
         raw_cursor: &CursorCoordinates,
     ) -> Result<Option<Vec<DefinitionInfo>>> {
+        self.get_definition_with_container(raw_cursor, false).await
+    }
+
+    /// Get definition information, optionally with the enclosing item's header
+    ///
+    /// With `include_container`, each definition's `container` is set to the
+    /// header of the `impl`, `trait`, struct, enum or union it's declared in, so
+    /// the self type of a method or the type owning a field is known.
+    pub async fn get_definition_with_container(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        include_container: bool,
+    ) -> Result<Option<Vec<DefinitionInfo>>> {
+        self.get_definition_with_options(
+            raw_cursor,
+            &DefinitionOptions {
+                include_container,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Get definition information, as configured by `options`
+    ///
+    /// See [`Self::get_definition_with_container`] for `include_container`.
+    /// With `follow_reexports`, a definition landing in a `use` item, such as
+    /// the alias of `pub use inner::Foo as Bar`, is followed to the original
+    /// item. The re-export is still returned, marked with `is_reexport`, just
+    /// before the definitions it leads to.
+    ///
+    /// With `fuzzy`, a position without definitions, such as whitespace or a
+    /// comment, is retried on the identifiers of its line, nearest first, until
+    /// one has definitions.
+    ///
+    /// Definitions outside of the workspace are marked with `is_external`. As
+    /// their files, e.g. in the cargo registry, may not be readable by the
+    /// caller, `include_external_source` returns the lines of each one's item
+    /// with `EXTERNAL_SOURCE_CONTEXT_LINES` around them, at most
+    /// `MAX_EXTERNAL_SOURCE_LINES` of them.
+    pub async fn get_definition_with_options(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        options: &DefinitionOptions,
+    ) -> Result<Option<Vec<DefinitionInfo>>> {
+        Ok(self
+            .get_definition_report(raw_cursor, options)
+            .await?
+            .definitions)
+    }
+
+    /// Get definition information like [`Self::get_definition_with_options`],
+    /// with a note when rust-analyzer failed to answer
+    ///
+    /// A rust-analyzer panic while resolving the position isn't an error, as
+    /// other positions are unaffected, but is reported in `note` so missing
+    /// definitions aren't mistaken for there being none.
+    pub async fn get_definition_report(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        options: &DefinitionOptions,
+    ) -> Result<DefinitionReport> {
+        let report = self.definitions_at(raw_cursor, options).await?;
+        let has_definitions = |report: &DefinitionReport| {
+            report
+                .definitions
+                .as_ref()
+                .is_some_and(|found| !found.is_empty())
+        };
+        if !options.fuzzy || has_definitions(&report) {
+            return Ok(report);
+        }
+
+        let cursor = self.resolve_cursor(raw_cursor);
+        for symbol in self.line_identifiers(&cursor)? {
+            // The symbol is placed like any cursor `symbol`, at its occurrence
+            // nearest to the column
+            let candidate = CursorCoordinates {
+                symbol: Some(symbol),
+                ..cursor.clone()
+            };
+            let found = self.definitions_at(&candidate, options).await?;
+            if has_definitions(&found) {
+                debug!(
+                    "No definition at {}:{}, using the identifier {:?}",
+                    cursor.line, cursor.column, candidate.symbol
+                );
+                return Ok(found);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Distinct identifiers on the cursor's line, nearest to its column first
+    ///
+    /// Identifiers are taken from the syntax tree, so the text of comments and
+    /// string literals is skipped.
+    fn line_identifiers(&self, cursor: &CursorCoordinates) -> Result<Vec<String>> {
+        let analysis = self.host.analysis();
+        let file_id = self
+            .file_watcher
+            .get_file_id(Path::new(&cursor.file_path))?;
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index for: {}", cursor.file_path))?;
+        let source_file = analysis
+            .parse(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to parse file: {}", cursor.file_path))?;
+        let line = cursor.line.saturating_sub(1);
+        let Some(line_start) = line_index.offset(LineCol { line, col: 0 }) else {
+            return Ok(Vec::new());
+        };
+        let line_end = line_index
+            .offset(LineCol {
+                line: line + 1,
+                col: 0,
+            })
+            .unwrap_or_else(|| source_file.syntax().text_range().end());
+        let line_range = TextRange::new(line_start, line_end);
+        let column = line_index
+            .offset(LineCol::from(cursor))
+            .unwrap_or(line_start);
+
+        let mut identifiers: Vec<SyntaxToken> = source_file
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|element| element.into_token())
+            .filter(|token| {
+                token.kind() == SyntaxKind::IDENT && line_range.contains_range(token.text_range())
+            })
+            .collect();
+        identifiers.sort_by_key(|token| {
+            let range = token.text_range();
+            if column < range.start() {
+                range.start() - column
+            } else {
+                (column + TextSize::from(1)).max(range.end()) - range.end()
+            }
+        });
+
+        let mut seen = HashSet::new();
+        Ok(identifiers
+            .into_iter()
+            .map(|token| token.text().to_string())
+            .filter(|text| seen.insert(text.clone()))
+            .collect())
+    }
+
+    /// Definitions at exactly the cursor position, without the fuzzy fallback
+    async fn definitions_at(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        options: &DefinitionOptions,
+    ) -> Result<DefinitionReport> {
+        let include_container = options.include_container;
         let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
 
         debug!(
@@ -412,10 +805,19 @@ impl RustAnalyzerish {
             file_id, offset, cursor.line, cursor.column
         );
 
+        // Querying at line 1 column 1 before a doc comment is known to panic
+        // in rust-analyzer, so positions with nothing to navigate to are
+        // answered upfront
+        if Self::is_unnavigable_offset(&analysis, file_id, offset)? {
+            debug!(
+                "Nothing to navigate to at {}:{}:{}",
+                cursor.file_path, cursor.line, cursor.column
+            );
+            return Ok(DefinitionReport::default());
+        }
+
         // Query for definitions
-        // Use std::panic::catch_unwind to handle potential panics in rust-analyzer
-        // Happens when we query colum: 1 row: 1
-        // TODO Report bug
+        // Use std::panic::catch_unwind to handle other panics in rust-analyzer
         let goto_config = GotoDefinitionConfig { minicore: MiniCore::default() };
         let goto_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
             analysis.goto_definition(Self::create_file_position(file_id, offset), &goto_config)
@@ -423,12 +825,19 @@ impl RustAnalyzerish {
 
         let definitions_result = match goto_result {
             Ok(result) => result,
-            Err(_panic) => {
-                debug!(
-                    "Caught panic during goto_definition for {}:{}:{}, likely due to edge case in rust-analyzer",
-                    cursor.file_path, cursor.line, cursor.column
+            Err(panic) => {
+                // Not a missing definition, so leave a trace worth reporting upstream
+                let message = RustAnalyzerUtils::panic_message(panic.as_ref());
+                warn!(
+                    "Caught panic during goto_definition for {}:{}:{}, likely due to edge case in rust-analyzer: {}",
+                    cursor.file_path, cursor.line, cursor.column, message
                 );
-                return Ok(None);
+                return Ok(DefinitionReport {
+                    definitions: None,
+                    note: Some(format!(
+                        "rust-analyzer failed while resolving this position, so definitions may exist anyway: {message}"
+                    )),
+                });
             }
         };
 
@@ -436,100 +845,31 @@ impl RustAnalyzerish {
             Ok(Some(range_info)) => {
                 let mut definitions = Vec::new();
 
-                for nav in range_info.info {
+                let mut navs = range_info.info;
+                if options.follow_reexports {
+                    navs = self.follow_reexports(&analysis, &goto_config, navs)?;
+                }
+                for nav in navs {
                     debug!("Navigation target: {:?}", nav);
-                    // Get file path from file_id
-                    if let Ok(line_index) = analysis.file_line_index(nav.file_id) {
-                        let start_line_col = line_index.line_col(nav.focus_or_full_range().start());
-                        let end_line_col = line_index.line_col(nav.focus_or_full_range().end());
-
-                        let file_path = {
-                            if let Some(path) = self.file_watcher.file_path(nav.file_id) {
-                                path
-                            } else {
-                                return Err(anyhow::anyhow!(
-                                    "File ID {:?} not found in VFS",
-                                    &nav.file_id
-                                ));
-                            }
-                        };
-
-                        // Get module path using moniker if available
-                        let module = if let Ok(Some(moniker_info)) =
-                            analysis.moniker(FilePosition {
-                                file_id: nav.file_id,
-                                offset: nav.focus_or_full_range().start(),
-                            }) {
-                            // Extract module path from moniker
-                            match &moniker_info.info.first() {
-                                Some(MonikerResult::Moniker(moniker)) => {
-                                    // Build full module path from crate name and description
-                                    let crate_name = &moniker.identifier.crate_name;
-                                    let module_parts: Vec<String> = moniker
-                                        .identifier
-                                        .description
-                                        .iter()
-                                        .map(|desc| desc.name.to_string())
-                                        .collect();
-
-                                    if module_parts.is_empty() {
-                                        crate_name.clone()
-                                    } else {
-                                        format!("{}::{}", crate_name, module_parts.join("::"))
-                                    }
-                                }
-                                Some(MonikerResult::Local { .. }) => {
-                                    // For local symbols, fall back to container name
-                                    nav.container_name
-                                        .as_ref()
-                                        .map(|name| name.to_string())
-                                        .unwrap_or_else(|| "local".to_string())
-                                }
-                                None => {
-                                    // Fall back to container name
-                                    nav.container_name
-                                        .as_ref()
-                                        .map(|name| name.to_string())
-                                        .unwrap_or_else(|| "unknown".to_string())
-                                }
-                            }
-                        } else {
-                            // Fall back to container name if moniker fails
-                            nav.container_name
-                                .as_ref()
-                                .map(|name| name.to_string())
-                                .unwrap_or_else(|| "unknown".to_string())
-                        };
-
-                        // Extract definition content from source
-                        let content = if let Ok(source_text) = analysis.file_text(nav.file_id) {
-                            let full_range = nav.full_range;
-                            let start_offset = full_range.start().into();
-                            let end_offset = full_range.end().into();
-
-                            if start_offset < source_text.len() && end_offset <= source_text.len() {
-                                source_text[start_offset..end_offset].to_string()
-                            } else {
-                                format!(
-                                    "// Content extraction failed: invalid range {start_offset}..{end_offset}"
-                                )
-                            }
-                        } else {
-                            "// Content extraction failed: could not read source".to_string()
-                        };
-
-                        let definition = DefinitionInfo {
-                            file_path,
-                            line: start_line_col.line + 1, // Convert back to 1-based
-                            column: start_line_col.col + 1, // Convert back to 1-based
-                            end_line: end_line_col.line + 1,
-                            end_column: end_line_col.col + 1,
-                            name: nav.name.to_string(),
-                            kind: nav.kind,
-                            description: nav.description.clone(),
-                            module,
-                            content,
-                        };
+                    if let Some(mut definition) =
+                        self.navigation_target_to_definition(&analysis, &nav)?
+                    {
+                        if include_container {
+                            definition.container = Self::enclosing_container(&analysis, &nav);
+                        }
+                        if options.follow_reexports {
+                            definition.is_reexport = self.reexport_use_tree(&nav).is_some();
+                        }
+                        if let Some(signature) = options
+                            .signature_only
+                            .then(|| Self::item_signature(&analysis, &nav))
+                            .flatten()
+                        {
+                            definition.content = signature;
+                        }
+                        if options.include_external_source && definition.is_external {
+                            definition.source = Self::source_excerpt(&analysis, &nav);
+                        }
                         debug!("Found definition: {:?}", definition);
                         definitions.push(definition);
                     }
@@ -542,14 +882,17 @@ impl RustAnalyzerish {
                     cursor.line,
                     cursor.column
                 );
-                Ok(Some(definitions))
+                Ok(DefinitionReport {
+                    definitions: Some(definitions),
+                    note: None,
+                })
             }
             Ok(None) => {
                 debug!(
                     "No definitions available for {}:{}:{}",
                     cursor.file_path, cursor.line, cursor.column
                 );
-                Ok(None)
+                Ok(DefinitionReport::default())
             }
             Err(e) => {
                 warn!("Goto definition analysis failed: {:?}", e);
@@ -558,574 +901,3963 @@ impl RustAnalyzerish {
         }
     }
 
-    /// Rename a symbol at the specified cursor position and apply the changes
-    /// to disk
-    pub async fn rename_symbol(
-        &mut self,
-        raw_cursor: &CursorCoordinates,
-        new_name: &str,
-    ) -> Result<Option<RenameResult>> {
-        // Get the rename information
-        let rename_result = self.get_rename_info(raw_cursor, new_name).await?;
+    /// Follow navigation targets landing in a `use` item to what it imports
+    ///
+    /// Each re-export is kept and directly followed by its targets, chasing
+    /// chains of re-exports up to a fixed depth to be safe from cycles.
+    fn follow_reexports(
+        &self,
+        analysis: &Analysis,
+        goto_config: &GotoDefinitionConfig,
+        navs: Vec<NavigationTarget>,
+    ) -> Result<Vec<NavigationTarget>> {
+        const MAX_REEXPORT_DEPTH: usize = 8;
+
+        let mut followed = Vec::new();
+        let mut pending: Vec<(NavigationTarget, usize)> =
+            navs.into_iter().rev().map(|nav| (nav, 0)).collect();
+        while let Some((nav, depth)) = pending.pop() {
+            let imported = match self.reexport_use_tree(&nav) {
+                Some(use_tree) if depth < MAX_REEXPORT_DEPTH => use_tree
+                    .path()
+                    .and_then(|path| path.segment())
+                    .and_then(|segment| segment.name_ref()),
+                _ => None,
+            };
+            let targets = match imported {
+                Some(name_ref) => analysis
+                    .goto_definition(
+                        Self::create_file_position(
+                            nav.file_id,
+                            name_ref.syntax().text_range().start(),
+                        ),
+                        goto_config,
+                    )
+                    .map_err(|e| anyhow::anyhow!("Goto definition analysis failed: {:?}", e))?
+                    .map(|range_info| range_info.info)
+                    .unwrap_or_default(),
+                None => Vec::new(),
+            };
 
-        if let Some(ref result) = rename_result {
-            // Apply the edits to disk
-            RustAnalyzerUtils::apply_rename_edits(result).await?;
+            if !targets.is_empty() {
+                debug!(
+                    "Following re-export {} to {} target(s)",
+                    nav.name,
+                    targets.len()
+                );
+            }
+            followed.push(nav);
+            pending.extend(targets.into_iter().rev().map(|nav| (nav, depth + 1)));
         }
+        Ok(followed)
+    }
 
-        Ok(rename_result)
+    /// The `use` tree a navigation target lands in, e.g. for an import alias
+    fn reexport_use_tree(&self, nav: &NavigationTarget) -> Option<ast::UseTree> {
+        let sema = Semantics::new(self.host.raw_database());
+        let source_file = sema.parse_guess_edition(nav.file_id);
+        source_file
+            .syntax()
+            .token_at_offset(nav.focus_or_full_range().start())
+            .right_biased()?
+            .parent_ancestors()
+            .find_map(ast::UseTree::cast)
     }
 
-    /// Find all references to a symbol at the specified cursor position
-    pub async fn find_references(
+    /// Get the fully qualified path of the symbol at a position, e.g.
+    /// `std::collections::HashMap`
+    ///
+    /// A lightweight alternative to [`Self::get_definition`] that only looks up
+    /// the symbol, without navigating to or extracting the definition. The
+    /// path is the one to import the item with from the cursor's file, so
+    /// public re-exports such as `std::collections::HashMap` are preferred and
+    /// items of the same crate start with `crate::`. An item that can't be
+    /// imported there, e.g. a private one, gets the path where it's defined,
+    /// starting with its crate name. Returns `None` for locals and positions
+    /// without a symbol.
+    pub async fn get_qualified_path(
         &mut self,
         raw_cursor: &CursorCoordinates,
-    ) -> Result<Option<Vec<ReferenceInfo>>> {
-        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+    ) -> Result<Option<String>> {
+        let (analysis, file_id, offset, _cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source_file = sema.parse_guess_edition(file_id);
+        let module_def = source_file
+            .syntax()
+            .token_at_offset(offset)
+            .find_map(|token| Self::token_definition(&sema, &token))
+            .and_then(Self::definition_module_def);
+        let import_path =
+            module_def
+                .zip(sema.file_to_module_def(file_id))
+                .and_then(|(module_def, from)| {
+                    let find_path_config = Self::assist_config().find_path_config(true);
+                    from.find_use_path(db, module_def, PrefixKind::ByCrate, find_path_config)
+                        .map(|path| path.display(db, from.krate().edition(db)).to_string())
+                });
+        if import_path.is_some() {
+            return Ok(import_path);
+        }
 
-        debug!(
-            "Attempting find_all_refs query for file {:?} at offset {:?} (line {} col {})",
-            file_id, offset, cursor.line, cursor.column
-        );
+        let monikers = analysis
+            .moniker(Self::create_file_position(file_id, offset))
+            .map_err(|e| anyhow::anyhow!("Moniker analysis failed: {:?}", e))?;
+        Ok(monikers.and_then(|monikers| monikers.info.iter().find_map(Self::moniker_path)))
+    }
 
-        // Query for all references
-        let find_refs_config = FindAllRefsConfig {
-            search_scope: None,
-            minicore: MiniCore::default(),
+    /// Full path of a moniker, from its crate name and description, or `None`
+    /// for locals
+    fn moniker_path(moniker: &MonikerResult) -> Option<String> {
+        let MonikerResult::Moniker(moniker) = moniker else {
+            return None;
         };
-        let references_result =
-            match analysis.find_all_refs(Self::create_file_position(file_id, offset), &find_refs_config) {
-                Ok(Some(search_results)) => search_results,
-                Ok(None) => {
-                    debug!("No references found at position");
-                    return Ok(None);
+        let crate_name = &moniker.identifier.crate_name;
+        let module_parts: Vec<String> = moniker
+            .identifier
+            .description
+            .iter()
+            .map(|desc| desc.name.to_string())
+            .collect();
+
+        if module_parts.is_empty() {
+            Some(crate_name.clone())
+        } else {
+            Some(format!("{}::{}", crate_name, module_parts.join("::")))
+        }
+    }
+
+    /// Convert a rust-analyzer navigation target into a DefinitionInfo
+    ///
+    /// Returns `Ok(None)` when the target file has no line index available and
+    /// an error when the file is missing from the VFS.
+    fn navigation_target_to_definition(
+        &self,
+        analysis: &Analysis,
+        nav: &NavigationTarget,
+    ) -> Result<Option<DefinitionInfo>> {
+        let Ok(line_index) = analysis.file_line_index(nav.file_id) else {
+            return Ok(None);
+        };
+        let start_line_col = line_index.line_col(nav.focus_or_full_range().start());
+        let end_line_col = line_index.line_col(nav.focus_or_full_range().end());
+
+        let file_path = {
+            if let Some(path) = self.file_watcher.file_path(nav.file_id) {
+                path
+            } else {
+                return Err(ErrorKind::FileNotFound
+                    .error(format!("File ID {:?} not found in VFS", &nav.file_id)));
+            }
+        };
+
+        // Get module path using moniker if available
+        let module = if let Ok(Some(moniker_info)) = analysis.moniker(FilePosition {
+            file_id: nav.file_id,
+            offset: nav.focus_or_full_range().start(),
+        }) {
+            // Extract module path from moniker
+            match &moniker_info.info.first() {
+                Some(moniker @ MonikerResult::Moniker(_)) => {
+                    Self::moniker_path(moniker).unwrap_or_default()
                 }
-                Err(e) => {
-                    debug!("Error finding references: {}", e);
-                    return Err(anyhow::anyhow!("Failed to find references: {}", e));
+                Some(MonikerResult::Local { .. }) => {
+                    // For local symbols, fall back to container name
+                    nav.container_name
+                        .as_ref()
+                        .map(|name| name.to_string())
+                        .unwrap_or_else(|| "local".to_string())
                 }
-            };
+                None => {
+                    // Fall back to container name
+                    nav.container_name
+                        .as_ref()
+                        .map(|name| name.to_string())
+                        .unwrap_or_else(|| "unknown".to_string())
+                }
+            }
+        } else {
+            // Fall back to container name if moniker fails
+            nav.container_name
+                .as_ref()
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| "unknown".to_string())
+        };
 
-        let mut references = Vec::new();
+        // Extract definition content from source
+        let content = if let Ok(source_text) = analysis.file_text(nav.file_id) {
+            let full_range = nav.full_range;
+            let start_offset = full_range.start().into();
+            let end_offset = full_range.end().into();
 
-        for search_result in references_result {
-            // Add the declaration (definition) if it exists
-            if let Some(declaration) = &search_result.declaration {
-                if let Ok(decl_line_index) = analysis.file_line_index(declaration.nav.file_id) {
-                    let decl_range = declaration.nav.focus_or_full_range();
-                    let start_line_col = decl_line_index.line_col(decl_range.start());
-                    let end_line_col = decl_line_index.line_col(decl_range.end());
+            if start_offset < source_text.len() && end_offset <= source_text.len() {
+                source_text[start_offset..end_offset].to_string()
+            } else {
+                format!("// Content extraction failed: invalid range {start_offset}..{end_offset}")
+            }
+        } else {
+            "// Content extraction failed: could not read source".to_string()
+        };
 
-                    if let Some(decl_file_path) =
-                        self.file_watcher.file_path(declaration.nav.file_id)
-                    {
-                        // Get the line content containing the declaration
-                        let content =
-                            if let Ok(file_text) = analysis.file_text(declaration.nav.file_id) {
-                                Self::get_line_content(&file_text, start_line_col.line as usize)
-                            } else {
-                                "".to_string()
-                            };
+        Ok(Some(DefinitionInfo {
+            file_path,
+            line: start_line_col.line + 1,  // Convert back to 1-based
+            column: start_line_col.col + 1, // Convert back to 1-based
+            end_line: end_line_col.line + 1,
+            end_column: end_line_col.col + 1,
+            name: nav.name.to_string(),
+            kind: nav.kind,
+            description: nav.description.clone(),
+            module,
+            content,
+            container: None,
+            is_reexport: false,
+            is_external: !self.file_watcher.is_workspace_file(nav.file_id),
+            source: None,
+        }))
+    }
 
-                        references.push(ReferenceInfo {
-                            file_path: decl_file_path,
-                            line: start_line_col.line + 1,
-                            column: start_line_col.col + 1,
-                            end_line: end_line_col.line + 1,
-                            end_column: end_line_col.col + 1,
-                            name: declaration.nav.name.to_string(),
-                            content,
-                            is_definition: true,
-                        });
-                    }
-                }
-            }
+    /// Lines of the item of a navigation target, with context around them
+    ///
+    /// The window is cut at `MAX_EXTERNAL_SOURCE_LINES`, marking the excerpt
+    /// truncated, so a huge item doesn't inline a whole file.
+    fn source_excerpt(analysis: &Analysis, nav: &NavigationTarget) -> Option<SourceExcerpt> {
+        let source_text = analysis.file_text(nav.file_id).ok()?;
+        let line_index = analysis.file_line_index(nav.file_id).ok()?;
+        let last_line = line_index.line_col(TextSize::of(&*source_text)).line;
+        let item_end_line = line_index.line_col(nav.full_range.end()).line;
+        let start_line = line_index
+            .line_col(nav.full_range.start())
+            .line
+            .saturating_sub(EXTERNAL_SOURCE_CONTEXT_LINES);
+        let end_line = (item_end_line + EXTERNAL_SOURCE_CONTEXT_LINES)
+            .min(last_line)
+            .min(start_line + MAX_EXTERNAL_SOURCE_LINES - 1);
+
+        let text = source_text
+            .lines()
+            .skip(start_line as usize)
+            .take((end_line - start_line + 1) as usize)
+            .map(|line| format!("{line}\n"))
+            .collect();
+        Some(SourceExcerpt {
+            start_line: start_line + 1,
+            end_line: end_line + 1,
+            text,
+            truncated: end_line < item_end_line,
+        })
+    }
 
-            // Process all references grouped by file
-            for (ref_file_id, ref_ranges) in search_result.references {
-                if let Ok(ref_line_index) = analysis.file_line_index(ref_file_id) {
-                    if let Some(ref_file_path) = self.file_watcher.file_path(ref_file_id) {
-                        // Get file text once for this file
-                        if let Ok(file_text) = analysis.file_text(ref_file_id) {
-                            let symbol_name = search_result
-                                .declaration
-                                .as_ref()
-                                .map(|d| d.nav.name.to_string())
-                                .unwrap_or_else(|| "unknown".to_string());
+    /// Describe the symbol at a position: its definitions, hover text and the
+    /// number of usages across the workspace
+    ///
+    /// Combines [`Self::get_definition`], [`Self::get_type_hint`] and
+    /// [`Self::find_references`] to save round trips. A symbol without hover
+    /// information, or whose hover fails, is still described by its
+    /// definitions. Returns `None` when there is neither.
+    pub async fn describe_symbol(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<SymbolDescription>> {
+        let definitions = self.get_definition(raw_cursor).await?.unwrap_or_default();
+        let type_hint = match self.get_type_hint(raw_cursor).await {
+            Ok(type_hint) => type_hint,
+            Err(e) => {
+                debug!("No hover for described symbol: {}", e);
+                None
+            }
+        };
+        if definitions.is_empty() && type_hint.is_none() {
+            return Ok(None);
+        }
 
-                            // Process each reference range in this file
-                            for (range, _category) in ref_ranges {
-                                let start_line_col = ref_line_index.line_col(range.start());
-                                let end_line_col = ref_line_index.line_col(range.end());
+        // Symbols without a definition, such as literals, have no references
+        let reference_count = if definitions.is_empty() {
+            0
+        } else {
+            self.find_references(raw_cursor)
+                .await?
+                .unwrap_or_default()
+                .iter()
+                .filter(|reference| !reference.is_definition)
+                .count()
+        };
 
-                                let content = Self::get_line_content(
-                                    &file_text,
-                                    start_line_col.line as usize,
-                                );
+        Ok(Some(SymbolDescription {
+            definitions,
+            type_hint,
+            reference_count,
+        }))
+    }
 
-                                references.push(ReferenceInfo {
-                                    file_path: ref_file_path.clone(),
-                                    line: start_line_col.line + 1,
-                                    column: start_line_col.col + 1,
-                                    end_line: end_line_col.line + 1,
-                                    end_column: end_line_col.col + 1,
-                                    name: symbol_name.clone(),
-                                    content,
-                                    is_definition: false,
-                                });
-                            }
-                        }
-                    }
+    /// Get the innermost function, impl, trait or module enclosing a position
+    ///
+    /// Walks up the syntax tree from the cursor, so it answers "what function
+    /// am I in" anywhere inside a body, including on whitespace and comments.
+    /// Closures are skipped in favour of the function containing them. The
+    /// range spans the whole scope while the content is only its header, e.g.
+    /// `pub fn new(name: String) -> Self`, nested in the enclosing impl or trait.
+    /// Returns `None` at the top level of a file.
+    pub async fn get_enclosing_scope(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<DefinitionInfo>> {
+        let (analysis, file_id, offset, _cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source_file = sema.parse_guess_edition(file_id);
+
+        let Some(token) = source_file.syntax().token_at_offset(offset).right_biased() else {
+            return Ok(None);
+        };
+        let Some((node, name, kind, body_start)) = token.parent_ancestors().find_map(|node| {
+            match_ast! {
+                match node {
+                    ast::Fn(it) => Some((
+                        it.syntax().clone(),
+                        it.name()?.text().to_string(),
+                        SymbolKind::Function,
+                        it.body().map(|body| body.syntax().text_range().start()),
+                    )),
+                    ast::Impl(it) => Some((
+                        it.syntax().clone(),
+                        Self::impl_header(&it)?,
+                        SymbolKind::Impl,
+                        it.assoc_item_list().map(|list| list.syntax().text_range().start()),
+                    )),
+                    ast::Trait(it) => Some((
+                        it.syntax().clone(),
+                        it.name()?.text().to_string(),
+                        SymbolKind::Trait,
+                        it.assoc_item_list().map(|list| list.syntax().text_range().start()),
+                    )),
+                    ast::Module(it) => Some((
+                        it.syntax().clone(),
+                        it.name()?.text().to_string(),
+                        SymbolKind::Module,
+                        it.item_list().map(|list| list.syntax().text_range().start()),
+                    )),
+                    _ => None,
                 }
             }
-        }
-
-        if references.is_empty() {
-            return Err(anyhow::anyhow!("No references or declarations found"));
-        }
+        }) else {
+            return Ok(None);
+        };
 
-        // Sort references by file path, then by line number
-        references.sort_by(|a, b| {
-            a.file_path
-                .cmp(&b.file_path)
-                .then_with(|| a.line.cmp(&b.line))
-                .then_with(|| a.column.cmp(&b.column))
+        let file_path = self.file_watcher.file_path(file_id).ok_or_else(|| {
+            ErrorKind::FileNotFound.error(format!("File ID {:?} not found in VFS", file_id))
+        })?;
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index for file {}", file_path))?;
+        let file_text = analysis
+            .file_text(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get file text for {}", file_path))?;
+
+        // Skip attributes and doc comments so the header starts at the item itself
+        let range = node.text_range();
+        let header_start = node
+            .children_with_tokens()
+            .find(|element| {
+                !matches!(
+                    element.kind(),
+                    SyntaxKind::ATTR | SyntaxKind::COMMENT | SyntaxKind::WHITESPACE
+                )
+            })
+            .map_or(range.start(), |element| element.text_range().start());
+        let header = &file_text[TextRange::new(header_start, body_start.unwrap_or(range.end()))];
+
+        let container = node.parent().and_then(|parent| {
+            parent.ancestors().find_map(|ancestor| {
+                match_ast! {
+                    match ancestor {
+                        ast::Impl(it) => Self::impl_header(&it),
+                        ast::Trait(it) => Some(format!("trait {}", it.name()?.text())),
+                        _ => None,
+                    }
+                }
+            })
         });
-        Ok(Some(references))
+        let module = sema
+            .scope(&node)
+            .map(|scope| Self::module_path(db, scope.module()))
+            .unwrap_or_else(|| "crate".to_string());
+
+        let start = line_index.line_col(range.start());
+        let end = line_index.line_col(range.end());
+        Ok(Some(DefinitionInfo {
+            file_path,
+            line: start.line + 1,
+            column: start.col + 1,
+            end_line: end.line + 1,
+            end_column: end.col + 1,
+            name,
+            kind: Some(kind),
+            content: header.split_whitespace().collect::<Vec<_>>().join(" "),
+            module,
+            description: None,
+            container,
+            is_reexport: false,
+            is_external: !self.file_watcher.is_workspace_file(file_id),
+            source: None,
+        }))
     }
 
-    /// Helper method to get line content from file text
-    fn get_line_content(file_text: &str, line_number: usize) -> String {
-        RustAnalyzerUtils::get_line_content(file_text, line_number).unwrap_or_default()
+    /// Header of an impl block, e.g. `impl Display for Person`
+    fn impl_header(imp: &ast::Impl) -> Option<String> {
+        let self_ty = imp.self_ty()?;
+        Some(match imp.trait_() {
+            Some(trait_) => format!("impl {trait_} for {self_ty}"),
+            None => format!("impl {self_ty}"),
+        })
     }
 
-    /// Get rename information without applying changes to disk
-    pub async fn get_rename_info(
+    /// Header of the `impl`, `trait` or type enclosing a navigation target
+    ///
+    /// Returns e.g. `impl Person` for a method, or `pub struct Person` for one
+    /// of its fields. Attributes and doc comments are left out, and whitespace
+    /// is collapsed so multi-line `where` clauses fit on one line.
+    fn enclosing_container(analysis: &Analysis, nav: &NavigationTarget) -> Option<String> {
+        let source_file = analysis.parse(nav.file_id).ok()?;
+        let file_text = analysis.file_text(nav.file_id).ok()?;
+
+        // Start above the target itself so a struct isn't its own container
+        let start = match source_file.syntax().covering_element(nav.full_range) {
+            NodeOrToken::Node(node) if node.text_range() != nav.full_range => node,
+            element => element.parent()?,
+        };
+
+        start.ancestors().find_map(|node| {
+            let body_start = match_ast! {
+                match node {
+                    ast::Impl(it) => it.assoc_item_list()?.syntax().text_range().start(),
+                    ast::Trait(it) => it.assoc_item_list()?.syntax().text_range().start(),
+                    ast::Struct(it) => it.field_list()?.syntax().text_range().start(),
+                    ast::Union(it) => it.record_field_list()?.syntax().text_range().start(),
+                    ast::Enum(it) => it.variant_list()?.syntax().text_range().start(),
+                    _ => return None,
+                }
+            };
+            let header_start = Self::header_start(&node)?;
+
+            let header = file_text.get(usize::from(header_start)..usize::from(body_start))?;
+            Some(header.split_whitespace().collect::<Vec<_>>().join(" "))
+        })
+    }
+
+    /// Start of an item past its leading attributes and doc comments
+    fn header_start(node: &SyntaxNode) -> Option<TextSize> {
+        let start = node
+            .children_with_tokens()
+            .find(|element| {
+                !matches!(
+                    element.kind(),
+                    SyntaxKind::ATTR | SyntaxKind::COMMENT | SyntaxKind::WHITESPACE
+                )
+            })?
+            .text_range()
+            .start();
+        Some(start)
+    }
+
+    /// Declaration of the item a navigation target points at, without its
+    /// attributes, doc comments or body
+    ///
+    /// Returns e.g. the `fn ...` line(s) of a function, up to the opening
+    /// brace, or `pub struct Person` for a struct with named fields. Items
+    /// without a body, like type aliases, keep their full text.
+    fn item_signature(analysis: &Analysis, nav: &NavigationTarget) -> Option<String> {
+        let source_file = analysis.parse(nav.file_id).ok()?;
+        let file_text = analysis.file_text(nav.file_id).ok()?;
+
+        let node = match source_file.syntax().covering_element(nav.full_range) {
+            NodeOrToken::Node(node) => node,
+            NodeOrToken::Token(token) => token.parent()?,
+        };
+        let body = match_ast! {
+            match node {
+                ast::Fn(it) => it.body().map(|it| it.syntax().clone()),
+                ast::Impl(it) => it.assoc_item_list().map(|it| it.syntax().clone()),
+                ast::Trait(it) => it.assoc_item_list().map(|it| it.syntax().clone()),
+                ast::Struct(it) => match it.field_list() {
+                    Some(ast::FieldList::RecordFieldList(it)) => Some(it.syntax().clone()),
+                    _ => None,
+                },
+                ast::Union(it) => it.record_field_list().map(|it| it.syntax().clone()),
+                ast::Enum(it) => it.variant_list().map(|it| it.syntax().clone()),
+                ast::Module(it) => it.item_list().map(|it| it.syntax().clone()),
+                _ => None,
+            }
+        };
+        let start = Self::header_start(&node)?;
+        let end = body.map_or_else(|| node.text_range().end(), |body| body.text_range().start());
+
+        let signature = file_text.get(usize::from(start)..usize::from(end))?;
+        Some(signature.trim_end().to_string())
+    }
+
+    /// Get the attributes of the item at the cursor, derives included
+    ///
+    /// The item is the innermost item, struct field or enum variant around the
+    /// cursor, so point at an item's name or anywhere inside it. Attributes are
+    /// returned as written, e.g. `#[derive(Debug, Clone)]`, in source order.
+    /// Doc comments are left out. Returns `None` outside of any item.
+    pub async fn get_item_attributes(
         &mut self,
         raw_cursor: &CursorCoordinates,
-        new_name: &str,
-    ) -> Result<Option<RenameResult>> {
+    ) -> Result<Option<Vec<String>>> {
+        let (_analysis, file_id, offset, _cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+        let sema = Semantics::new(self.host.raw_database());
+        let source_file = sema.parse_guess_edition(file_id);
+
+        let Some(token) = source_file.syntax().token_at_offset(offset).right_biased() else {
+            return Ok(None);
+        };
+        let item = token
+            .parent_ancestors()
+            .find(|node| {
+                ast::Item::can_cast(node.kind())
+                    || ast::RecordField::can_cast(node.kind())
+                    || ast::Variant::can_cast(node.kind())
+            })
+            .and_then(ast::AnyHasAttrs::cast);
+
+        Ok(item.map(|item| {
+            item.attrs()
+                .map(|attr| attr.syntax().text().to_string())
+                .collect()
+        }))
+    }
+
+    /// Get every variant of the enum at the cursor, with its fields
+    ///
+    /// Point at the enum's name, a use of it or one of its variants, e.g.
+    /// `Shape::Circle`. Unlike hover text, which stops after a few variants,
+    /// all variants are listed in declaration order. Field types are returned
+    /// as written in the enum. Returns `None` if the cursor isn't on an enum.
+    pub async fn get_enum_variants(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<Vec<VariantInfo>>> {
         let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
 
         debug!(
-            "Attempting rename for file {:?} at offset {:?} (line {} col {}) to '{}'",
-            file_id, offset, cursor.line, cursor.column, new_name
+            "Attempting enum variants query for file {:?} at offset {:?} (line {} col {})",
+            file_id, offset, cursor.line, cursor.column
         );
 
-        let position = Self::create_file_position(file_id, offset);
+        // A variant navigates to itself, so its enum is found further up
+        let Some((enum_def, line_index)) =
+            Self::definition_ancestor::<ast::Enum>(&analysis, file_id, offset)?
+        else {
+            return Ok(None);
+        };
 
-        // TODO Consider separating this to a separate tool
-        // First, prepare the rename to validate it's possible
-        // let prepare_result = match analysis.prepare_rename(position) {
-        //     Ok(result) => result,
-        //     Err(e) => {
-        //         warn!("Failed to prepare rename: {:?}", e);
-        //         bail!("Failed to prepare rename: {:?}", e)
-        //     }
-        // };
+        let variants: Vec<VariantInfo> = enum_def
+            .variant_list()
+            .into_iter()
+            .flat_map(|list| list.variants())
+            .filter_map(|variant| {
+                let (kind, fields) = match variant.field_list() {
+                    None => (VariantKind::Unit, Vec::new()),
+                    Some(ast::FieldList::TupleFieldList(list)) => (
+                        VariantKind::Tuple,
+                        list.fields()
+                            .enumerate()
+                            .map(|(index, field)| VariantField {
+                                name: index.to_string(),
+                                ty: Self::field_type(field.ty()),
+                            })
+                            .collect(),
+                    ),
+                    Some(ast::FieldList::RecordFieldList(list)) => (
+                        VariantKind::Record,
+                        list.fields()
+                            .filter_map(|field| {
+                                Some(VariantField {
+                                    name: field.name()?.text().to_string(),
+                                    ty: Self::field_type(field.ty()),
+                                })
+                            })
+                            .collect(),
+                    ),
+                };
+                let name = variant.name()?;
+                Some(VariantInfo {
+                    name: name.text().to_string(),
+                    kind,
+                    fields,
+                    discriminant: variant.expr().map(|expr| expr.syntax().text().to_string()),
+                    line: line_index.line_col(name.syntax().text_range().start()).line + 1,
+                })
+            })
+            .collect();
 
-        // let _prepare_range_info = match prepare_result {
-        //     Ok(range_info) => range_info,
-        //     Err(rename_error) => {
-        //         debug!("Rename not possible: {:?}", rename_error);
-        //         return Ok(None);
-        //     }
-        // };
+        debug!(
+            "Found {} variants for {}:{}:{}",
+            variants.len(),
+            cursor.file_path,
+            cursor.line,
+            cursor.column
+        );
+        Ok(Some(variants))
+    }
 
-        // Perform the actual rename
-        let rename_config = RenameConfig {
-            prefer_no_std: false,
-            prefer_prelude: true,
-            prefer_absolute: false,
+    /// Get every field of the struct or union at the cursor, with its visibility
+    ///
+    /// Point at the type's name, a use of it or one of its fields. Unlike
+    /// hover text, which stops after a few fields, all fields are listed in
+    /// declaration order, named by their index for tuple structs. Types and
+    /// visibilities are returned as written. Returns `None` if the cursor
+    /// isn't on a struct or union.
+    pub async fn get_struct_fields(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<Vec<FieldInfo>>> {
+        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+
+        debug!(
+            "Attempting struct fields query for file {:?} at offset {:?} (line {} col {})",
+            file_id, offset, cursor.line, cursor.column
+        );
+
+        let Some((adt, line_index)) =
+            Self::definition_ancestor::<ast::Adt>(&analysis, file_id, offset)?
+        else {
+            return Ok(None);
         };
-        let rename_result = match analysis.rename(position, new_name, &rename_config) {
-            Ok(result) => result,
-            Err(e) => {
-                warn!("Failed to perform rename: {:?}", e);
-                return Err(anyhow::anyhow!("Failed to perform rename: {:?}", e));
-            }
+        let field_list = match adt {
+            ast::Adt::Struct(it) => it.field_list(),
+            ast::Adt::Union(it) => it.record_field_list().map(ast::FieldList::RecordFieldList),
+            ast::Adt::Enum(_) => return Ok(None),
         };
 
-        let source_change = match rename_result {
-            Ok(source_change) => source_change,
-            Err(rename_error) => {
-                debug!("Rename failed: {:?}", rename_error);
-                return Ok(None);
-            }
+        let visibility = |visibility: Option<ast::Visibility>| {
+            visibility.map(|visibility| visibility.syntax().text().to_string())
+        };
+        let line = |node: &SyntaxNode| line_index.line_col(node.text_range().start()).line + 1;
+        let fields: Vec<FieldInfo> = match field_list {
+            None => Vec::new(),
+            Some(ast::FieldList::TupleFieldList(list)) => list
+                .fields()
+                .enumerate()
+                .map(|(index, field)| FieldInfo {
+                    name: index.to_string(),
+                    ty: Self::field_type(field.ty()),
+                    visibility: visibility(field.visibility()),
+                    line: line(field.syntax()),
+                })
+                .collect(),
+            Some(ast::FieldList::RecordFieldList(list)) => list
+                .fields()
+                .filter_map(|field| {
+                    Some(FieldInfo {
+                        name: field.name()?.text().to_string(),
+                        ty: Self::field_type(field.ty()),
+                        visibility: visibility(field.visibility()),
+                        line: line(field.syntax()),
+                    })
+                })
+                .collect(),
         };
 
-        // Convert SourceChange to our RenameResult format
-        let mut file_changes = Vec::new();
-
-        for (file_id, edit_tuple) in source_change.source_file_edits {
-            // Get file path from file_id
-            let file_path = {
-                if let Some(path) = self.file_watcher.file_path(file_id) {
-                    path
-                } else {
-                    return Err(anyhow::anyhow!("File ID {:?} not found in VFS", file_id));
-                }
-            };
+        debug!(
+            "Found {} fields for {}:{}:{}",
+            fields.len(),
+            cursor.file_path,
+            cursor.line,
+            cursor.column
+        );
+        Ok(Some(fields))
+    }
 
-            // Get line index for this file
-            let file_line_index = analysis
-                .file_line_index(file_id)
-                .map_err(|_| anyhow::anyhow!("Failed to get line index for file {:?}", file_id))?;
+    /// Get every variant of the enum at `item_path`, see [`Self::get_enum_variants`]
+    ///
+    /// `item_path` is a plain name such as `Shape` or a path such as
+    /// `shapes::Shape`, matched like in [`Self::find_trait_impls`]. Returns
+    /// `None` if no enum matches, and an error if several do.
+    pub async fn get_enum_variants_by_path(
+        &mut self,
+        item_path: &str,
+    ) -> Result<Option<Vec<VariantInfo>>> {
+        match self.item_cursor(item_path, &[SymbolKind::Enum])? {
+            Some(cursor) => self.get_enum_variants(&cursor).await,
+            None => Ok(None),
+        }
+    }
 
-            // Convert text edits - the tuple is (TextEdit, Option<SnippetEdit>)
-            let mut edits = Vec::new();
-            let text_edit = &edit_tuple.0; // Get the TextEdit from the tuple
+    /// Get every field of the struct or union at `item_path`, see
+    /// [`Self::get_struct_fields`] and [`Self::get_enum_variants_by_path`]
+    pub async fn get_struct_fields_by_path(
+        &mut self,
+        item_path: &str,
+    ) -> Result<Option<Vec<FieldInfo>>> {
+        match self.item_cursor(item_path, &[SymbolKind::Struct, SymbolKind::Union])? {
+            Some(cursor) => self.get_struct_fields(&cursor).await,
+            None => Ok(None),
+        }
+    }
 
-            for edit in text_edit.iter() {
-                let start_line_col = file_line_index.line_col(edit.delete.start());
-                let end_line_col = file_line_index.line_col(edit.delete.end());
+    /// Cursor on the name of the single item of one of `kinds` at `item_path`
+    fn item_cursor(
+        &mut self,
+        item_path: &str,
+        kinds: &[SymbolKind],
+    ) -> Result<Option<CursorCoordinates>> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+        let analysis = self.host.analysis();
 
-                edits.push(TextEdit {
-                    line: start_line_col.line + 1,  // Convert to 1-based
-                    column: start_line_col.col + 1, // Convert to 1-based
-                    end_line: end_line_col.line + 1,
-                    end_column: end_line_col.col + 1,
-                    new_text: edit.insert.clone(),
-                });
+        let name = item_path.rsplit("::").next().unwrap_or(item_path);
+        let mut query = Query::new(name.to_string());
+        query.libs();
+        let navs = analysis
+            .symbol_search(query, MAX_SYMBOL_CANDIDATES)
+            .map_err(|e| anyhow::anyhow!("Item symbol search failed: {:?}", e))?;
+
+        let mut matches = Vec::new();
+        for nav in navs.iter().filter(|nav| {
+            nav.kind.is_some_and(|kind| kinds.contains(&kind)) && nav.name.as_str() == name
+        }) {
+            let Some(definition) = self.navigation_target_to_definition(&analysis, nav)? else {
+                continue;
+            };
+            if item_path.contains("::")
+                && definition.module != item_path
+                && !definition.module.ends_with(&format!("::{item_path}"))
+            {
+                continue;
             }
+            matches.push(definition);
+        }
 
-            file_changes.push(FileChange { file_path, edits });
+        match matches.as_slice() {
+            [] => Ok(None),
+            [definition] => Ok(Some(CursorCoordinates {
+                file_path: definition.file_path.clone(),
+                line: definition.line,
+                column: definition.column,
+                symbol: None,
+            })),
+            _ => Err(anyhow::anyhow!(
+                "Several items match '{}': {}. Pass a longer path",
+                item_path,
+                matches
+                    .iter()
+                    .map(|definition| definition.module.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
         }
+    }
 
-        debug!(
-            "Rename successful: {} file(s) will be changed",
-            file_changes.len()
-        );
+    /// Find the innermost `N` around the definition of the symbol at `offset`
+    ///
+    /// Returns it with the line index of its file, or `None` if no definition
+    /// lies within a node of that kind.
+    fn definition_ancestor<N: AstNode>(
+        analysis: &Analysis,
+        file_id: FileId,
+        offset: TextSize,
+    ) -> Result<Option<(N, Arc<LineIndex>)>> {
+        let goto_config = GotoDefinitionConfig {
+            minicore: MiniCore::default(),
+        };
+        let navs = analysis
+            .goto_definition(Self::create_file_position(file_id, offset), &goto_config)
+            .map_err(|e| anyhow::anyhow!("Goto definition analysis failed: {:?}", e))?
+            .map(|range_info| range_info.info)
+            .unwrap_or_default();
+
+        for nav in navs {
+            let source_file = analysis
+                .parse(nav.file_id)
+                .map_err(|_| anyhow::anyhow!("Failed to parse file {:?}", nav.file_id))?;
+            let node = match source_file.syntax().covering_element(nav.full_range) {
+                NodeOrToken::Node(node) => Some(node),
+                NodeOrToken::Token(token) => token.parent(),
+            };
+            if let Some(found) = node.and_then(|node| node.ancestors().find_map(N::cast)) {
+                let line_index = analysis.file_line_index(nav.file_id).map_err(|_| {
+                    anyhow::anyhow!("Failed to get line index for {:?}", nav.file_id)
+                })?;
+                return Ok(Some((found, line_index)));
+            }
+        }
+        Ok(None)
+    }
 
-        Ok(Some(RenameResult { file_changes }))
+    /// Text of a field type as written, empty if it failed to parse
+    fn field_type(ty: Option<ast::Type>) -> String {
+        ty.map(|ty| ty.syntax().text().to_string())
+            .unwrap_or_default()
     }
 
-    /// View a Rust file with inlay hints
-    pub async fn view_inlay_hints(
+    /// Get type definition information at the specified cursor position
+    ///
+    /// Unlike `get_definition`, this navigates to the definition of the type of
+    /// the expression under the cursor, e.g. on `let x = foo()` it returns the
+    /// definition of `x`'s type.
+    pub async fn get_type_definition(
         &mut self,
-        file_path: &str,
-        start_line: Option<u32>,
-        end_line: Option<u32>,
-    ) -> Result<String> {
-        let path = PathBuf::from(file_path);
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<Vec<DefinitionInfo>>> {
+        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
 
-        // Ensure file watcher changes are applied
-        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+        debug!(
+            "Attempting goto_type_definition query for file {:?} at offset {:?} (line {} col {})",
+            file_id, offset, cursor.line, cursor.column
+        );
 
-        let analysis = self.host.analysis();
-        let file_id = self.file_watcher.get_file_id(&path)?;
+        match analysis.goto_type_definition(Self::create_file_position(file_id, offset)) {
+            Ok(Some(range_info)) => {
+                let mut definitions = Vec::new();
 
-        // Get the file content
-        let file_content = analysis
-            .file_text(file_id)
-            .map_err(|_| anyhow::anyhow!("Failed to get file content for: {}", file_path))?;
+                for nav in range_info.info {
+                    debug!("Navigation target: {:?}", nav);
+                    if let Some(definition) =
+                        self.navigation_target_to_definition(&analysis, &nav)?
+                    {
+                        debug!("Found type definition: {:?}", definition);
+                        definitions.push(definition);
+                    }
+                }
 
-        // Configure inlay hints to show type information
-        let inlay_config = InlayHintsConfig {
-            render_colons: false,
-            type_hints: true,
-            sized_bound: false,
-            discriminant_hints: DiscriminantHints::Never,
-            parameter_hints: true,
-            generic_parameter_hints: GenericParameterHints {
-                type_hints: false,
-                lifetime_hints: false,
-                const_hints: false,
-            },
-            chaining_hints: false,
-            adjustment_hints: AdjustmentHints::Never,
-            adjustment_hints_mode: AdjustmentHintsMode::Prefix,
-            adjustment_hints_hide_outside_unsafe: false,
-            adjustment_hints_disable_reborrows: false,
-            closure_return_type_hints: ClosureReturnTypeHints::Never,
-            closure_capture_hints: false,
-            binding_mode_hints: false,
-            implicit_drop_hints: false,
-            lifetime_elision_hints: LifetimeElisionHints::Never,
-            param_names_for_lifetime_elision_hints: false,
-            hide_named_constructor_hints: false,
-            hide_closure_initialization_hints: false,
-            hide_closure_parameter_hints: false,
-            range_exclusive_hints: false,
-            closure_style: ClosureStyle::ImplFn,
-            max_length: None,
-            closing_brace_hints_min_lines: None,
-            fields_to_resolve: InlayFieldsToResolve {
-                resolve_text_edits: false,
-                resolve_hint_tooltip: false,
-                resolve_label_tooltip: false,
-                resolve_label_location: false,
-                resolve_label_command: false,
+                debug!(
+                    "Found {} type definitions for {}:{}:{}",
+                    definitions.len(),
+                    cursor.file_path,
+                    cursor.line,
+                    cursor.column
+                );
+                Ok(Some(definitions))
+            }
+            Ok(None) => {
+                debug!(
+                    "No type definitions available for {}:{}:{}",
+                    cursor.file_path, cursor.line, cursor.column
+                );
+                Ok(None)
+            }
+            Err(e) => {
+                warn!("Goto type definition analysis failed: {:?}", e);
+                Err(anyhow::anyhow!(
+                    "Goto type definition analysis failed: {:?}",
+                    e
+                ))
+            }
+        }
+    }
+
+    /// Get declaration information at the specified cursor position
+    ///
+    /// The declaration differs from the definition for items of `extern` blocks
+    /// and trait items: on a call of a trait method it is the method in the
+    /// trait, while `get_definition` returns the method of the impl. Elsewhere
+    /// this falls back to the definition.
+    pub async fn get_declaration(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<Vec<DefinitionInfo>>> {
+        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+
+        debug!(
+            "Attempting goto_declaration query for file {:?} at offset {:?} (line {} col {})",
+            file_id, offset, cursor.line, cursor.column
+        );
+
+        let goto_config = GotoDefinitionConfig {
+            minicore: MiniCore::default(),
+        };
+        match analysis.goto_declaration(Self::create_file_position(file_id, offset), &goto_config) {
+            Ok(Some(range_info)) => {
+                let mut declarations = Vec::new();
+
+                for nav in range_info.info {
+                    debug!("Navigation target: {:?}", nav);
+                    if let Some(declaration) =
+                        self.navigation_target_to_definition(&analysis, &nav)?
+                    {
+                        debug!("Found declaration: {:?}", declaration);
+                        declarations.push(declaration);
+                    }
+                }
+
+                debug!(
+                    "Found {} declarations for {}:{}:{}",
+                    declarations.len(),
+                    cursor.file_path,
+                    cursor.line,
+                    cursor.column
+                );
+                Ok(Some(declarations))
+            }
+            Ok(None) => {
+                debug!(
+                    "No declarations available for {}:{}:{}",
+                    cursor.file_path, cursor.line, cursor.column
+                );
+                Ok(None)
+            }
+            Err(e) => {
+                warn!("Goto declaration analysis failed: {:?}", e);
+                Err(anyhow::anyhow!("Goto declaration analysis failed: {:?}", e))
+            }
+        }
+    }
+
+    /// Get the type returned by the function or method call at the cursor
+    ///
+    /// Point at the name of the called function or method. The type is the
+    /// one inferred for the call in its context, so generic parameters are
+    /// substituted, e.g. `Vec<i32>` for a `collect()` assigned to a `Vec<i32>`.
+    /// In a method chain only the call of the method under the cursor is
+    /// resolved. Returns `None` if the cursor isn't on a call.
+    pub async fn get_call_return_type(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<String>> {
+        let (_analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source_file = sema.parse_guess_edition(file_id);
+
+        let Some(token) = source_file.syntax().token_at_offset(offset).right_biased() else {
+            return Ok(None);
+        };
+        // The innermost call whose callee is under the cursor, so the receiver
+        // and arguments of a call don't count as the call itself
+        let call = token.parent_ancestors().find_map(|node| {
+            let callee = match_ast! {
+                match node {
+                    ast::CallExpr(it) => it.expr().map(|it| it.syntax().text_range()),
+                    ast::MethodCallExpr(it) => it.name_ref().map(|it| it.syntax().text_range()),
+                    _ => None,
+                }
+            };
+            callee
+                .is_some_and(|range| range.contains_inclusive(offset))
+                .then(|| ast::Expr::cast(node))
+                .flatten()
+        });
+        let Some(call) = call else {
+            debug!(
+                "No call at {}:{}:{}",
+                cursor.file_path, cursor.line, cursor.column
+            );
+            return Ok(None);
+        };
+
+        let Some(type_info) = sema.type_of_expr(&call) else {
+            return Ok(None);
+        };
+        let display_target = sema
+            .file_to_module_def(file_id)
+            .map(|module| module.krate().to_display_target(db))
+            .ok_or_else(|| {
+                ErrorKind::FileNotFound
+                    .error(format!("File {:?} is not part of any crate", file_id))
+            })?;
+        Ok(Some(
+            type_info.original.display(db, display_target).to_string(),
+        ))
+    }
+
+    /// Compare the function or trait at the cursor with a previous version
+    ///
+    /// Point at the item or a use of it. `baseline` is the previous version as
+    /// Rust source, such as the ruskel rendering of an older release, and the
+    /// current version is the item's definition. See
+    /// [`RustAnalyzerUtils::diff_signatures`] for what is compared. Returns
+    /// `None` if nothing is defined at the cursor.
+    pub async fn diff_signature(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        baseline: &str,
+    ) -> Result<Option<SignatureDiff>> {
+        let options = DefinitionOptions {
+            follow_reexports: true,
+            ..Default::default()
+        };
+        let Some(definition) = self
+            .get_definition_with_options(raw_cursor, &options)
+            .await?
+            .and_then(|definitions| {
+                definitions
+                    .into_iter()
+                    .find(|definition| !definition.is_reexport)
+            })
+        else {
+            return Ok(None);
+        };
+        RustAnalyzerUtils::diff_signatures(baseline, &definition.content).map(Some)
+    }
+
+    /// Get the tests related to the item at the specified cursor position
+    ///
+    /// Finds test functions that exercise the item under the cursor, e.g. the
+    /// tests calling a function, so callers know which tests to run after
+    /// changing it.
+    pub async fn get_related_tests(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Vec<DefinitionInfo>> {
+        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+
+        debug!(
+            "Attempting related_tests query for file {:?} at offset {:?} (line {} col {})",
+            file_id, offset, cursor.line, cursor.column
+        );
+
+        let runnables = analysis
+            .related_tests(Self::create_file_position(file_id, offset), None)
+            .map_err(|e| {
+                warn!("Related tests analysis failed: {:?}", e);
+                anyhow::anyhow!("Related tests analysis failed: {:?}", e)
+            })?;
+
+        let mut tests = Vec::new();
+        for runnable in runnables {
+            debug!("Related test: {:?}", runnable.nav);
+            if let Some(test) = self.navigation_target_to_definition(&analysis, &runnable.nav)? {
+                tests.push(test);
+            }
+        }
+
+        debug!(
+            "Found {} related tests for {}:{}:{}",
+            tests.len(),
+            cursor.file_path,
+            cursor.line,
+            cursor.column
+        );
+        Ok(tests)
+    }
+
+    /// Get the tests, benchmarks and binaries that can be run at the cursor
+    ///
+    /// Returns every runnable whose item encloses the cursor, innermost last,
+    /// e.g. both a test function and its test module. Each comes with the cargo
+    /// arguments to run it, scoped by module path, and the package directory to
+    /// run them from.
+    pub async fn get_runnables(&mut self, raw_cursor: &CursorCoordinates) -> Result<Vec<Runnable>> {
+        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+
+        debug!(
+            "Attempting runnables query for file {:?} at offset {:?} (line {} col {})",
+            file_id, offset, cursor.line, cursor.column
+        );
+
+        let runnables = analysis.runnables(file_id).map_err(|e| {
+            warn!("Runnables analysis failed: {:?}", e);
+            anyhow::anyhow!("Runnables analysis failed: {:?}", e)
+        })?;
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get line index: {:?}", e))?;
+
+        // Cargo picks the package from the working directory
+        let package_dir = Path::new(&cursor.file_path)
+            .ancestors()
+            .find(|dir| dir.join("Cargo.toml").exists())
+            .ok_or_else(|| {
+                ErrorKind::FileNotFound
+                    .error(format!("No Cargo.toml found for {}", cursor.file_path))
+            })?;
+        let cwd = match self.file_watcher.project_root() {
+            Some(root) => match package_dir.strip_prefix(root.to_string()) {
+                Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+                Ok(relative) => relative.display().to_string(),
+                Err(_) => package_dir.display().to_string(),
+            },
+            None => package_dir.display().to_string(),
+        };
+
+        let mut result = Vec::new();
+        for runnable in runnables {
+            if runnable.nav.file_id != file_id
+                || !runnable.nav.full_range.contains_inclusive(offset)
+            {
+                continue;
+            }
+
+            let (label, cargo_args) = match &runnable.kind {
+                RunnableKind::Test { test_id, .. } => (
+                    format!("test {test_id}"),
+                    vec![
+                        "test".to_string(),
+                        "--".to_string(),
+                        test_id.to_string(),
+                        "--exact".to_string(),
+                    ],
+                ),
+                RunnableKind::TestMod { path } => (
+                    format!("test-mod {path}"),
+                    vec!["test".to_string(), "--".to_string(), path.clone()],
+                ),
+                RunnableKind::Bench { test_id } => (
+                    format!("bench {test_id}"),
+                    vec![
+                        "bench".to_string(),
+                        "--".to_string(),
+                        test_id.to_string(),
+                        "--exact".to_string(),
+                    ],
+                ),
+                RunnableKind::DocTest { test_id } => (
+                    format!("doctest {test_id}"),
+                    vec![
+                        "test".to_string(),
+                        "--doc".to_string(),
+                        "--".to_string(),
+                        test_id.to_string(),
+                    ],
+                ),
+                RunnableKind::Bin => ("run binary".to_string(), vec!["run".to_string()]),
+            };
+
+            let position = line_index.line_col(runnable.nav.focus_or_full_range().start());
+            result.push(Runnable {
+                label,
+                file_path: cursor.file_path.clone(),
+                line: position.line + 1,
+                column: position.col + 1,
+                cargo_args,
+                cwd: cwd.clone(),
+            });
+        }
+
+        debug!(
+            "Found {} runnables at {}:{}:{}",
+            result.len(),
+            cursor.file_path,
+            cursor.line,
+            cursor.column
+        );
+        Ok(result)
+    }
+
+    /// Rename a symbol at the specified cursor position and apply the changes
+    /// to disk
+    pub async fn rename_symbol(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        new_name: &str,
+    ) -> Result<Option<RenameResult>> {
+        self.rename_symbol_with_options(raw_cursor, new_name, &ApplyOptions::default())
+            .await
+    }
+
+    /// Rename a symbol at the specified cursor position and apply the changes
+    /// to disk, as configured by `options`
+    pub async fn rename_symbol_with_options(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        new_name: &str,
+        options: &ApplyOptions,
+    ) -> Result<Option<RenameResult>> {
+        // Get the rename information
+        let mut rename_result = self.get_rename_info(raw_cursor, new_name).await?;
+
+        if let Some(result) = &mut rename_result {
+            // Check the moves first, so a taken file name leaves everything untouched
+            RustAnalyzerUtils::check_file_renames(&result.file_renames)?;
+            // Apply the edits to disk, unless this is a dry run
+            RustAnalyzerUtils::apply_file_changes(&mut result.file_changes, options).await?;
+            if !options.dry_run {
+                RustAnalyzerUtils::apply_file_renames(&result.file_renames).await?;
+            }
+        }
+
+        Ok(rename_result)
+    }
+
+    /// Find all references to a symbol at the specified cursor position
+    pub async fn find_references(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<Vec<ReferenceInfo>>> {
+        self.find_references_with_context(raw_cursor, 0).await
+    }
+
+    /// Find all references to a symbol, with `context_lines` lines of source
+    /// before and after each reference in its content
+    pub async fn find_references_with_context(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        context_lines: u32,
+    ) -> Result<Option<Vec<ReferenceInfo>>> {
+        self.find_references_with_options(
+            raw_cursor,
+            &ReferenceOptions {
+                context_lines,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Find references to a symbol, as configured by `options`
+    ///
+    /// Narrowing the [`ReferenceScope`] to the cursor's file or crate avoids
+    /// scanning the whole workspace. Locals are always searched in their file
+    /// only, see [`Self::reference_scope`]. The declaration is returned even
+    /// when it lies outside the scope.
+    pub async fn find_references_with_options(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        options: &ReferenceOptions,
+    ) -> Result<Option<Vec<ReferenceInfo>>> {
+        let context_lines = options.context_lines;
+        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+        let scope = self.narrowed_scope(file_id, offset, options.scope);
+
+        debug!(
+            "Attempting find_all_refs query for file {:?} at offset {:?} (line {} col {}) in {:?} scope",
+            file_id, offset, cursor.line, cursor.column, scope
+        );
+
+        // Query for all references
+        let find_refs_config = FindAllRefsConfig {
+            search_scope: self.search_scope(file_id, scope)?,
+            minicore: MiniCore::default(),
+        };
+        let references_result =
+            match analysis.find_all_refs(Self::create_file_position(file_id, offset), &find_refs_config) {
+                Ok(Some(search_results)) => search_results,
+                Ok(None) => {
+                    debug!("No references found at position");
+                    return Ok(None);
+                }
+                Err(e) => {
+                    debug!("Error finding references: {}", e);
+                    return Err(anyhow::anyhow!("Failed to find references: {}", e));
+                }
+            };
+
+        let mut references = Vec::new();
+
+        for search_result in references_result {
+            // Add the declaration (definition) if it exists
+            if let Some(declaration) = &search_result.declaration {
+                if let Ok(decl_line_index) = analysis.file_line_index(declaration.nav.file_id) {
+                    let decl_range = declaration.nav.focus_or_full_range();
+                    let start_line_col = decl_line_index.line_col(decl_range.start());
+                    let end_line_col = decl_line_index.line_col(decl_range.end());
+
+                    if let Some(decl_file_path) =
+                        self.file_watcher.file_path(declaration.nav.file_id)
+                    {
+                        // Get the line content containing the declaration
+                        let content =
+                            if let Ok(file_text) = analysis.file_text(declaration.nav.file_id) {
+                                Self::get_context_content(
+                                    &file_text,
+                                    start_line_col.line as usize,
+                                    context_lines,
+                                )
+                            } else {
+                                "".to_string()
+                            };
+
+                        references.push(ReferenceInfo {
+                            file_path: decl_file_path,
+                            line: start_line_col.line + 1,
+                            column: start_line_col.col + 1,
+                            end_line: end_line_col.line + 1,
+                            end_column: end_line_col.col + 1,
+                            name: declaration.nav.name.to_string(),
+                            content,
+                            is_definition: true,
+                            category: ReferenceCategory::default(),
+                        });
+                    }
+                }
+            }
+
+            // Process all references grouped by file
+            for (ref_file_id, ref_ranges) in search_result.references {
+                if let Ok(ref_line_index) = analysis.file_line_index(ref_file_id) {
+                    if let Some(ref_file_path) = self.file_watcher.file_path(ref_file_id) {
+                        // Get file text once for this file
+                        if let Ok(file_text) = analysis.file_text(ref_file_id) {
+                            let symbol_name = search_result
+                                .declaration
+                                .as_ref()
+                                .map(|d| d.nav.name.to_string())
+                                .unwrap_or_else(|| "unknown".to_string());
+
+                            // Process each reference range in this file
+                            for (range, category) in ref_ranges {
+                                let start_line_col = ref_line_index.line_col(range.start());
+                                let end_line_col = ref_line_index.line_col(range.end());
+
+                                let content = Self::get_context_content(
+                                    &file_text,
+                                    start_line_col.line as usize,
+                                    context_lines,
+                                );
+
+                                references.push(ReferenceInfo {
+                                    file_path: ref_file_path.clone(),
+                                    line: start_line_col.line + 1,
+                                    column: start_line_col.col + 1,
+                                    end_line: end_line_col.line + 1,
+                                    end_column: end_line_col.col + 1,
+                                    name: symbol_name.clone(),
+                                    content,
+                                    is_definition: false,
+                                    category: category.into(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if references.is_empty() {
+            return Err(anyhow::anyhow!("No references or declarations found"));
+        }
+
+        // Sort references by file path, then by line number
+        references.sort_by(|a, b| {
+            a.file_path
+                .cmp(&b.file_path)
+                .then_with(|| a.line.cmp(&b.line))
+                .then_with(|| a.column.cmp(&b.column))
+        });
+        Ok(Some(references))
+    }
+
+    /// Count the references to the symbol at the cursor without collecting them
+    ///
+    /// A cheaper variant of [`Self::find_references_with_options`] for dead-code
+    /// triage, as references aren't turned into locations with content. With
+    /// `stop_at_first` the search ends at the first reference found, for when
+    /// only `is_used` matters. The definition itself isn't counted. Returns
+    /// `None` if there is no symbol at the cursor.
+    pub async fn count_references(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        scope: ReferenceScope,
+        stop_at_first: bool,
+    ) -> Result<Option<ReferenceCount>> {
+        let (_analysis, file_id, offset, _cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+        let search_scope = self.search_scope(file_id, scope)?;
+        let sema = Semantics::new(self.host.raw_database());
+        let source_file = sema.parse_guess_edition(file_id);
+
+        let Some((name, definition)) =
+            source_file
+                .syntax()
+                .token_at_offset(offset)
+                .find_map(|token| {
+                    Self::token_definition(&sema, &token)
+                        .map(|definition| (token.text().to_string(), definition))
+                })
+        else {
+            return Ok(None);
+        };
+
+        let mut count = 0;
+        definition
+            .usages(&sema)
+            .set_scope(search_scope.as_ref())
+            .search(&mut |_file_id, _reference| {
+                count += 1;
+                stop_at_first
+            });
+        debug!(
+            "Counted {} reference(s) to {} in {:?} scope",
+            count, name, scope
+        );
+
+        Ok(Some(ReferenceCount {
+            name,
+            count,
+            is_used: count > 0,
+            complete: !(stop_at_first && count > 0),
+        }))
+    }
+
+    /// Whether a token at `offset` names or references a definition, looking
+    /// into macro calls
+    fn has_definition_at(&self, file_id: FileId, offset: TextSize) -> bool {
+        let sema = Semantics::new(self.host.raw_database());
+        let source_file = sema.parse_guess_edition(file_id);
+        source_file
+            .syntax()
+            .token_at_offset(offset)
+            .flat_map(|token| sema.descend_into_macros(token))
+            .any(|token| Self::token_definition(&sema, &token).is_some())
+    }
+
+    /// Definition named by an identifier token, or referenced by it
+    fn token_definition(
+        sema: &Semantics<'_, RootDatabase>,
+        token: &SyntaxToken,
+    ) -> Option<Definition> {
+        let parent = token.parent()?;
+        match_ast! {
+            match parent {
+                ast::Name(it) => NameClass::classify(sema, &it).and_then(NameClass::defined),
+                ast::NameRef(it) => match NameRefClass::classify(sema, &it)? {
+                    NameRefClass::Definition(definition, ..) => Some(definition),
+                    _ => None,
+                },
+                ast::Lifetime(it) => match NameClass::classify_lifetime(sema, &it) {
+                    Some(class) => class.defined(),
+                    None => match NameRefClass::classify_lifetime(sema, &it)? {
+                        NameRefClass::Definition(definition, ..) => Some(definition),
+                        _ => None,
+                    },
+                },
+                ast::SelfParam(it) => sema.to_def(&it).map(Definition::Local),
+                _ => None,
+            }
+        }
+    }
+
+    /// Get the scope references to the symbol at the cursor are searched in
+    ///
+    /// This is the requested `scope`, narrowed to [`ReferenceScope::File`] for
+    /// locals, generic parameters and labels, which can't be referenced from
+    /// another file, so finding their references never scans the workspace.
+    pub async fn reference_scope(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        scope: ReferenceScope,
+    ) -> Result<ReferenceScope> {
+        let (_analysis, file_id, offset, _cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+        Ok(self.narrowed_scope(file_id, offset, scope))
+    }
+
+    /// Narrow `scope` to the file for symbols that only live in one body
+    fn narrowed_scope(
+        &self,
+        file_id: FileId,
+        offset: TextSize,
+        scope: ReferenceScope,
+    ) -> ReferenceScope {
+        let sema = Semantics::new(self.host.raw_database());
+        let source_file = sema.parse_guess_edition(file_id);
+        let definition = source_file
+            .syntax()
+            .token_at_offset(offset)
+            .find_map(|token| Self::token_definition(&sema, &token));
+        match definition {
+            Some(Definition::Local(_) | Definition::GenericParam(_) | Definition::Label(_)) => {
+                ReferenceScope::File
+            }
+            _ => scope,
+        }
+    }
+
+    /// rust-analyzer search scope for a [`ReferenceScope`] around a file, `None`
+    /// meaning the whole workspace
+    fn search_scope(&self, file_id: FileId, scope: ReferenceScope) -> Result<Option<SearchScope>> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        match scope {
+            ReferenceScope::File => Ok(Some(SearchScope::single_file(
+                sema.attach_first_edition(file_id),
+            ))),
+            ReferenceScope::Crate => {
+                let krate = sema
+                    .file_to_module_def(file_id)
+                    .map(|module| module.krate())
+                    .ok_or_else(|| {
+                        ErrorKind::FileNotFound
+                            .error(format!("File {:?} is not part of any crate", file_id))
+                    })?;
+                Ok(Some(SearchScope::krate(db, krate)))
+            }
+            ReferenceScope::Workspace => Ok(None),
+        }
+    }
+
+    /// Helper method to get line content, with `context_lines` lines before and after it
+    fn get_context_content(file_text: &str, line_number: usize, context_lines: u32) -> String {
+        RustAnalyzerUtils::get_lines_around(file_text, line_number, context_lines as usize)
+            .unwrap_or_default()
+    }
+
+    /// Get rename information without applying changes to disk
+    ///
+    /// Returns `None` when there is no renamable symbol at the cursor, and an
+    /// error with rust-analyzer's reason when the rename is refused, e.g. because
+    /// the new name conflicts with an existing item. Invalid new names are
+    /// rejected up front, see [`RustAnalyzerUtils::validate_rename_name`].
+    ///
+    /// Snippet edits are dropped, so the edits never contain `$0` markers, see
+    /// [`RustAnalyzerUtils::convert_text_edit`].
+    pub async fn get_rename_info(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        new_name: &str,
+    ) -> Result<Option<RenameResult>> {
+        RustAnalyzerUtils::validate_rename_name(new_name)?;
+        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+
+        debug!(
+            "Attempting rename for file {:?} at offset {:?} (line {} col {}) to '{}'",
+            file_id, offset, cursor.line, cursor.column, new_name
+        );
+
+        if !self.has_definition_at(file_id, offset) {
+            debug!("No renamable symbol at the cursor");
+            return Ok(None);
+        }
+        let position = Self::create_file_position(file_id, offset);
+
+        // TODO Consider separating this to a separate tool
+        // First, prepare the rename to validate it's possible
+        // let prepare_result = match analysis.prepare_rename(position) {
+        //     Ok(result) => result,
+        //     Err(e) => {
+        //         warn!("Failed to prepare rename: {:?}", e);
+        //         bail!("Failed to prepare rename: {:?}", e)
+        //     }
+        // };
+
+        // let _prepare_range_info = match prepare_result {
+        //     Ok(range_info) => range_info,
+        //     Err(rename_error) => {
+        //         debug!("Rename not possible: {:?}", rename_error);
+        //         return Ok(None);
+        //     }
+        // };
+
+        // Perform the actual rename
+        let rename_config = RenameConfig {
+            prefer_no_std: false,
+            prefer_prelude: true,
+            prefer_absolute: false,
+        };
+        let rename_result = match analysis.rename(position, new_name, &rename_config) {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Failed to perform rename: {:?}", e);
+                return Err(anyhow::anyhow!("Failed to perform rename: {:?}", e));
+            }
+        };
+
+        let source_change = match rename_result {
+            Ok(source_change) => source_change,
+            Err(rename_error) => {
+                debug!("Rename refused: {:?}", rename_error);
+                return Err(anyhow::anyhow!("Cannot rename: {}", rename_error));
+            }
+        };
+
+        // Convert SourceChange to our RenameResult format
+        let mut file_changes = Vec::new();
+        let mut line_previews = Vec::new();
+
+        for (file_id, edit_tuple) in source_change.source_file_edits {
+            // Get file path from file_id
+            let file_path = {
+                if let Some(path) = self.file_watcher.file_path(file_id) {
+                    path
+                } else {
+                    return Err(ErrorKind::FileNotFound
+                        .error(format!("File ID {:?} not found in VFS", file_id)));
+                }
+            };
+
+            // Get line index for this file
+            let file_line_index = analysis
+                .file_line_index(file_id)
+                .map_err(|_| anyhow::anyhow!("Failed to get line index for file {:?}", file_id))?;
+
+            // Convert text edits - the tuple is (TextEdit, Option<SnippetEdit>)
+            let (text_edit, snippet_edit) = &edit_tuple;
+            let edits = RustAnalyzerUtils::convert_text_edit(
+                &file_line_index,
+                text_edit,
+                snippet_edit.as_ref(),
+            );
+
+            let file_text = analysis
+                .file_text(file_id)
+                .map_err(|_| anyhow::anyhow!("Failed to get file text for {}", file_path))?;
+            line_previews.extend(RustAnalyzerUtils::line_previews(
+                &file_path, &file_text, &edits,
+            ));
+
+            file_changes.push(FileChange {
+                file_path,
+                edits,
+                diff: None,
+            });
+        }
+        line_previews.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+
+        // Module renames also move the backing file, e.g. `foo.rs` to `bar.rs`
+        let mut file_renames = Vec::new();
+        for file_system_edit in source_change.file_system_edits {
+            let (from, to) = match file_system_edit {
+                FileSystemEdit::MoveFile { src, dst } => {
+                    let from = self.file_watcher.file_path(src).ok_or_else(|| {
+                        ErrorKind::FileNotFound.error(format!("File ID {:?} not found in VFS", src))
+                    })?;
+                    (from, self.anchored_path(dst.anchor, &dst.path)?)
+                }
+                FileSystemEdit::MoveDir { src, dst, .. } => (
+                    self.anchored_path(src.anchor, &src.path)?,
+                    self.anchored_path(dst.anchor, &dst.path)?,
+                ),
+                FileSystemEdit::CreateFile { dst, .. } => {
+                    warn!("Ignoring file creation {} in rename", dst.path);
+                    continue;
+                }
+            };
+            file_renames.push(FileRename { from, to });
+        }
+
+        debug!(
+            "Rename successful: {} file(s) will be changed, {} moved",
+            file_changes.len(),
+            file_renames.len()
+        );
+
+        Ok(Some(RenameResult {
+            file_changes,
+            file_renames,
+            line_previews,
+        }))
+    }
+
+    /// Resolve a path relative to the directory of the `anchor` file
+    fn anchored_path(&self, anchor: FileId, path: &str) -> Result<String> {
+        let anchor_path = self.file_watcher.file_path(anchor).ok_or_else(|| {
+            ErrorKind::FileNotFound.error(format!("File ID {:?} not found in VFS", anchor))
+        })?;
+        let directory = Path::new(&anchor_path)
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("File {} has no parent", anchor_path))?;
+        Ok(RustAnalyzerUtils::normalize_path(&directory.join(path))
+            .to_string_lossy()
+            .to_string())
+    }
+
+    /// View a Rust file with inlay hints
+    pub async fn view_inlay_hints(
+        &mut self,
+        file_path: &str,
+        start_line: Option<u32>,
+        end_line: Option<u32>,
+    ) -> Result<String> {
+        let (file_content, line_index, inlay_hints) = self.compute_inlay_hints(file_path)?;
+
+        let insertions = inlay_hints
+            .iter()
+            .map(|hint| {
+                let hint_text = Self::inlay_hint_text(hint);
+                match hint.position {
+                    InlayHintPosition::After => (hint.range.end(), format!(": {}", hint_text)),
+                    InlayHintPosition::Before => (hint.range.start(), format!("{}: ", hint_text)),
+                }
+            })
+            .collect();
+        let lines = start_line.zip(end_line);
+        RustAnalyzerUtils::splice_line_range(&file_content, &line_index, insertions, lines)
+    }
+
+    /// Get the inlay hints of a file as positioned items, without splicing
+    /// them into the source
+    ///
+    /// Hints are the same as [`Self::view_inlay_hints`] shows, in file order.
+    /// `start_line` and `end_line` (1-based, inclusive) keep only the hints
+    /// anchored in that range; either can be omitted.
+    pub async fn get_inlay_hints(
+        &mut self,
+        file_path: &str,
+        start_line: Option<u32>,
+        end_line: Option<u32>,
+    ) -> Result<Vec<InlayHintInfo>> {
+        let (_file_content, line_index, inlay_hints) = self.compute_inlay_hints(file_path)?;
+
+        let mut hints: Vec<InlayHintInfo> = inlay_hints
+            .iter()
+            .map(|hint| {
+                let (offset, position) = match hint.position {
+                    InlayHintPosition::After => (hint.range.end(), "after"),
+                    InlayHintPosition::Before => (hint.range.start(), "before"),
+                };
+                let line_col = line_index.line_col(offset);
+                InlayHintInfo {
+                    line: line_col.line + 1,
+                    column: line_col.col + 1,
+                    position: position.to_string(),
+                    text: Self::inlay_hint_text(hint),
+                    kind: format!("{:?}", hint.kind).to_lowercase(),
+                }
+            })
+            .filter(|hint| {
+                start_line.is_none_or(|start| hint.line >= start)
+                    && end_line.is_none_or(|end| hint.line <= end)
+            })
+            .collect();
+        hints.sort_by_key(|hint| (hint.line, hint.column));
+        Ok(hints)
+    }
+
+    /// Compute the inlay hints of a file, with its content and line index
+    fn compute_inlay_hints(
+        &mut self,
+        file_path: &str,
+    ) -> Result<(String, LineIndex, Vec<InlayHint>)> {
+        let path = PathBuf::from(file_path);
+
+        // Ensure file watcher changes are applied
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let analysis = self.host.analysis();
+        let file_id = self.file_watcher.get_file_id(&path)?;
+
+        // Get the file content
+        let file_content = analysis
+            .file_text(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get file content for: {}", file_path))?
+            .to_string();
+        let line_index = LineIndex::new(&file_content);
+
+        // Configure inlay hints to show type information
+        let settings = &self.config.inlay_hints;
+        let inlay_config = InlayHintsConfig {
+            render_colons: settings.render_colons,
+            type_hints: settings.type_hints,
+            sized_bound: false,
+            discriminant_hints: DiscriminantHints::Never,
+            parameter_hints: settings.parameter_hints,
+            generic_parameter_hints: GenericParameterHints {
+                type_hints: false,
+                lifetime_hints: false,
+                const_hints: false,
+            },
+            chaining_hints: settings.chaining_hints,
+            adjustment_hints: AdjustmentHints::Never,
+            adjustment_hints_mode: AdjustmentHintsMode::Prefix,
+            adjustment_hints_hide_outside_unsafe: false,
+            adjustment_hints_disable_reborrows: false,
+            closure_return_type_hints: ClosureReturnTypeHints::Never,
+            closure_capture_hints: settings.closure_capture_hints,
+            binding_mode_hints: settings.binding_mode_hints,
+            implicit_drop_hints: settings.implicit_drop_hints,
+            lifetime_elision_hints: LifetimeElisionHints::Never,
+            param_names_for_lifetime_elision_hints: false,
+            hide_named_constructor_hints: settings.hide_named_constructor_hints,
+            hide_closure_initialization_hints: settings.hide_closure_initialization_hints,
+            hide_closure_parameter_hints: false,
+            range_exclusive_hints: settings.range_exclusive_hints,
+            closure_style: ClosureStyle::ImplFn,
+            max_length: settings.max_length,
+            closing_brace_hints_min_lines: None,
+            fields_to_resolve: InlayFieldsToResolve {
+                resolve_text_edits: false,
+                resolve_hint_tooltip: false,
+                resolve_label_tooltip: false,
+                resolve_label_location: false,
+                resolve_label_command: false,
+            },
+            minicore: MiniCore::default(),
+        };
+
+        // Get inlay hints for the entire file
+        let inlay_hints = analysis
+            .inlay_hints(&inlay_config, file_id, None)
+            .map_err(|_| anyhow::anyhow!("Failed to get inlay hints for file: {}", file_path))?;
+
+        debug!(
+            "Found {} inlay hints for file: {}",
+            inlay_hints.len(),
+            file_path
+        );
+
+        Ok((file_content, line_index, inlay_hints))
+    }
+
+    /// Label of an inlay hint, joining its parts
+    fn inlay_hint_text(hint: &InlayHint) -> String {
+        hint.label
+            .parts
+            .iter()
+            .map(|part| part.text.as_str())
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
+    /// Get semantic highlight ranges for a file
+    ///
+    /// Returns every token rust-analyzer classifies, with its highlight tag
+    /// (e.g. `function`, `keyword`, `local`) and modifiers (e.g. `declaration`,
+    /// `mutable`), in file order.
+    pub async fn get_semantic_tokens(&mut self, file_path: &str) -> Result<Vec<HighlightRange>> {
+        let path = PathBuf::from(file_path);
+
+        // Ensure file watcher changes are applied
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let analysis = self.host.analysis();
+        let file_id = self.file_watcher.get_file_id(&path)?;
+
+        let file_content = analysis
+            .file_text(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get file content for: {}", file_path))?;
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index for: {}", file_path))?;
+
+        let highlight_config = HighlightConfig {
+            strings: true,
+            comments: true,
+            punctuation: false,
+            specialize_punctuation: false,
+            specialize_operator: true,
+            operator: true,
+            inject_doc_comment: false,
+            macro_bang: true,
+            syntactic_name_ref_highlighting: false,
+            minicore: MiniCore::default(),
+        };
+        let ranges = analysis
+            .highlight(highlight_config, file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to highlight file: {}", file_path))?;
+
+        debug!(
+            "Found {} highlight ranges for file: {}",
+            ranges.len(),
+            file_path
+        );
+
+        let tokens = ranges
+            .into_iter()
+            .filter_map(|range| {
+                // Displayed as `tag.modifier.modifier`, e.g. `function.declaration.public`
+                let highlight = range.highlight.to_string();
+                let mut parts = highlight.split('.');
+                let tag = parts.next()?.to_string();
+                if tag == "none" {
+                    return None;
+                }
+
+                let start = line_index.line_col(range.range.start());
+                let end = line_index.line_col(range.range.end());
+                Some(HighlightRange {
+                    line: start.line + 1,
+                    column: start.col + 1,
+                    end_line: end.line + 1,
+                    end_column: end.col + 1,
+                    text: file_content[range.range].to_string(),
+                    tag,
+                    modifiers: parts.map(str::to_string).collect(),
+                })
+            })
+            .collect();
+
+        Ok(tokens)
+    }
+
+    /// Get the syntax tree of a file, like rust-analyzer's "Show Syntax Tree"
+    ///
+    /// Every node and token is printed on its own line, indented by depth, as
+    /// its kind and text range followed by the text of tokens, e.g.
+    /// `IDENT@3..7 "main"`. With `range`, 1-based inclusive start and end
+    /// lines, only the smallest node covering those lines is printed. Offsets
+    /// are always relative to the start of the file.
+    pub async fn get_syntax_tree(
+        &mut self,
+        file_path: &str,
+        range: Option<(u32, u32)>,
+    ) -> Result<String> {
+        let path = PathBuf::from(file_path);
+
+        // Ensure file watcher changes are applied
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let analysis = self.host.analysis();
+        let file_id = self.file_watcher.get_file_id(&path)?;
+
+        let source_file = analysis
+            .parse(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to parse file: {}", file_path))?;
+        let root = source_file.syntax().clone();
+
+        let node = match range {
+            Some((start, end)) => {
+                let file_content = analysis.file_text(file_id).map_err(|_| {
+                    anyhow::anyhow!("Failed to get file content for: {}", file_path)
+                })?;
+                let line_index = analysis
+                    .file_line_index(file_id)
+                    .map_err(|_| anyhow::anyhow!("Failed to get line index for: {}", file_path))?;
+                let range = RustAnalyzerUtils::line_range(&file_content, &line_index, start, end)?;
+
+                // Surrounding whitespace would widen the covering node to the parent
+                let text = &file_content[range];
+                let leading = TextSize::of(&text[..text.len() - text.trim_start().len()]);
+                let trailing = TextSize::of(&text[text.trim_end().len()..]);
+                let range = if leading + trailing < range.len() {
+                    TextRange::new(range.start() + leading, range.end() - trailing)
+                } else {
+                    range
+                };
+
+                match root.covering_element(range) {
+                    NodeOrToken::Node(node) => node,
+                    NodeOrToken::Token(token) => token.parent().unwrap_or(root),
+                }
+            }
+            None => root,
+        };
+
+        Ok(format!("{node:#?}"))
+    }
+
+    /// Names in scope at a position that are gated behind an unstable feature
+    ///
+    /// rust-analyzer only offers unstable items on nightly toolchains and
+    /// doesn't flag them on completion items, so they are found by resolving
+    /// the names in scope and checking for `#[unstable]`. Methods and fields
+    /// completed after a `.` are not in scope and are never reported.
+    fn unstable_names_in_scope(&self, file_id: FileId, offset: TextSize) -> HashSet<String> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source_file = sema.parse_guess_edition(file_id);
+
+        let Some(scope) = source_file
+            .syntax()
+            .token_at_offset(offset)
+            .left_biased()
+            .and_then(|token| token.parent())
+            .and_then(|node| sema.scope(&node))
+        else {
+            return HashSet::new();
+        };
+
+        let mut unstable = HashSet::new();
+        scope.process_all_names(&mut |name, def| {
+            if def.attrs(db).is_some_and(|attrs| attrs.is_unstable()) {
+                unstable.insert(name.as_str().to_string());
+            }
+        });
+        unstable
+    }
+
+    /// Resolve the edit adding each import of a completion item
+    fn completion_imports(
+        &self,
+        analysis: &Analysis,
+        config: &CompletionConfig<'_>,
+        position: FilePosition,
+        import_paths: &[String],
+    ) -> Result<Vec<CompletionImport>> {
+        import_paths
+            .iter()
+            .map(|path| {
+                let text_edits = analysis
+                    .resolve_completion_edits(config, position, [path.clone()])
+                    .map_err(|e| anyhow::anyhow!("Failed to resolve import {}: {:?}", path, e))?;
+                let edits = text_edits
+                    .into_iter()
+                    .flat_map(|text_edit| text_edit.into_iter())
+                    .map(|indel| (indel.delete, indel.insert))
+                    .collect();
+                Ok(CompletionImport {
+                    path: path.clone(),
+                    text_edit: self.file_change_from_edits(analysis, position.file_id, edits)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Import insertion settings shared by completions, assists and
+    /// [`Self::organize_imports`]
+    fn insert_use_config() -> InsertUseConfig {
+        InsertUseConfig {
+            granularity: ImportGranularity::Crate,
+            enforce_granularity: true,
+            prefix_kind: PrefixKind::Plain,
+            group: true,
+            skip_glob_imports: true,
+        }
+    }
+
+    /// Assist config with reasonable defaults, shared by all assist-based tools
+    fn assist_config() -> AssistConfig {
+        AssistConfig {
+            snippet_cap: None,
+            allowed: None,
+            insert_use: Self::insert_use_config(),
+            prefer_no_std: false,
+            prefer_prelude: false,
+            prefer_absolute: false,
+            assist_emit_must_use: false,
+            term_search_fuel: 400,
+            term_search_borrowck: true,
+            code_action_grouping: false,
+            expr_fill_default: ra_ap_ide_db::assists::ExprFillDefaultMode::Todo,
+            prefer_self_ty: false,
+        }
+    }
+
+    /// Diagnostics config shared by all diagnostic-based tools
+    ///
+    /// Experimental diagnostics stay enabled, as `unused_variables` is one of
+    /// them. Fixes import items the same way assists do.
+    fn diagnostics_config() -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            enabled: true,
+            proc_macros_enabled: true,
+            proc_attr_macros_enabled: true,
+            disable_experimental: false,
+            disabled: Default::default(),
+            expr_fill_default: ra_ap_ide_db::assists::ExprFillDefaultMode::Todo,
+            style_lints: true,
+            snippet_cap: None,
+            insert_use: Self::insert_use_config(),
+            prefer_no_std: false,
+            prefer_prelude: false,
+            prefer_absolute: false,
+            term_search_fuel: 400,
+            term_search_borrowck: true,
+            show_rename_conflicts: true,
+        }
+    }
+
+    /// Convert a rust-analyzer source change to our format
+    fn file_changes_from_source_change(
+        &self,
+        analysis: &Analysis,
+        source_change: SourceChange,
+    ) -> Vec<FileChange> {
+        source_change
+            .source_file_edits
+            .into_iter()
+            .map(|(file_id, (text_edit, _snippet_edit))| {
+                let file_path = self
+                    .file_watcher
+                    .file_path(file_id)
+                    .unwrap_or_else(|| "unknown".to_string());
+
+                let edits = text_edit
+                    .into_iter()
+                    .map(|indel| {
+                        let line_index = analysis.file_line_index(file_id).unwrap();
+                        let start_line_col = line_index.line_col(indel.delete.start());
+                        let end_line_col = line_index.line_col(indel.delete.end());
+
+                        TextEdit {
+                            line: start_line_col.line + 1,
+                            column: start_line_col.col + 1,
+                            end_line: end_line_col.line + 1,
+                            end_column: end_line_col.col + 1,
+                            new_text: indel.insert,
+                        }
+                    })
+                    .collect();
+
+                FileChange {
+                    file_path,
+                    edits,
+                    diff: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Get available code assists at the specified cursor position
+    pub async fn get_assists(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Option<Vec<AssistInfo>>> {
+        let raw_cursor = &self.resolve_cursor(raw_cursor);
+        let file_content = std::fs::read_to_string(&raw_cursor.file_path).unwrap_or_default();
+        let cursor = raw_cursor.resolve_coordinates(RustAnalyzerUtils::strip_bom(&file_content));
+
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let path = PathBuf::from(&cursor.file_path);
+        let file_id = self.file_watcher.get_file_id(&path)?;
+
+        let analysis = self.host.analysis();
+
+        // Convert 1-based line/column to 0-based for rust-analyzer
+        let line_col = LineCol {
+            line: cursor.line.saturating_sub(1),
+            col: cursor.column.saturating_sub(1),
+        };
+
+        // Get the line index and convert to TextSize offset
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index"))?;
+
+        let offset = line_index.offset(line_col).unwrap_or(TextSize::from(0));
+
+        self.debug_cursor_position(&cursor, file_id, offset, &analysis);
+
+        let file_range = FileRange {
+            file_id,
+            range: TextRange::new(offset, offset),
+        };
+
+        let assist_config = Self::assist_config();
+
+        // Get available assists
+        let assists_result = assists(
+            self.host.raw_database(),
+            &assist_config,
+            AssistResolveStrategy::None,
+            file_range,
+        );
+
+        if assists_result.is_empty() {
+            Ok(None)
+        } else {
+            let assist_infos = assists_result.iter().map(Self::assist_info).collect();
+
+            Ok(Some(assist_infos))
+        }
+    }
+
+    /// Get the code assists available across a file, grouped by position
+    ///
+    /// Asks for assists at the start of every token, skipping whitespace,
+    /// comments and brackets, and adds the quick fixes of diagnostics. An
+    /// assist applying to a whole node is listed once, at the first position
+    /// offering it, so apply it with [`Self::apply_assist`] at that position.
+    /// `range`, 1-based inclusive start and end lines, limits the positions
+    /// asked. At most `MAX_FILE_ASSISTS` assists are returned, and the search
+    /// stops there with the result marked truncated.
+    pub async fn get_file_assists(
+        &mut self,
+        file_path: &str,
+        range: Option<(u32, u32)>,
+    ) -> Result<FileAssists> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let analysis = self.host.analysis();
+        let file_path = self.resolve_path(file_path);
+        let file_id = self.file_watcher.get_file_id(Path::new(&file_path))?;
+        let file_content = analysis
+            .file_text(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get file content for: {}", file_path))?;
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index for: {}", file_path))?;
+        let range = match range {
+            Some((start, end)) => {
+                RustAnalyzerUtils::line_range(&file_content, &line_index, start, end)?
+            }
+            None => TextRange::up_to(TextSize::of(&*file_content)),
+        };
+        let source_file = analysis
+            .parse(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to parse file: {}", file_path))?;
+
+        let offsets: Vec<TextSize> = source_file
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|element| element.into_token())
+            .filter(|token| {
+                !token.kind().is_trivia()
+                    && !matches!(
+                        token.kind(),
+                        SyntaxKind::L_PAREN
+                            | SyntaxKind::R_PAREN
+                            | SyntaxKind::L_CURLY
+                            | SyntaxKind::R_CURLY
+                            | SyntaxKind::L_BRACK
+                            | SyntaxKind::R_BRACK
+                            | SyntaxKind::SEMICOLON
+                    )
+                    && range.contains(token.text_range().start())
+            })
+            .map(|token| token.text_range().start())
+            .collect();
+
+        let assist_config = Self::assist_config();
+        let mut by_offset: BTreeMap<TextSize, Vec<AssistInfo>> = BTreeMap::new();
+        let mut seen = HashSet::new();
+        let mut count = 0;
+        let mut truncated = false;
+        // Returns false once the cap is hit, to stop asking for more
+        let mut add = |offset: TextSize, assist: &Assist| {
+            if !seen.insert((assist.id.0, assist.target)) {
+                return true;
+            }
+            if count == MAX_FILE_ASSISTS {
+                truncated = true;
+                return false;
+            }
+            by_offset
+                .entry(offset)
+                .or_default()
+                .push(Self::assist_info(assist));
+            count += 1;
+            true
+        };
+
+        let diagnostics = analysis
+            .full_diagnostics(
+                &Self::diagnostics_config(),
+                AssistResolveStrategy::None,
+                file_id,
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to get diagnostics for {}: {:?}", file_path, e))?;
+        'collect: {
+            for diagnostic in diagnostics {
+                let offset = diagnostic.range.range.start();
+                if !range.contains(offset) {
+                    continue;
+                }
+                for fix in diagnostic.fixes.iter().flatten() {
+                    if !add(offset, fix) {
+                        break 'collect;
+                    }
+                }
+            }
+
+            for offset in offsets {
+                let file_range = FileRange {
+                    file_id,
+                    range: TextRange::empty(offset),
+                };
+                for assist in assists(
+                    self.host.raw_database(),
+                    &assist_config,
+                    AssistResolveStrategy::None,
+                    file_range,
+                ) {
+                    if !add(offset, &assist) {
+                        break 'collect;
+                    }
+                }
+            }
+        }
+
+        debug!(
+            "Found {} assists in {}{}",
+            count,
+            file_path,
+            if truncated { ", truncated" } else { "" }
+        );
+        let positions = by_offset
+            .into_iter()
+            .map(|(offset, assists)| {
+                let position = line_index.line_col(offset);
+                PositionedAssists {
+                    line: position.line + 1,
+                    column: position.col + 1,
+                    assists,
+                }
+            })
+            .collect();
+        Ok(FileAssists {
+            positions,
+            truncated,
+        })
+    }
+
+    /// Describe an assist of rust-analyzer, without its source change
+    fn assist_info(assist: &Assist) -> AssistInfo {
+        AssistInfo {
+            id: assist.id.0.to_string(),
+            kind: if let Some(group) = &assist.group {
+                group.0.to_string()
+            } else {
+                "refactor".to_string()
+            },
+            label: assist.label.to_string(),
+            target: format!("{:?}", assist.target),
+            source_change: None,
+        }
+    }
+
+    /// Apply a specific code assist at the specified cursor position
+    pub async fn apply_assist(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        assist_id: &str,
+    ) -> Result<Option<AssistSourceChange>> {
+        self.apply_assist_with_options(raw_cursor, assist_id, &ApplyOptions::default())
+            .await
+    }
+
+    /// Apply a specific code assist at the specified cursor position, as
+    /// configured by `options`
+    pub async fn apply_assist_with_options(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        assist_id: &str,
+        options: &ApplyOptions,
+    ) -> Result<Option<AssistSourceChange>> {
+        let raw_cursor = &self.resolve_cursor(raw_cursor);
+        let file_content = std::fs::read_to_string(&raw_cursor.file_path).unwrap_or_default();
+        let cursor = raw_cursor.resolve_coordinates(RustAnalyzerUtils::strip_bom(&file_content));
+
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let path = PathBuf::from(&cursor.file_path);
+        let file_id = self.file_watcher.get_file_id(&path)?;
+
+        let analysis = self.host.analysis();
+
+        // Convert 1-based line/column to 0-based for rust-analyzer
+        let line_col = LineCol {
+            line: cursor.line.saturating_sub(1),
+            col: cursor.column.saturating_sub(1),
+        };
+
+        // Get the line index and convert to TextSize offset
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index"))?;
+
+        let offset = line_index.offset(line_col).unwrap_or(TextSize::from(0));
+
+        self.debug_cursor_position(&cursor, file_id, offset, &analysis);
+
+        let file_range = FileRange {
+            file_id,
+            range: TextRange::new(offset, offset),
+        };
+
+        let assist_config = Self::assist_config();
+
+        // Get available assists with resolved source changes
+        let assists_result = assists(
+            self.host.raw_database(),
+            &assist_config,
+            AssistResolveStrategy::All,
+            file_range,
+        );
+
+        // Find the specific assist by ID
+        let target_assist = assists_result
+            .into_iter()
+            .find(|assist| assist.id.0 == assist_id);
+
+        if let Some(assist) = target_assist {
+            if let Some(source_change) = assist.source_change {
+                let is_snippet = source_change.is_snippet;
+                let mut file_changes =
+                    self.file_changes_from_source_change(&analysis, source_change);
+
+                // Apply the changes to disk, unless this is a dry run
+                RustAnalyzerUtils::apply_file_changes(&mut file_changes, options).await?;
+
+                let assist_source_change = AssistSourceChange {
+                    file_changes,
+                    is_snippet,
+                };
+
+                Ok(Some(assist_source_change))
+            } else {
+                Err(anyhow::anyhow!("Assist has no source change available"))
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Candidate imports for the unresolved name at the cursor, best match first
+    ///
+    /// Uses the same candidates as rust-analyzer's `auto_import` assist, e.g.
+    /// `std::collections::HashMap` for an unresolved `HashMap`. Returns an
+    /// empty list when the name already resolves or nothing matches.
+    pub async fn get_import_suggestions(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+    ) -> Result<Vec<String>> {
+        let (_analysis, file_id, offset, _cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+
+        let mut suggestions: Vec<String> = Vec::new();
+        let paths = self
+            .import_assists(file_id, offset, AssistResolveStrategy::None)
+            .into_iter()
+            .filter_map(|assist| {
+                RustAnalyzerUtils::import_path_from_label(&assist.label.to_string())
+            });
+        for path in paths {
+            if !suggestions.contains(&path) {
+                suggestions.push(path);
+            }
+        }
+        Ok(suggestions)
+    }
+
+    /// Import `import_path` for the unresolved name at the cursor and write the
+    /// change to disk
+    ///
+    /// `import_path` must be one of [`Self::get_import_suggestions`], with or
+    /// without the surrounding `use` and `;`. Returns `None` if it isn't.
+    pub async fn apply_import(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        import_path: &str,
+    ) -> Result<Option<AssistSourceChange>> {
+        let (analysis, file_id, offset, _cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+        let import_path = import_path
+            .trim()
+            .trim_start_matches("use ")
+            .trim_end_matches(';')
+            .trim();
+
+        let Some(source_change) = self
+            .import_assists(file_id, offset, AssistResolveStrategy::All)
+            .into_iter()
+            .find(|assist| {
+                RustAnalyzerUtils::import_path_from_label(&assist.label.to_string()).as_deref()
+                    == Some(import_path)
+            })
+            .and_then(|assist| assist.source_change)
+        else {
+            return Ok(None);
+        };
+
+        let is_snippet = source_change.is_snippet;
+        let file_changes = self.file_changes_from_source_change(&analysis, source_change);
+        for file_change in &file_changes {
+            RustAnalyzerUtils::apply_file_change(file_change).await?;
+        }
+
+        Ok(Some(AssistSourceChange {
+            file_changes,
+            is_snippet,
+        }))
+    }
+
+    /// The `auto_import` assists at an offset, one per candidate path
+    fn import_assists(
+        &self,
+        file_id: FileId,
+        offset: TextSize,
+        resolve: AssistResolveStrategy,
+    ) -> Vec<ra_ap_ide_db::assists::Assist> {
+        assists(
+            self.host.raw_database(),
+            &Self::assist_config(),
+            resolve,
+            FileRange {
+                file_id,
+                range: TextRange::empty(offset),
             },
+        )
+        .into_iter()
+        .filter(|assist| assist.id.0 == "auto_import")
+        .collect()
+    }
+
+    /// Extract the statements on lines `start_line..=end_line` into a new function
+    ///
+    /// Applies rust-analyzer's `extract_function` assist to the lines, naming
+    /// the new function `new_name`, and writes the changes to disk. The lines
+    /// must cover complete statements of a single block, otherwise an error
+    /// explains how to adjust the range. Returns `None` if the assist is not
+    /// applicable, e.g. when the statements contain a `return` it can't handle.
+    pub async fn extract_function(
+        &mut self,
+        file_path: &str,
+        start_line: u32,
+        end_line: u32,
+        new_name: &str,
+    ) -> Result<Option<AssistSourceChange>> {
+        if !RustAnalyzerUtils::is_valid_identifier(new_name) {
+            return Err(anyhow::anyhow!(
+                "'{}' is not a valid function name",
+                new_name
+            ));
+        }
+        if start_line == 0 || end_line < start_line {
+            return Err(ErrorKind::InvalidPosition
+                .error(format!("Invalid line range {}-{}", start_line, end_line)));
+        }
+
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let file_id = self.file_watcher.get_file_id(&PathBuf::from(file_path))?;
+        let analysis = self.host.analysis();
+        let range =
+            Self::statement_range(&analysis, file_id, start_line, end_line).map_err(|e| {
+                error::with_prefix(e, format!("{}:{}-{}", file_path, start_line, end_line))
+            })?;
+
+        let assists_result = assists(
+            self.host.raw_database(),
+            &Self::assist_config(),
+            AssistResolveStrategy::All,
+            FileRange { file_id, range },
+        );
+        let Some(source_change) = assists_result
+            .into_iter()
+            .find(|assist| assist.id.0 == "extract_function")
+            .and_then(|assist| assist.source_change)
+        else {
+            return Ok(None);
+        };
+
+        let is_snippet = source_change.is_snippet;
+        let mut file_changes = self.file_changes_from_source_change(&analysis, source_change);
+
+        // The assist picks a placeholder name such as `fun_name`, replace it
+        let placeholder = RegexBuilder::new(r"\bfn (fun_name\d*)\b")
+            .build()?
+            .captures_iter(
+                &file_changes
+                    .iter()
+                    .flat_map(|file_change| &file_change.edits)
+                    .map(|edit| edit.new_text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+            .next()
+            .map(|captures| captures[1].to_string());
+        if let Some(placeholder) = placeholder {
+            let placeholder = RegexBuilder::new(&format!(r"\b{placeholder}\b")).build()?;
+            for edit in file_changes
+                .iter_mut()
+                .flat_map(|file_change| &mut file_change.edits)
+            {
+                edit.new_text = placeholder
+                    .replace_all(&edit.new_text, new_name)
+                    .into_owned();
+            }
+        }
+
+        for file_change in &file_changes {
+            RustAnalyzerUtils::apply_file_change(file_change).await?;
+        }
+
+        Ok(Some(AssistSourceChange {
+            file_changes,
+            is_snippet,
+        }))
+    }
+
+    /// Text range of the statements on lines `start_line..=end_line`, checking
+    /// that it starts and ends on statement boundaries of the same block
+    fn statement_range(
+        analysis: &Analysis,
+        file_id: FileId,
+        start_line: u32,
+        end_line: u32,
+    ) -> Result<TextRange> {
+        let text = analysis
+            .file_text(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get file text"))?;
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index"))?;
+
+        let line_start = |line: u32| line_index.offset(LineCol { line, col: 0 });
+        let start = line_start(start_line - 1).ok_or_else(|| {
+            ErrorKind::InvalidPosition
+                .error(format!("Line {} is past the end of the file", start_line))
+        })?;
+        let end = line_start(end_line).unwrap_or(TextSize::of(&*text));
+
+        // Trim surrounding whitespace so the range matches the statements exactly
+        let selected = &text[TextRange::new(start, end)];
+        let leading = selected.len() - selected.trim_start().len();
+        let trailing = selected.len() - selected.trim_end().len();
+        if leading == selected.len() {
+            return Err(anyhow::anyhow!("The lines contain no statements"));
+        }
+        let range = TextRange::new(
+            start + TextSize::from(leading as u32),
+            end - TextSize::from(trailing as u32),
+        );
+
+        let source_file = analysis
+            .parse(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to parse file"))?;
+        let covering = match source_file.syntax().covering_element(range) {
+            NodeOrToken::Node(node) => Some(node),
+            NodeOrToken::Token(token) => token.parent(),
+        };
+        let stmt_list = covering
+            .and_then(|node| node.ancestors().find_map(ast::StmtList::cast))
+            .ok_or_else(|| anyhow::anyhow!("The lines are not inside a function body"))?;
+
+        let boundaries: Vec<TextRange> = stmt_list
+            .statements()
+            .map(|stmt| stmt.syntax().text_range())
+            .chain(stmt_list.tail_expr().map(|expr| expr.syntax().text_range()))
+            .collect();
+        let starts_statement = boundaries.iter().any(|stmt| stmt.start() == range.start());
+        let ends_statement = boundaries.iter().any(|stmt| stmt.end() == range.end());
+        if !starts_statement || !ends_statement {
+            return Err(anyhow::anyhow!(
+                "The lines don't cover complete statements of a single block; \
+                 extend the range to start and end on whole statements"
+            ));
+        }
+
+        Ok(range)
+    }
+
+    /// Merge and sort the imports at the top of a file
+    ///
+    /// Returns the rewritten file content without writing it to disk. See
+    /// [`RustAnalyzerUtils::organize_imports`] for the ordering.
+    pub async fn organize_imports(&mut self, file_path: &str) -> Result<String> {
+        self.organize_imports_with_options(file_path, false).await
+    }
+
+    /// Merge and sort the imports at the top of a file, optionally removing
+    /// the unused ones first
+    ///
+    /// Unused imports are removed with rust-analyzer's `remove_unused_imports`
+    /// assist, so imports it can't prove unused, e.g. of trait methods called
+    /// through macros, are kept.
+    pub async fn organize_imports_with_options(
+        &mut self,
+        file_path: &str,
+        remove_unused: bool,
+    ) -> Result<String> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let file_id = self.file_watcher.get_file_id(&PathBuf::from(file_path))?;
+        let analysis = self.host.analysis();
+        let mut text = analysis
+            .file_text(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get file text"))?
+            .to_string();
+
+        if remove_unused {
+            let source_file = analysis
+                .parse(file_id)
+                .map_err(|_| anyhow::anyhow!("Failed to parse file"))?;
+            let imports = RustAnalyzerUtils::leading_imports(&source_file);
+            if let (Some(first), Some(last)) = (imports.first(), imports.last()) {
+                let range = TextRange::new(
+                    first.syntax().text_range().start(),
+                    last.syntax().text_range().end(),
+                );
+                let source_change = assists(
+                    self.host.raw_database(),
+                    &Self::assist_config(),
+                    AssistResolveStrategy::All,
+                    FileRange { file_id, range },
+                )
+                .into_iter()
+                .find(|assist| assist.id.0 == "remove_unused_imports")
+                .and_then(|assist| assist.source_change)
+                .and_then(|mut source_change| source_change.source_file_edits.remove(&file_id));
+
+                if let Some((text_edit, _snippet_edit)) = source_change {
+                    debug!("Removing unused imports from {}", file_path);
+                    text_edit.apply(&mut text);
+                }
+            }
+        }
+
+        let db = self.host.raw_database();
+        let edition = Semantics::new(db)
+            .file_to_module_def(file_id)
+            .map(|module| module.krate().edition(db))
+            .unwrap_or(Edition::CURRENT);
+        Ok(RustAnalyzerUtils::organize_imports(
+            &text,
+            edition,
+            Self::insert_use_config().granularity,
+        ))
+    }
+
+    /// Get the edits rust-analyzer makes after a character is typed
+    ///
+    /// `typed_char` must already be in the file with the cursor on it, e.g. a
+    /// `.` just typed at the start of a line, which gets indented to continue
+    /// the method chain above, or the `=` of a `let` missing its `;`. Only
+    /// `.`, `=`, `<`, `>`, `{`, `(` and `|` trigger formatting, other
+    /// characters never have edits. Returns `None` when nothing changes.
+    pub async fn on_type_format(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        typed_char: char,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+
+        let file_content = analysis
+            .file_text(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get file content for: {}", cursor.file_path))?;
+        // The line index doesn't check columns, which may be past the end of
+        // the file or inside a multi-byte character
+        let Some(rest) = file_content.get(usize::from(offset)..) else {
+            return Err(anyhow::anyhow!(
+                "Invalid position {}:{}:{}, past the end of the file or inside a character",
+                cursor.file_path,
+                cursor.line,
+                cursor.column
+            ));
+        };
+        if rest.chars().next() != Some(typed_char) {
+            return Err(anyhow::anyhow!(
+                "Expected '{}' at {}:{}:{}, type it before formatting",
+                typed_char,
+                cursor.file_path,
+                cursor.line,
+                cursor.column
+            ));
+        }
+
+        let position = Self::create_file_position(file_id, offset);
+        let Some(mut source_change) = analysis
+            .on_char_typed(position, typed_char)
+            .map_err(|e| anyhow::anyhow!("On-type formatting failed: {:?}", e))?
+        else {
+            return Ok(None);
+        };
+        let Some((text_edit, snippet_edit)) = source_change.source_file_edits.remove(&file_id)
+        else {
+            return Ok(None);
+        };
+
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index for: {}", cursor.file_path))?;
+        let edits =
+            RustAnalyzerUtils::convert_text_edit(&line_index, &text_edit, snippet_edit.as_ref());
+        debug!(
+            "On-type formatting '{}' at {}:{}:{} produced {} edit(s)",
+            typed_char,
+            cursor.file_path,
+            cursor.line,
+            cursor.column,
+            edits.len()
+        );
+        Ok((!edits.is_empty()).then_some(edits))
+    }
+
+    /// Compute the edits moving the module-level item at the cursor to `target_module`
+    ///
+    /// `target_module` is a module path of the item's crate, such as
+    /// `crate::shapes` or `shapes`. Qualified references and imports are
+    /// rewritten to the new path, and the item's old module gets a `use` for it.
+    /// Private items become `pub(crate)` so existing users keep compiling.
+    /// Names the item uses unqualified, e.g. siblings in its old module, are
+    /// imported next to it; the move fails when one of them isn't visible from
+    /// the target, such as a private helper. References that can't be
+    /// rewritten automatically, e.g. entries of grouped imports, are reported
+    /// as warnings. Nothing is written to disk.
+    pub async fn get_move_item_info(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        target_module: &str,
+    ) -> Result<MoveItemResult> {
+        let (analysis, file_id, offset, cursor) = self.setup_cursor_analysis(raw_cursor).await?;
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source_file = sema.parse_guess_edition(file_id);
+
+        // The innermost item declared directly in a module, not in an impl or trait
+        let item = source_file
+            .syntax()
+            .token_at_offset(offset)
+            .right_biased()
+            .and_then(|token| {
+                token
+                    .parent_ancestors()
+                    .filter_map(ast::Item::cast)
+                    .find(|item| {
+                        matches!(
+                            item,
+                            ast::Item::Fn(_)
+                                | ast::Item::Struct(_)
+                                | ast::Item::Enum(_)
+                                | ast::Item::Union(_)
+                                | ast::Item::Trait(_)
+                                | ast::Item::Const(_)
+                                | ast::Item::Static(_)
+                                | ast::Item::TypeAlias(_)
+                        ) && item.syntax().parent().is_some_and(|parent| {
+                            ast::SourceFile::can_cast(parent.kind())
+                                || ast::ItemList::can_cast(parent.kind())
+                        })
+                    })
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No movable item found at {}:{}:{}",
+                    cursor.file_path,
+                    cursor.line,
+                    cursor.column
+                )
+            })?;
+        let name = item
+            .syntax()
+            .children()
+            .find_map(ast::Name::cast)
+            .ok_or_else(|| anyhow::anyhow!("Item at cursor has no name"))?;
+        let item_name = name.text().to_string();
+
+        let source_module = NameClass::classify(&sema, &name)
+            .and_then(NameClass::defined)
+            .and_then(|definition| definition.module(db))
+            .ok_or_else(|| anyhow::anyhow!("Failed to resolve the module of '{}'", item_name))?;
+        let target_path = if target_module == "crate" || target_module.starts_with("crate::") {
+            target_module.to_string()
+        } else {
+            format!("crate::{target_module}")
+        };
+        let target = source_module
+            .krate()
+            .modules(db)
+            .into_iter()
+            .find(|module| Self::module_path(db, *module) == target_path)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Target module '{}' does not exist in the crate of '{}'",
+                    target_module,
+                    item_name
+                )
+            })?;
+        let from_module = Self::module_path(db, source_module);
+        if target == source_module {
+            return Err(anyhow::anyhow!(
+                "'{}' is already in module {}",
+                item_name,
+                from_module
+            ));
+        }
+
+        // Where the item goes in the target module
+        let target_source = target.definition_source(db);
+        let target_file_id = target_source.file_id.original_file(db).file_id(db);
+        let (insert_offset, target_container) = match &target_source.value {
+            ModuleSource::SourceFile(file) => (file.syntax().text_range().end(), None),
+            ModuleSource::Module(module) => {
+                let r_curly = module
+                    .item_list()
+                    .and_then(|item_list| item_list.r_curly_token())
+                    .ok_or_else(|| anyhow::anyhow!("Target module {} has no body", target_path))?;
+                (
+                    r_curly.text_range().start(),
+                    Some(module.syntax().text_range()),
+                )
+            }
+            ModuleSource::BlockExpr(_) => {
+                return Err(anyhow::anyhow!(
+                    "Cannot move items into block module {}",
+                    target_path
+                ));
+            }
+        };
+
+        let mut edits: BTreeMap<FileId, Vec<(TextRange, String)>> = BTreeMap::new();
+        let mut warnings = Vec::new();
+
+        // Remove the item with its trailing whitespace and re-insert it in the target
+        let item_range = item.syntax().text_range();
+        let delete_end = item
+            .syntax()
+            .next_sibling_or_token()
+            .filter(|element| element.kind() == SyntaxKind::WHITESPACE)
+            .map_or(item_range.end(), |whitespace| whitespace.text_range().end());
+        edits.entry(file_id).or_default().push((
+            TextRange::new(item_range.start(), delete_end),
+            String::new(),
+        ));
+
+        // Names the item uses unqualified, e.g. helpers of its old module
+        let item_def = NameClass::classify(&sema, &name)
+            .and_then(NameClass::defined)
+            .and_then(Self::definition_module_def);
+        let imports = Self::moved_item_imports(&sema, &item, item_def, target)?;
+
+        let mut item_text = item.syntax().to_string();
+        if item
+            .syntax()
+            .children()
+            .all(|node| !ast::Visibility::can_cast(node.kind()))
+        {
+            let keyword_start = item
+                .syntax()
+                .children_with_tokens()
+                .find(|element| {
+                    !matches!(
+                        element.kind(),
+                        SyntaxKind::ATTR | SyntaxKind::COMMENT | SyntaxKind::WHITESPACE
+                    )
+                })
+                .map_or(item_range.start(), |element| element.text_range().start());
+            item_text.insert_str(
+                usize::from(keyword_start - item_range.start()),
+                "pub(crate) ",
+            );
+        }
+        edits.entry(target_file_id).or_default().push((
+            TextRange::empty(insert_offset),
+            format!("\n{imports}{item_text}\n"),
+        ));
+
+        // Rewrite references outside of the moved item
+        let new_path = format!("{target_path}::{item_name}");
+        let source_container = (file_id, Self::inline_module_range(item.syntax()));
+        let find_refs_config = FindAllRefsConfig {
+            search_scope: None,
             minicore: MiniCore::default(),
         };
+        let references = analysis
+            .find_all_refs(
+                Self::create_file_position(file_id, name.syntax().text_range().start()),
+                &find_refs_config,
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to find references: {:?}", e))?
+            .unwrap_or_default();
+
+        let mut needs_import = false;
+        for search_result in references {
+            for (ref_file_id, ranges) in search_result.references {
+                let ref_file = sema.parse_guess_edition(ref_file_id);
+                for (range, _category) in ranges {
+                    if ref_file_id == file_id && item_range.contains_range(range) {
+                        continue;
+                    }
+                    let node = match ref_file.syntax().covering_element(range) {
+                        NodeOrToken::Node(node) => node,
+                        NodeOrToken::Token(token) => match token.parent() {
+                            Some(node) => node,
+                            None => continue,
+                        },
+                    };
+                    let path = node
+                        .ancestors()
+                        .find_map(ast::PathSegment::cast)
+                        .and_then(|segment| segment.syntax().parent())
+                        .and_then(ast::Path::cast);
+                    let location = || {
+                        let file_path = self
+                            .file_watcher
+                            .file_path(ref_file_id)
+                            .unwrap_or_else(|| "unknown".to_string());
+                        let line = analysis
+                            .file_line_index(ref_file_id)
+                            .map(|index| index.line_col(range.start()).line + 1)
+                            .unwrap_or(0);
+                        format!("{file_path}:{line}")
+                    };
+
+                    match path {
+                        Some(path) if path.qualifier().is_some() => {
+                            let qualifier = path.qualifier().unwrap();
+                            edits
+                                .entry(ref_file_id)
+                                .or_default()
+                                .push((qualifier.syntax().text_range(), target_path.clone()));
+                        }
+                        Some(path)
+                            if path
+                                .syntax()
+                                .ancestors()
+                                .any(|node| ast::UseTree::can_cast(node.kind())) =>
+                        {
+                            warnings.push(format!(
+                                "Update the import of '{}' at {} to {}",
+                                item_name,
+                                location(),
+                                new_path
+                            ));
+                        }
+                        Some(_) => {
+                            let container = (ref_file_id, Self::inline_module_range(&node));
+                            if container == source_container {
+                                needs_import = true;
+                            } else if container != (target_file_id, target_container) {
+                                warnings.push(format!(
+                                    "Check the reference to '{}' at {}, it may rely on a glob import",
+                                    item_name,
+                                    location()
+                                ));
+                            }
+                        }
+                        None => warnings.push(format!(
+                            "Update the reference to '{}' at {}",
+                            item_name,
+                            location()
+                        )),
+                    }
+                }
+            }
+        }
+
+        // The old module still uses the item unqualified, so import it there
+        if needs_import {
+            let import = match source_container.1 {
+                Some(_) => item
+                    .syntax()
+                    .ancestors()
+                    .find_map(ast::Module::cast)
+                    .and_then(|module| module.item_list())
+                    .and_then(|item_list| item_list.l_curly_token())
+                    .map(|l_curly| {
+                        (
+                            TextRange::empty(l_curly.text_range().end()),
+                            format!("\n    use {new_path};"),
+                        )
+                    }),
+                None => source_file.items().next().map(|first_item| {
+                    (
+                        TextRange::empty(first_item.syntax().text_range().start()),
+                        format!("use {new_path};\n\n"),
+                    )
+                }),
+            };
+            edits.entry(file_id).or_default().extend(import);
+        }
+
+        let mut file_changes = Vec::new();
+        for (edit_file_id, file_edits) in edits {
+            file_changes.push(self.file_change_from_edits(&analysis, edit_file_id, file_edits)?);
+        }
+
+        debug!(
+            "Moving '{}' from {} to {} changes {} file(s)",
+            item_name,
+            from_module,
+            target_path,
+            file_changes.len()
+        );
+        Ok(MoveItemResult {
+            item_name,
+            from_module,
+            to_module: target_path,
+            file_changes,
+            warnings,
+        })
+    }
+
+    /// `use` items the target module needs for the names a moved item refers
+    /// to unqualified, one per line, e.g. a sibling function of its old module
+    ///
+    /// Names already in scope in the target, such as prelude items, need none.
+    /// Fails when a name isn't visible from the target, e.g. a private helper
+    /// of the old module, as the moved item wouldn't compile.
+    fn moved_item_imports(
+        sema: &Semantics<'_, RootDatabase>,
+        item: &ast::Item,
+        item_def: Option<ModuleDef>,
+        target: Module,
+    ) -> Result<String> {
+        let db = sema.db;
+        let find_path_config = Self::assist_config().find_path_config(true);
+        let edition = target.krate().edition(db);
+
+        let mut imports = BTreeSet::new();
+        let mut hidden = BTreeSet::new();
+        for segment in item
+            .syntax()
+            .descendants()
+            .filter_map(ast::PathSegment::cast)
+        {
+            let path = segment.parent_path();
+            let in_use_tree = path
+                .syntax()
+                .ancestors()
+                .any(|node| ast::UseTree::can_cast(node.kind()));
+            if path.qualifier().is_some() || in_use_tree {
+                continue;
+            }
+            let Some(ast::PathSegmentKind::Name(name_ref)) = segment.kind() else {
+                continue;
+            };
+            let Some(NameRefClass::Definition(definition, ..)) =
+                NameRefClass::classify(sema, &name_ref)
+            else {
+                continue;
+            };
+            // Items declared in the item's own body move along with it
+            let is_local = definition.module(db).is_some_and(|module| {
+                matches!(
+                    module.definition_source(db).value,
+                    ModuleSource::BlockExpr(_)
+                )
+            });
+            let Some(module_def) = Self::definition_module_def(definition) else {
+                continue;
+            };
+            if is_local || Some(module_def) == item_def {
+                continue;
+            }
+
+            match target.find_path(db, module_def, find_path_config) {
+                Some(path) => {
+                    let path = path.display(db, edition).to_string();
+                    if path.contains("::") {
+                        imports.insert(path);
+                    }
+                }
+                None => {
+                    hidden.insert(name_ref.text().to_string());
+                }
+            }
+        }
+
+        if !hidden.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Cannot move the item to {}, it uses items that aren't visible there: {}. \
+                 Make them pub(crate) or move them first",
+                Self::module_path(db, target),
+                hidden.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        Ok(imports
+            .into_iter()
+            .map(|path| format!("use {path};\n"))
+            .collect())
+    }
+
+    /// The module-level item a definition names, `None` for locals, fields
+    /// and the like
+    fn definition_module_def(definition: Definition) -> Option<ModuleDef> {
+        Some(match definition {
+            Definition::Module(it) => it.into(),
+            Definition::Function(it) => it.into(),
+            Definition::Adt(it) => it.into(),
+            Definition::Variant(it) => it.into(),
+            Definition::Const(it) => it.into(),
+            Definition::Static(it) => it.into(),
+            Definition::Trait(it) => it.into(),
+            Definition::TypeAlias(it) => it.into(),
+            Definition::Macro(it) => it.into(),
+            _ => return None,
+        })
+    }
+
+    /// Move the module-level item at the cursor to `target_module` and write
+    /// the changes to disk
+    ///
+    /// See [`Self::get_move_item_info`] for how references are updated.
+    pub async fn move_item(
+        &mut self,
+        raw_cursor: &CursorCoordinates,
+        target_module: &str,
+    ) -> Result<MoveItemResult> {
+        let result = self.get_move_item_info(raw_cursor, target_module).await?;
+        for file_change in &result.file_changes {
+            RustAnalyzerUtils::apply_file_change(file_change).await?;
+        }
+        Ok(result)
+    }
+
+    /// Path of a module from its crate root, e.g. `crate::shapes::circle`
+    fn module_path(db: &RootDatabase, module: Module) -> String {
+        let mut segments = vec!["crate".to_string()];
+        segments.extend(
+            module
+                .path_to_root(db)
+                .into_iter()
+                .rev()
+                .filter_map(|module| module.name(db))
+                .map(|name| name.as_str().to_string()),
+        );
+        segments.join("::")
+    }
+
+    /// Range of the innermost inline `mod { ... }` containing a node, `None`
+    /// when the node is at the top level of its file
+    fn inline_module_range(node: &SyntaxNode) -> Option<TextRange> {
+        node.ancestors()
+            .filter_map(ast::Module::cast)
+            .find(|module| module.item_list().is_some())
+            .map(|module| module.syntax().text_range())
+    }
+
+    /// Convert raw text edits of a file into a 1-based [`FileChange`]
+    fn file_change_from_edits(
+        &self,
+        analysis: &Analysis,
+        file_id: FileId,
+        edits: Vec<(TextRange, String)>,
+    ) -> Result<FileChange> {
+        let file_path = self.file_watcher.file_path(file_id).ok_or_else(|| {
+            ErrorKind::FileNotFound.error(format!("File ID {:?} not found in VFS", file_id))
+        })?;
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index for file {}", file_path))?;
+
+        let edits = edits
+            .into_iter()
+            .map(|(range, new_text)| {
+                let start = line_index.line_col(range.start());
+                let end = line_index.line_col(range.end());
+                TextEdit {
+                    line: start.line + 1,
+                    column: start.col + 1,
+                    end_line: end.line + 1,
+                    end_column: end.col + 1,
+                    new_text,
+                }
+            })
+            .collect();
+        Ok(FileChange {
+            file_path,
+            edits,
+            diff: None,
+        })
+    }
+
+    /// Describe the loaded workspace: project root, crate graph and VFS size
+    ///
+    /// Useful to find out why a file isn't analyzed, e.g. because it belongs
+    /// to another workspace or isn't reachable from any crate root. Workspace
+    /// crates are listed first, then dependencies and the sysroot, by name.
+    pub async fn get_workspace_info(&mut self) -> Result<WorkspaceInfo> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+        let db = self.host.raw_database();
+
+        let mut crates: Vec<CrateInfo> = ra_ap_hir::Crate::all(db)
+            .into_iter()
+            .map(|krate| self.crate_info(krate))
+            .collect();
+        crates.sort_by(|a, b| {
+            (!a.is_workspace_member, &a.name).cmp(&(!b.is_workspace_member, &b.name))
+        });
+
+        Ok(WorkspaceInfo {
+            project_root: self
+                .file_watcher
+                .project_root()
+                .map(|root| root.to_string()),
+            crates,
+            vfs_file_count: self.file_watcher.vfs().iter().count(),
+            workspace_file_count: self.file_watcher.workspace_files().len(),
+        })
+    }
+
+    /// Report the memory allocated by the process and the size of the workspace
+    ///
+    /// Memoized query results grow over a long session, so this helps decide
+    /// when to drop and reload a workspace. The number of cached queries isn't
+    /// reported: rust-analyzer's per-query memory report is an empty stub in
+    /// ra_ap 0.0.304, since its move to the new salsa.
+    pub async fn get_memory_usage(&mut self) -> Result<MemoryUsage> {
+        let allocated = ra_ap_profile::memory_usage().allocated;
+
+        Ok(MemoryUsage {
+            allocated_mb: allocated.megabytes().max(0) as u64,
+            workspace_file_count: self.file_watcher.workspace_files().len(),
+            vfs_file_count: self.file_watcher.vfs().iter().count(),
+        })
+    }
+
+    /// Get the resolved version, edition and enabled features of a crate
+    ///
+    /// Reads the loaded crate graph, so it answers "which version of serde am
+    /// I actually using" without parsing `Cargo.lock`. Dashes and underscores
+    /// in `crate_name` are interchangeable. Returns every crate of that name,
+    /// workspace members first, since a dependency can be resolved to several
+    /// versions at once.
+    pub async fn get_crate_info(&mut self, crate_name: &str) -> Result<Vec<CrateInfo>> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+        let db = self.host.raw_database();
+
+        let wanted = crate_name.replace('-', "_");
+        let mut crates: Vec<CrateInfo> = ra_ap_hir::Crate::all(db)
+            .into_iter()
+            .map(|krate| self.crate_info(krate))
+            .filter(|krate| krate.name.replace('-', "_") == wanted)
+            .collect();
+        if crates.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No crate named '{}' in the crate graph",
+                crate_name
+            ));
+        }
+        crates.sort_by(|a, b| {
+            (!a.is_workspace_member, &a.version).cmp(&(!b.is_workspace_member, &b.version))
+        });
+        crates.dedup();
+        Ok(crates)
+    }
+
+    /// Get the edition of the crate containing a file, e.g. "2021"
+    ///
+    /// Crates of a workspace can use different editions, which gate syntax
+    /// such as `async` closures and change name resolution.
+    pub async fn get_file_edition(&mut self, file_path: &str) -> Result<String> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+        let file_path = self.resolve_path(file_path);
+        let file_id = self.file_watcher.get_file_id(Path::new(&file_path))?;
+
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        sema.file_to_module_def(file_id)
+            .map(|module| module.krate().edition(db).to_string())
+            .ok_or_else(|| {
+                ErrorKind::FileNotFound
+                    .error(format!("File {} is not part of any crate", file_path))
+            })
+    }
+
+    /// Describe a crate of the crate graph
+    fn crate_info(&self, krate: ra_ap_hir::Crate) -> CrateInfo {
+        let db = self.host.raw_database();
+        let mut features: Vec<String> = krate
+            .cfg(db)
+            .get_cfg_values("feature")
+            .map(|feature| feature.to_string())
+            .collect();
+        features.sort();
+        CrateInfo {
+            name: krate
+                .display_name(db)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| "<unnamed>".to_string()),
+            version: krate.version(db),
+            edition: krate.edition(db).to_string(),
+            features,
+            root_file: self.file_watcher.file_path(krate.root_file(db)),
+            is_workspace_member: krate.origin(db).is_local(),
+        }
+    }
 
-        // Get inlay hints for the entire file
-        let inlay_hints = analysis
-            .inlay_hints(&inlay_config, file_id, None)
-            .map_err(|_| anyhow::anyhow!("Failed to get inlay hints for file: {}", file_path))?;
+    /// Get diagnostics for every source file of the loaded workspace
+    ///
+    /// Uses the already primed analysis rather than running `cargo check`, so
+    /// only diagnostics computed natively by rust-analyzer are reported.
+    /// Results are sorted by severity, then file path and position.
+    pub async fn get_workspace_diagnostics(&mut self) -> Result<Vec<DiagnosticInfo>> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let analysis = self.host.analysis();
+        let config = Self::diagnostics_config();
+
+        let mut diagnostics = Vec::new();
+        for (file_id, file_path) in self.file_watcher.workspace_files() {
+            diagnostics.extend(Self::collect_file_diagnostics(
+                &analysis, &config, file_id, &file_path,
+            )?);
+        }
+
+        diagnostics.sort_by(|a, b| {
+            a.severity
+                .cmp(&b.severity)
+                .then_with(|| a.file_path.cmp(&b.file_path))
+                .then_with(|| a.line.cmp(&b.line))
+                .then_with(|| a.column.cmp(&b.column))
+        });
 
         debug!(
-            "Found {} inlay hints for file: {}",
-            inlay_hints.len(),
-            file_path
+            "Found {} workspace diagnostics: {}",
+            diagnostics.len(),
+            RustAnalyzerUtils::diagnostics_summary(&diagnostics)
         );
 
-        // Use TextEditBuilder to apply all inlay hints as insertions
-        let mut builder = TextEditBuilder::default();
+        Ok(diagnostics)
+    }
 
-        for hint in inlay_hints {
-            // Create the type annotation text
-            let hint_text = hint
-                .label
-                .parts
-                .iter()
-                .map(|part| part.text.as_str())
-                .collect::<Vec<_>>()
-                .join("");
+    /// Get diagnostics for a single file, sorted by position
+    ///
+    /// Like [`Self::get_workspace_diagnostics`], only diagnostics computed
+    /// natively by rust-analyzer are reported.
+    pub async fn get_file_diagnostics(&mut self, file_path: &str) -> Result<Vec<DiagnosticInfo>> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
 
-            let (offset, full_hint_text) = match hint.position {
-                InlayHintPosition::After => (hint.range.end(), format!(": {}", hint_text)),
-                InlayHintPosition::Before => (hint.range.start(), format!("{}: ", hint_text)),
-            };
+        let analysis = self.host.analysis();
+        let config = Self::diagnostics_config();
+        let file_path = self.resolve_path(file_path);
+        let file_id = self.file_watcher.get_file_id(Path::new(&file_path))?;
 
-            trace!("Inlay hint at offset {:?}: {:?}", offset, hint);
+        let mut diagnostics =
+            Self::collect_file_diagnostics(&analysis, &config, file_id, &file_path)?;
+        diagnostics.sort_by(|a, b| a.line.cmp(&b.line).then_with(|| a.column.cmp(&b.column)));
 
-            // Insert the annotation at the correct position
-            builder.insert(offset, full_hint_text);
+        Ok(diagnostics)
+    }
+
+    /// Find unused imports, variables and items in a file, or in every
+    /// workspace file under a directory
+    ///
+    /// Unused variables come from rust-analyzer's `unused_variables`
+    /// diagnostic and unused imports from its `remove_unused_imports` assist,
+    /// so imports are always `fixable`. rust-analyzer has no dead code lint, so
+    /// functions, types and constants are reported when a reference search
+    /// finds no use outside of their own definition. Those marked `is_pub`
+    /// may be unused on purpose, as part of the crate's API. `main`, tests,
+    /// exported symbols and items allowing `dead_code` are never reported, nor
+    /// are `pub use` re-exports. Results are sorted by file path and position.
+    pub async fn find_unused_items(&mut self, path: &str) -> Result<Vec<UnusedItem>> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+
+        let path = self.resolve_path(path);
+        let files: Vec<(FileId, String)> = if Path::new(&path).is_dir() {
+            self.file_watcher
+                .workspace_files()
+                .into_iter()
+                .filter(|(_, file_path)| Path::new(file_path).starts_with(&path))
+                .collect()
+        } else {
+            vec![(
+                self.file_watcher.get_file_id(Path::new(&path))?,
+                path.clone(),
+            )]
+        };
+        if files.is_empty() {
+            return Err(anyhow::anyhow!("No workspace files found under {}", path));
         }
 
-        // Apply all edits to the content
-        let text_edit = builder.finish();
-        let mut result = file_content.to_string();
-        text_edit.apply(&mut result);
+        let mut unused = Vec::new();
+        for (file_id, file_path) in files {
+            unused.extend(self.find_unused_in_file(file_id, &file_path)?);
+        }
+        unused.sort_by(|a, b| {
+            a.file_path
+                .cmp(&b.file_path)
+                .then_with(|| a.line.cmp(&b.line))
+                .then_with(|| a.column.cmp(&b.column))
+        });
 
-        // If line range was specified, extract only that range from the result
-        if let (Some(start), Some(end)) = (start_line, end_line) {
-            let lines: Vec<&str> = result.lines().collect();
-            let start_idx = (start.saturating_sub(1) as usize).min(lines.len());
-            let end_idx = (end as usize).min(lines.len());
+        debug!("Found {} unused items in {}", unused.len(), path);
+        Ok(unused)
+    }
 
-            if start_idx >= lines.len() || end_idx <= start_idx {
-                return Err(anyhow::anyhow!("Range outside of the file limits"));
+    /// Unused imports, variables and items of a single file
+    fn find_unused_in_file(&self, file_id: FileId, file_path: &str) -> Result<Vec<UnusedItem>> {
+        let analysis = self.host.analysis();
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source_file = sema.parse_guess_edition(file_id);
+        let file_content = analysis
+            .file_text(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get file content for: {}", file_path))?;
+        let line_index = analysis
+            .file_line_index(file_id)
+            .map_err(|_| anyhow::anyhow!("Failed to get line index for: {}", file_path))?;
+        let unused_item = |offset: TextSize, kind: UnusedKind, name: String| {
+            let position = line_index.line_col(offset);
+            UnusedItem {
+                file_path: file_path.to_string(),
+                line: position.line + 1,
+                column: position.col + 1,
+                kind,
+                name,
+                is_pub: false,
+                fixable: false,
             }
+        };
 
-            let selected_lines = &lines[start_idx..end_idx];
-            Ok(selected_lines.join("\n"))
-        } else {
-            Ok(result)
+        let mut unused = Vec::new();
+        let diagnostics = analysis
+            .full_diagnostics(
+                &Self::diagnostics_config(),
+                AssistResolveStrategy::None,
+                file_id,
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to get diagnostics for {}: {:?}", file_path, e))?;
+        for diagnostic in diagnostics
+            .into_iter()
+            .filter(|diagnostic| diagnostic.code.as_str() == "unused_variables")
+        {
+            let range = diagnostic.range.range;
+            unused.push(UnusedItem {
+                fixable: diagnostic.fixes.is_some_and(|fixes| !fixes.is_empty()),
+                ..unused_item(
+                    range.start(),
+                    UnusedKind::Variable,
+                    file_content[range].to_string(),
+                )
+            });
         }
-    }
 
-    /// Get available code assists at the specified cursor position
-    pub async fn get_assists(
-        &mut self,
-        raw_cursor: &CursorCoordinates,
-    ) -> Result<Option<Vec<AssistInfo>>> {
-        let cursor = raw_cursor.resolve_coordinates(
-            &std::fs::read_to_string(&raw_cursor.file_path).unwrap_or_default(),
-        );
+        let assist_config = Self::assist_config();
+        for use_item in source_file
+            .syntax()
+            .descendants()
+            .filter_map(ast::Use::cast)
+        {
+            if use_item
+                .visibility()
+                .is_some_and(|visibility| visibility.syntax().text() == "pub")
+            {
+                continue;
+            }
+            let file_range = FileRange {
+                file_id,
+                range: use_item.syntax().text_range(),
+            };
+            let removable = assists(db, &assist_config, AssistResolveStrategy::None, file_range)
+                .iter()
+                .any(|assist| assist.id.0 == "remove_unused_imports");
+            if removable {
+                let text = use_item.syntax().text().to_string();
+                unused.push(UnusedItem {
+                    fixable: true,
+                    ..unused_item(
+                        file_range.range.start(),
+                        UnusedKind::Import,
+                        text.split_whitespace().collect::<Vec<_>>().join(" "),
+                    )
+                });
+            }
+        }
 
-        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+        // Only pub items can be used outside of their crate
+        let crate_scope = self.search_scope(file_id, ReferenceScope::Crate)?;
+        for node in source_file.syntax().descendants() {
+            let Some((kind, name)) = Self::dead_code_candidate(&node) else {
+                continue;
+            };
+            let Some(definition) = NameClass::classify(&sema, &name).and_then(NameClass::defined)
+            else {
+                continue;
+            };
+            let is_pub = ast::AnyHasVisibility::cast(node.clone())
+                .and_then(|item| item.visibility())
+                .is_some_and(|visibility| visibility.syntax().text() == "pub");
+
+            // Recursive calls and the like don't make an item used
+            let item_range = node.text_range();
+            let mut used = false;
+            definition
+                .usages(&sema)
+                .set_scope(if is_pub { None } else { crate_scope.as_ref() })
+                .search(&mut |reference_file_id, reference| {
+                    used = reference_file_id.file_id(db) != file_id
+                        || !item_range.contains_range(reference.range);
+                    used
+                });
+            if used {
+                continue;
+            }
 
-        let path = PathBuf::from(&cursor.file_path);
-        let file_id = self.file_watcher.get_file_id(&path)?;
+            unused.push(UnusedItem {
+                is_pub,
+                ..unused_item(
+                    name.syntax().text_range().start(),
+                    kind,
+                    name.text().to_string(),
+                )
+            });
+        }
 
-        let analysis = self.host.analysis();
+        Ok(unused)
+    }
 
-        // Convert 1-based line/column to 0-based for rust-analyzer
-        let line_col = LineCol {
-            line: cursor.line.saturating_sub(1),
-            col: cursor.column.saturating_sub(1),
+    /// Kind and name of an item checked for dead code
+    ///
+    /// Only items of modules, and functions and constants of inherent impls,
+    /// are checked, as trait items are used through their trait.
+    fn dead_code_candidate(node: &SyntaxNode) -> Option<(UnusedKind, ast::Name)> {
+        let item = ast::Item::cast(node.clone())?;
+        let parent = node.parent()?;
+        let in_module = matches!(
+            parent.kind(),
+            SyntaxKind::SOURCE_FILE | SyntaxKind::ITEM_LIST
+        );
+        let in_inherent_impl = ast::AssocItemList::cast(parent)
+            .and_then(|list| list.syntax().parent())
+            .and_then(ast::Impl::cast)
+            .is_some_and(|impl_item| impl_item.trait_().is_none());
+
+        let exempt = item.attrs().any(|attr| {
+            let path = attr
+                .path()
+                .map(|path| path.syntax().text().to_string())
+                .unwrap_or_default();
+            path.ends_with("test")
+                || path.ends_with("bench")
+                || path.starts_with("proc_macro")
+                || path == "no_mangle"
+                || path == "export_name"
+                || (path == "allow" && attr.syntax().text().to_string().contains("dead_code"))
+        });
+        if exempt {
+            return None;
+        }
+
+        let (kind, name) = match &item {
+            ast::Item::Fn(it) if in_module || in_inherent_impl => {
+                (UnusedKind::Function, it.name()?)
+            }
+            ast::Item::Const(it) if in_module || in_inherent_impl => {
+                (UnusedKind::Constant, it.name()?)
+            }
+            ast::Item::Static(it) if in_module => (UnusedKind::Constant, it.name()?),
+            ast::Item::Struct(it) if in_module => (UnusedKind::Type, it.name()?),
+            ast::Item::Enum(it) if in_module => (UnusedKind::Type, it.name()?),
+            ast::Item::Union(it) if in_module => (UnusedKind::Type, it.name()?),
+            ast::Item::Trait(it) if in_module => (UnusedKind::Type, it.name()?),
+            ast::Item::TypeAlias(it) if in_module => (UnusedKind::Type, it.name()?),
+            _ => return None,
         };
+        if kind == UnusedKind::Function && in_module && name.text() == "main" {
+            return None;
+        }
+        Some((kind, name))
+    }
+
+    /// Compute the diagnostics of a single file, skipping allowed lints
+    fn collect_file_diagnostics(
+        analysis: &Analysis,
+        config: &DiagnosticsConfig,
+        file_id: FileId,
+        file_path: &str,
+    ) -> Result<Vec<DiagnosticInfo>> {
+        let diagnostics = analysis
+            .full_diagnostics(config, AssistResolveStrategy::None, file_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get diagnostics for {}: {:?}", file_path, e))?;
 
-        // Get the line index and convert to TextSize offset
         let line_index = analysis
             .file_line_index(file_id)
-            .map_err(|_| anyhow::anyhow!("Failed to get line index"))?;
+            .map_err(|_| anyhow::anyhow!("Failed to get line index for file: {}", file_path))?;
 
-        let offset = line_index.offset(line_col).unwrap_or(TextSize::from(0));
+        Ok(diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                let start_line_col = line_index.line_col(diagnostic.range.range.start());
+                let end_line_col = line_index.line_col(diagnostic.range.range.end());
+
+                DiagnosticInfo {
+                    file_path: file_path.to_string(),
+                    line: start_line_col.line + 1,
+                    column: start_line_col.col + 1,
+                    end_line: end_line_col.line + 1,
+                    end_column: end_line_col.col + 1,
+                    severity: diagnostic.severity.into(),
+                    code: diagnostic.code.as_str().to_string(),
+                    message: diagnostic.message,
+                }
+            })
+            .filter(|diagnostic| diagnostic.severity != DiagnosticSeverity::Allow)
+            .collect())
+    }
 
-        self.debug_cursor_position(&cursor, file_id, offset, &analysis);
+    /// Find the impls of a trait in the workspace, by trait name
+    ///
+    /// `trait_name` is either a plain name such as `Display` or a path such as
+    /// `fmt::Display`, matched against the end of the trait's module path.
+    /// Traits of dependencies are found too, but only impls declared in
+    /// workspace crates are returned. When several traits match, each one is
+    /// returned with its own impls.
+    pub async fn find_trait_impls(&mut self, trait_name: &str) -> Result<Vec<TraitImpls>> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+        let analysis = self.host.analysis();
 
-        let file_range = FileRange {
-            file_id,
-            range: TextRange::new(offset, offset),
-        };
+        let name = trait_name.rsplit("::").next().unwrap_or(trait_name);
+        let mut query = Query::new(name.to_string());
+        query.libs();
+        let navs = analysis
+            .symbol_search(query, MAX_SYMBOL_CANDIDATES)
+            .map_err(|e| anyhow::anyhow!("Trait symbol search failed: {:?}", e))?;
 
-        // Create assist config with reasonable defaults
-        let assist_config = AssistConfig {
-            snippet_cap: None,
-            allowed: None,
-            insert_use: InsertUseConfig {
-                granularity: ImportGranularity::Crate,
-                enforce_granularity: true,
-                prefix_kind: PrefixKind::Plain,
-                group: true,
-                skip_glob_imports: true,
-            },
-            prefer_no_std: false,
-            prefer_prelude: false,
-            prefer_absolute: false,
-            assist_emit_must_use: false,
-            term_search_fuel: 400,
-            term_search_borrowck: true,
-            code_action_grouping: false,
-            expr_fill_default: ra_ap_ide_db::assists::ExprFillDefaultMode::Todo,
-            prefer_self_ty: false,
+        let workspace_files: Vec<FileId> = self
+            .file_watcher
+            .workspace_files()
+            .into_iter()
+            .map(|(file_id, _)| file_id)
+            .collect();
+        let goto_config = GotoImplementationConfig {
+            filter_adjacent_derive_implementations: false,
         };
 
-        // Get available assists
-        let assists_result = assists(
-            self.host.raw_database(),
-            &assist_config,
-            AssistResolveStrategy::None,
-            file_range,
-        );
+        let mut results = Vec::new();
+        let traits = navs
+            .into_iter()
+            .filter(|nav| nav.kind == Some(SymbolKind::Trait) && nav.name.as_str() == name);
+        for nav in traits {
+            let Some(trait_definition) = self.navigation_target_to_definition(&analysis, &nav)?
+            else {
+                continue;
+            };
+            if trait_name.contains("::")
+                && trait_definition.module != trait_name
+                && !trait_definition
+                    .module
+                    .ends_with(&format!("::{trait_name}"))
+            {
+                continue;
+            }
 
-        if assists_result.is_empty() {
-            Ok(None)
-        } else {
-            let assist_infos = assists_result
-                .into_iter()
-                .map(|assist| AssistInfo {
-                    id: assist.id.0.to_string(),
-                    kind: if let Some(group) = &assist.group {
-                        group.0.to_string()
-                    } else {
-                        "refactor".to_string()
-                    },
-                    label: assist.label.to_string(),
-                    target: format!("{:?}", assist.target),
-                    source_change: None,
-                })
-                .collect();
+            let position =
+                Self::create_file_position(nav.file_id, nav.focus_or_full_range().start());
+            let impl_navs = analysis
+                .goto_implementation(&goto_config, position)
+                .map_err(|e| anyhow::anyhow!("Goto implementation failed: {:?}", e))?
+                .map(|range_info| range_info.info)
+                .unwrap_or_default();
 
-            Ok(Some(assist_infos))
+            let mut impls = Vec::new();
+            for impl_nav in impl_navs
+                .iter()
+                .filter(|impl_nav| workspace_files.contains(&impl_nav.file_id))
+            {
+                if let Some(definition) =
+                    self.navigation_target_to_definition(&analysis, impl_nav)?
+                {
+                    impls.push(definition);
+                }
+            }
+            impls.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+
+            debug!(
+                "Found {} workspace impl(s) of trait {}",
+                impls.len(),
+                trait_definition.module
+            );
+            results.push(TraitImpls {
+                trait_definition,
+                impls,
+            });
         }
+
+        Ok(results)
     }
 
-    /// Apply a specific code assist at the specified cursor position
-    pub async fn apply_assist(
+    /// Search the workspace for symbols whose name matches `query`
+    ///
+    /// Only symbols declared in workspace crates are returned, not those of
+    /// dependencies or the standard library. See [`WorkspaceSymbolsOptions`]
+    /// for the match modes, the kind filter and pagination.
+    ///
+    /// An empty or `*` query lists every symbol of the kind given in the
+    /// options, e.g. all traits of the workspace, and is an error without one.
+    ///
+    /// Matches are sorted by file, then position, so pages are deterministic.
+    pub async fn get_workspace_symbols(
         &mut self,
-        raw_cursor: &CursorCoordinates,
-        assist_id: &str,
-    ) -> Result<Option<AssistSourceChange>> {
-        let cursor = raw_cursor.resolve_coordinates(
-            &std::fs::read_to_string(&raw_cursor.file_path).unwrap_or_default(),
-        );
-
+        query: &str,
+        options: &WorkspaceSymbolsOptions,
+    ) -> Result<WorkspaceSymbolsPage> {
         self.file_watcher.drain_and_apply_changes(&mut self.host)?;
-
-        let path = PathBuf::from(&cursor.file_path);
-        let file_id = self.file_watcher.get_file_id(&path)?;
-
         let analysis = self.host.analysis();
 
-        // Convert 1-based line/column to 0-based for rust-analyzer
-        let line_col = LineCol {
-            line: cursor.line.saturating_sub(1),
-            col: cursor.column.saturating_sub(1),
+        let query_lower = query.to_lowercase();
+        let regex = match options.mode {
+            SymbolMatchMode::Regex => Some(
+                RegexBuilder::new(query)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| {
+                        ErrorKind::InvalidParams.error(format!("Invalid regex '{}': {}", query, e))
+                    })?,
+            ),
+            _ => None,
         };
+        let kind_filter = options
+            .kind
+            .as_ref()
+            .map(|kind| kind.replace('_', "").to_lowercase());
+        let list_kind = matches!(query.trim(), "" | "*");
+        if list_kind && kind_filter.is_none() {
+            return Err(anyhow::anyhow!(
+                "An empty query lists all symbols of a kind, so it needs a kind filter, e.g. `trait`"
+            ));
+        }
 
-        // Get the line index and convert to TextSize offset
-        let line_index = analysis
-            .file_line_index(file_id)
-            .map_err(|_| anyhow::anyhow!("Failed to get line index"))?;
-
-        let offset = line_index.offset(line_col).unwrap_or(TextSize::from(0));
-
-        self.debug_cursor_position(&cursor, file_id, offset, &analysis);
-
-        let file_range = FileRange {
-            file_id,
-            range: TextRange::new(offset, offset),
+        // rust-analyzer's fuzzy search returns a superset of substring and prefix
+        // matches. A regex can't be searched for, so candidates are all symbols.
+        let search_text = if list_kind || options.mode == SymbolMatchMode::Regex {
+            String::new()
+        } else {
+            query.to_string()
         };
+        let mut ra_query = Query::new(search_text);
+        // Listing types skips the far more numerous functions, fields and locals
+        if kind_filter
+            .as_deref()
+            .is_some_and(|kind| matches!(kind, "struct" | "enum" | "union" | "trait" | "typealias"))
+        {
+            ra_query.only_types();
+        }
+        let navs = analysis
+            .symbol_search(ra_query, MAX_SYMBOL_CANDIDATES)
+            .map_err(|e| anyhow::anyhow!("Workspace symbol search failed: {:?}", e))?;
+
+        let mut symbols = Vec::new();
+        for nav in navs {
+            let name = nav.name.to_string();
+            let name_lower = name.to_lowercase();
+            let name_matches = match options.mode {
+                _ if list_kind => true,
+                SymbolMatchMode::Substring => name_lower.contains(&query_lower),
+                SymbolMatchMode::Prefix => name_lower.starts_with(&query_lower),
+                SymbolMatchMode::Fuzzy => {
+                    RustAnalyzerUtils::is_fuzzy_match(&name_lower, &query_lower)
+                }
+                SymbolMatchMode::Regex => regex.as_ref().is_some_and(|regex| regex.is_match(&name)),
+            };
+            let kind_matches = match (&kind_filter, nav.kind) {
+                (Some(filter), Some(kind)) => format!("{kind:?}").to_lowercase() == *filter,
+                (Some(_), None) => false,
+                (None, _) => true,
+            };
+            if !name_matches || !kind_matches {
+                continue;
+            }
 
-        // Create assist config with reasonable defaults
-        let assist_config = AssistConfig {
-            snippet_cap: None,
-            allowed: None,
-            insert_use: InsertUseConfig {
-                granularity: ImportGranularity::Crate,
-                enforce_granularity: true,
-                prefix_kind: PrefixKind::Plain,
-                group: true,
-                skip_glob_imports: true,
-            },
-            prefer_no_std: false,
-            prefer_prelude: false,
-            prefer_absolute: false,
-            assist_emit_must_use: false,
-            term_search_fuel: 400,
-            term_search_borrowck: true,
-            code_action_grouping: false,
-            expr_fill_default: ra_ap_ide_db::assists::ExprFillDefaultMode::Todo,
-            prefer_self_ty: false,
-        };
+            let Some(file_path) = self.file_watcher.file_path(nav.file_id) else {
+                continue;
+            };
+            let line_index = analysis
+                .file_line_index(nav.file_id)
+                .map_err(|_| anyhow::anyhow!("Failed to get line index for file: {}", file_path))?;
+            let position = line_index.line_col(nav.focus_or_full_range().start());
+
+            symbols.push(WorkspaceSymbol {
+                name,
+                kind: nav.kind,
+                container_name: nav.container_name.as_ref().map(|name| name.to_string()),
+                file_path,
+                line: position.line + 1,
+                column: position.col + 1,
+            });
+        }
 
-        // Get available assists with resolved source changes
-        let assists_result = assists(
-            self.host.raw_database(),
-            &assist_config,
-            AssistResolveStrategy::All,
-            file_range,
+        debug!(
+            "Found {} workspace symbols matching '{}' ({:?})",
+            symbols.len(),
+            query,
+            options.mode
         );
 
-        // Find the specific assist by ID
-        let target_assist = assists_result
+        symbols.sort_by(|a, b| {
+            (&a.file_path, a.line, a.column, &a.name).cmp(&(
+                &b.file_path,
+                b.line,
+                b.column,
+                &b.name,
+            ))
+        });
+        let total = symbols.len();
+        let symbols = symbols
             .into_iter()
-            .find(|assist| assist.id.0 == assist_id);
-
-        if let Some(assist) = target_assist {
-            if let Some(source_change) = assist.source_change {
-                // Convert rust-analyzer source change to our format
-                let file_changes = source_change
-                    .source_file_edits
-                    .into_iter()
-                    .map(|(file_id, (text_edit, _snippet_edit))| {
-                        let file_path = self
-                            .file_watcher
-                            .file_path(file_id)
-                            .unwrap_or_else(|| "unknown".to_string());
+            .skip(options.offset)
+            .take(options.limit.unwrap_or(usize::MAX))
+            .collect();
+
+        Ok(WorkspaceSymbolsPage {
+            symbols,
+            offset: options.offset,
+            total,
+        })
+    }
 
-                        let edits = text_edit
-                            .into_iter()
-                            .map(|indel| {
-                                let line_index = analysis.file_line_index(file_id).unwrap();
-                                let start_line_col = line_index.line_col(indel.delete.start());
-                                let end_line_col = line_index.line_col(indel.delete.end());
+    /// Index of the public items declared in workspace crates, sorted by path
+    ///
+    /// Items come from the same symbol search as [`Self::get_workspace_symbols`]
+    /// and are kept when other crates can name them: `pub`, not e.g.
+    /// `pub(crate)`, in modules that are all `pub` up to the crate root. This
+    /// includes enum variants and the methods of public traits, which have no
+    /// visibility of their own. The search stops at `MAX_SYMBOL_CANDIDATES`
+    /// symbols, marking the index truncated. The index is cached until the
+    /// file watcher applies changes.
+    pub async fn get_public_api(&mut self) -> Result<PublicApi> {
+        self.file_watcher.drain_and_apply_changes(&mut self.host)?;
+        let revision = self.file_watcher.revision();
+        if let Some((_, public_api)) = self
+            .public_api
+            .as_ref()
+            .filter(|(cached_revision, _)| *cached_revision == revision)
+        {
+            return Ok(public_api.clone());
+        }
 
-                                TextEdit {
-                                    line: start_line_col.line + 1,
-                                    column: start_line_col.col + 1,
-                                    end_line: end_line_col.line + 1,
-                                    end_column: end_line_col.col + 1,
-                                    new_text: indel.insert,
-                                }
-                            })
-                            .collect();
+        let analysis = self.host.analysis();
+        let navs = analysis
+            .symbol_search(Query::new(String::new()), MAX_SYMBOL_CANDIDATES)
+            .map_err(|e| anyhow::anyhow!("Workspace symbol search failed: {:?}", e))?;
+        let truncated = navs.len() >= MAX_SYMBOL_CANDIDATES;
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let mut items = Vec::new();
+        for nav in navs {
+            let Some(file_path) = self.file_watcher.file_path(nav.file_id) else {
+                continue;
+            };
+            let source_file = sema.parse_guess_edition(nav.file_id);
+            let Some(node) = (match source_file.syntax().covering_element(nav.full_range) {
+                NodeOrToken::Node(node) => Some(node),
+                NodeOrToken::Token(token) => token.parent(),
+            }) else {
+                continue;
+            };
+            let is_public = source_file
+                .syntax()
+                .token_at_offset(nav.focus_or_full_range().start())
+                .find_map(|token| Self::token_definition(&sema, &token))
+                .is_some_and(|definition| Self::is_publicly_reachable(db, definition));
+            let Some(scope) = sema.scope(&node).filter(|_| is_public) else {
+                continue;
+            };
 
-                        FileChange { file_path, edits }
-                    })
-                    .collect();
+            // `crate::shapes` becomes `sample::shapes`, in case of several crates
+            let crate_name = scope
+                .krate()
+                .display_name(db)
+                .map_or_else(|| "crate".to_string(), |name| name.to_string());
+            let module_path =
+                Self::module_path(db, scope.module()).replacen("crate", &crate_name, 1);
+            let container = node
+                .ancestors()
+                .skip(1)
+                .take_while(|ancestor| !ast::Module::can_cast(ancestor.kind()))
+                .find_map(|ancestor| {
+                    match_ast! {
+                        match ancestor {
+                            ast::Impl(it) => it.self_ty().map(|ty| ty.syntax().text().to_string()),
+                            ast::Trait(it) => it.name().map(|name| name.text().to_string()),
+                            ast::Struct(it) => it.name().map(|name| name.text().to_string()),
+                            ast::Union(it) => it.name().map(|name| name.text().to_string()),
+                            ast::Enum(it) => it.name().map(|name| name.text().to_string()),
+                            _ => None,
+                        }
+                    }
+                });
+            let name = nav.name.to_string();
+            let path = match container {
+                Some(container) => format!("{module_path}::{container}::{name}"),
+                None => format!("{module_path}::{name}"),
+            };
 
-                // Apply the changes to disk
-                for file_change in &file_changes {
-                    RustAnalyzerUtils::apply_file_change(file_change).await?;
-                }
+            let line_index = analysis
+                .file_line_index(nav.file_id)
+                .map_err(|_| anyhow::anyhow!("Failed to get line index for file: {}", file_path))?;
+            let position = line_index.line_col(nav.focus_or_full_range().start());
+            items.push(PublicApiItem {
+                name,
+                kind: nav.kind,
+                path,
+                file_path,
+                line: position.line + 1,
+            });
+        }
 
-                let assist_source_change = AssistSourceChange {
-                    file_changes,
-                    is_snippet: source_change.is_snippet,
-                };
+        debug!(
+            "Indexed {} public items at revision {}{}",
+            items.len(),
+            revision,
+            if truncated { ", truncated" } else { "" }
+        );
+        items.sort_by(|a, b| (&a.path, &a.file_path, a.line).cmp(&(&b.path, &b.file_path, b.line)));
+        let public_api = PublicApi { items, truncated };
+        self.public_api = Some((revision, public_api.clone()));
+        Ok(public_api)
+    }
 
-                Ok(Some(assist_source_change))
-            } else {
-                Err(anyhow::anyhow!("Assist has no source change available"))
+    /// Whether a definition is `pub` and so is every module up to its crate
+    /// root, so other crates can name it
+    fn is_publicly_reachable(db: &RootDatabase, definition: Definition) -> bool {
+        if definition.visibility(db) != Some(ra_ap_hir::Visibility::Public) {
+            return false;
+        }
+        let mut module = definition.module(db);
+        while let Some(current) = module {
+            let Some(parent) = current.parent(db) else {
+                // The crate root
+                return true;
+            };
+            if ra_ap_hir::HasVisibility::visibility(&current, db) != ra_ap_hir::Visibility::Public {
+                return false;
             }
-        } else {
-            Ok(None)
+            module = Some(parent);
         }
+        false
     }
 }
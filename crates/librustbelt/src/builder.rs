@@ -4,27 +4,186 @@
 //! instances with workspace configuration, separating initialization concerns
 //! from runtime operations.
 
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use ra_ap_base_db::RootQueryDb;
+use ra_ap_base_db::salsa::Setter;
+use ra_ap_cfg::{CfgAtom, CfgDiff};
 use ra_ap_ide::AnalysisHost;
-use ra_ap_ide_db::prime_caches;
-use ra_ap_load_cargo::{LoadCargoConfig, ProcMacroServerChoice, load_workspace_at};
+use ra_ap_ide_db::{RootDatabase, prime_caches};
+use ra_ap_intern::Symbol;
+use ra_ap_load_cargo::{LoadCargoConfig, ProcMacroServerChoice, load_workspace, load_workspace_at};
 use ra_ap_profile::StopWatch;
-use ra_ap_project_model::{CargoConfig, ProjectManifest, RustLibSource};
+use ra_ap_project_model::{
+    CargoConfig, CfgOverrides, ManifestPath, ProjectManifest, ProjectWorkspace, RustLibSource,
+};
+use ra_ap_syntax::Edition;
 use ra_ap_vfs::AbsPathBuf;
-use tracing::{info, trace};
+use tracing::{info, trace, warn};
 
 use super::analyzer::RustAnalyzerish;
+use super::config::AnalyzerConfig;
+use super::error::ErrorKind;
 use super::file_watcher::FileWatcher;
 use super::utils::RustAnalyzerUtils;
 
+/// User-facing options controlling how a workspace is loaded
+///
+/// Unlike [`CargoConfig`], these are plain values that can be parsed from CLI
+/// flags and passed between processes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceOptions {
+    /// cfgs to enable (`test`, `feature="foo"`) or disable (`-debug_assertions`)
+    pub cfgs: Vec<String>,
+    /// Rust edition forced on workspace crates, see
+    /// [`RustAnalyzerishBuilder::with_edition`]
+    pub edition: Option<String>,
+    /// Analyze `#[cfg(test)]` code of workspace crates, see
+    /// [`RustAnalyzerishBuilder::with_test_cfg`]
+    pub include_tests: bool,
+    /// Skip loading the sysroot, see [`RustAnalyzerishBuilder::without_sysroot`]
+    pub no_sysroot: bool,
+    /// Explicit sysroot of the toolchain to analyze with, see
+    /// [`RustAnalyzerishBuilder::with_sysroot_path`]
+    pub sysroot: Option<String>,
+    /// Don't query `cargo metadata` for the sysroot, see
+    /// [`RustAnalyzerishBuilder::with_sysroot_query_metadata`]
+    pub no_sysroot_metadata: bool,
+    /// Skip the proc-macro server, see [`RustAnalyzerishBuilder::without_proc_macro_server`]
+    pub no_proc_macros: bool,
+    /// Analyze `.rs` files outside any cargo project as standalone crates,
+    /// see [`RustAnalyzerishBuilder::from_file_with_options`]
+    pub allow_detached: bool,
+    /// Cap the threads used to prime caches, see [`RustAnalyzerishBuilder::with_prime_threads`]
+    pub prime_threads: Option<usize>,
+    /// Skip cache priming, see [`RustAnalyzerishBuilder::without_cache_priming`]
+    pub no_prime_caches: bool,
+    /// Extra directories not to watch, see [`RustAnalyzerishBuilder::with_watch_excludes`]
+    pub watch_excludes: Vec<String>,
+    /// Extra file extensions to watch, see [`RustAnalyzerishBuilder::with_watch_extensions`]
+    pub watch_extensions: Vec<String>,
+    /// JSON config file overriding analyzer settings, see
+    /// [`RustAnalyzerishBuilder::with_config_file`]
+    pub config_file: Option<String>,
+}
+
+impl WorkspaceOptions {
+    /// Parse `cfgs` into overrides applied to every crate in the workspace
+    pub fn cfg_overrides(&self) -> Result<CfgOverrides> {
+        let mut enabled = Vec::new();
+        let mut disabled = Vec::new();
+        for cfg in &self.cfgs {
+            match cfg.strip_prefix('-') {
+                Some(cfg) => disabled.push(Self::parse_cfg_atom(cfg)?),
+                None => enabled.push(Self::parse_cfg_atom(cfg)?),
+            }
+        }
+
+        Ok(CfgOverrides {
+            global: CfgDiff::new(enabled, disabled),
+            selective: Default::default(),
+        })
+    }
+
+    /// Parse `edition` such as `2021`, if set
+    pub fn edition(&self) -> Result<Option<Edition>> {
+        self.edition
+            .as_deref()
+            .map(|edition| {
+                edition.trim().parse::<Edition>().map_err(|_| {
+                    anyhow::anyhow!(
+                        "Invalid edition '{}': expected one of 2015, 2018, 2021, 2024",
+                        edition
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// Parse a single cfg such as `test` or `feature="foo"`
+    fn parse_cfg_atom(cfg: &str) -> Result<CfgAtom> {
+        let (name, value) = match cfg.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim().trim_matches('"'))),
+            None => (cfg.trim(), None),
+        };
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("Invalid cfg '{}': missing name", cfg));
+        }
+
+        Ok(match value {
+            Some(value) => CfgAtom::KeyValue {
+                key: Symbol::intern(name),
+                value: Symbol::intern(value),
+            },
+            None => CfgAtom::Flag(Symbol::intern(name)),
+        })
+    }
+}
+
+/// A stage of workspace loading, reported to a [`ProgressCallback`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadProgress {
+    /// Loading the workspace, with cargo's status message
+    LoadingWorkspace(String),
+    /// Priming caches for the crate graph
+    PrimingCaches { done: usize, total: usize },
+    /// Loading and priming are done
+    Finished,
+}
+
+impl fmt::Display for LoadProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadProgress::LoadingWorkspace(message) => write!(f, "Loading workspace: {message}"),
+            LoadProgress::PrimingCaches { done, total } => {
+                write!(f, "Priming caches {done}/{total}")
+            }
+            LoadProgress::Finished => write!(f, "Workspace loaded"),
+        }
+    }
+}
+
+/// Callback receiving progress while a workspace is loaded
+///
+/// Called from the loading threads, so it must be cheap and thread-safe.
+pub type ProgressCallback = Arc<dyn Fn(LoadProgress) + Send + Sync>;
+
 /// Builder for creating configured RustAnalyzerish instances
-#[derive(Debug)]
 pub struct RustAnalyzerishBuilder {
     project_root: Option<PathBuf>,
+    detached_file: Option<PathBuf>,
     cargo_config: CargoConfig,
     load_config: LoadCargoConfig,
+    edition: Option<Edition>,
+    sysroot_query_metadata: bool,
+    prime_threads: Option<usize>,
+    prime_caches: bool,
+    progress: Option<ProgressCallback>,
+    watch_excludes: Vec<String>,
+    watch_extensions: Vec<String>,
+    config: Option<AnalyzerConfig>,
+}
+
+impl fmt::Debug for RustAnalyzerishBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RustAnalyzerishBuilder")
+            .field("project_root", &self.project_root)
+            .field("detached_file", &self.detached_file)
+            .field("cargo_config", &self.cargo_config)
+            .field("load_config", &self.load_config)
+            .field("edition", &self.edition)
+            .field("sysroot_query_metadata", &self.sysroot_query_metadata)
+            .field("prime_threads", &self.prime_threads)
+            .field("prime_caches", &self.prime_caches)
+            .field("progress", &self.progress.is_some())
+            .field("watch_excludes", &self.watch_excludes)
+            .field("watch_extensions", &self.watch_extensions)
+            .field("config", &self.config)
+            .finish()
+    }
 }
 
 impl Default for RustAnalyzerishBuilder {
@@ -38,6 +197,7 @@ impl RustAnalyzerishBuilder {
     pub fn new() -> Self {
         Self {
             project_root: None,
+            detached_file: None,
             cargo_config: CargoConfig {
                 sysroot: Some(RustLibSource::Discover),
                 all_targets: true,
@@ -50,6 +210,14 @@ impl RustAnalyzerishBuilder {
                 with_proc_macro_server: ProcMacroServerChoice::Sysroot,
                 prefill_caches: false, // We handle this manually to add more cores
             },
+            edition: None,
+            sysroot_query_metadata: true,
+            prime_threads: None,
+            prime_caches: true,
+            progress: None,
+            watch_excludes: Vec::new(),
+            watch_extensions: Vec::new(),
+            config: None,
         }
     }
 
@@ -65,12 +233,266 @@ impl RustAnalyzerishBuilder {
         Ok(Self::new().with_workspace(project_root))
     }
 
+    /// Create a builder analyzing a single file outside any cargo project
+    ///
+    /// The file becomes the root of a synthetic crate, like rust-analyzer's
+    /// detached files, so scratch files can be analyzed. Other modules of the
+    /// file's directory are only loaded if declared with `mod`.
+    pub fn from_detached_file<P: AsRef<Path>>(file_path: P) -> Result<Self> {
+        let file_path = file_path.as_ref();
+        if file_path
+            .extension()
+            .is_none_or(|extension| extension != "rs")
+        {
+            return Err(anyhow::anyhow!(
+                "Detached file {} is not a Rust source file",
+                file_path.display()
+            ));
+        }
+        let file_path = file_path.canonicalize()?;
+        let parent = file_path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Detached file {} has no parent", file_path.display()))?
+            .to_path_buf();
+
+        let mut builder = Self::new().with_workspace(parent);
+        builder.detached_file = Some(file_path);
+        Ok(builder)
+    }
+
+    /// Create a builder for a file and apply workspace options
+    ///
+    /// Falls back to [`Self::from_detached_file`] when the file has no
+    /// enclosing `Cargo.toml` and `options.allow_detached` is set. Otherwise
+    /// out-of-workspace files are an error, like with [`Self::from_file`].
+    pub fn from_file_with_options<P: AsRef<Path>>(
+        file_path: P,
+        options: &WorkspaceOptions,
+    ) -> Result<Self> {
+        let builder = match Self::discover_project_root(file_path.as_ref())? {
+            Some(project_root) => Self::new().with_workspace(project_root),
+            None if options.allow_detached => {
+                info!(
+                    "No Cargo.toml found, analyzing {} as a detached file",
+                    file_path.as_ref().display()
+                );
+                Self::from_detached_file(file_path)?
+            }
+            None => return Err(Self::no_manifest_error(file_path.as_ref())),
+        };
+        builder.with_options(options)
+    }
+
+    /// The workspace root directory, if one has been set
+    pub fn project_root(&self) -> Option<&Path> {
+        self.project_root.as_deref()
+    }
+
     /// Configure cargo settings
     pub fn with_cargo_config(mut self, cargo_config: CargoConfig) -> Self {
         self.cargo_config = cargo_config;
         self
     }
 
+    /// Override cfgs for the crates in the workspace
+    pub fn with_cfg_overrides(mut self, cfg_overrides: CfgOverrides) -> Self {
+        self.cargo_config.cfg_overrides = cfg_overrides;
+        self
+    }
+
+    /// Whether `cfg(test)` is set for workspace crates, off by default
+    ///
+    /// With it set, code is analyzed as it compiles under `cargo test`:
+    /// `#[cfg(test)]` modules and items resolve, and items disabled with
+    /// `#[cfg(not(test))]` don't. Dependencies are never compiled for tests, so
+    /// they are unaffected. Other cfgs can be set with [`Self::with_cfg_overrides`].
+    pub fn with_test_cfg(mut self, include_tests: bool) -> Self {
+        self.cargo_config.set_test = include_tests;
+        self
+    }
+
+    /// Don't load the standard library sources
+    ///
+    /// Makes loading considerably faster, at the cost of accuracy: types and
+    /// items from `std`, `core` and `alloc` won't resolve, so type hints,
+    /// completions and definitions involving them are missing or `{unknown}`.
+    pub fn without_sysroot(mut self) -> Self {
+        self.cargo_config.sysroot = None;
+        self
+    }
+
+    /// Load the standard library of the toolchain at `sysroot`, instead of
+    /// discovering the active one
+    ///
+    /// `sysroot` is a toolchain directory as printed by `rustc --print sysroot`,
+    /// e.g. `~/.rustup/toolchains/1.85.0-x86_64-unknown-linux-gnu`. Useful when
+    /// the project pins a toolchain that isn't active where the analyzer runs.
+    /// Fails if the directory doesn't exist; a toolchain without the `rust-src`
+    /// component is accepted with a warning since std won't resolve.
+    pub fn with_sysroot_path<P: AsRef<Path>>(mut self, sysroot: P) -> Result<Self> {
+        let sysroot = sysroot.as_ref();
+        if !sysroot.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Sysroot {} is not a directory. Pass the output of `rustc --print sysroot` for the toolchain",
+                sysroot.display()
+            ));
+        }
+        if !sysroot.join("lib/rustlib/src/rust/library").is_dir() {
+            warn!(
+                "Sysroot {} has no standard library sources, std won't resolve. Install them with `rustup component add rust-src`",
+                sysroot.display()
+            );
+        }
+        let sysroot = RustAnalyzerUtils::path_to_abs_path(&sysroot.canonicalize()?)?;
+        self.cargo_config.sysroot = Some(RustLibSource::Path(sysroot));
+        Ok(self)
+    }
+
+    /// Whether to query `cargo metadata` for the sysroot's own dependencies,
+    /// the default
+    ///
+    /// The pinned rust-analyzer always loads the sysroot as a cargo workspace,
+    /// so disabling this only warns that the setting has no effect. Skip the
+    /// sysroot entirely with [`Self::without_sysroot`] to avoid the query.
+    pub fn with_sysroot_query_metadata(mut self, sysroot_query_metadata: bool) -> Self {
+        self.sysroot_query_metadata = sysroot_query_metadata;
+        self
+    }
+
+    /// Force the Rust edition of workspace crates
+    ///
+    /// Overrides the `edition` of every crate of the workspace, while
+    /// dependencies and the sysroot keep their own. Useful to check how code
+    /// parses and resolves under another edition before migrating.
+    pub fn with_edition(mut self, edition: Edition) -> Self {
+        self.edition = Some(edition);
+        self
+    }
+
+    /// Don't start a proc-macro server
+    ///
+    /// Makes loading faster, at the cost of accuracy: derives and attribute
+    /// macros from proc-macro crates aren't expanded, so the items they
+    /// generate are unknown to the analyzer.
+    pub fn without_proc_macro_server(mut self) -> Self {
+        self.load_config.with_proc_macro_server = ProcMacroServerChoice::None;
+        self
+    }
+
+    /// Cap the number of threads used to prime caches
+    ///
+    /// Defaults to the number of physical cores, which can starve other jobs
+    /// on shared machines such as CI runners. A value of zero is treated as one.
+    pub fn with_prime_threads(mut self, threads: usize) -> Self {
+        self.prime_threads = Some(threads);
+        self
+    }
+
+    /// Whether to prime caches after loading the workspace, the default
+    ///
+    /// With priming disabled the analyzer is lazy: startup is near-instant since
+    /// salsa computes what each query needs on demand, but the first query is
+    /// correspondingly slower, and so is the first query touching a new area of
+    /// the workspace. Useful for interactive tools that may only look at a
+    /// handful of files.
+    pub fn with_prime_caches(mut self, prime_caches: bool) -> Self {
+        self.prime_caches = prime_caches;
+        self
+    }
+
+    /// Don't prime caches after loading the workspace
+    ///
+    /// Shorthand for `with_prime_caches(false)`.
+    pub fn without_cache_priming(self) -> Self {
+        self.with_prime_caches(false)
+    }
+
+    /// Report loading and cache priming progress to a callback
+    ///
+    /// Progress is always traced; the callback additionally surfaces it to
+    /// users, e.g. as MCP progress notifications during long loads.
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Don't watch these directories for changes, besides `target` and `.git`
+    ///
+    /// Paths are relative to the workspace root, or absolute, and may be glob
+    /// patterns such as `crates/*/generated`. Excluding generated or vendored
+    /// directories of large repositories reduces watcher overhead and avoids
+    /// reloading on irrelevant changes.
+    pub fn with_watch_excludes(mut self, paths: Vec<String>) -> Self {
+        self.watch_excludes.extend(paths);
+        self
+    }
+
+    /// Also watch files with these extensions, besides `rs` and `toml`
+    pub fn with_watch_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.watch_extensions.extend(extensions);
+        self
+    }
+
+    /// Override the hover, completion and inlay hint settings
+    ///
+    /// Without an explicit config, a `rustbelt.json` at the workspace root is
+    /// loaded if present, see [`AnalyzerConfig`].
+    pub fn with_config(mut self, config: AnalyzerConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Load the analyzer settings from a JSON config file
+    pub fn with_config_file<P: AsRef<Path>>(self, path: P) -> Result<Self> {
+        Ok(self.with_config(AnalyzerConfig::load(path)?))
+    }
+
+    /// Apply user-facing workspace options
+    pub fn with_options(mut self, options: &WorkspaceOptions) -> Result<Self> {
+        if !options.cfgs.is_empty() {
+            self = self.with_cfg_overrides(options.cfg_overrides()?);
+        }
+        if let Some(edition) = options.edition()? {
+            self = self.with_edition(edition);
+        }
+        if options.include_tests {
+            self = self.with_test_cfg(true);
+        }
+        if let Some(sysroot) = &options.sysroot {
+            if options.no_sysroot {
+                return Err(anyhow::anyhow!(
+                    "An explicit sysroot can't be combined with skipping the sysroot"
+                ));
+            }
+            self = self.with_sysroot_path(sysroot)?;
+        }
+        if options.no_sysroot {
+            self = self.without_sysroot();
+        }
+        if options.no_sysroot_metadata {
+            self = self.with_sysroot_query_metadata(false);
+        }
+        if options.no_proc_macros {
+            self = self.without_proc_macro_server();
+        }
+        if let Some(threads) = options.prime_threads {
+            self = self.with_prime_threads(threads);
+        }
+        if options.no_prime_caches {
+            self = self.without_cache_priming();
+        }
+        if !options.watch_excludes.is_empty() {
+            self = self.with_watch_excludes(options.watch_excludes.clone());
+        }
+        if !options.watch_extensions.is_empty() {
+            self = self.with_watch_extensions(options.watch_extensions.clone());
+        }
+        if let Some(config_file) = &options.config_file {
+            self = self.with_config_file(config_file)?;
+        }
+        Ok(self)
+    }
+
     /// Configure load settings
     pub fn with_load_config(mut self, load_config: LoadCargoConfig) -> Self {
         self.load_config = load_config;
@@ -85,14 +507,38 @@ impl RustAnalyzerishBuilder {
             .ok_or_else(|| anyhow::anyhow!("No workspace root specified."))?;
 
         let abs_project_root = RustAnalyzerUtils::path_to_abs_path(&project_root)?;
+        // Fail on a broken config before spending time loading the workspace
+        let config = match &self.config {
+            Some(config) => config.clone(),
+            None => AnalyzerConfig::discover(&project_root)?.unwrap_or_default(),
+        };
 
         let (analysis_host, file_watcher) = self.load_workspace(&abs_project_root)?;
 
-        Ok(RustAnalyzerish::new(analysis_host, file_watcher))
+        let mut analyzer = RustAnalyzerish::new(analysis_host, file_watcher);
+        analyzer.set_caches_primed(self.prime_caches);
+        analyzer.set_config(config);
+        Ok(analyzer)
     }
 
     /// Find the project root by looking for Cargo.toml
     fn find_project_root(file_path: &Path) -> Result<PathBuf> {
+        Self::discover_project_root(file_path)?.ok_or_else(|| Self::no_manifest_error(file_path))
+    }
+
+    /// The error for a file outside of any cargo project
+    fn no_manifest_error(file_path: &Path) -> anyhow::Error {
+        ErrorKind::FileNotFound.error(format!(
+            "No Cargo.toml found for {}. Allow detached files to analyze it standalone",
+            file_path.display()
+        ))
+    }
+
+    /// Find the project root of a file, or `None` if it isn't in any project
+    ///
+    /// Other failures, such as a missing file or several candidate manifests,
+    /// are errors so they aren't mistaken for a file outside any project.
+    fn discover_project_root(file_path: &Path) -> Result<Option<PathBuf>> {
         let path = if file_path.is_absolute() {
             info!(
                 "Finding project root for absolute path: {}",
@@ -108,23 +554,58 @@ impl RustAnalyzerishBuilder {
         };
 
         let abs_path = RustAnalyzerUtils::path_to_abs_path(&path)?;
-        let root = ProjectManifest::discover_single(&abs_path)?;
-        Ok(root.manifest_path().parent().to_path_buf().into())
+        let mut manifests = ProjectManifest::discover(&abs_path)
+            .with_context(|| format!("Failed to search for a Cargo.toml for {}", path.display()))?;
+        if manifests.len() > 1 {
+            return Err(anyhow::anyhow!(
+                "Found {} project manifests for {}, expected one",
+                manifests.len(),
+                path.display()
+            ));
+        }
+        Ok(manifests
+            .pop()
+            .map(|root| root.manifest_path().parent().to_path_buf().into()))
     }
 
     /// Load workspace and return (AnalysisHost, FileWatcher)
     fn load_workspace(&self, abs_project_root: &AbsPathBuf) -> Result<(AnalysisHost, FileWatcher)> {
         info!("Loading workspace from: {}", abs_project_root);
         let mut stop_watch = StopWatch::start();
+        report(
+            &self.progress,
+            LoadProgress::LoadingWorkspace(abs_project_root.to_string()),
+        );
 
-        let (db, vfs, _proc_macro) = load_workspace_at(
-            abs_project_root.as_ref(),
-            &self.cargo_config,
-            &self.load_config,
-            &|msg| {
-                trace!("Workspace loading progress: {}", msg);
-            },
-        )?;
+        if !self.sysroot_query_metadata && self.cargo_config.sysroot.is_some() {
+            warn!(
+                "Skipping the sysroot metadata query isn't supported by this rust-analyzer, the sysroot is loaded as a cargo workspace"
+            );
+        }
+
+        let (mut db, vfs, _proc_macro) = match &self.detached_file {
+            Some(detached_file) => {
+                let manifest =
+                    ManifestPath::try_from(RustAnalyzerUtils::path_to_abs_path(detached_file)?)
+                        .map_err(|path| anyhow::anyhow!("Invalid detached file: {}", path))?;
+                let workspace =
+                    ProjectWorkspace::load_detached_file(&manifest, &self.cargo_config)?;
+                load_workspace(workspace, &self.cargo_config.extra_env, &self.load_config)?
+            }
+            None => load_workspace_at(
+                abs_project_root.as_ref(),
+                &self.cargo_config,
+                &self.load_config,
+                &|msg| {
+                    trace!("Workspace loading progress: {}", msg);
+                    report(&self.progress, LoadProgress::LoadingWorkspace(msg));
+                },
+            )?,
+        };
+
+        if let Some(edition) = self.edition {
+            Self::override_edition(&mut db, edition);
+        }
 
         // Create analysis host with the loaded database
         let mut host = AnalysisHost::with_database(db);
@@ -136,29 +617,78 @@ impl RustAnalyzerishBuilder {
             elapsed.memory.allocated.megabytes() as u64
         );
 
-        // Set up file watching
+        // Set up file watching, of only the file itself when detached
         let mut file_watcher = FileWatcher::new();
-        file_watcher.setup_file_watching(abs_project_root.clone(), vfs, &mut host)?;
+        file_watcher.set_watch_excludes(self.watch_excludes.clone());
+        file_watcher.set_watch_extensions(self.watch_extensions.clone());
+        match &self.detached_file {
+            Some(detached_file) => file_watcher.setup_detached_file_watching(
+                RustAnalyzerUtils::path_to_abs_path(detached_file)?,
+                vfs,
+                &mut host,
+            )?,
+            None => file_watcher.setup_file_watching(abs_project_root.clone(), vfs, &mut host)?,
+        }
 
-        // Prime caches with all available cores for better performance
-        let threads = num_cpus::get_physical();
-        prime_caches::parallel_prime_caches(host.raw_database(), threads, &|progress| {
-            trace!("Cache priming progress: {:?}", progress);
-        });
+        // Prime caches with all available cores unless capped or disabled
+        if self.prime_caches {
+            let threads = self
+                .prime_threads
+                .unwrap_or_else(num_cpus::get_physical)
+                .max(1);
+            prime_caches::parallel_prime_caches(host.raw_database(), threads, &|progress| {
+                trace!("Cache priming progress: {:?}", progress);
+                report(
+                    &self.progress,
+                    LoadProgress::PrimingCaches {
+                        done: progress.crates_done,
+                        total: progress.crates_total,
+                    },
+                );
+            });
 
-        let elapsed = stop_watch.elapsed();
-        info!(
-            "Cache priming time with {} cores: {:?}ms, total memory allocated: {}MB",
-            threads,
-            elapsed.time.as_millis(),
-            elapsed.memory.allocated.megabytes() as u64
-        );
+            let elapsed = stop_watch.elapsed();
+            info!(
+                "Cache priming time with {} cores: {:?}ms, total memory allocated: {}MB",
+                threads,
+                elapsed.time.as_millis(),
+                elapsed.memory.allocated.megabytes() as u64
+            );
+        } else {
+            info!(
+                "Skipping cache priming, workspace ready after {:?}ms. The first queries will be slower while caches are computed on demand",
+                stop_watch.elapsed().time.as_millis()
+            );
+        }
 
         // Print all files in vfs for debugging
         for (file_id, vfs_path) in file_watcher.vfs().iter() {
             trace!("Loaded file in VFS: {:?} - {}", file_id, vfs_path);
         }
 
+        report(&self.progress, LoadProgress::Finished);
         Ok((host, file_watcher))
     }
+
+    /// Set the edition of every workspace crate, leaving dependencies alone
+    fn override_edition(db: &mut RootDatabase, edition: Edition) {
+        let crates = db.all_crates();
+        for &krate in crates.iter() {
+            let data = krate.data(db);
+            if !data.origin.is_local() || data.edition == edition {
+                continue;
+            }
+            let mut data = data.clone();
+            data.edition = edition;
+            krate.set_data(db).to(data);
+        }
+        info!("Analyzing workspace crates with edition {}", edition);
+    }
+}
+
+/// Forward progress to the callback, if any
+fn report(callback: &Option<ProgressCallback>, progress: LoadProgress) {
+    if let Some(callback) = callback {
+        callback(progress);
+    }
 }
@@ -0,0 +1,226 @@
+//! Tuning of rust-analyzer's hover, completion and inlay hint configs
+//!
+//! Defaults match what the tools were designed around. A `rustbelt.json` file
+//! at the workspace root, or one passed explicitly, overrides them at startup
+//! without recompiling. Every key is optional, e.g.
+//!
+//! ```json
+//! {
+//!     "hover": { "max_fields_count": 20 },
+//!     "completion": { "enable_term_search": true },
+//!     "inlay_hints": { "chaining_hints": true, "max_length": 25 }
+//! }
+//! ```
+//!
+//! Keys are named after the rust-analyzer config fields they set, in
+//! `HoverConfig`, `CompletionConfig` and `InlayHintsConfig` respectively.
+//! Unknown keys are logged as warnings and otherwise ignored.
+
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+/// Name of the config file discovered at the workspace root
+pub const CONFIG_FILE_NAME: &str = "rustbelt.json";
+
+/// Overrides for the rust-analyzer configs used by the analyzer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalyzerConfig {
+    /// Settings of type hints, see [`HoverSettings`]
+    pub hover: HoverSettings,
+    /// Settings of completions, see [`CompletionSettings`]
+    pub completion: CompletionSettings,
+    /// Settings of inlay hints, see [`InlayHintSettings`]
+    pub inlay_hints: InlayHintSettings,
+}
+
+/// Fields of rust-analyzer's `HoverConfig` used by type hints
+///
+/// Memory layout and drop glue are requested per call with `TypeHintOptions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HoverSettings {
+    /// `links_in_hover`: keep links to definitions in documentation
+    pub links_in_hover: bool,
+    /// `documentation`: include the doc comments of the symbol
+    pub documentation: bool,
+    /// `keywords`: show documentation when hovering keywords
+    pub keywords: bool,
+    /// `max_trait_assoc_items_count`: associated items shown for a trait, all if `null`
+    pub max_trait_assoc_items_count: Option<usize>,
+    /// `max_fields_count`: fields shown for a struct or variant, all if `null`
+    pub max_fields_count: Option<usize>,
+    /// `max_enum_variants_count`: variants shown for an enum, all if `null`
+    pub max_enum_variants_count: Option<usize>,
+}
+
+impl Default for HoverSettings {
+    fn default() -> Self {
+        Self {
+            links_in_hover: true,
+            documentation: true,
+            keywords: true,
+            max_trait_assoc_items_count: Some(10),
+            max_fields_count: Some(10),
+            max_enum_variants_count: Some(10),
+        }
+    }
+}
+
+/// Fields of rust-analyzer's `CompletionConfig`
+///
+/// Snippets, import edits and the result limit are set per call with
+/// `CompletionOptions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompletionSettings {
+    /// `enable_postfix_completions`: offer `.if`, `.match`, `.ok()` and the like
+    pub enable_postfix_completions: bool,
+    /// `enable_self_on_the_fly`: complete fields and methods of `self` without `self.`
+    pub enable_self_on_the_fly: bool,
+    /// `enable_auto_iter`: complete iterator methods on `IntoIterator` types
+    pub enable_auto_iter: bool,
+    /// `enable_auto_await`: complete methods of the output of futures
+    pub enable_auto_await: bool,
+    /// `enable_private_editable`: complete private items of workspace crates
+    pub enable_private_editable: bool,
+    /// `enable_term_search`: suggest expressions of the expected type
+    pub enable_term_search: bool,
+    /// `term_search_fuel`: effort spent on term search
+    pub term_search_fuel: u64,
+    /// `full_function_signatures`: show the whole signature in function details
+    pub full_function_signatures: bool,
+    /// `add_semicolon_to_unit`: end calls of unit functions with `;`
+    pub add_semicolon_to_unit: bool,
+    /// `prefer_no_std`: import from `core` or `alloc` rather than `std`
+    pub prefer_no_std: bool,
+    /// `prefer_prelude`: import through preludes when possible
+    pub prefer_prelude: bool,
+    /// `prefer_absolute`: import with paths starting with `::`
+    pub prefer_absolute: bool,
+}
+
+impl Default for CompletionSettings {
+    fn default() -> Self {
+        Self {
+            enable_postfix_completions: true,
+            enable_self_on_the_fly: false,
+            enable_auto_iter: true,
+            enable_auto_await: true,
+            enable_private_editable: false,
+            enable_term_search: false,
+            term_search_fuel: 400,
+            full_function_signatures: false,
+            add_semicolon_to_unit: false,
+            prefer_no_std: false,
+            prefer_prelude: true,
+            prefer_absolute: false,
+        }
+    }
+}
+
+/// Fields of rust-analyzer's `InlayHintsConfig`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InlayHintSettings {
+    /// `render_colons`: prefix type hints with `: `
+    pub render_colons: bool,
+    /// `type_hints`: types of `let` bindings and patterns
+    pub type_hints: bool,
+    /// `parameter_hints`: parameter names of call arguments
+    pub parameter_hints: bool,
+    /// `chaining_hints`: types at each step of method chains
+    pub chaining_hints: bool,
+    /// `closure_capture_hints`: variables captured by closures
+    pub closure_capture_hints: bool,
+    /// `binding_mode_hints`: `ref` and `&` of pattern bindings
+    pub binding_mode_hints: bool,
+    /// `implicit_drop_hints`: where values are dropped
+    pub implicit_drop_hints: bool,
+    /// `hide_named_constructor_hints`: skip types of `Type::new()`-like constructors
+    pub hide_named_constructor_hints: bool,
+    /// `hide_closure_initialization_hints`: skip types of `let` bound closures
+    pub hide_closure_initialization_hints: bool,
+    /// `range_exclusive_hints`: `<` and `>` on the bounds of exclusive ranges
+    pub range_exclusive_hints: bool,
+    /// `max_length`: truncate longer hints, unlimited if `null`
+    pub max_length: Option<usize>,
+}
+
+impl Default for InlayHintSettings {
+    fn default() -> Self {
+        Self {
+            render_colons: false,
+            type_hints: true,
+            parameter_hints: true,
+            chaining_hints: false,
+            closure_capture_hints: false,
+            binding_mode_hints: false,
+            implicit_drop_hints: false,
+            hide_named_constructor_hints: false,
+            hide_closure_initialization_hints: false,
+            range_exclusive_hints: false,
+            max_length: None,
+        }
+    }
+}
+
+impl AnalyzerConfig {
+    /// Parse a JSON config, warning about keys that don't override anything
+    pub fn from_json(text: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(text)
+            .map_err(|e| anyhow::anyhow!("Invalid rustbelt config: {}", e))?;
+        for key in Self::unknown_keys(&value) {
+            warn!("Ignoring unknown rustbelt config key '{}'", key);
+        }
+        serde_json::from_value(value).map_err(|e| anyhow::anyhow!("Invalid rustbelt config: {}", e))
+    }
+
+    /// Load a JSON config file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read config file {}: {}", path.display(), e))?;
+        Self::from_json(&text)
+            .map_err(|e| anyhow::anyhow!("Failed to load config file {}: {}", path.display(), e))
+    }
+
+    /// Load [`CONFIG_FILE_NAME`] from a workspace root, if the file exists
+    pub fn discover<P: AsRef<Path>>(workspace_root: P) -> Result<Option<Self>> {
+        let path = workspace_root.as_ref().join(CONFIG_FILE_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        info!("Loading config from {}", path.display());
+        Self::load(path).map(Some)
+    }
+
+    /// Dotted paths of the keys of `value` that aren't config fields, e.g.
+    /// `hover.max_field_count`
+    pub fn unknown_keys(value: &Value) -> Vec<String> {
+        // The defaults serialize every field, so they double as the schema
+        let known = serde_json::to_value(Self::default()).unwrap_or_default();
+        let mut unknown = Vec::new();
+        Self::collect_unknown_keys(value, &known, "", &mut unknown);
+        unknown
+    }
+
+    fn collect_unknown_keys(value: &Value, known: &Value, prefix: &str, unknown: &mut Vec<String>) {
+        let (Value::Object(fields), Value::Object(known_fields)) = (value, known) else {
+            return;
+        };
+        for (key, field) in fields {
+            let path = format!("{prefix}{key}");
+            match known_fields.get(key) {
+                Some(known_field) => {
+                    Self::collect_unknown_keys(field, known_field, &format!("{path}."), unknown)
+                }
+                None => unknown.push(path),
+            }
+        }
+    }
+}
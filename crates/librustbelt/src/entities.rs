@@ -1,8 +1,21 @@
-use ra_ap_ide::LineCol;
+use ra_ap_ide::{LineCol, Severity};
 use ra_ap_ide_db::SymbolKind;
+use ra_ap_ide_db::search::ReferenceCategory as RaReferenceCategory;
 use serde::{Deserialize, Serialize};
 
 const TOLERANCE: u32 = 5;
+
+/// Serialize a rust-analyzer symbol kind by its name, e.g. `"Struct"`
+fn serialize_symbol_kind<S: serde::Serializer>(
+    kind: &Option<SymbolKind>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match kind {
+        Some(kind) => serializer.serialize_some(&format!("{kind:?}")),
+        None => serializer.serialize_none(),
+    }
+}
+
 /// Cursor coordinates for specifying position in a file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -126,7 +139,7 @@ impl From<&CursorCoordinates> for LineCol {
 }
 
 /// Information about a definition location
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DefinitionInfo {
     /// Path to the file containing the definition
     pub file_path: String,
@@ -141,6 +154,7 @@ pub struct DefinitionInfo {
     /// Name of the defined symbol
     pub name: String,
     /// Kind of the symbol (function, struct, etc.)
+    #[serde(serialize_with = "serialize_symbol_kind")]
     pub kind: Option<SymbolKind>,
     /// Content of the definition
     pub content: String,
@@ -148,27 +162,122 @@ pub struct DefinitionInfo {
     pub module: String,
     /// Rustdoc description, if available
     pub description: Option<String>,
+    /// Header of the enclosing `impl`, `trait` or type, e.g. `impl Person`,
+    /// when requested with `include_container`
+    pub container: Option<String>,
+    /// Whether this is a `use` item re-exporting the definitions that follow
+    /// it, when requested with `follow_reexports`
+    pub is_reexport: bool,
+    /// Whether the definition is outside of the workspace, in the sysroot or
+    /// a dependency, whose files may not be readable by the caller
+    pub is_external: bool,
+    /// Source lines around an external definition, when requested with
+    /// `include_external_source`
+    pub source: Option<SourceExcerpt>,
+}
+
+/// Lines of a source file around an item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceExcerpt {
+    /// First line (1-based) of the excerpt
+    pub start_line: u32,
+    /// Last line (1-based, inclusive) of the excerpt
+    pub end_line: u32,
+    /// Text of the lines, each ending with a newline
+    pub text: String,
+    /// Whether the excerpt was cut short of the end of the item
+    pub truncated: bool,
+}
+
+/// A trait and the impls of it found in the workspace
+#[derive(Debug, Clone, Serialize)]
+pub struct TraitImpls {
+    /// Definition of the trait, with its module path
+    pub trait_definition: DefinitionInfo,
+    /// Impl blocks of the trait declared in workspace crates
+    pub impls: Vec<DefinitionInfo>,
 }
 
 /// Information about a rename operation result
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RenameResult {
     /// Files that will be changed by the rename operation
     pub file_changes: Vec<FileChange>,
+    /// Files and directories moved by the rename, e.g. `foo.rs` to `bar.rs`
+    /// when renaming module `foo`
+    pub file_renames: Vec<FileRename>,
+    /// Every changed line before and after the rename, in file order, shown
+    /// by [`RenameResult::preview`]
+    pub line_previews: Vec<LinePreview>,
+}
+
+impl RenameResult {
+    /// Readable preview showing each changed line before and after the rename,
+    /// instead of the edit ranges the [`Display`](std::fmt::Display) shows
+    pub fn preview(&self) -> String {
+        let mut preview = format!("Rename changes {} file(s):\n", self.file_changes.len());
+        for line_preview in &self.line_previews {
+            preview.push_str(&format!("\n{line_preview}\n"));
+        }
+        for file_rename in &self.file_renames {
+            preview.push_str(&format!("\n{file_rename}\n"));
+        }
+        preview
+    }
+}
+
+/// Lines changed by a set of edits, before and after applying them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinePreview {
+    /// Path to the changed file
+    pub file_path: String,
+    /// First changed line (1-based)
+    pub line: u32,
+    /// Original text of the changed lines
+    pub before: String,
+    /// Text of the same lines once the edits are applied
+    pub after: String,
+}
+
+/// A file or directory moved by a rename, after its text edits are applied
+#[derive(Debug, Clone, Serialize)]
+pub struct FileRename {
+    /// Current path of the file or directory
+    pub from: String,
+    /// Path it is moved to
+    pub to: String,
+}
+
+/// Result of moving an item to another module
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveItemResult {
+    /// Name of the moved item
+    pub item_name: String,
+    /// Path of the module the item was moved out of, e.g. `crate::a`
+    pub from_module: String,
+    /// Path of the module the item was moved into
+    pub to_module: String,
+    /// Files changed by the move, including updated references
+    pub file_changes: Vec<FileChange>,
+    /// References that could not be updated automatically
+    pub warnings: Vec<String>,
 }
 
 /// Information about changes to a single file during rename
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FileChange {
     /// Path to the file that will be changed
     pub file_path: String,
     /// List of text edits to apply to this file
     pub edits: Vec<TextEdit>,
+    /// Unified diff of the applied change, if requested with
+    /// [`ApplyOptions::return_diff`]
+    pub diff: Option<String>,
 }
 
 /// A single text edit within a file
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TextEdit {
     /// Line number (1-based) where the edit starts
@@ -184,7 +293,7 @@ pub struct TextEdit {
 }
 
 /// A type hint for a given symbol
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TypeHint {
     pub file_path: String,
     /// Line number (1-based) where the edit starts
@@ -193,10 +302,141 @@ pub struct TypeHint {
     pub column: u32,
     pub symbol: String,
     pub canonical_types: Vec<String>,
+    /// Whether the type was fully inferred, false when the hover contains
+    /// placeholders such as `{unknown}` or `Vec<_>`
+    pub fully_resolved: bool,
+}
+
+/// Definition, type and usage count of a symbol, gathered in one call
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolDescription {
+    /// Where the symbol is defined, empty for e.g. literals
+    pub definitions: Vec<DefinitionInfo>,
+    /// Hover text and types of the symbol, if rust-analyzer has any
+    pub type_hint: Option<TypeHint>,
+    /// Number of usages across the workspace, excluding definitions
+    pub reference_count: usize,
+}
+
+impl std::fmt::Display for SymbolDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.definitions.is_empty() {
+            writeln!(f, "Definition: none found")?;
+        }
+        for definition in &self.definitions {
+            writeln!(f, "Definition: {definition:#}")?;
+        }
+        match &self.type_hint {
+            Some(type_hint) => writeln!(f, "Type: {type_hint}")?,
+            None => writeln!(f, "Type: no hover information available")?,
+        }
+        write!(f, "References: {}", self.reference_count)
+    }
+}
+
+/// Extra information to include in a type hint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeHintOptions {
+    /// Report size, alignment, field offsets and niches, as computed by
+    /// rust-analyzer for the host target
+    pub include_layout: bool,
+    /// Report whether dropping the type runs any code, and which fields need drop
+    pub show_drop_glue: bool,
+}
+
+/// Definitions found at a position, with a note when the lookup failed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DefinitionReport {
+    /// Definitions found, `None` when there is nothing to navigate to
+    pub definitions: Option<Vec<DefinitionInfo>>,
+    /// Non-fatal problem hit while looking, e.g. a rust-analyzer panic, in
+    /// which case missing definitions may still exist
+    pub note: Option<String>,
+}
+
+/// Options for resolving definitions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DefinitionOptions {
+    /// Include the header of the enclosing `impl`, `trait` or type
+    pub include_container: bool,
+    /// Follow definitions landing in a re-export to the original item
+    pub follow_reexports: bool,
+    /// Trim content to the item's signature, without attributes, docs or body
+    pub signature_only: bool,
+    /// When nothing is found at the position, retry on the nearest identifier of the line
+    pub fuzzy: bool,
+    /// Include the source lines of definitions outside of the workspace, such
+    /// as those in the cargo registry or the sysroot
+    pub include_external_source: bool,
+}
+
+/// How edits computed by a refactoring are applied
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ApplyOptions {
+    /// Include a unified diff of every changed file in the result
+    pub return_diff: bool,
+    /// Compute the changes without writing them to disk
+    pub dry_run: bool,
+}
+
+/// Options for a completion query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompletionOptions {
+    /// Also complete items that aren't in scope yet, returning for each the
+    /// edit adding its import, see [`CompletionItem::imports`]
+    pub include_import_edits: bool,
+    /// Return snippet bodies with tabstops and argument placeholders, see
+    /// [`CompletionItem::snippet`]. Leave off unless the caller expands
+    /// LSP-style snippets, as the tabstops would otherwise be inserted verbatim.
+    pub include_snippets: bool,
+    /// Maximum number of completions rust-analyzer computes, defaulting to
+    /// [`CompletionOptions::DEFAULT_LIMIT`] and capped at
+    /// [`CompletionOptions::MAX_LIMIT`]. Larger limits take longer and cost
+    /// more tokens to return.
+    pub limit: Option<usize>,
+    /// Keep rust-analyzer's order instead of sorting by relevance, useful
+    /// when debugging the ranking
+    pub unsorted: bool,
+    /// Return each item's relevance score, see [`CompletionItem::relevance`]
+    pub include_scores: bool,
+    /// Drop completions marked `#[deprecated]`
+    pub exclude_deprecated: bool,
+    /// Drop completions gated behind an unstable feature, so that generated
+    /// code builds on stable. Only names in scope are checked, not methods or
+    /// fields completed after a `.`.
+    pub exclude_unstable: bool,
+    /// Drop completions that need an import, such as methods of traits that
+    /// aren't in scope, so every suggestion compiles as-is. Only matters with
+    /// `include_import_edits`, as other completions are always in scope.
+    pub only_in_scope: bool,
+}
+
+impl CompletionOptions {
+    /// Completions computed when no limit is given
+    pub const DEFAULT_LIMIT: usize = 200;
+    /// Upper bound on the limit, to keep queries responsive
+    pub const MAX_LIMIT: usize = 2000;
+
+    /// Limit passed to rust-analyzer, clamped to `1..=MAX_LIMIT`
+    pub fn effective_limit(&self) -> usize {
+        self.limit
+            .unwrap_or(Self::DEFAULT_LIMIT)
+            .clamp(1, Self::MAX_LIMIT)
+    }
+}
+
+/// An import a completion needs, with the edit that adds it
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionImport {
+    /// Path of the imported item, e.g. `std::collections::HashMap`
+    pub path: String,
+    /// Edit inserting the `use`, which can be applied with
+    /// [`crate::RustAnalyzerUtils::apply_file_change`]
+    pub text_edit: FileChange,
 }
 
 /// A completion item for a given cursor position
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CompletionItem {
     /// The primary name/identifier
     pub name: String,
@@ -204,6 +444,12 @@ pub struct CompletionItem {
     // pub aliases: Vec<String>,
     /// Required import
     pub required_import: Option<String>,
+    /// Imports to add with their edits, only computed when
+    /// [`CompletionOptions::include_import_edits`] is set
+    pub imports: Vec<CompletionImport>,
+    /// Snippet to insert instead of the name, e.g. `foo(${1:x})$0`, only
+    /// computed when [`CompletionOptions::include_snippets`] is set
+    pub snippet: Option<String>,
     /// The trait this method comes from (for trait methods)
     // pub trait_source: Option<String>,
     /// The kind of completion (function, variable, etc.)
@@ -216,10 +462,13 @@ pub struct CompletionItem {
     pub documentation: Option<String>,
     /// Whether this completion is deprecated
     pub deprecated: bool,
+    /// Relevance score from rust-analyzer, higher is more relevant, only
+    /// returned when [`CompletionOptions::include_scores`] is set
+    pub relevance: Option<u32>,
 }
 
 /// Information about a reference location
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ReferenceInfo {
     /// Path to the file containing the reference
     pub file_path: String,
@@ -233,10 +482,117 @@ pub struct ReferenceInfo {
     pub end_column: u32,
     /// Name of the referenced symbol
     pub name: String,
-    /// Content of the reference (the line containing the reference)
+    /// Content of the reference (the line containing the reference, plus any
+    /// requested context lines around it)
     pub content: String,
     /// Whether this is a definition (true) or usage (false)
     pub is_definition: bool,
+    /// How the reference uses the symbol, empty for definitions
+    pub category: ReferenceCategory,
+}
+
+/// Where references to a symbol are searched for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReferenceScope {
+    /// Only the file of the cursor, fast for local variables
+    File,
+    /// The crate containing the file of the cursor
+    Crate,
+    /// Every crate of the workspace
+    #[default]
+    Workspace,
+}
+
+impl std::str::FromStr for ReferenceScope {
+    type Err = anyhow::Error;
+
+    fn from_str(scope: &str) -> anyhow::Result<Self> {
+        match scope.to_lowercase().as_str() {
+            "file" => Ok(Self::File),
+            "crate" => Ok(Self::Crate),
+            "workspace" => Ok(Self::Workspace),
+            _ => Err(anyhow::anyhow!(
+                "Invalid reference scope '{}': expected file, crate or workspace",
+                scope
+            )),
+        }
+    }
+}
+
+/// Options for finding references
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReferenceOptions {
+    /// Number of source lines to include before and after each reference
+    pub context_lines: u32,
+    /// Where references are searched for
+    pub scope: ReferenceScope,
+}
+
+/// Number of references to a symbol, without their locations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReferenceCount {
+    /// Name of the referenced symbol
+    pub name: String,
+    /// Number of references found, not counting the definition
+    pub count: usize,
+    /// Whether the symbol is referenced at all in the searched scope
+    pub is_used: bool,
+    /// Whether every reference was counted, false if the search stopped at
+    /// the first one
+    pub complete: bool,
+}
+
+/// Formats the count, e.g. `name: 3 reference(s)` or `name: unused`
+impl std::fmt::Display for ReferenceCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.is_used, self.complete) {
+            (false, _) => write!(f, "{}: unused", self.name),
+            (true, false) => write!(f, "{}: used", self.name),
+            (true, true) => write!(f, "{}: {} reference(s)", self.name, self.count),
+        }
+    }
+}
+
+/// How a reference uses the referenced symbol
+///
+/// A reference can fall in several categories, e.g. `x += 1` both reads and
+/// writes `x`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ReferenceCategory {
+    /// The symbol's value is read
+    pub read: bool,
+    /// The symbol is assigned or mutably borrowed
+    pub write: bool,
+    /// The reference is part of a `use` item
+    pub import: bool,
+    /// The reference is in test code
+    pub test: bool,
+}
+
+impl From<RaReferenceCategory> for ReferenceCategory {
+    fn from(category: RaReferenceCategory) -> Self {
+        Self {
+            read: category.contains(RaReferenceCategory::READ),
+            write: category.contains(RaReferenceCategory::WRITE),
+            import: category.contains(RaReferenceCategory::IMPORT),
+            test: category.contains(RaReferenceCategory::TEST),
+        }
+    }
+}
+
+impl std::fmt::Display for ReferenceCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let labels: Vec<&str> = [
+            (self.read, "read"),
+            (self.write, "write"),
+            (self.import, "import"),
+            (self.test, "test"),
+        ]
+        .into_iter()
+        .filter_map(|(set, label)| set.then_some(label))
+        .collect();
+        write!(f, "{}", labels.join(", "))
+    }
 }
 
 impl std::fmt::Display for TypeHint {
@@ -249,17 +605,84 @@ impl std::fmt::Display for TypeHint {
             self.column,
             self.symbol,
             self.canonical_types.join(", ")
-        )
+        )?;
+        if !self.fully_resolved {
+            write!(
+                f,
+                "\nPartially inferred: placeholders stand for unknown types"
+            )?;
+        }
+        Ok(())
     }
 }
 
+/// The default format is `file:line:column` followed by the content. The
+/// alternate (verbose) format, `{:#}`, additionally includes the end position,
+/// the symbol kind and the module path: `file:line:column-end_line:end_column
+/// (Kind) module`. Re-exports are marked with `(re-export)` after the position.
 impl std::fmt::Display for DefinitionInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
+        let reexport = if self.is_reexport { " (re-export)" } else { "" };
+        let external = if self.is_external { " (external)" } else { "" };
+        if f.alternate() {
+            write!(
+                f,
+                "{}:{}:{}-{}:{}{reexport}{external}",
+                self.file_path, self.line, self.column, self.end_line, self.end_column
+            )?;
+            if let Some(kind) = self.kind {
+                write!(f, " ({kind:?})")?;
+            }
+            write!(f, " {}\n{}", self.module, self.content_with_container())?;
+        } else {
+            write!(
+                f,
+                "{}:{}:{}{reexport}{external}\n{}",
+                self.file_path,
+                self.line,
+                self.column,
+                self.content_with_container()
+            )?;
+        }
+        if let Some(source) = &self.source {
+            write!(
+                f,
+                "\n\nSource of {}:{}-{}:\n{}",
+                self.file_path, source.start_line, source.end_line, source.text
+            )?;
+            if source.truncated {
+                writeln!(f, "... (truncated)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DefinitionInfo {
+    /// Definition content, nested in its container's header when known
+    fn content_with_container(&self) -> String {
+        match &self.container {
+            Some(container) => format!("{container} {{\n    {}\n}}", self.content),
+            None => self.content.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for TraitImpls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
             f,
-            "{}:{}:{}\n{}",
-            self.file_path, self.line, self.column, self.content
-        )
+            "trait {} ({}:{}:{}): {} impl(s)",
+            self.trait_definition.module,
+            self.trait_definition.file_path,
+            self.trait_definition.line,
+            self.trait_definition.column,
+            self.impls.len()
+        )?;
+        for definition in &self.impls {
+            writeln!(f, "{definition}")?;
+        }
+        Ok(())
     }
 }
 
@@ -274,6 +697,49 @@ impl std::fmt::Display for RenameResult {
         for file_change in &self.file_changes {
             writeln!(f, "{file_change}")?;
         }
+        for file_rename in &self.file_renames {
+            writeln!(f, "{file_rename}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for LinePreview {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file_path, self.line)?;
+        for line in self.before.lines() {
+            write!(f, "\n  - {line}")?;
+        }
+        for line in self.after.lines() {
+            write!(f, "\n  + {line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for FileRename {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Moved {} to {}", self.from, self.to)
+    }
+}
+
+impl std::fmt::Display for MoveItemResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Moved `{}` from {} to {}, {} file(s) changed:",
+            self.item_name,
+            self.from_module,
+            self.to_module,
+            self.file_changes.len()
+        )?;
+        writeln!(f)?;
+        for file_change in &self.file_changes {
+            writeln!(f, "{file_change}")?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "Warning: {warning}")?;
+        }
         Ok(())
     }
 }
@@ -284,6 +750,9 @@ impl std::fmt::Display for FileChange {
         for edit in &self.edits {
             writeln!(f, "  ↳ {edit}")?;
         }
+        if let Some(diff) = &self.diff {
+            write!(f, "{diff}")?;
+        }
         Ok(())
     }
 }
@@ -307,6 +776,26 @@ impl std::fmt::Display for CompletionItem {
         if let Some(ref sig) = self.signature {
             write!(f, " - {sig}")?;
         }
+        if let Some(relevance) = self.relevance {
+            write!(f, " [relevance {relevance}]")?;
+        }
+        // Import edits below already name the import
+        if let Some(import) = self
+            .required_import
+            .as_ref()
+            .filter(|_| self.imports.is_empty())
+        {
+            write!(f, " (requires import of {import})")?;
+        }
+        if let Some(ref snippet) = self.snippet {
+            write!(f, "\n  Snippet: {snippet}")?;
+        }
+        for import in &self.imports {
+            write!(f, "\n  Requires `use {};`", import.path)?;
+            for edit in &import.text_edit.edits {
+                write!(f, "\n    {edit}")?;
+            }
+        }
         Ok(())
     }
 }
@@ -314,20 +803,401 @@ impl std::fmt::Display for CompletionItem {
 impl std::fmt::Display for ReferenceInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let ref_type = if self.is_definition { "def" } else { "ref" };
+        let category = self.category.to_string();
         write!(
             f,
-            "{}:{}:{} ({}) - {}",
+            "{}:{}:{} ({}{}{}) -",
             self.file_path,
             self.line,
             self.column,
             ref_type,
-            self.content.trim()
+            if category.is_empty() { "" } else { ", " },
+            category,
+        )?;
+        // Context lines keep their indentation and go below the location
+        if self.content.contains('\n') {
+            write!(f, "\n{}", self.content)
+        } else {
+            write!(f, " {}", self.content.trim())
+        }
+    }
+}
+
+/// An inlay hint at a position, such as an inferred type or parameter name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct InlayHintInfo {
+    /// Line number (1-based) of the position the hint is anchored to
+    pub line: u32,
+    /// Column number (1-based) of the position the hint is anchored to
+    pub column: u32,
+    /// Whether the hint is shown `before` or `after` its position
+    pub position: String,
+    /// Hint label, e.g. `i32` for a type hint or `name` for a parameter hint
+    pub text: String,
+    /// Hint kind, e.g. `type`, `parameter` or `chaining`
+    pub kind: String,
+}
+
+impl std::fmt::Display for InlayHintInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{} {} {}: {}",
+            self.line, self.column, self.position, self.kind, self.text
         )
     }
 }
 
+/// A semantically highlighted token in a file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HighlightRange {
+    /// Line number (1-based) where the token starts
+    pub line: u32,
+    /// Column number (1-based) where the token starts
+    pub column: u32,
+    /// Line number (1-based) where the token ends
+    pub end_line: u32,
+    /// Column number (1-based) where the token ends
+    pub end_column: u32,
+    /// Source text of the token
+    pub text: String,
+    /// Highlight tag, e.g. `function`, `struct`, `keyword` or `local`
+    pub tag: String,
+    /// Highlight modifiers, e.g. `declaration`, `mutable` or `unsafe`
+    pub modifiers: Vec<String>,
+}
+
+impl std::fmt::Display for HighlightRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}-{}:{} {}",
+            self.line, self.column, self.end_line, self.end_column, self.tag
+        )?;
+        if !self.modifiers.is_empty() {
+            write!(f, " [{}]", self.modifiers.join(", "))?;
+        }
+        write!(f, " {}", self.text.escape_debug())
+    }
+}
+
+/// A test, benchmark or binary that can be run with cargo
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Runnable {
+    /// Human readable label, e.g. `test tests::it_works`
+    pub label: String,
+    /// Path to the file declaring the runnable
+    pub file_path: String,
+    /// Line number (1-based) of the runnable's name
+    pub line: u32,
+    /// Column number (1-based) of the runnable's name
+    pub column: u32,
+    /// Arguments to pass to cargo, e.g. `["test", "--", "tests::it_works", "--exact"]`
+    pub cargo_args: Vec<String>,
+    /// Directory to run cargo from, relative to the workspace root
+    pub cwd: String,
+}
+
+impl std::fmt::Display for Runnable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}:{}:{})\n  cd {} && cargo {}",
+            self.label,
+            self.file_path,
+            self.line,
+            self.column,
+            self.cwd,
+            self.cargo_args.join(" ")
+        )
+    }
+}
+
+/// A symbol found by a workspace symbol search
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkspaceSymbol {
+    /// Name of the symbol
+    pub name: String,
+    /// Kind of the symbol (struct, function, trait, ...)
+    #[serde(serialize_with = "serialize_symbol_kind")]
+    pub kind: Option<SymbolKind>,
+    /// Name of the containing item, e.g. the type of a method
+    pub container_name: Option<String>,
+    /// Path to the file declaring the symbol
+    pub file_path: String,
+    /// Line number (1-based) of the symbol's name
+    pub line: u32,
+    /// Column number (1-based) of the symbol's name
+    pub column: u32,
+}
+
+impl std::fmt::Display for WorkspaceSymbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(kind) = self.kind {
+            write!(f, " ({kind:?})")?;
+        }
+        if let Some(container_name) = &self.container_name {
+            write!(f, " in {container_name}")?;
+        }
+        write!(f, " - {}:{}:{}", self.file_path, self.line, self.column)
+    }
+}
+
+/// The public API map of the workspace
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublicApi {
+    /// Public items, sorted by path
+    pub items: Vec<PublicApiItem>,
+    /// Whether the symbol search hit its limit, so items may be missing
+    pub truncated: bool,
+}
+
+/// A public item of the workspace, part of its public API map
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublicApiItem {
+    /// Name of the item
+    pub name: String,
+    /// Kind of the item (struct, function, trait, ...)
+    #[serde(serialize_with = "serialize_symbol_kind")]
+    pub kind: Option<SymbolKind>,
+    /// Path of the item from its crate, e.g. `sample::Person::new`
+    pub path: String,
+    /// Path to the file declaring the item
+    pub file_path: String,
+    /// Line number (1-based) of the item's name
+    pub line: u32,
+}
+
+/// One line per item: `path (Kind) file:line`
+impl std::fmt::Display for PublicApiItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some(kind) = self.kind {
+            write!(f, " ({kind:?})")?;
+        }
+        write!(f, " {}:{}", self.file_path, self.line)
+    }
+}
+
+/// How a workspace symbol query is matched against symbol names
+///
+/// All modes are case-insensitive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymbolMatchMode {
+    /// The name contains the query
+    #[default]
+    Substring,
+    /// The name starts with the query
+    Prefix,
+    /// The query's characters appear in the name in order, e.g. `hsmp` for `HashMap`
+    Fuzzy,
+    /// The name matches the query as a regular expression
+    Regex,
+}
+
+impl std::str::FromStr for SymbolMatchMode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> anyhow::Result<Self> {
+        match mode.to_lowercase().as_str() {
+            "substring" => Ok(Self::Substring),
+            "prefix" => Ok(Self::Prefix),
+            "fuzzy" => Ok(Self::Fuzzy),
+            "regex" => Ok(Self::Regex),
+            _ => Err(anyhow::anyhow!(
+                "Invalid match mode '{}': expected substring, prefix, fuzzy or regex",
+                mode
+            )),
+        }
+    }
+}
+
+/// Summary of a loaded workspace, for diagnosing missing files
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkspaceInfo {
+    /// Root directory of the workspace, once file watching is set up
+    pub project_root: Option<String>,
+    /// All crates of the crate graph, workspace members first
+    pub crates: Vec<CrateInfo>,
+    /// Number of files in the VFS, including dependencies and the sysroot
+    pub vfs_file_count: usize,
+    /// Number of Rust source files inside the workspace
+    pub workspace_file_count: usize,
+}
+
+/// Readiness of a loaded analyzer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AnalyzerStatus {
+    /// Root directory of the workspace, once file watching is set up
+    pub project_root: Option<String>,
+    /// Number of Rust source files inside the workspace
+    pub workspace_file_count: usize,
+    /// Number of files in the VFS, including dependencies and the sysroot
+    pub vfs_file_count: usize,
+    /// Whether caches were primed after loading, so that first queries are fast
+    pub caches_primed: bool,
+}
+
+impl std::fmt::Display for AnalyzerStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Workspace loaded: yes")?;
+        writeln!(
+            f,
+            "Project root: {}",
+            self.project_root.as_deref().unwrap_or("<unknown>")
+        )?;
+        writeln!(
+            f,
+            "Files: {} workspace source file(s), {} in VFS",
+            self.workspace_file_count, self.vfs_file_count
+        )?;
+        if self.caches_primed {
+            write!(f, "Caches primed: yes")
+        } else {
+            write!(
+                f,
+                "Caches primed: no (lazy mode, first queries compute caches on demand)"
+            )
+        }
+    }
+}
+
+/// Memory used by a loaded analyzer, to decide whether to reload a workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemoryUsage {
+    /// Memory currently allocated by the process, in megabytes
+    pub allocated_mb: u64,
+    /// Number of Rust source files inside the workspace
+    pub workspace_file_count: usize,
+    /// Number of files loaded in the VFS, including dependencies
+    pub vfs_file_count: usize,
+}
+
+impl std::fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Allocated: {}MB", self.allocated_mb)?;
+        write!(
+            f,
+            "Files: {} workspace source file(s), {} in VFS",
+            self.workspace_file_count, self.vfs_file_count
+        )
+    }
+}
+
+/// A crate of the crate graph
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrateInfo {
+    /// Display name of the crate
+    pub name: String,
+    /// Resolved version of the crate, when cargo reports one
+    pub version: Option<String>,
+    /// Rust edition of the crate, e.g. `2024`
+    pub edition: String,
+    /// Enabled cargo features, sorted
+    pub features: Vec<String>,
+    /// Path to the crate root file, e.g. `src/lib.rs`
+    pub root_file: Option<String>,
+    /// Whether the crate is part of the workspace rather than a dependency
+    pub is_workspace_member: bool,
+}
+
+impl std::fmt::Display for WorkspaceInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Project root: {}",
+            self.project_root.as_deref().unwrap_or("<unknown>")
+        )?;
+        writeln!(
+            f,
+            "Files in VFS: {} ({} workspace source file(s))",
+            self.vfs_file_count, self.workspace_file_count
+        )?;
+        write!(f, "Crates ({}):", self.crates.len())?;
+        for krate in &self.crates {
+            write!(f, "\n  {krate}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The alternate format, `{:#}`, additionally lists the enabled features.
+impl std::fmt::Display for CrateInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let origin = if self.is_workspace_member {
+            "workspace"
+        } else {
+            "dependency"
+        };
+        write!(f, "{}", self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, " {version}")?;
+        }
+        write!(
+            f,
+            " (edition {}, {}) - {}",
+            self.edition,
+            origin,
+            self.root_file.as_deref().unwrap_or("<unknown root>")
+        )?;
+        if f.alternate() {
+            if self.features.is_empty() {
+                write!(f, "\n  Features: none")?;
+            } else {
+                write!(f, "\n  Features: {}", self.features.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Options for a workspace symbol search
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceSymbolsOptions {
+    /// How the query is matched against symbol names
+    pub mode: SymbolMatchMode,
+    /// Only return symbols of this kind, e.g. `struct` or `trait`
+    pub kind: Option<String>,
+    /// Number of matches to skip, for paging through results
+    pub offset: usize,
+    /// Maximum number of matches to return, all of them if `None`
+    pub limit: Option<usize>,
+}
+
+/// A page of workspace symbol search results
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkspaceSymbolsPage {
+    /// Matching symbols on this page, sorted by file then position
+    pub symbols: Vec<WorkspaceSymbol>,
+    /// Number of matches skipped before this page
+    pub offset: usize,
+    /// Total number of matches across all pages
+    pub total: usize,
+}
+
+impl WorkspaceSymbolsPage {
+    /// Summary header, e.g. `Showing symbols 11-20 of 42`
+    pub fn header(&self) -> String {
+        if self.symbols.is_empty() {
+            format!(
+                "No symbols on this page ({} match(es) in total)",
+                self.total
+            )
+        } else {
+            format!(
+                "Showing symbols {}-{} of {}",
+                self.offset + 1,
+                self.offset + self.symbols.len(),
+                self.total
+            )
+        }
+    }
+}
+
 /// Information about a code assist (code action)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AssistInfo {
     pub id: String,
@@ -343,8 +1213,48 @@ impl std::fmt::Display for AssistInfo {
     }
 }
 
+/// The code assists available at one position of a file
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionedAssists {
+    /// Line number (1-based) of the position
+    pub line: u32,
+    /// Column number (1-based) of the position
+    pub column: u32,
+    /// Assists first available at this position, to apply with this position
+    pub assists: Vec<AssistInfo>,
+}
+
+impl std::fmt::Display for PositionedAssists {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)?;
+        for assist in &self.assists {
+            write!(f, "\n  [{}] {}", assist.id, assist)?;
+        }
+        Ok(())
+    }
+}
+
+/// The code assists available across a file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileAssists {
+    /// Assists grouped by the position first offering them, in file order
+    pub positions: Vec<PositionedAssists>,
+    /// Whether more assists were available past the cap
+    pub truncated: bool,
+}
+
+impl FileAssists {
+    /// Number of assists across all positions
+    pub fn count(&self) -> usize {
+        self.positions
+            .iter()
+            .map(|position| position.assists.len())
+            .sum()
+    }
+}
+
 /// Source change for an assist
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AssistSourceChange {
     pub file_changes: Vec<FileChange>,
@@ -353,6 +1263,315 @@ pub struct AssistSourceChange {
 
 impl std::fmt::Display for AssistSourceChange {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Changes to {} files", self.file_changes.len())
+        write!(f, "Changes to {} files", self.file_changes.len())?;
+        for diff in self
+            .file_changes
+            .iter()
+            .filter_map(|change| change.diff.as_ref())
+        {
+            write!(f, "\n{diff}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Severity of a diagnostic, ordered from most to least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    WeakWarning,
+    Allow,
+}
+
+impl From<Severity> for DiagnosticSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Error => DiagnosticSeverity::Error,
+            Severity::Warning => DiagnosticSeverity::Warning,
+            Severity::WeakWarning => DiagnosticSeverity::WeakWarning,
+            Severity::Allow => DiagnosticSeverity::Allow,
+        }
+    }
+}
+
+impl std::fmt::Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+            DiagnosticSeverity::WeakWarning => "weak warning",
+            DiagnosticSeverity::Allow => "allow",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// A diagnostic (error, warning, etc.) reported by rust-analyzer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiagnosticInfo {
+    /// Path to the file containing the diagnostic
+    pub file_path: String,
+    /// Line number (1-based) where the diagnostic starts
+    pub line: u32,
+    /// Column number (1-based) where the diagnostic starts
+    pub column: u32,
+    /// Line number (1-based) where the diagnostic ends
+    pub end_line: u32,
+    /// Column number (1-based) where the diagnostic ends
+    pub end_column: u32,
+    /// Severity of the diagnostic
+    pub severity: DiagnosticSeverity,
+    /// Diagnostic code, e.g. `E0308` or `unused_variables`
+    pub code: String,
+    /// Human readable message
+    pub message: String,
+}
+
+impl std::fmt::Display for DiagnosticInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{} {}[{}]: {}",
+            self.file_path, self.line, self.column, self.severity, self.code, self.message
+        )
+    }
+}
+
+/// A variant of an enum, with its fields
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VariantInfo {
+    /// Name of the variant
+    pub name: String,
+    /// Shape of the variant's fields
+    pub kind: VariantKind,
+    /// Fields of the variant in declaration order, empty for unit variants
+    pub fields: Vec<VariantField>,
+    /// Explicit discriminant as written, e.g. `1` in `A = 1`
+    pub discriminant: Option<String>,
+    /// Line number (1-based) of the variant
+    pub line: u32,
+}
+
+/// Shape of the fields of an enum variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum VariantKind {
+    /// No fields, e.g. `None`
+    Unit,
+    /// Unnamed fields, e.g. `Some(T)`
+    Tuple,
+    /// Named fields, e.g. `Move { x: i32, y: i32 }`
+    Record,
+}
+
+/// A field of an enum variant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VariantField {
+    /// Name of the field, or its index for tuple variants
+    pub name: String,
+    /// Type of the field as written, e.g. `Option<String>`
+    pub ty: String,
+}
+
+/// Formats the variant as declared, e.g. `Move { x: i32, y: i32 }` or `A = 1`
+impl std::fmt::Display for VariantInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        match self.kind {
+            VariantKind::Unit => {}
+            VariantKind::Tuple => {
+                let types: Vec<&str> = self.fields.iter().map(|field| field.ty.as_str()).collect();
+                write!(f, "({})", types.join(", "))?;
+            }
+            VariantKind::Record => {
+                let fields: Vec<String> = self
+                    .fields
+                    .iter()
+                    .map(|field| format!("{}: {}", field.name, field.ty))
+                    .collect();
+                write!(f, " {{ {} }}", fields.join(", "))?;
+            }
+        }
+        if let Some(discriminant) = &self.discriminant {
+            write!(f, " = {discriminant}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A field of a struct or union
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldInfo {
+    /// Name of the field, or its index for tuple structs
+    pub name: String,
+    /// Type of the field as written, e.g. `Option<String>`
+    pub ty: String,
+    /// Visibility as written, e.g. `pub` or `pub(crate)`, `None` if private
+    pub visibility: Option<String>,
+    /// Line number (1-based) of the field
+    pub line: u32,
+}
+
+/// Formats the field as declared, e.g. `pub(crate) name: String`
+impl std::fmt::Display for FieldInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(visibility) = &self.visibility {
+            write!(f, "{visibility} ")?;
+        }
+        write!(f, "{}: {}", self.name, self.ty)
+    }
+}
+
+/// Differences between two versions of a function or trait signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SignatureDiff {
+    /// Name of the compared function or trait, as currently declared
+    pub name: String,
+    /// Changes from the baseline to the current signature, in declaration order
+    pub changes: Vec<SignatureChange>,
+}
+
+impl SignatureDiff {
+    /// Whether any change can break callers or implementors
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|change| change.breaking)
+    }
+}
+
+/// A single change between two versions of a signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SignatureChange {
+    /// Whether the part was added, removed or changed
+    pub kind: SignatureChangeKind,
+    /// Changed part, e.g. `parameter 2 (age)`, `return type` or `method len`
+    pub subject: String,
+    /// Baseline version of the part, `None` if added
+    pub old: Option<String>,
+    /// Current version of the part, `None` if removed
+    pub new: Option<String>,
+    /// Whether the change can break callers or implementors
+    pub breaking: bool,
+}
+
+/// How a part of a signature changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SignatureChangeKind {
+    /// Only in the current signature
+    Added,
+    /// Only in the baseline signature
+    Removed,
+    /// In both signatures, but different
+    Changed,
+}
+
+/// Formats the diff as a summary line followed by one line per change
+impl std::fmt::Display for SignatureDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.changes.is_empty() {
+            return write!(f, "{}: signatures match", self.name);
+        }
+        let breaking = self.changes.iter().filter(|change| change.breaking).count();
+        write!(
+            f,
+            "{}: {} change(s), {} breaking",
+            self.name,
+            self.changes.len(),
+            breaking
+        )?;
+        for change in &self.changes {
+            write!(f, "\n  {change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the change, e.g. `[breaking] changed return type: u32 -> u64`
+impl std::fmt::Display for SignatureChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.breaking {
+            write!(f, "[breaking] ")?;
+        }
+        let verb = match self.kind {
+            SignatureChangeKind::Added => "added",
+            SignatureChangeKind::Removed => "removed",
+            SignatureChangeKind::Changed => "changed",
+        };
+        write!(f, "{} {}", verb, self.subject)?;
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, ": {} -> {}", old, new),
+            (Some(part), None) | (None, Some(part)) => write!(f, ": {}", part),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// A variable, import or item nothing refers to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnusedItem {
+    /// Path to the file containing the item
+    pub file_path: String,
+    /// Line number (1-based) of the item's name, or of the `use` item
+    pub line: u32,
+    /// Column number (1-based) of the item's name, or of the `use` item
+    pub column: u32,
+    /// What kind of item is unused
+    pub kind: UnusedKind,
+    /// Name of the item, or the whole `use` item for imports
+    pub name: String,
+    /// Whether the item is `pub`, so it may be unused on purpose as part of
+    /// the crate's API
+    pub is_pub: bool,
+    /// Whether rust-analyzer has a fix, such as removing unused imports
+    pub fixable: bool,
+}
+
+/// Kind of an [`UnusedItem`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum UnusedKind {
+    /// A `use` item with at least one unused import
+    Import,
+    /// A local variable or parameter
+    Variable,
+    /// A function or method
+    Function,
+    /// A struct, enum, union, trait or type alias
+    Type,
+    /// A `const` or `static`
+    Constant,
+}
+
+impl std::fmt::Display for UnusedKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            UnusedKind::Import => "import",
+            UnusedKind::Variable => "variable",
+            UnusedKind::Function => "function",
+            UnusedKind::Type => "type",
+            UnusedKind::Constant => "constant",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// Formats the item, e.g. `src/lib.rs:3:1 import use std::fmt; (fixable)`
+impl std::fmt::Display for UnusedItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{} {}{} {}",
+            self.file_path,
+            self.line,
+            self.column,
+            if self.is_pub { "pub " } else { "" },
+            self.kind,
+            self.name
+        )?;
+        if self.fixable {
+            write!(f, " (fixable)")?;
+        }
+        if self.is_pub {
+            write!(f, " (public API, may be intentional)")?;
+        }
+        Ok(())
     }
 }
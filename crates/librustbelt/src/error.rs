@@ -0,0 +1,70 @@
+//! Typed errors for callers that react to what went wrong
+//!
+//! Most errors are plain `anyhow` messages. The ones callers may want to tell
+//! apart, such as a file missing from the workspace, wrap an [`AnalyzerError`]
+//! found with [`ErrorKind::of`], so rewording a message doesn't change how it
+//! is reported.
+
+use std::fmt;
+
+/// What an [`AnalyzerError`] reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The file is not part of the loaded workspace, or of any project
+    FileNotFound,
+    /// A line, column or line range lies outside of the file
+    InvalidPosition,
+    /// An argument is malformed, such as an invalid regex
+    InvalidParams,
+}
+
+impl ErrorKind {
+    /// The kind of the first [`AnalyzerError`] in the chain of `error`, if any
+    ///
+    /// Context added with [`anyhow::Context`] keeps the kind of the error it
+    /// wraps.
+    pub fn of(error: &anyhow::Error) -> Option<Self> {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<AnalyzerError>())
+            .map(|error| error.kind)
+    }
+
+    /// An error of this kind with `message`
+    pub fn error(self, message: impl Into<String>) -> anyhow::Error {
+        AnalyzerError {
+            kind: self,
+            message: message.into(),
+        }
+        .into()
+    }
+}
+
+/// Prefix the message of `error` with `prefix`, keeping its kind
+///
+/// Unlike [`anyhow::Context`], the message of `error` stays part of the
+/// displayed message.
+pub fn with_prefix(error: anyhow::Error, prefix: impl fmt::Display) -> anyhow::Error {
+    let message = format!("{prefix}: {error}");
+    match ErrorKind::of(&error) {
+        Some(kind) => kind.error(message),
+        None => anyhow::anyhow!(message),
+    }
+}
+
+/// An error of a known [`ErrorKind`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyzerError {
+    /// What went wrong
+    pub kind: ErrorKind,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for AnalyzerError {}
@@ -3,16 +3,19 @@
 //! This module handles file system watching and VFS synchronization,
 //! keeping the analysis host updated with file changes.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use crossbeam_channel::{Receiver, unbounded};
 use ra_ap_ide::{AnalysisHost, FileId};
 use ra_ap_ide_db::ChangeWithProcMacros;
-use ra_ap_vfs::loader::{Handle, Message};
-use ra_ap_vfs::{AbsPathBuf, Vfs, VfsPath};
+use ra_ap_vfs::loader::{Directories, Entry, Handle, Message};
+use ra_ap_vfs::{AbsPath, AbsPathBuf, Vfs, VfsPath};
 use ra_ap_vfs_notify::NotifyHandle;
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
+
+use super::error::ErrorKind;
+use super::utils::RustAnalyzerUtils;
 
 /// File watching configuration and state
 #[derive(Debug)]
@@ -20,6 +23,11 @@ pub struct FileWatcher {
     vfs_receiver: Option<Receiver<Message>>,
     vfs_handle: Option<NotifyHandle>,
     vfs: Vfs,
+    project_root: Option<AbsPathBuf>,
+    extra_excludes: Vec<String>,
+    exclude_patterns: Vec<glob::Pattern>,
+    extra_extensions: Vec<String>,
+    revision: u64,
 }
 
 impl Default for FileWatcher {
@@ -35,23 +43,151 @@ impl FileWatcher {
             vfs_receiver: None,
             vfs_handle: None,
             vfs: Vfs::default(),
+            project_root: None,
+            extra_excludes: Vec::new(),
+            exclude_patterns: Vec::new(),
+            extra_extensions: Vec::new(),
+            revision: 0,
         }
     }
 
+    /// Don't watch these directories in addition to `target` and `.git`
+    ///
+    /// Relative paths are resolved against the project root, and may be glob
+    /// patterns such as `crates/*/generated`. Takes effect on the next call to
+    /// [`Self::setup_file_watching`].
+    pub fn set_watch_excludes(&mut self, paths: Vec<String>) {
+        self.extra_excludes = paths;
+    }
+
+    /// Watch files with these extensions in addition to `rs` and `toml`
+    ///
+    /// A leading dot is optional. Takes effect on the next call to
+    /// [`Self::setup_file_watching`].
+    pub fn set_watch_extensions(&mut self, extensions: Vec<String>) {
+        self.extra_extensions = extensions
+            .into_iter()
+            .map(|extension| extension.trim_start_matches('.').to_string())
+            .collect();
+    }
+
     /// Set up file watching for the workspace
     pub fn setup_file_watching(
         &mut self,
         abs_project_root: AbsPathBuf,
         vfs: Vfs,
-        _host: &mut AnalysisHost,
+        host: &mut AnalysisHost,
     ) -> Result<()> {
         tracing::info!(
             "Setting up file watching for workspace: {}",
             abs_project_root
         );
 
+        // Watch the entire project directory for changes
+        let mut extensions = vec!["rs".to_string(), "toml".to_string()];
+        extensions.extend(self.extra_extensions.iter().cloned());
+        let mut exclude = vec![
+            abs_project_root.join("target"),
+            abs_project_root.join(".git"),
+        ];
+        exclude.extend(Self::resolve_watch_excludes(
+            &abs_project_root,
+            &self.extra_excludes,
+        ));
+        self.exclude_patterns = self
+            .extra_excludes
+            .iter()
+            .filter(|path| Self::is_glob(path))
+            .filter_map(|path| glob::Pattern::new(abs_project_root.join(path).as_str()).ok())
+            .collect();
+        let entry = Entry::Directories(Directories {
+            extensions,
+            include: vec![abs_project_root.clone()],
+            exclude,
+        });
+        self.start_watching(abs_project_root, vfs, entry, host)
+    }
+
+    /// Resolve watch excludes against the project root
+    ///
+    /// Plain paths are kept as they are, whether they exist or not, while glob
+    /// patterns expand to the directories they currently match. Invalid
+    /// patterns are skipped with a warning.
+    pub fn resolve_watch_excludes(abs_project_root: &AbsPath, paths: &[String]) -> Vec<AbsPathBuf> {
+        let mut excludes = Vec::new();
+        for path in paths {
+            let abs_path = abs_project_root.join(path);
+            if !Self::is_glob(path) {
+                excludes.push(abs_path);
+                continue;
+            }
+            let matches = match glob::glob(abs_path.as_str()) {
+                Ok(matches) => matches,
+                Err(e) => {
+                    warn!("Ignoring invalid watch exclude pattern {}: {}", path, e);
+                    continue;
+                }
+            };
+            let mut dirs: Vec<AbsPathBuf> = matches
+                .filter_map(|entry| entry.ok())
+                .filter(|dir| dir.is_dir())
+                .filter_map(|dir| RustAnalyzerUtils::path_to_abs_path(&dir).ok())
+                .collect();
+            dirs.sort();
+            excludes.extend(dirs);
+        }
+        excludes
+    }
+
+    /// Whether a watch exclude is a glob pattern rather than a plain path
+    fn is_glob(path: &str) -> bool {
+        path.contains(['*', '?', '['])
+    }
+
+    /// Whether a file lies in a directory matching a watch exclude pattern
+    ///
+    /// Catches directories created after [`Self::setup_file_watching`],
+    /// which the patterns did not expand to.
+    fn is_excluded(&self, abs_path: &AbsPath) -> bool {
+        !self.exclude_patterns.is_empty()
+            && std::iter::successors(abs_path.parent(), |dir| dir.parent()).any(|dir| {
+                self.exclude_patterns
+                    .iter()
+                    .any(|pattern| pattern.matches(dir.as_str()))
+            })
+    }
+
+    /// Set up file watching for a single file outside any cargo project
+    ///
+    /// Only the file itself is watched, since its directory may be arbitrarily
+    /// large. The directory still serves as the project root.
+    pub fn setup_detached_file_watching(
+        &mut self,
+        abs_file: AbsPathBuf,
+        vfs: Vfs,
+        host: &mut AnalysisHost,
+    ) -> Result<()> {
+        tracing::info!("Setting up file watching for detached file: {}", abs_file);
+
+        let abs_project_root = abs_file
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Detached file {} has no parent", abs_file))?
+            .to_path_buf();
+        self.start_watching(abs_project_root, vfs, Entry::Files(vec![abs_file]), host)
+    }
+
+    /// Replace the VFS with the loaded one and start watching `entry`
+    fn start_watching(
+        &mut self,
+        abs_project_root: AbsPathBuf,
+        vfs: Vfs,
+        entry: Entry,
+        host: &mut AnalysisHost,
+    ) -> Result<()> {
         // Replace our VFS with the loaded workspace VFS
         self.vfs = vfs;
+        self.project_root = Some(abs_project_root);
+        Self::strip_loaded_boms(&self.vfs, host);
 
         // Create a channel for VFS loader messages
         let (sender, receiver) = unbounded::<Message>();
@@ -63,12 +199,53 @@ impl FileWatcher {
         self.vfs_receiver = Some(receiver);
         self.vfs_handle = Some(vfs_handle);
 
-        // Configure the VFS to watch the workspace files
-        self.configure_vfs_watching(abs_project_root)?;
+        // Configure the VFS to watch the workspace files, and the files
+        // generated by build scripts which live in the excluded target directory
+        let mut entries = vec![entry];
+        let out_dirs = self.build_script_out_dirs();
+        if !out_dirs.is_empty() {
+            debug!("Watching {} build script output dir(s)", out_dirs.len());
+            entries.push(Entry::Directories(Directories {
+                extensions: vec!["rs".to_string()],
+                include: out_dirs,
+                exclude: Vec::new(),
+            }));
+        }
+        self.configure_vfs_watching(entries)?;
 
         Ok(())
     }
 
+    /// Strip the byte order mark of files loaded with the workspace
+    ///
+    /// The workspace loader hands files to the analysis host as they are on
+    /// disk, while changes picked up later are stripped of their BOM, see
+    /// [`RustAnalyzerUtils::strip_bom`].
+    fn strip_loaded_boms(vfs: &Vfs, host: &mut AnalysisHost) {
+        let analysis = host.analysis();
+        let mut change = ChangeWithProcMacros::default();
+        let mut stripped = 0;
+        for (file_id, _) in vfs.iter() {
+            let Ok(text) = analysis.file_text(file_id) else {
+                continue;
+            };
+            let without_bom = RustAnalyzerUtils::strip_bom(&text);
+            if without_bom.len() != text.len() {
+                change.change_file(file_id, Some(without_bom.to_owned()));
+                stripped += 1;
+            }
+        }
+        drop(analysis);
+
+        if stripped > 0 {
+            debug!(
+                "Stripped the byte order mark of {} loaded file(s)",
+                stripped
+            );
+            host.apply_change(change);
+        }
+    }
+
     /// Drain all pending messages from the file watcher and apply changes synchronously
     pub fn drain_and_apply_changes(&mut self, host: &mut AnalysisHost) -> Result<()> {
         let Some(ref receiver) = self.vfs_receiver else {
@@ -88,6 +265,10 @@ impl FileWatcher {
 
                     // Process the loaded files
                     for (abs_path, contents) in files {
+                        if self.is_excluded(&abs_path) {
+                            trace!("Ignoring change to excluded file: {:?}", abs_path);
+                            continue;
+                        }
                         debug!("File changed: {:?}", abs_path);
                         let vfs_path: VfsPath = abs_path.to_path_buf().into();
                         self.vfs.set_file_contents(vfs_path, contents.clone());
@@ -106,7 +287,7 @@ impl FileWatcher {
             let new_contents = match changed_file.change {
                 ra_ap_vfs::Change::Create(v, _) | ra_ap_vfs::Change::Modify(v, _) => {
                     if let Ok(text) = std::str::from_utf8(&v) {
-                        Some(text.to_owned())
+                        Some(RustAnalyzerUtils::strip_bom(text).to_owned())
                     } else {
                         None
                     }
@@ -117,31 +298,29 @@ impl FileWatcher {
         }
 
         host.apply_change(change);
+        self.revision += 1;
 
         Ok(())
     }
 
+    /// Number of times file changes were applied to the analysis host
+    ///
+    /// Results derived from the whole workspace can be cached until this changes.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
     /// Configure VFS to watch workspace files
-    fn configure_vfs_watching(&mut self, abs_project_root: AbsPathBuf) -> Result<()> {
+    fn configure_vfs_watching(&mut self, entries: Vec<Entry>) -> Result<()> {
         let Some(ref mut loader) = self.vfs_handle else {
             return Ok(());
         };
 
-        debug!("Configuring VFS watching for: {}", abs_project_root);
+        debug!("Configuring VFS watching for: {:?}", entries);
 
         let config = ra_ap_vfs::loader::Config {
-            load: vec![
-                // Watch the entire project directory for changes
-                ra_ap_vfs::loader::Entry::Directories(ra_ap_vfs::loader::Directories {
-                    extensions: vec!["rs".to_string(), "toml".to_string()],
-                    include: vec![abs_project_root.clone()],
-                    exclude: vec![
-                        abs_project_root.join("target"),
-                        abs_project_root.join(".git"),
-                    ],
-                }),
-            ],
-            watch: vec![0], // Watch the first (and only) load entry
+            watch: (0..entries.len()).collect(), // Watch every load entry
+            load: entries,
             version: 0,
         };
 
@@ -153,7 +332,9 @@ impl FileWatcher {
     }
 
     pub fn get_file_id(&self, path: &Path) -> Result<FileId> {
-        let vfs_path = Self::path_to_vfs_path(path)?;
+        let path = &self.resolve_path(path);
+        let vfs_path = Self::path_to_vfs_path(path)
+            .map_err(|e| ErrorKind::FileNotFound.error(format!("{:#}", e)))?;
         if let Some((file_id, _)) = self.vfs.file_id(&vfs_path) {
             debug!(
                 "File found in VFS: {} with FileId: {:?}",
@@ -163,7 +344,7 @@ impl FileWatcher {
             Ok(file_id)
         } else {
             error!("File not found in VFS: {}", path.display());
-            Err(anyhow::anyhow!("File not found in VFS: {}", path.display()))
+            Err(ErrorKind::FileNotFound.error(format!("File not found in VFS: {}", path.display())))
         }
     }
 
@@ -181,6 +362,108 @@ impl FileWatcher {
         }
     }
 
+    /// List the Rust source files in the VFS that belong to the watched workspace
+    ///
+    /// Files from the sysroot, dependencies and the target directory are
+    /// excluded, except for the files generated by build scripts of the
+    /// workspace. Results are sorted by path.
+    pub fn workspace_files(&self) -> Vec<(FileId, String)> {
+        let mut files: Vec<(FileId, String)> = self
+            .vfs
+            .iter()
+            .filter_map(|(file_id, vfs_path)| {
+                let is_workspace_source = self.is_workspace_source(vfs_path.as_path()?);
+                is_workspace_source.then(|| (file_id, vfs_path.to_string()))
+            })
+            .collect();
+        files.sort_by(|a, b| a.1.cmp(&b.1));
+        files
+    }
+
+    /// Whether a file is one of the [`Self::workspace_files`], rather than a
+    /// file of the sysroot or of a dependency
+    pub fn is_workspace_file(&self, file_id: FileId) -> bool {
+        self.vfs.exists(file_id)
+            && self
+                .vfs
+                .file_path(file_id)
+                .as_path()
+                .is_some_and(|abs_path| self.is_workspace_source(abs_path))
+    }
+
+    /// Whether a path is a Rust source file of the watched workspace
+    fn is_workspace_source(&self, abs_path: &AbsPath) -> bool {
+        let Some(ref project_root) = self.project_root else {
+            return false;
+        };
+        let target_dir = project_root.join("target");
+        abs_path.starts_with(project_root)
+            && (!abs_path.starts_with(&target_dir)
+                || Self::out_dir_of(abs_path, &target_dir).is_some())
+            && abs_path.extension() == Some("rs")
+    }
+
+    /// Build script output directories, the `OUT_DIR`s, of the loaded files
+    ///
+    /// rust-analyzer runs the build scripts while loading the workspace, so
+    /// files they generate and `include!` are part of the VFS.
+    fn build_script_out_dirs(&self) -> Vec<AbsPathBuf> {
+        let Some(ref project_root) = self.project_root else {
+            return Vec::new();
+        };
+        let target_dir = project_root.join("target");
+
+        let mut out_dirs: Vec<AbsPathBuf> = self
+            .vfs
+            .iter()
+            .filter_map(|(_, vfs_path)| Self::out_dir_of(vfs_path.as_path()?, &target_dir))
+            .collect();
+        out_dirs.sort();
+        out_dirs.dedup();
+        out_dirs
+    }
+
+    /// The `OUT_DIR` containing `path`, e.g. `target/debug/build/<package>-<hash>/out`
+    fn out_dir_of(path: &AbsPath, target_dir: &AbsPath) -> Option<AbsPathBuf> {
+        let mut dir = path.parent()?;
+        while dir.starts_with(target_dir) {
+            let is_out_dir = dir.file_name() == Some("out")
+                && dir.parent()?.parent()?.file_name() == Some("build");
+            if is_out_dir {
+                return Some(dir.to_path_buf());
+            }
+            dir = dir.parent()?;
+        }
+        None
+    }
+
+    /// Resolve a path relative to the workspace root
+    ///
+    /// Absolute paths are returned unchanged, as are relative paths when no
+    /// workspace is loaded yet or when they don't exist under the root, which
+    /// keeps paths relative to the current directory working.
+    pub fn resolve_path(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+        match &self.project_root {
+            Some(root) => {
+                let joined = PathBuf::from(root.to_string()).join(path);
+                if joined.exists() {
+                    joined
+                } else {
+                    path.to_path_buf()
+                }
+            }
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Root directory of the watched workspace, once watching is set up
+    pub fn project_root(&self) -> Option<&AbsPathBuf> {
+        self.project_root.as_ref()
+    }
+
     /// Get a reference to the VFS
     pub fn vfs(&self) -> &Vfs {
         &self.vfs
@@ -4,17 +4,37 @@
 
 pub mod analyzer;
 pub mod builder;
+pub mod config;
 pub mod entities;
+pub mod error;
 pub mod file_watcher;
 pub mod utils;
 
 pub use analyzer::RustAnalyzerish;
 pub use builder::RustAnalyzerishBuilder;
+pub use config::AnalyzerConfig;
 pub use entities::{
-    AssistInfo, AssistSourceChange, CompletionItem, CursorCoordinates, DefinitionInfo, FileChange,
-    ReferenceInfo, RenameResult, TextEdit, TypeHint,
+    AnalyzerStatus, ApplyOptions, AssistInfo, AssistSourceChange, CompletionImport, CompletionItem,
+    CompletionOptions, CrateInfo, CursorCoordinates, DefinitionInfo, DefinitionOptions,
+    DefinitionReport, DiagnosticInfo, DiagnosticSeverity, FieldInfo, FileAssists, FileChange,
+    FileRename, HighlightRange, InlayHintInfo, LinePreview, MemoryUsage, MoveItemResult,
+    PositionedAssists, PublicApi, PublicApiItem, ReferenceCategory, ReferenceCount, ReferenceInfo,
+    ReferenceOptions, ReferenceScope, RenameResult, Runnable, SignatureChange, SignatureChangeKind,
+    SignatureDiff, SourceExcerpt, SymbolDescription, SymbolMatchMode, TextEdit, TraitImpls,
+    TypeHint, TypeHintOptions, UnusedItem, UnusedKind, VariantField, VariantInfo, VariantKind,
+    WorkspaceInfo, WorkspaceSymbol, WorkspaceSymbolsOptions, WorkspaceSymbolsPage,
 };
+pub use error::{AnalyzerError, ErrorKind};
 pub use utils::RustAnalyzerUtils;
 
+/// Cargo features librustbelt was compiled with
+pub fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "schemars") {
+        features.push("schemars");
+    }
+    features
+}
+
 /// Result type alias for the library
 pub type Result<T> = anyhow::Result<T>;
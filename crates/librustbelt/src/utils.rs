@@ -3,28 +3,55 @@
 //! This module contains static utility functions that don't require
 //! an analyzer instance, such as text editing operations.
 
-use std::path::Path;
+use std::any::Any;
+use std::path::{Component, Path, PathBuf};
 
 use anyhow::Result;
 use ra_ap_ide::{LineCol, LineIndex, TextRange, TextSize};
-use ra_ap_ide_db::text_edit::TextEditBuilder;
+use ra_ap_ide_db::imports::insert_use::ImportGranularity;
+use ra_ap_ide_db::imports::merge_imports::{MergeBehavior, try_merge_imports};
+use ra_ap_ide_db::source_change::SnippetEdit;
+use ra_ap_ide_db::text_edit::{TextEdit as RaTextEdit, TextEditBuilder};
+use ra_ap_syntax::ast::{HasAttrs, HasGenericParams, HasName, HasTypeBounds, HasVisibility};
+use ra_ap_syntax::{AstNode, Direction, Edition, SyntaxKind, SyntaxNode, SyntaxToken, ast};
+use similar::TextDiff;
 use tokio::fs;
 
-use super::entities::{FileChange, RenameResult};
+use super::entities::{
+    ApplyOptions, DiagnosticInfo, DiagnosticSeverity, FileChange, FileRename, LinePreview,
+    RenameResult, SignatureChange, SignatureChangeKind, SignatureDiff, TextEdit,
+};
+use super::error::ErrorKind;
 
 /// Utility functions for Rust analyzer operations
 pub struct RustAnalyzerUtils;
 
+/// UTF-8 byte order mark, which some editors on Windows start files with
+const UTF8_BOM: &str = "\u{feff}";
+
+/// Types rust-analyzer renders when inference fails
+const TYPE_PLACEHOLDERS: &[&str] = &["{unknown}", "{error}"];
+
+/// Strict and reserved keywords, which can only be used as raw identifiers
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "try",
+    "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield",
+];
+
 impl RustAnalyzerUtils {
     /// Apply rename edits to files on disk using rust-analyzer's TextEditBuilder
     pub async fn apply_rename_edits(rename_result: &RenameResult) -> Result<()> {
         for file_change in &rename_result.file_changes {
-            // Read the current file content
-            let mut content = fs::read_to_string(&file_change.file_path)
+            // Read the current file content, with positions counted after any BOM
+            let original = fs::read_to_string(&file_change.file_path)
                 .await
                 .map_err(|e| {
                     anyhow::anyhow!("Failed to read file {}: {}", file_change.file_path, e)
                 })?;
+            let mut content = Self::strip_bom(&original).to_string();
 
             // Create TextEditBuilder to handle multiple edits atomically
             let mut builder = TextEditBuilder::default();
@@ -38,12 +65,10 @@ impl RustAnalyzerUtils {
                 let start_offset =
                     Self::line_col_to_offset_with_index(&line_index, edit.line, edit.column)
                         .ok_or_else(|| {
-                            anyhow::anyhow!(
+                            ErrorKind::InvalidPosition.error(format!(
                                 "Invalid start position {}:{} in file {}",
-                                edit.line,
-                                edit.column,
-                                file_change.file_path
-                            )
+                                edit.line, edit.column, file_change.file_path
+                            ))
                         })?;
 
                 let end_offset = Self::line_col_to_offset_with_index(
@@ -52,12 +77,10 @@ impl RustAnalyzerUtils {
                     edit.end_column,
                 )
                 .ok_or_else(|| {
-                    anyhow::anyhow!(
+                    ErrorKind::InvalidPosition.error(format!(
                         "Invalid end position {}:{} in file {}",
-                        edit.end_line,
-                        edit.end_column,
-                        file_change.file_path
-                    )
+                        edit.end_line, edit.end_column, file_change.file_path
+                    ))
                 })?;
 
                 // Create rust-analyzer TextRange
@@ -70,6 +93,8 @@ impl RustAnalyzerUtils {
             // Build the TextEdit and apply it atomically
             let text_edit = builder.finish();
             text_edit.apply(&mut content);
+            Self::preserve_final_newline(&original, &mut content);
+            Self::restore_bom(&original, &mut content);
 
             // Write the modified content back to the file
             fs::write(&file_change.file_path, content)
@@ -95,6 +120,54 @@ impl RustAnalyzerUtils {
         line_index.offset(line_col)
     }
 
+    /// Convert a 1-based position to an offset, checking it lies within the text
+    ///
+    /// Unlike [`Self::line_col_to_offset_with_index`], columns past the end of
+    /// their line and positions inside a multi-byte character are rejected.
+    /// The column just past the last character of a line is valid.
+    pub fn checked_offset(
+        text: &str,
+        line_index: &LineIndex,
+        line: u32,
+        column: u32,
+    ) -> Result<TextSize> {
+        if line == 0 || column == 0 {
+            return Err(anyhow::anyhow!("lines and columns are 1-based"));
+        }
+        let line_start = line_index
+            .offset(LineCol {
+                line: line - 1,
+                col: 0,
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "line {} is past the end of the file ({} lines)",
+                    line,
+                    text.lines().count()
+                )
+            })?;
+        let line_text = text[usize::from(line_start)..]
+            .split('\n')
+            .next()
+            .unwrap_or_default();
+        let column_offset = (column - 1) as usize;
+        if column_offset > line_text.len() {
+            return Err(anyhow::anyhow!(
+                "column {} is past the end of line {} ({} bytes)",
+                column,
+                line,
+                line_text.len()
+            ));
+        }
+        if !line_text.is_char_boundary(column_offset) {
+            return Err(anyhow::anyhow!(
+                "column {} is inside a multi-byte character",
+                column
+            ));
+        }
+        Ok(line_start + TextSize::from(column_offset as u32))
+    }
+
     /// Helper method to get line content from file text
     pub fn get_line_content(file_text: &str, line_number: usize) -> Option<String> {
         let lines: Vec<&str> = file_text.lines().collect();
@@ -105,6 +178,824 @@ impl RustAnalyzerUtils {
         }
     }
 
+    /// Helper method to get a line with up to `context` lines before and after it
+    ///
+    /// The range is clamped at the start and end of the file.
+    pub fn get_lines_around(file_text: &str, line_number: usize, context: usize) -> Option<String> {
+        let lines: Vec<&str> = file_text.lines().collect();
+        if line_number >= lines.len() {
+            return None;
+        }
+        let start = line_number.saturating_sub(context);
+        let end = line_number.saturating_add(context).min(lines.len() - 1);
+        Some(lines[start..=end].join("\n"))
+    }
+
+    /// Insert annotations such as inlay hints into a text, keeping only `lines`
+    ///
+    /// `lines` are 1-based and inclusive, or the whole text if `None`. The
+    /// range is selected before splicing, so annotations containing newlines,
+    /// e.g. long closure types, can't shift it. Annotations anchored at the end
+    /// of the text belong to its last line.
+    pub fn splice_line_range(
+        text: &str,
+        line_index: &LineIndex,
+        insertions: Vec<(TextSize, String)>,
+        lines: Option<(u32, u32)>,
+    ) -> Result<String> {
+        let text_range = TextRange::up_to(TextSize::of(text));
+        let range = match lines {
+            Some((start, end)) => Self::line_range(text, line_index, start, end)?,
+            None => text_range,
+        };
+
+        let mut builder = TextEditBuilder::default();
+        for (offset, annotation) in insertions {
+            let at_end_of_text = offset == range.end() && range.end() == text_range.end();
+            if range.contains(offset) || at_end_of_text {
+                // Insert relative to the selected range
+                builder.insert(offset - range.start(), annotation);
+            }
+        }
+
+        let mut result = text[range].to_string();
+        builder.finish().apply(&mut result);
+        match lines {
+            Some(_) => Ok(result.lines().collect::<Vec<_>>().join("\n")),
+            None => Ok(result),
+        }
+    }
+
+    /// Text range of lines `start` to `end` (1-based, inclusive), including
+    /// the newline of the last line
+    pub fn line_range(
+        text: &str,
+        line_index: &LineIndex,
+        start: u32,
+        end: u32,
+    ) -> Result<TextRange> {
+        let line_count = text.lines().count();
+        let start_idx = (start.saturating_sub(1) as usize).min(line_count);
+        let end_idx = (end as usize).min(line_count);
+
+        if start_idx >= line_count || end_idx <= start_idx {
+            return Err(anyhow::anyhow!("Range outside of the file limits"));
+        }
+
+        let line_start = |line: usize| {
+            line_index.offset(LineCol {
+                line: line as u32,
+                col: 0,
+            })
+        };
+        let start_offset = line_start(start_idx)
+            .ok_or_else(|| anyhow::anyhow!("Range outside of the file limits"))?;
+        let end_offset = line_start(end_idx).unwrap_or(TextSize::of(text));
+        Ok(TextRange::new(start_offset, end_offset))
+    }
+
+    /// Whether the characters of `query` appear in `name` in order
+    ///
+    /// Both are compared as given, so lowercase them first for a
+    /// case-insensitive match.
+    pub fn is_fuzzy_match(name: &str, query: &str) -> bool {
+        let mut name_chars = name.chars();
+        query
+            .chars()
+            .all(|query_char| name_chars.any(|name_char| name_char == query_char))
+    }
+
+    /// Whether the code blocks of hover markup are free of inference placeholders
+    ///
+    /// rust-analyzer renders types it failed to infer as `{unknown}` or
+    /// `{error}`, and uninferred generic arguments as `_`, e.g. `Vec<_>`.
+    /// A `_` followed by `:` or `=` is a pattern, e.g. `fn f(_: u8)`, not a type.
+    /// Documentation outside code blocks is ignored.
+    pub fn is_fully_resolved(markup: &str) -> bool {
+        let mut in_code = false;
+        for line in markup.lines() {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+                continue;
+            }
+            if !in_code {
+                continue;
+            }
+            if TYPE_PLACEHOLDERS
+                .iter()
+                .any(|placeholder| line.contains(placeholder))
+            {
+                return false;
+            }
+
+            let chars: Vec<char> = line.chars().collect();
+            let is_ident = |c: &char| c.is_alphanumeric() || *c == '_';
+            let has_inferred_type = chars.iter().enumerate().any(|(index, c)| {
+                *c == '_'
+                    && !index
+                        .checked_sub(1)
+                        .and_then(|before| chars.get(before))
+                        .is_some_and(is_ident)
+                    && chars[index + 1..]
+                        .iter()
+                        .find(|next| !next.is_whitespace())
+                        .is_none_or(|next| matches!(next, '>' | ',' | ')' | ']' | ';'))
+            });
+            if has_inferred_type {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether `name` is a plain Rust identifier, such as a new function name
+    pub fn is_valid_identifier(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|first| first == '_' || first.is_alphabetic())
+            && chars.all(|c| c == '_' || c.is_alphanumeric())
+            && name != "_"
+    }
+
+    /// Whether `name` is a strict or reserved keyword of the 2024 edition
+    ///
+    /// Weak keywords such as `union` and `macro_rules` are valid identifiers.
+    pub fn is_keyword(name: &str) -> bool {
+        RUST_KEYWORDS.contains(&name)
+    }
+
+    /// Check that `name` can be the new name of a rename, before asking
+    /// rust-analyzer
+    ///
+    /// Names starting with `'` rename lifetimes and labels and must be followed
+    /// by an identifier. Other names must be identifiers, or raw identifiers
+    /// such as `r#type`. Returns an error explaining why the name is invalid.
+    pub fn validate_rename_name(name: &str) -> Result<()> {
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("The new name is empty"));
+        }
+        if let Some(lifetime) = name.strip_prefix('\'') {
+            if lifetime == "_" || lifetime == "static" {
+                return Err(anyhow::anyhow!(
+                    "'{}' is reserved and can't be the new name of a lifetime or label",
+                    name
+                ));
+            }
+            if !Self::is_valid_identifier(lifetime) || Self::is_keyword(lifetime) {
+                return Err(anyhow::anyhow!(
+                    "'{}' is not a valid lifetime or label name: expected `'` followed by an identifier",
+                    name
+                ));
+            }
+            return Ok(());
+        }
+        if let Some(raw) = name.strip_prefix("r#") {
+            if !Self::is_valid_identifier(raw) || ["crate", "self", "Self", "super"].contains(&raw)
+            {
+                return Err(anyhow::anyhow!("'{}' is not a valid raw identifier", name));
+            }
+            return Ok(());
+        }
+        if Self::is_keyword(name) {
+            return Err(anyhow::anyhow!(
+                "'{}' is a keyword, use the raw identifier r#{} instead",
+                name,
+                name
+            ));
+        }
+        // `_` is left to rust-analyzer, which allows it for some locals
+        if name != "_" && !Self::is_valid_identifier(name) {
+            return Err(anyhow::anyhow!(
+                "'{}' is not a valid identifier: expected a letter or `_` followed by letters, digits or `_`",
+                name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Import path of an `auto_import` assist label, e.g. `std::fmt` for
+    /// "Import `std::fmt`"
+    pub fn import_path_from_label(label: &str) -> Option<String> {
+        let (_, rest) = label.split_once('`')?;
+        let (path, _) = rest.split_once('`')?;
+        Some(path.to_string())
+    }
+
+    /// The first run of `use` items of a file
+    ///
+    /// The run ends at the first other item, or at anything but whitespace
+    /// between two imports, so comments and `#[cfg]`-gated imports are left
+    /// where they are.
+    pub fn leading_imports(source_file: &ast::SourceFile) -> Vec<ast::Use> {
+        let mut imports: Vec<ast::Use> = Vec::new();
+        let items = source_file
+            .items()
+            .skip_while(|item| !matches!(item, ast::Item::Use(_)));
+        for item in items {
+            let ast::Item::Use(import) = item else {
+                break;
+            };
+            if import.attrs().next().is_some() {
+                break;
+            }
+            if let Some(previous) = imports.last() {
+                let whitespace_only = previous
+                    .syntax()
+                    .siblings_with_tokens(Direction::Next)
+                    .skip(1)
+                    .take_while(|element| element.as_node() != Some(import.syntax()))
+                    .all(|element| element.kind() == SyntaxKind::WHITESPACE);
+                if !whitespace_only {
+                    break;
+                }
+            }
+            imports.push(import);
+        }
+        imports
+    }
+
+    /// Merge and sort the leading imports of a Rust source file
+    ///
+    /// Imports are merged with rust-analyzer's merge rules for `granularity`,
+    /// then sorted into `std`/`core`/`alloc`, external crate, `crate`, `super`
+    /// and `self` groups separated by blank lines. Imports with a visibility,
+    /// such as `pub use` re-exports, are sorted into groups of their own after
+    /// the private ones. The rest of the file is returned unchanged.
+    ///
+    /// `edition` is the edition of the file's crate, which decides how the
+    /// imports are parsed.
+    pub fn organize_imports(
+        text: &str,
+        edition: Edition,
+        granularity: ImportGranularity,
+    ) -> String {
+        let source_file = ast::SourceFile::parse(text, edition).tree();
+        let imports = Self::leading_imports(&source_file);
+        let (Some(first), Some(last)) = (imports.first(), imports.last()) else {
+            return text.to_string();
+        };
+        let range = TextRange::new(
+            first.syntax().text_range().start(),
+            last.syntax().text_range().end(),
+        );
+
+        let merge_behavior = match granularity {
+            ImportGranularity::Crate => Some(MergeBehavior::Crate),
+            ImportGranularity::Module => Some(MergeBehavior::Module),
+            ImportGranularity::One => Some(MergeBehavior::One),
+            _ => None,
+        };
+        let mut merged: Vec<ast::Use> = Vec::new();
+        for import in &imports {
+            let import = import.clone_for_update();
+            let merge = merge_behavior.and_then(|merge_behavior| {
+                merged.iter().enumerate().find_map(|(index, existing)| {
+                    try_merge_imports(existing, &import, merge_behavior)
+                        .map(|merged_import| (index, merged_import))
+                })
+            });
+            match merge {
+                Some((index, merged_import)) => merged[index] = merged_import,
+                None => merged.push(import),
+            }
+        }
+
+        let sort_key = |import: &ast::Use| {
+            let visibility = import
+                .visibility()
+                .map(|visibility| visibility.syntax().to_string());
+            let path = import
+                .use_tree()
+                .map(|use_tree| use_tree.syntax().to_string())
+                .unwrap_or_default();
+            (visibility, Self::import_group(&path), path)
+        };
+        merged.sort_by_cached_key(sort_key);
+
+        let mut organized = String::new();
+        let mut previous_group = None;
+        for import in &merged {
+            let (visibility, group, _) = sort_key(import);
+            let group = (visibility, group);
+            if let Some(previous_group) = previous_group {
+                organized.push('\n');
+                if previous_group != group {
+                    organized.push('\n');
+                }
+            }
+            organized.push_str(&import.to_string());
+            previous_group = Some(group);
+        }
+
+        let mut result = text.to_string();
+        result.replace_range(std::ops::Range::<usize>::from(range), &organized);
+        result
+    }
+
+    /// Sort group of an import path: std, external crates, crate, super, self
+    fn import_group(path: &str) -> u8 {
+        let first_segment = path
+            .trim_start_matches("::")
+            .split("::")
+            .next()
+            .unwrap_or_default();
+        match first_segment {
+            "std" | "core" | "alloc" => 0,
+            "crate" => 2,
+            "super" => 3,
+            "self" => 4,
+            _ => 1,
+        }
+    }
+
+    /// Compare two versions of a function or trait declaration
+    ///
+    /// Both sides are Rust source containing the item, such as its definition
+    /// or the ruskel rendering of a previous release. Bodies, docs, attributes
+    /// and formatting are ignored. Parameters are compared by position, as
+    /// callers pass them, and trait items by name. A change is breaking when
+    /// it can stop existing callers or implementors from compiling: a new
+    /// parameter is, a new provided trait method or dropping `unsafe` isn't.
+    ///
+    /// The baseline can hold many items, such as a whole crate's skeleton: the
+    /// function or trait named like the current one is compared. A baseline
+    /// with a single function or trait is compared whatever its name, to
+    /// report a rename. Fails when the baseline has no item of that name.
+    pub fn diff_signatures(baseline: &str, current: &str) -> Result<SignatureDiff> {
+        let new = Self::signature_items(current)
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No function or trait found in: {}", current.trim()))?;
+        let name = Self::signature_item_name(&new);
+        let mut candidates = Self::signature_items(baseline);
+        let old = match candidates
+            .iter()
+            .position(|item| Self::signature_item_name(item) == name)
+        {
+            Some(index) => candidates.swap_remove(index),
+            None if candidates.len() == 1 => candidates.remove(0),
+            None if candidates.is_empty() => {
+                return Err(anyhow::anyhow!(
+                    "No function or trait found in: {}",
+                    baseline.trim()
+                ));
+            }
+            None => {
+                return Err(anyhow::anyhow!(
+                    "No function or trait named {} in the baseline",
+                    name.as_deref().unwrap_or("<unnamed>")
+                ));
+            }
+        };
+
+        let mut changes = Vec::new();
+        let (old_name, new_name) = match (&old, &new) {
+            (ast::Item::Fn(old), ast::Item::Fn(new)) => {
+                Self::diff_fns(old, new, None, &mut changes);
+                (old.name(), new.name())
+            }
+            (ast::Item::Trait(old), ast::Item::Trait(new)) => {
+                Self::diff_traits(old, new, &mut changes);
+                (old.name(), new.name())
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Can't compare a {} with a {}",
+                    Self::signature_item_kind(&old),
+                    Self::signature_item_kind(&new)
+                ));
+            }
+        };
+
+        let old_name = old_name.map(|name| name.text().to_string());
+        let new_name = new_name.map(|name| name.text().to_string());
+        if old_name != new_name {
+            changes.insert(
+                0,
+                Self::signature_change("name".to_string(), old_name, new_name.clone(), true),
+            );
+        }
+        Ok(SignatureDiff {
+            name: new_name.unwrap_or_default(),
+            changes,
+        })
+    }
+
+    /// The functions and traits of `text` in order, possibly nested in a module
+    /// or impl, without the methods of the traits
+    fn signature_items(text: &str) -> Vec<ast::Item> {
+        let source_file = ast::SourceFile::parse(text, Edition::CURRENT).tree();
+        source_file
+            .syntax()
+            .descendants()
+            .filter_map(ast::Item::cast)
+            .filter(|item| match item {
+                ast::Item::Fn(function) => !function
+                    .syntax()
+                    .ancestors()
+                    .any(|ancestor| ast::Trait::can_cast(ancestor.kind())),
+                ast::Item::Trait(_) => true,
+                _ => false,
+            })
+            .collect()
+    }
+
+    fn signature_item_name(item: &ast::Item) -> Option<String> {
+        let name = match item {
+            ast::Item::Fn(function) => function.name(),
+            ast::Item::Trait(trait_) => trait_.name(),
+            _ => None,
+        };
+        name.map(|name| name.text().to_string())
+    }
+
+    fn signature_item_kind(item: &ast::Item) -> &'static str {
+        match item {
+            ast::Item::Fn(_) => "function",
+            _ => "trait",
+        }
+    }
+
+    /// Record the changes between two versions of a function, or of the
+    /// method `method` of a trait
+    fn diff_fns(
+        old: &ast::Fn,
+        new: &ast::Fn,
+        method: Option<&str>,
+        changes: &mut Vec<SignatureChange>,
+    ) {
+        let subject = |part: &str| match method {
+            Some(method) => format!("{part} of method {method}"),
+            None => part.to_string(),
+        };
+
+        // Adding `const` or removing `unsafe` only relaxes what callers can do.
+        // The last two values tell whether adding and removing are breaking.
+        for (qualifier, in_old, in_new, breaking_if_added, breaking_if_removed) in [
+            (
+                "const",
+                old.const_token().is_some(),
+                new.const_token().is_some(),
+                false,
+                true,
+            ),
+            (
+                "async",
+                old.async_token().is_some(),
+                new.async_token().is_some(),
+                true,
+                true,
+            ),
+            (
+                "unsafe",
+                old.unsafe_token().is_some(),
+                new.unsafe_token().is_some(),
+                true,
+                false,
+            ),
+        ] {
+            if in_old != in_new {
+                changes.push(Self::signature_change(
+                    subject("qualifier"),
+                    in_old.then(|| qualifier.to_string()),
+                    in_new.then(|| qualifier.to_string()),
+                    if in_new {
+                        breaking_if_added
+                    } else {
+                        breaking_if_removed
+                    },
+                ));
+            }
+        }
+        Self::diff_signature_part(
+            changes,
+            subject("ABI"),
+            old.abi().map(|it| it.syntax().clone()),
+            new.abi().map(|it| it.syntax().clone()),
+        );
+        Self::diff_signature_part(
+            changes,
+            subject("generic parameters"),
+            old.generic_param_list().map(|it| it.syntax().clone()),
+            new.generic_param_list().map(|it| it.syntax().clone()),
+        );
+        Self::diff_signature_part(
+            changes,
+            subject("where clause"),
+            old.where_clause().map(|it| it.syntax().clone()),
+            new.where_clause().map(|it| it.syntax().clone()),
+        );
+
+        let old_params = old.param_list();
+        let new_params = new.param_list();
+        Self::diff_signature_part(
+            changes,
+            subject("receiver"),
+            old_params
+                .as_ref()
+                .and_then(|it| it.self_param())
+                .map(|it| it.syntax().clone()),
+            new_params
+                .as_ref()
+                .and_then(|it| it.self_param())
+                .map(|it| it.syntax().clone()),
+        );
+        let old_params: Vec<ast::Param> = old_params.iter().flat_map(|it| it.params()).collect();
+        let new_params: Vec<ast::Param> = new_params.iter().flat_map(|it| it.params()).collect();
+        for index in 0..old_params.len().max(new_params.len()) {
+            let old_param = old_params.get(index);
+            let new_param = new_params.get(index);
+            let name = new_param
+                .or(old_param)
+                .and_then(|param| param.pat())
+                .map(|pat| Self::signature_text(pat.syntax(), None))
+                .unwrap_or_default();
+            let part = subject(&format!("parameter {} ({})", index + 1, name));
+            let ty_tokens = |param: &ast::Param| {
+                param
+                    .ty()
+                    .map(|ty| Self::signature_tokens(ty.syntax(), None))
+            };
+            let param_text = |param: &ast::Param| Self::signature_text(param.syntax(), None);
+
+            match (old_param, new_param) {
+                (Some(old_param), Some(new_param))
+                    if ty_tokens(old_param) != ty_tokens(new_param) =>
+                {
+                    changes.push(Self::signature_change(
+                        part,
+                        Some(param_text(old_param)),
+                        Some(param_text(new_param)),
+                        true,
+                    ));
+                }
+                // Only the pattern changed, which callers don't see
+                (Some(old_param), Some(new_param)) => {
+                    if param_text(old_param) != param_text(new_param) {
+                        changes.push(Self::signature_change(
+                            subject(&format!("parameter {} name", index + 1)),
+                            Some(param_text(old_param)),
+                            Some(param_text(new_param)),
+                            false,
+                        ));
+                    }
+                }
+                (old_param, new_param) => changes.push(Self::signature_change(
+                    part,
+                    old_param.map(param_text),
+                    new_param.map(param_text),
+                    true,
+                )),
+            }
+        }
+
+        // A missing return type is `()`
+        let return_type = |function: &ast::Fn| {
+            function
+                .ret_type()
+                .and_then(|ret_type| ret_type.ty())
+                .map(|ty| {
+                    (
+                        Self::signature_tokens(ty.syntax(), None),
+                        Self::signature_text(ty.syntax(), None),
+                    )
+                })
+                .unwrap_or_else(|| ("( )".to_string(), "()".to_string()))
+        };
+        let (old_tokens, old_text) = return_type(old);
+        let (new_tokens, new_text) = return_type(new);
+        if old_tokens != new_tokens {
+            changes.push(Self::signature_change(
+                subject("return type"),
+                Some(old_text),
+                Some(new_text),
+                true,
+            ));
+        }
+    }
+
+    /// Record the changes between two versions of a trait and its items
+    fn diff_traits(old: &ast::Trait, new: &ast::Trait, changes: &mut Vec<SignatureChange>) {
+        let (in_old, in_new) = (old.unsafe_token().is_some(), new.unsafe_token().is_some());
+        if in_old != in_new {
+            changes.push(Self::signature_change(
+                "qualifier".to_string(),
+                in_old.then(|| "unsafe".to_string()),
+                in_new.then(|| "unsafe".to_string()),
+                true,
+            ));
+        }
+        Self::diff_signature_part(
+            changes,
+            "generic parameters".to_string(),
+            old.generic_param_list().map(|it| it.syntax().clone()),
+            new.generic_param_list().map(|it| it.syntax().clone()),
+        );
+        Self::diff_signature_part(
+            changes,
+            "supertraits".to_string(),
+            old.type_bound_list().map(|it| it.syntax().clone()),
+            new.type_bound_list().map(|it| it.syntax().clone()),
+        );
+        Self::diff_signature_part(
+            changes,
+            "where clause".to_string(),
+            old.where_clause().map(|it| it.syntax().clone()),
+            new.where_clause().map(|it| it.syntax().clone()),
+        );
+
+        let old_items = Self::trait_items(old);
+        let new_items = Self::trait_items(new);
+        for (key, old_item) in &old_items {
+            if !new_items.iter().any(|(new_key, _)| new_key == key) {
+                changes.push(Self::signature_change(
+                    key.clone(),
+                    Some(Self::trait_item_text(old_item)),
+                    None,
+                    true,
+                ));
+            }
+        }
+        for (key, new_item) in &new_items {
+            let old_item = old_items
+                .iter()
+                .find(|(old_key, _)| old_key == key)
+                .map(|(_, item)| item);
+            match (old_item, new_item) {
+                (Some(ast::AssocItem::Fn(old_fn)), ast::AssocItem::Fn(new_fn)) => {
+                    let name = new_fn
+                        .name()
+                        .map(|name| name.text().to_string())
+                        .unwrap_or_default();
+                    Self::diff_fns(old_fn, new_fn, Some(&name), changes);
+                    // Implementors relying on the default no longer compile without it
+                    let (old_default, new_default) =
+                        (old_fn.body().is_some(), new_fn.body().is_some());
+                    if old_default != new_default {
+                        changes.push(SignatureChange {
+                            kind: if new_default {
+                                SignatureChangeKind::Added
+                            } else {
+                                SignatureChangeKind::Removed
+                            },
+                            subject: format!("default implementation of method {name}"),
+                            old: None,
+                            new: None,
+                            breaking: old_default,
+                        });
+                    }
+                }
+                (Some(old_item), new_item) => Self::diff_signature_part(
+                    changes,
+                    key.clone(),
+                    Some(old_item.syntax().clone()),
+                    Some(new_item.syntax().clone()),
+                ),
+                // New items only break implementors when they have no default
+                (None, new_item) => {
+                    let has_default = match new_item {
+                        ast::AssocItem::Fn(it) => it.body().is_some(),
+                        ast::AssocItem::TypeAlias(it) => it.ty().is_some(),
+                        ast::AssocItem::Const(it) => it.body().is_some(),
+                        ast::AssocItem::MacroCall(_) => true,
+                    };
+                    changes.push(Self::signature_change(
+                        key.clone(),
+                        None,
+                        Some(Self::trait_item_text(new_item)),
+                        !has_default,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Items of a trait keyed by kind and name, e.g. `method len` or `type Item`
+    fn trait_items(trait_: &ast::Trait) -> Vec<(String, ast::AssocItem)> {
+        trait_
+            .assoc_item_list()
+            .iter()
+            .flat_map(|list| list.assoc_items())
+            .filter_map(|item| {
+                let key = match &item {
+                    ast::AssocItem::Fn(it) => format!("method {}", it.name()?.text()),
+                    ast::AssocItem::TypeAlias(it) => format!("type {}", it.name()?.text()),
+                    ast::AssocItem::Const(it) => format!("const {}", it.name()?.text()),
+                    ast::AssocItem::MacroCall(_) => return None,
+                };
+                Some((key, item))
+            })
+            .collect()
+    }
+
+    /// Declaration of a trait item, without the body of methods
+    fn trait_item_text(item: &ast::AssocItem) -> String {
+        let skip = match item {
+            ast::AssocItem::Fn(it) => it.body().map(|body| body.syntax().text_range()),
+            _ => None,
+        };
+        Self::signature_text(item.syntax(), skip)
+            .trim_end_matches(';')
+            .trim_end()
+            .to_string()
+    }
+
+    /// Record a change of an optional part of a signature, such as a where
+    /// clause, if its tokens differ. Any difference is breaking.
+    fn diff_signature_part(
+        changes: &mut Vec<SignatureChange>,
+        subject: String,
+        old: Option<SyntaxNode>,
+        new: Option<SyntaxNode>,
+    ) {
+        let tokens = |node: &SyntaxNode| Self::signature_tokens(node, None);
+        if old.as_ref().map(tokens) == new.as_ref().map(tokens) {
+            return;
+        }
+        changes.push(Self::signature_change(
+            subject,
+            old.map(|node| Self::signature_text(&node, None)),
+            new.map(|node| Self::signature_text(&node, None)),
+            true,
+        ));
+    }
+
+    fn signature_change(
+        subject: String,
+        old: Option<String>,
+        new: Option<String>,
+        breaking: bool,
+    ) -> SignatureChange {
+        let kind = match (&old, &new) {
+            (None, Some(_)) => SignatureChangeKind::Added,
+            (Some(_), None) => SignatureChangeKind::Removed,
+            _ => SignatureChangeKind::Changed,
+        };
+        SignatureChange {
+            kind,
+            subject,
+            old,
+            new,
+            breaking,
+        }
+    }
+
+    /// Tokens of a declaration that matter for compatibility, separated by
+    /// single spaces, leaving out attributes, comments and the `skip` range
+    fn signature_tokens(node: &SyntaxNode, skip: Option<TextRange>) -> String {
+        Self::declaration_tokens(node, skip)
+            .filter(|token| token.kind() != SyntaxKind::WHITESPACE)
+            .map(|token| token.text().to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Declaration as written, without attributes, comments and the `skip`
+    /// range, on a single line
+    fn signature_text(node: &SyntaxNode, skip: Option<TextRange>) -> String {
+        let mut text = String::new();
+        for token in Self::declaration_tokens(node, skip) {
+            if token.kind() != SyntaxKind::WHITESPACE {
+                text.push_str(token.text());
+            } else if !text.is_empty() && !text.ends_with(' ') {
+                text.push(' ');
+            }
+        }
+        text.trim_end().to_string()
+    }
+
+    fn declaration_tokens(
+        node: &SyntaxNode,
+        skip: Option<TextRange>,
+    ) -> impl Iterator<Item = SyntaxToken> {
+        node.descendants_with_tokens()
+            .filter_map(|element| element.into_token())
+            .filter(move |token| {
+                token.kind() != SyntaxKind::COMMENT
+                    && !skip.is_some_and(|range| range.contains_range(token.text_range()))
+                    && !token
+                        .parent_ancestors()
+                        .any(|ancestor| ancestor.kind() == SyntaxKind::ATTR)
+            })
+    }
+
+    /// Summarize diagnostics as counts per severity, e.g. "2 error(s), 1 warning(s)"
+    pub fn diagnostics_summary(diagnostics: &[DiagnosticInfo]) -> String {
+        let count = |severity: DiagnosticSeverity| {
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == severity)
+                .count()
+        };
+        format!(
+            "{} error(s), {} warning(s), {} weak warning(s)",
+            count(DiagnosticSeverity::Error),
+            count(DiagnosticSeverity::Warning),
+            count(DiagnosticSeverity::WeakWarning)
+        )
+    }
+
     /// Convert a PathBuf to AbsPathBuf for rust-analyzer operations
     pub fn path_to_abs_path(path: &Path) -> Result<ra_ap_vfs::AbsPathBuf> {
         use anyhow::Context;
@@ -119,10 +1010,214 @@ impl RustAnalyzerUtils {
 
     /// Apply a file change to disk (used by assists)
     pub async fn apply_file_change(file_change: &FileChange) -> Result<()> {
+        Self::write_file_change(file_change).await.map(|_| ())
+    }
+
+    /// Apply a file change to disk and return a unified diff of it
+    pub async fn apply_file_change_with_diff(file_change: &FileChange) -> Result<String> {
+        let (original, modified) = Self::write_file_change(file_change).await?;
+        Ok(Self::unified_diff(
+            &file_change.file_path,
+            &original,
+            &modified,
+        ))
+    }
+
+    /// Apply file changes as configured by `options`
+    ///
+    /// Writes the changes to disk unless `options.dry_run` is set, and stores
+    /// a diff in each change if `options.return_diff` is set.
+    pub async fn apply_file_changes(
+        file_changes: &mut [FileChange],
+        options: &ApplyOptions,
+    ) -> Result<()> {
+        for file_change in file_changes {
+            let (original, modified) = if options.dry_run {
+                Self::preview_file_change(file_change).await?
+            } else {
+                Self::write_file_change(file_change).await?
+            };
+            if options.return_diff {
+                file_change.diff = Some(Self::unified_diff(
+                    &file_change.file_path,
+                    &original,
+                    &modified,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert a rust-analyzer text edit into 1-based edits of our format
+    ///
+    /// A snippet edit only carries tabstops and placeholders telling an editor
+    /// where to put the cursor; the text edit is complete without it. We apply
+    /// edits to disk rather than in an editor, so snippet edits are dropped and
+    /// the new text never contains snippet markers such as `$0`.
+    pub fn convert_text_edit(
+        line_index: &LineIndex,
+        text_edit: &RaTextEdit,
+        snippet_edit: Option<&SnippetEdit>,
+    ) -> Vec<TextEdit> {
+        if snippet_edit.is_some() {
+            tracing::debug!("Dropping snippet edit, only keeping its text edit");
+        }
+        text_edit
+            .iter()
+            .map(|indel| {
+                let start_line_col = line_index.line_col(indel.delete.start());
+                let end_line_col = line_index.line_col(indel.delete.end());
+                TextEdit {
+                    line: start_line_col.line + 1,
+                    column: start_line_col.col + 1,
+                    end_line: end_line_col.line + 1,
+                    end_column: end_line_col.col + 1,
+                    new_text: indel.insert.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Preview the lines of `text` changed by `edits`, before and after
+    ///
+    /// Edits touching the same or overlapping lines are grouped into one
+    /// preview. Columns are byte offsets into their line, as produced by
+    /// [`Self::convert_text_edit`]; edits not landing on a character boundary
+    /// are left out of the preview.
+    pub fn line_previews(file_path: &str, text: &str, edits: &[TextEdit]) -> Vec<LinePreview> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut edits: Vec<&TextEdit> = edits.iter().collect();
+        edits.sort_by_key(|edit| (edit.line, edit.column));
+
+        let mut previews = Vec::new();
+        let mut index = 0;
+        while index < edits.len() {
+            let first_line = edits[index].line.max(1);
+            let mut last_line = edits[index].end_line.max(first_line);
+            let mut end = index + 1;
+            while end < edits.len() && edits[end].line <= last_line {
+                last_line = last_line.max(edits[end].end_line);
+                end += 1;
+            }
+
+            let span_start = (first_line as usize - 1).min(lines.len());
+            let span_end = (last_line as usize).min(lines.len());
+            let span = &lines[span_start..span_end];
+            let before = span.join("\n");
+
+            // Byte offset in `before` of a 1-based line and column
+            let offset = |line: u32, column: u32| {
+                let line = (line.max(first_line) - first_line) as usize;
+                let line_start: usize = span.iter().take(line).map(|text| text.len() + 1).sum();
+                (line_start + column.saturating_sub(1) as usize).min(before.len())
+            };
+
+            // Applied back to front, so earlier offsets stay valid
+            let mut after = before.clone();
+            for edit in edits[index..end].iter().rev() {
+                let start = offset(edit.line, edit.column);
+                let stop = offset(edit.end_line, edit.end_column).max(start);
+                if after.is_char_boundary(start) && after.is_char_boundary(stop) {
+                    after.replace_range(start..stop, &edit.new_text);
+                }
+            }
+
+            previews.push(LinePreview {
+                file_path: file_path.to_string(),
+                line: first_line,
+                before,
+                after,
+            });
+            index = end;
+        }
+        previews
+    }
+
+    /// Check that file renames can be applied: every source exists and no
+    /// destination is taken
+    pub fn check_file_renames(file_renames: &[FileRename]) -> Result<()> {
+        for file_rename in file_renames {
+            if !Path::new(&file_rename.from).exists() {
+                return Err(anyhow::anyhow!(
+                    "Cannot move {}: not found",
+                    file_rename.from
+                ));
+            }
+            if Path::new(&file_rename.to).exists() {
+                return Err(anyhow::anyhow!(
+                    "Cannot move {} to {}: the destination already exists",
+                    file_rename.from,
+                    file_rename.to
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Move files and directories on disk, in order
+    pub async fn apply_file_renames(file_renames: &[FileRename]) -> Result<()> {
+        for file_rename in file_renames {
+            fs::rename(&file_rename.from, &file_rename.to)
+                .await
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "Failed to move {} to {}: {}",
+                        file_rename.from,
+                        file_rename.to,
+                        e
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Lexically resolve `.` and `..` components, without touching the file system
+    pub fn normalize_path(path: &Path) -> PathBuf {
+        let mut normalized = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    normalized.pop();
+                }
+                component => normalized.push(component),
+            }
+        }
+        normalized
+    }
+
+    /// Unified diff between two versions of a file, with 3 lines of context
+    pub fn unified_diff(file_path: &str, original: &str, modified: &str) -> String {
+        TextDiff::from_lines(original, modified)
+            .unified_diff()
+            .context_radius(3)
+            .header(file_path, file_path)
+            .to_string()
+    }
+
+    /// Apply a file change to disk, returning the original and modified content
+    async fn write_file_change(file_change: &FileChange) -> Result<(String, String)> {
+        let (original, modified) = Self::preview_file_change(file_change).await?;
+
+        // Write the modified content back to the file
+        fs::write(&file_change.file_path, &modified)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("Failed to write file {}: {}", file_change.file_path, e)
+            })?;
+
+        Ok((original, modified))
+    }
+
+    /// Compute the content of a file after a change without writing it,
+    /// returning the original and modified content
+    async fn preview_file_change(file_change: &FileChange) -> Result<(String, String)> {
         // Read the current file content
-        let mut content = fs::read_to_string(&file_change.file_path)
+        let original = fs::read_to_string(&file_change.file_path)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", file_change.file_path, e))?;
+        // Positions are counted after any BOM, like in the analysis host
+        let mut content = Self::strip_bom(&original).to_string();
 
         // Create TextEditBuilder to handle multiple edits atomically
         let mut builder = TextEditBuilder::default();
@@ -130,46 +1225,117 @@ impl RustAnalyzerUtils {
         // Create line index for UTF-8 safe position conversion
         let line_index = LineIndex::new(&content);
 
-        // Add all edits to the builder (no need to sort - TextEditBuilder handles ordering)
+        // Validate every range before applying anything, so a bad edit leaves
+        // the file untouched
+        let mut ranges = Vec::with_capacity(file_change.edits.len());
         for edit in &file_change.edits {
-            // Convert 1-based line/column to character offset using LineIndex for UTF-8 safety
-            let start_offset =
-                Self::line_col_to_offset_with_index(&line_index, edit.line, edit.column)
-                    .ok_or_else(|| {
-                        anyhow::anyhow!(
-                            "Invalid start position {}:{} in file {}",
-                            edit.line,
-                            edit.column,
-                            file_change.file_path
-                        )
-                    })?;
-
+            let start_offset = Self::checked_offset(&content, &line_index, edit.line, edit.column)
+                .map_err(|e| {
+                    ErrorKind::InvalidPosition.error(format!(
+                        "Invalid start position {}:{} in file {}: {}",
+                        edit.line, edit.column, file_change.file_path, e
+                    ))
+                })?;
             let end_offset =
-                Self::line_col_to_offset_with_index(&line_index, edit.end_line, edit.end_column)
-                    .ok_or_else(|| {
-                        anyhow::anyhow!(
-                            "Invalid end position {}:{} in file {}",
-                            edit.end_line,
-                            edit.end_column,
-                            file_change.file_path
-                        )
+                Self::checked_offset(&content, &line_index, edit.end_line, edit.end_column)
+                    .map_err(|e| {
+                        ErrorKind::InvalidPosition.error(format!(
+                            "Invalid end position {}:{} in file {}: {}",
+                            edit.end_line, edit.end_column, file_change.file_path, e
+                        ))
                     })?;
+            if end_offset < start_offset {
+                return Err(anyhow::anyhow!(
+                    "Edit {}:{}-{}:{} in file {} ends before it starts",
+                    edit.line,
+                    edit.column,
+                    edit.end_line,
+                    edit.end_column,
+                    file_change.file_path
+                ));
+            }
+            ranges.push((TextRange::new(start_offset, end_offset), edit));
+        }
+
+        // TextEditBuilder requires disjoint ranges
+        ranges.sort_by_key(|(range, _)| (range.start(), range.end()));
+        for pair in ranges.windows(2) {
+            let ((previous, previous_edit), (next, next_edit)) = (&pair[0], &pair[1]);
+            if next.start() < previous.end() {
+                return Err(anyhow::anyhow!(
+                    "Edits {}:{}-{}:{} and {}:{}-{}:{} overlap in file {}",
+                    previous_edit.line,
+                    previous_edit.column,
+                    previous_edit.end_line,
+                    previous_edit.end_column,
+                    next_edit.line,
+                    next_edit.column,
+                    next_edit.end_line,
+                    next_edit.end_column,
+                    file_change.file_path
+                ));
+            }
+        }
 
-            let text_range = TextRange::new(start_offset, end_offset);
+        for (text_range, edit) in ranges {
             builder.replace(text_range, edit.new_text.clone());
         }
 
         // Build the final text edit and apply it
         let text_edit = builder.finish();
         text_edit.apply(&mut content);
+        Self::preserve_final_newline(&original, &mut content);
+        Self::restore_bom(&original, &mut content);
 
-        // Write the modified content back to the file
-        fs::write(&file_change.file_path, content)
-            .await
-            .map_err(|e| {
-                anyhow::anyhow!("Failed to write file {}: {}", file_change.file_path, e)
-            })?;
+        Ok((original, content))
+    }
 
-        Ok(())
+    /// Text of a file without its leading UTF-8 byte order mark, if any
+    ///
+    /// Editors don't count the BOM as a column, so file text is given to the
+    /// analysis host and edited without it to keep first-line positions right.
+    pub fn strip_bom(text: &str) -> &str {
+        text.strip_prefix(UTF8_BOM).unwrap_or(text)
+    }
+
+    /// Put back the byte order mark of `original` at the start of edited text
+    fn restore_bom(original: &str, modified: &mut String) {
+        if original.starts_with(UTF8_BOM) {
+            modified.insert_str(0, UTF8_BOM);
+        }
+    }
+
+    /// Message of a panic caught with `catch_unwind`
+    ///
+    /// Panics with a formatted message carry a `String`, those with a literal
+    /// a `&str`. Other payloads have no message to show.
+    pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+        payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "<non-string panic payload>".to_string())
+    }
+
+    /// Give an edited file the trailing newline state of its original content
+    ///
+    /// Edits at the end of a file can add or remove its final newline, which
+    /// shows up as noise in diffs. The newline added back matches the original
+    /// line ending. Empty originals, such as new files, are left alone.
+    pub fn preserve_final_newline(original: &str, modified: &mut String) {
+        if original.is_empty() || modified.is_empty() {
+            return;
+        }
+        match (original.ends_with('\n'), modified.ends_with('\n')) {
+            (true, false) if original.ends_with("\r\n") => modified.push_str("\r\n"),
+            (true, false) => modified.push('\n'),
+            (false, true) => {
+                modified.pop();
+                if modified.ends_with('\r') {
+                    modified.pop();
+                }
+            }
+            _ => {}
+        }
     }
 }
@@ -0,0 +1,503 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use librustbelt::ErrorKind;
+use librustbelt::builder::{LoadProgress, RustAnalyzerishBuilder, WorkspaceOptions};
+use librustbelt::config::AnalyzerConfig;
+use librustbelt::entities::{CursorCoordinates, TypeHint};
+use librustbelt::file_watcher::FileWatcher;
+use librustbelt::utils::RustAnalyzerUtils;
+
+fn get_sample_file_path() -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests/sample-project/src/main.rs");
+    path
+}
+
+#[test]
+fn test_cfg_overrides_accepts_flags_and_key_values() {
+    let options = WorkspaceOptions {
+        cfgs: vec![
+            "test".to_string(),
+            "feature=\"serde\"".to_string(),
+            "-debug_assertions".to_string(),
+        ],
+        ..Default::default()
+    };
+    assert!(options.cfg_overrides().is_ok());
+}
+
+#[test]
+fn test_cfg_overrides_rejects_missing_name() {
+    for cfg in ["", "-", "=\"value\""] {
+        let options = WorkspaceOptions {
+            cfgs: vec![cfg.to_string()],
+            ..Default::default()
+        };
+        assert!(
+            options.cfg_overrides().is_err(),
+            "cfg '{cfg}' should be rejected"
+        );
+    }
+}
+
+#[test]
+fn test_edition_option_is_validated() {
+    let options = WorkspaceOptions {
+        edition: Some("2021".to_string()),
+        ..Default::default()
+    };
+    assert!(options.edition().expect("2021 should parse").is_some());
+    assert!(WorkspaceOptions::default().edition().unwrap().is_none());
+
+    for edition in ["", "2020", "latest"] {
+        let options = WorkspaceOptions {
+            edition: Some(edition.to_string()),
+            ..Default::default()
+        };
+        assert!(
+            options.edition().is_err(),
+            "edition '{edition}' should be rejected"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_edition_override_applies_to_workspace_crates() {
+    let sample_path = get_sample_file_path();
+    let options = WorkspaceOptions {
+        edition: Some("2018".to_string()),
+        no_sysroot: true,
+        no_proc_macros: true,
+        no_prime_caches: true,
+        ..Default::default()
+    };
+    let mut analyzer = RustAnalyzerishBuilder::from_file(&sample_path)
+        .expect("Failed to create builder")
+        .with_options(&options)
+        .expect("Failed to apply options")
+        .build()
+        .expect("Failed to build analyzer");
+
+    let edition = analyzer
+        .get_file_edition(sample_path.to_str().unwrap())
+        .await
+        .expect("Error getting file edition");
+    assert_eq!(edition, "2018");
+}
+
+#[test]
+fn test_config_keeps_defaults_of_missing_keys() {
+    let config = AnalyzerConfig::from_json(
+        r#"{ "hover": { "max_fields_count": null }, "inlay_hints": { "max_length": 25 } }"#,
+    )
+    .expect("Failed to parse config");
+    assert_eq!(config.hover.max_fields_count, None);
+    assert_eq!(config.hover.max_enum_variants_count, Some(10));
+    assert_eq!(config.inlay_hints.max_length, Some(25));
+    assert_eq!(config.completion, AnalyzerConfig::default().completion);
+
+    assert_eq!(
+        AnalyzerConfig::from_json("{}").expect("Failed to parse config"),
+        AnalyzerConfig::default()
+    );
+    assert!(AnalyzerConfig::from_json(r#"{ "hover": { "documentation": "yes" } }"#).is_err());
+}
+
+#[test]
+fn test_config_unknown_keys() {
+    let value = serde_json::json!({
+        "hover": { "documentation": false, "max_field_count": 3 },
+        "completions": {},
+        "inlay_hints": { "type_hints": true }
+    });
+    assert_eq!(
+        AnalyzerConfig::unknown_keys(&value),
+        ["completions", "hover.max_field_count"]
+    );
+    // Unknown keys are reported but don't prevent loading
+    let config = AnalyzerConfig::from_json(&value.to_string()).expect("Failed to parse config");
+    assert!(!config.hover.documentation);
+}
+
+#[test]
+fn test_missing_config_file_is_an_error() {
+    let options = WorkspaceOptions {
+        config_file: Some("/nonexistent/rustbelt.json".to_string()),
+        ..Default::default()
+    };
+    let error = RustAnalyzerishBuilder::new()
+        .with_options(&options)
+        .expect_err("A missing config file should be rejected");
+    assert!(
+        error.to_string().contains("/nonexistent/rustbelt.json"),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn test_fast_load_without_sysroot_and_proc_macros() {
+    let sample_path = get_sample_file_path();
+
+    let start = Instant::now();
+    let mut full = RustAnalyzerishBuilder::from_file(&sample_path)
+        .expect("Failed to create builder")
+        .build()
+        .expect("Failed to build analyzer");
+    let full_load = start.elapsed();
+
+    let start = Instant::now();
+    let options = WorkspaceOptions {
+        no_sysroot: true,
+        no_proc_macros: true,
+        ..Default::default()
+    };
+    let mut analyzer = RustAnalyzerishBuilder::from_file(&sample_path)
+        .expect("Failed to create builder")
+        .with_options(&options)
+        .expect("Failed to apply options")
+        .build()
+        .expect("Failed to build analyzer");
+    let fast_load = start.elapsed();
+
+    println!("Full load: {full_load:?}, without sysroot and proc macros: {fast_load:?}");
+    assert!(
+        fast_load < full_load,
+        "Loading without sysroot and proc macros took {fast_load:?}, a full load {full_load:?}"
+    );
+
+    // Workspace-local types still resolve without the sysroot
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_string_lossy().to_string(),
+        line: 33,
+        column: 9,
+        symbol: None,
+    };
+    let type_hint = analyzer
+        .get_type_hint(&cursor)
+        .await
+        .expect("Error getting type hint")
+        .expect("No type hint found");
+    assert!(
+        type_hint
+            .canonical_types
+            .iter()
+            .any(|ty| ty.contains("Person")),
+        "Unexpected types: {:?}",
+        type_hint.canonical_types
+    );
+
+    // std types only resolve with the sysroot, the price of the fast load
+    let nested = CursorCoordinates { line: 46, ..cursor };
+    let std_types = |type_hint: Option<TypeHint>| {
+        type_hint
+            .map(|type_hint| type_hint.canonical_types)
+            .unwrap_or_default()
+    };
+    let with_sysroot = std_types(
+        full.get_type_hint(&nested)
+            .await
+            .expect("Error getting type hint"),
+    );
+    assert!(
+        with_sysroot.contains(&"alloc::vec::Vec".to_string()),
+        "{with_sysroot:?}"
+    );
+    let without_sysroot = std_types(
+        analyzer
+            .get_type_hint(&nested)
+            .await
+            .expect("Error getting type hint"),
+    );
+    assert!(
+        !without_sysroot.contains(&"alloc::vec::Vec".to_string()),
+        "{without_sysroot:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_load_with_capped_or_skipped_cache_priming() {
+    let sample_path = get_sample_file_path();
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_string_lossy().to_string(),
+        line: 33,
+        column: 9,
+        symbol: None,
+    };
+
+    for options in [
+        WorkspaceOptions {
+            no_sysroot: true,
+            prime_threads: Some(1),
+            ..Default::default()
+        },
+        WorkspaceOptions {
+            no_sysroot: true,
+            no_prime_caches: true,
+            ..Default::default()
+        },
+    ] {
+        let mut analyzer = RustAnalyzerishBuilder::from_file_with_options(&sample_path, &options)
+            .expect("Failed to create builder")
+            .build()
+            .expect("Failed to build analyzer");
+
+        let status = analyzer.status();
+        assert_eq!(status.caches_primed, !options.no_prime_caches);
+        assert_eq!(status.workspace_file_count, 2);
+
+        // Queries work the same whether or not caches were primed
+        let type_hint = analyzer
+            .get_type_hint(&cursor)
+            .await
+            .expect("Error getting type hint")
+            .expect("No type hint found");
+        assert!(
+            type_hint
+                .canonical_types
+                .iter()
+                .any(|ty| ty.contains("Person")),
+            "Unexpected types with {options:?}: {:?}",
+            type_hint.canonical_types
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_lazy_mode_timings() {
+    let sample_path = get_sample_file_path();
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_string_lossy().to_string(),
+        line: 33,
+        column: 9,
+        symbol: None,
+    };
+
+    let mut answers = Vec::new();
+    for prime_caches in [true, false] {
+        let start = Instant::now();
+        let mut analyzer = RustAnalyzerishBuilder::from_file(&sample_path)
+            .expect("Failed to create builder")
+            .without_sysroot()
+            .with_prime_caches(prime_caches)
+            .build()
+            .expect("Failed to build analyzer");
+        let startup = start.elapsed();
+
+        assert_eq!(analyzer.status().caches_primed, prime_caches);
+
+        let start = Instant::now();
+        let type_hint = analyzer
+            .get_type_hint(&cursor)
+            .await
+            .expect("Error getting type hint")
+            .expect("No type hint found");
+        let first_query = start.elapsed();
+
+        println!(
+            "Prime caches: {prime_caches}, startup: {startup:?}, first query: {first_query:?}"
+        );
+        answers.push(type_hint.canonical_types);
+    }
+
+    // Skipping the priming only defers work, it must not change the answer
+    assert_eq!(answers[0], answers[1]);
+}
+
+#[test]
+fn test_load_progress_callback() {
+    let stages = Arc::new(Mutex::new(Vec::new()));
+    let recorded = stages.clone();
+    RustAnalyzerishBuilder::from_file(get_sample_file_path())
+        .expect("Failed to create builder")
+        .without_sysroot()
+        .with_progress(Arc::new(move |progress| {
+            recorded.lock().unwrap().push(progress)
+        }))
+        .build()
+        .expect("Failed to build analyzer");
+
+    let stages = stages.lock().unwrap();
+    assert!(
+        matches!(stages.first(), Some(LoadProgress::LoadingWorkspace(_))),
+        "Unexpected stages: {stages:?}"
+    );
+    assert!(
+        stages
+            .iter()
+            .any(|stage| matches!(stage, LoadProgress::PrimingCaches { .. })),
+        "Unexpected stages: {stages:?}"
+    );
+    assert_eq!(stages.last(), Some(&LoadProgress::Finished));
+}
+
+/// Write a scratch file in a fresh directory outside any cargo project
+fn write_scratch_file(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rustbelt-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Failed to create scratch directory");
+    let path = dir.join("scratch.rs");
+    std::fs::write(&path, "fn main() {\n    let answer = 42u8;\n}\n")
+        .expect("Failed to write scratch file");
+    path
+}
+
+#[test]
+fn test_detached_file_requires_opt_in() {
+    let scratch_path = write_scratch_file("no-detached");
+
+    let error =
+        RustAnalyzerishBuilder::from_file_with_options(&scratch_path, &WorkspaceOptions::default())
+            .expect_err("Files outside a cargo project should be rejected by default");
+    assert!(
+        error.to_string().contains("No Cargo.toml found"),
+        "Unexpected error: {error}"
+    );
+    assert_eq!(ErrorKind::of(&error), Some(ErrorKind::FileNotFound));
+}
+
+#[test]
+fn test_detached_fallback_only_for_files_outside_a_project() {
+    let options = WorkspaceOptions {
+        allow_detached: true,
+        ..Default::default()
+    };
+
+    // A missing file is not a file outside any project
+    let missing_path = std::env::temp_dir().join("rustbelt-missing-detached.rs");
+    let error = RustAnalyzerishBuilder::from_file_with_options(&missing_path, &options)
+        .expect_err("Missing files should be rejected");
+    assert!(
+        error.to_string().contains("Failed to canonicalize path"),
+        "Unexpected error: {error}"
+    );
+
+    // Files of a project keep loading the project, not a detached file
+    let builder = RustAnalyzerishBuilder::from_file_with_options(get_sample_file_path(), &options)
+        .expect("Failed to create builder");
+    assert!(
+        builder
+            .project_root()
+            .is_some_and(|root| root.join("Cargo.toml").is_file()),
+        "Unexpected root: {:?}",
+        builder.project_root()
+    );
+}
+
+#[tokio::test]
+async fn test_detached_file_type_hint() {
+    let scratch_path = write_scratch_file("detached");
+    let options = WorkspaceOptions {
+        allow_detached: true,
+        no_sysroot: true,
+        no_proc_macros: true,
+        ..Default::default()
+    };
+    let mut analyzer = RustAnalyzerishBuilder::from_file_with_options(&scratch_path, &options)
+        .expect("Failed to create builder for detached file")
+        .build()
+        .expect("Failed to build analyzer for detached file");
+
+    let cursor = CursorCoordinates {
+        file_path: scratch_path.to_string_lossy().to_string(),
+        line: 2,
+        column: 9,
+        symbol: None,
+    };
+    let type_hint = analyzer
+        .get_type_hint(&cursor)
+        .await
+        .expect("Error getting type hint")
+        .expect("No type hint found");
+    assert!(
+        type_hint.symbol.contains("u8"),
+        "Unexpected hint: {type_hint}"
+    );
+}
+
+#[test]
+fn test_with_sysroot_path_rejects_missing_directory() {
+    let error = RustAnalyzerishBuilder::new()
+        .with_sysroot_path("/nonexistent/toolchain")
+        .expect_err("A missing sysroot should be rejected");
+    assert!(
+        error.to_string().contains("/nonexistent/toolchain"),
+        "{error}"
+    );
+
+    let options = WorkspaceOptions {
+        no_sysroot: true,
+        sysroot: Some(env!("CARGO_MANIFEST_DIR").to_string()),
+        ..Default::default()
+    };
+    assert!(
+        RustAnalyzerishBuilder::new()
+            .with_options(&options)
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_load_with_explicit_sysroot() {
+    let Ok(output) = std::process::Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+    else {
+        println!("rustc not found, skipping");
+        return;
+    };
+    let sysroot = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let sample_path = get_sample_file_path();
+    let options = WorkspaceOptions {
+        sysroot: Some(sysroot),
+        no_proc_macros: true,
+        ..Default::default()
+    };
+    let mut analyzer = RustAnalyzerishBuilder::from_file_with_options(&sample_path, &options)
+        .expect("Failed to create builder")
+        .build()
+        .expect("Failed to build analyzer with an explicit sysroot");
+
+    // `people` is a std `HashMap`, which only resolves with the sysroot loaded
+    let type_hint = analyzer
+        .get_type_hint(&CursorCoordinates {
+            file_path: sample_path.to_string_lossy().to_string(),
+            line: 31,
+            column: 13,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting type hint")
+        .expect("No type hint for people");
+    assert!(type_hint.symbol.contains("HashMap"), "{type_hint}");
+}
+
+#[test]
+fn test_watch_excludes_expand_globs() {
+    let dir = std::env::temp_dir().join(format!("rustbelt-watch-globs-{}", std::process::id()));
+    for sub in ["crates/a/generated", "crates/b/generated", "crates/c/src"] {
+        std::fs::create_dir_all(dir.join(sub)).expect("Failed to create scratch directory");
+    }
+    let root = RustAnalyzerUtils::path_to_abs_path(&dir).expect("Failed to resolve scratch root");
+
+    let excludes = FileWatcher::resolve_watch_excludes(
+        &root,
+        &["crates/*/generated".to_string(), "vendor".to_string()],
+    );
+    let excludes: Vec<String> = excludes.iter().map(|path| path.to_string()).collect();
+    assert_eq!(
+        excludes,
+        vec![
+            root.join("crates/a/generated").to_string(),
+            root.join("crates/b/generated").to_string(),
+            root.join("vendor").to_string(),
+        ]
+    );
+
+    // Invalid patterns are skipped rather than excluding everything
+    let excludes = FileWatcher::resolve_watch_excludes(&root, &["crates/[".to_string()]);
+    assert!(excludes.is_empty(), "{excludes:?}");
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,147 @@
+use librustbelt::entities::{CompletionOptions, DefinitionInfo, SourceExcerpt, SymbolMatchMode};
+use ra_ap_ide::SymbolKind;
+
+fn sample_definition() -> DefinitionInfo {
+    DefinitionInfo {
+        file_path: "/test/src/main.rs".to_string(),
+        line: 5,
+        column: 12,
+        end_line: 5,
+        end_column: 18,
+        name: "Person".to_string(),
+        kind: Some(SymbolKind::Struct),
+        content: "pub struct Person {}".to_string(),
+        module: "sample::Person".to_string(),
+        description: None,
+        container: None,
+        is_reexport: false,
+        is_external: false,
+        source: None,
+    }
+}
+
+#[test]
+fn test_definition_display_compact() {
+    let definition = sample_definition();
+
+    assert_eq!(
+        definition.to_string(),
+        "/test/src/main.rs:5:12\npub struct Person {}"
+    );
+}
+
+#[test]
+fn test_definition_display_verbose() {
+    let definition = sample_definition();
+
+    assert_eq!(
+        format!("{definition:#}"),
+        "/test/src/main.rs:5:12-5:18 (Struct) sample::Person\npub struct Person {}"
+    );
+}
+
+#[test]
+fn test_symbol_match_mode_from_str() {
+    assert_eq!(
+        "fuzzy".parse::<SymbolMatchMode>().unwrap(),
+        SymbolMatchMode::Fuzzy
+    );
+    assert_eq!(
+        "Regex".parse::<SymbolMatchMode>().unwrap(),
+        SymbolMatchMode::Regex
+    );
+    assert!("glob".parse::<SymbolMatchMode>().is_err());
+}
+
+#[test]
+fn test_definition_display_with_container() {
+    let definition = DefinitionInfo {
+        name: "new".to_string(),
+        kind: Some(SymbolKind::Method),
+        content: "pub fn new() -> Self {}".to_string(),
+        container: Some("impl Person".to_string()),
+        ..sample_definition()
+    };
+
+    assert_eq!(
+        definition.to_string(),
+        "/test/src/main.rs:5:12\nimpl Person {\n    pub fn new() -> Self {}\n}"
+    );
+}
+
+#[test]
+fn test_definition_display_reexport() {
+    let definition = DefinitionInfo {
+        content: "pub use inner::Person as Member;".to_string(),
+        is_reexport: true,
+        ..sample_definition()
+    };
+
+    assert_eq!(
+        definition.to_string(),
+        "/test/src/main.rs:5:12 (re-export)\npub use inner::Person as Member;"
+    );
+}
+
+#[test]
+fn test_definition_display_external_source() {
+    let definition = DefinitionInfo {
+        file_path: "/registry/src/lib.rs".to_string(),
+        is_external: true,
+        source: Some(SourceExcerpt {
+            start_line: 4,
+            end_line: 6,
+            text: "\npub struct Person {}\n\n".to_string(),
+            truncated: false,
+        }),
+        ..sample_definition()
+    };
+
+    assert_eq!(
+        definition.to_string(),
+        "/registry/src/lib.rs:5:12 (external)\npub struct Person {}\n\n\
+         Source of /registry/src/lib.rs:4-6:\n\npub struct Person {}\n\n"
+    );
+
+    let definition = DefinitionInfo {
+        source: Some(SourceExcerpt {
+            start_line: 1,
+            end_line: 1,
+            text: "pub struct Person {\n".to_string(),
+            truncated: true,
+        }),
+        ..definition
+    };
+    assert!(
+        definition.to_string().ends_with(
+            "Source of /registry/src/lib.rs:1-1:\npub struct Person {\n... (truncated)\n"
+        )
+    );
+}
+
+#[test]
+fn test_definition_serializes_to_json() {
+    let json = serde_json::to_value(sample_definition()).expect("Failed to serialize");
+
+    assert_eq!(json["file_path"], "/test/src/main.rs");
+    assert_eq!(json["line"], 5);
+    assert_eq!(json["kind"], "Struct");
+    assert_eq!(json["container"], serde_json::Value::Null);
+    assert_eq!(json["is_reexport"], false);
+    assert_eq!(json["is_external"], false);
+}
+
+#[test]
+fn test_completion_limit_is_clamped() {
+    let limit = |limit| {
+        CompletionOptions {
+            limit,
+            ..Default::default()
+        }
+        .effective_limit()
+    };
+    assert_eq!(limit(None), CompletionOptions::DEFAULT_LIMIT);
+    assert_eq!(limit(Some(5)), 5);
+    assert_eq!(limit(Some(0)), 1);
+    assert_eq!(limit(Some(usize::MAX)), CompletionOptions::MAX_LIMIT);
+}
@@ -4,7 +4,13 @@ use std::{
 };
 
 use librustbelt::{
-    analyzer::RustAnalyzerish, builder::RustAnalyzerishBuilder, entities::CursorCoordinates,
+    ErrorKind,
+    analyzer::RustAnalyzerish,
+    builder::RustAnalyzerishBuilder,
+    entities::{
+        CompletionOptions, CursorCoordinates, DefinitionOptions, ReferenceOptions, ReferenceScope,
+        SymbolMatchMode, TypeHintOptions, UnusedKind, WorkspaceSymbolsOptions,
+    },
 };
 use ra_ap_ide::SymbolKind;
 use tokio::sync::Mutex;
@@ -63,6 +69,107 @@ async fn test_type_hint_simple_variable() {
     );
 }
 
+#[tokio::test]
+async fn test_type_hint_with_layout() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // The `age` field of `Person` on line 7
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line: 7,
+        column: 9,
+        symbol: None,
+    };
+    let type_info = analyzer
+        .get_type_hint_with_options(
+            &cursor,
+            &TypeHintOptions {
+                include_layout: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting type hint")
+        .expect("Expected type info but got None");
+
+    println!("Type info with layout for 'age': {type_info}");
+    assert!(type_info.symbol.contains("size = 4"));
+    assert!(type_info.symbol.contains("align = 4"));
+    assert!(type_info.symbol.contains("offset = "));
+
+    // Layout is opt-in
+    let type_info = analyzer
+        .get_type_hint(&cursor)
+        .await
+        .expect("Error getting type hint")
+        .expect("Expected type info but got None");
+    assert!(!type_info.symbol.contains("size = "));
+}
+
+#[tokio::test]
+async fn test_type_hint_with_drop_glue() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // The `Person` struct on line 5, which owns `String`s
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line: 5,
+        column: 12,
+        symbol: None,
+    };
+    let type_info = analyzer
+        .get_type_hint_with_options(
+            &cursor,
+            &TypeHintOptions {
+                show_drop_glue: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting type hint")
+        .expect("Expected type info but got None");
+
+    println!("Type info with drop glue for 'Person': {type_info}");
+    assert!(type_info.symbol.contains("Drop"));
+
+    // Drop glue is opt-in
+    let type_info = analyzer
+        .get_type_hint(&cursor)
+        .await
+        .expect("Error getting type hint")
+        .expect("Expected type info but got None");
+    assert!(!type_info.symbol.contains("Drop"));
+}
+
+#[tokio::test]
+async fn test_type_hint_deduplicates_canonical_types() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // The `pair: (Person, Person)` parameter on line 109
+    let type_info = analyzer
+        .get_type_hint(&CursorCoordinates {
+            file_path: sample_path.to_str().unwrap().to_string(),
+            line: 109,
+            column: 20,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting type hint")
+        .expect("Expected type info but got None");
+
+    println!("Type info for 'pair': {type_info}");
+    assert_eq!(
+        type_info.canonical_types,
+        vec!["sample::Person".to_string()]
+    );
+}
+
 #[tokio::test]
 #[ignore = "Requires extracting function signatures"]
 async fn test_type_hint_function_call() {
@@ -222,6 +329,173 @@ async fn test_get_definition_method() {
     assert!(has_method_def, "Should find with_email method definition");
 }
 
+#[tokio::test]
+async fn test_get_definition_with_container() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line: 33,
+        column: 55, // `.with_email`
+        symbol: None,
+    };
+
+    let definitions = analyzer
+        .get_definition_with_container(&cursor, true)
+        .await
+        .expect("Error getting definition")
+        .expect("Expected to find definition for method");
+    println!("Definition with container: {}", definitions[0]);
+    assert_eq!(definitions[0].container.as_deref(), Some("impl Person"));
+    assert!(
+        definitions[0]
+            .to_string()
+            .contains("impl Person {\n    pub fn with_email")
+    );
+
+    // Without the option the container is left out
+    let definitions = analyzer
+        .get_definition(&cursor)
+        .await
+        .expect("Error getting definition")
+        .expect("Expected to find definition for method");
+    assert_eq!(definitions[0].container, None);
+
+    // A struct isn't its own container
+    let definitions = analyzer
+        .get_definition_with_container(
+            &CursorCoordinates {
+                column: 18, // `Person::new`
+                ..cursor
+            },
+            true,
+        )
+        .await
+        .expect("Error getting definition")
+        .expect("Expected to find definition for Person struct");
+    assert_eq!(definitions[0].container, None);
+}
+
+#[tokio::test]
+async fn test_get_definition_fuzzy() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line: 33,
+        column: 17, // the space before `Person::new`
+        symbol: None,
+    };
+
+    let definitions = analyzer
+        .get_definition(&cursor)
+        .await
+        .expect("Error getting definition");
+    assert!(
+        definitions.is_none_or(|definitions| definitions.is_empty()),
+        "No definition expected on whitespace"
+    );
+
+    let options = DefinitionOptions {
+        fuzzy: true,
+        ..Default::default()
+    };
+    let definitions = analyzer
+        .get_definition_with_options(&cursor, &options)
+        .await
+        .expect("Error getting definition")
+        .expect("Expected the nearest identifier to be used");
+    assert_eq!(definitions[0].name, "Person");
+
+    // The words of a comment aren't identifiers to retry on
+    let cursor = CursorCoordinates {
+        line: 40,
+        column: 8, // `Test` in `// Test various expressions for type hints`
+        ..cursor
+    };
+    let definitions = analyzer
+        .get_definition_with_options(&cursor, &options)
+        .await
+        .expect("Error getting definition");
+    assert!(
+        definitions.is_none_or(|definitions| definitions.is_empty()),
+        "No definition expected in a comment"
+    );
+}
+
+#[tokio::test]
+async fn test_get_definition_signature_only() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line: 33,
+        column: 55, // `.with_email`
+        symbol: None,
+    };
+    let options = DefinitionOptions {
+        signature_only: true,
+        ..Default::default()
+    };
+
+    let definitions = analyzer
+        .get_definition_with_options(&cursor, &options)
+        .await
+        .expect("Error getting definition")
+        .expect("Expected to find definition for method");
+    assert_eq!(
+        definitions[0].content,
+        "pub fn with_email(mut self, email: String) -> Self"
+    );
+
+    // The derive attribute and fields of a struct are left out
+    let definitions = analyzer
+        .get_definition_with_options(
+            &CursorCoordinates {
+                column: 18, // `Person::new`
+                ..cursor
+            },
+            &options,
+        )
+        .await
+        .expect("Error getting definition")
+        .expect("Expected to find definition for Person struct");
+    assert_eq!(definitions[0].content, "pub struct Person");
+}
+
+#[tokio::test]
+async fn test_get_type_definition_variable() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // Test get type definition for the 'person' variable on line 33 (Person)
+    let definitions = analyzer
+        .get_type_definition(&CursorCoordinates {
+            file_path: sample_path.to_str().unwrap().to_string(),
+            line: 33,
+            column: 9,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting type definition")
+        .expect("Expected to find type definition for person variable");
+
+    println!("Type definitions {definitions:?}");
+
+    // The variable's type is the Person struct, not the variable itself
+    let has_person_def = definitions.iter().any(|def| {
+        def.name == "Person"
+            && matches!(def.kind, Some(SymbolKind::Struct))
+            && def.file_path.ends_with("main.rs")
+            && def.line == 5
+    });
+    assert!(has_person_def, "Should find Person struct type definition");
+}
+
 #[tokio::test]
 async fn test_error_handling_invalid_position() {
     let analyzer = get_shared_analyzer().await;
@@ -239,7 +513,38 @@ async fn test_error_handling_invalid_position() {
         .await;
 
     // Should return an error for invalid position
-    assert!(result.is_err());
+    let error = result.expect_err("Expected an error for an invalid position");
+    assert_eq!(ErrorKind::of(&error), Some(ErrorKind::InvalidPosition));
+
+    // Lines and columns are 1-based
+    let error = analyzer
+        .get_type_hint(&CursorCoordinates {
+            file_path: sample_path.to_str().unwrap().to_string(),
+            line: 0,
+            column: 1,
+            symbol: None,
+        })
+        .await
+        .expect_err("Expected an error for line 0");
+    assert_eq!(ErrorKind::of(&error), Some(ErrorKind::InvalidPosition));
+
+    // Line ranges are checked before and after loading the file
+    for (start_line, end_line) in [(0, 1), (9999, 9999)] {
+        let error = analyzer
+            .extract_function(
+                sample_path.to_str().unwrap(),
+                start_line,
+                end_line,
+                "extracted",
+            )
+            .await
+            .expect_err("Expected an error for an invalid line range");
+        assert_eq!(
+            ErrorKind::of(&error),
+            Some(ErrorKind::InvalidPosition),
+            "{error}"
+        );
+    }
 }
 
 #[tokio::test]
@@ -258,7 +563,27 @@ async fn test_error_handling_nonexistent_file() {
         .await;
 
     // Should return an error for non-existent file
-    assert!(result.is_err());
+    let error = result.expect_err("Expected an error for a missing file");
+    assert_eq!(ErrorKind::of(&error), Some(ErrorKind::FileNotFound));
+
+    // Files outside the loaded workspace aren't in the VFS either
+    let outside_path = std::env::temp_dir().join(format!(
+        "rustbelt-outside-workspace-{}.rs",
+        std::process::id()
+    ));
+    std::fs::write(&outside_path, "fn main() {}\n").expect("Failed to write scratch file");
+    let error = analyzer
+        .get_type_hint(&CursorCoordinates {
+            file_path: outside_path.to_string_lossy().to_string(),
+            line: 1,
+            column: 4,
+            symbol: None,
+        })
+        .await
+        .expect_err("Expected an error for a file outside the workspace");
+    std::fs::remove_file(&outside_path).ok();
+    assert!(error.to_string().contains("not found in VFS"), "{error}");
+    assert_eq!(ErrorKind::of(&error), Some(ErrorKind::FileNotFound));
 }
 
 #[tokio::test]
@@ -290,6 +615,43 @@ async fn test_no_definition_available() {
     }
 }
 
+#[tokio::test]
+async fn test_no_definition_at_file_start() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // Before and inside the `///` of the doc comment starting the file
+    for column in [1, 2, 4] {
+        let result = analyzer
+            .get_definition(&CursorCoordinates {
+                file_path: sample_path.to_str().unwrap().to_string(),
+                line: 1,
+                column,
+                symbol: None,
+            })
+            .await
+            .expect("Error getting definition");
+        assert!(result.is_none(), "Definitions at 1:{column}: {result:?}");
+    }
+
+    // Nothing to navigate to is not a failure worth a note
+    let report = analyzer
+        .get_definition_report(
+            &CursorCoordinates {
+                file_path: sample_path.to_str().unwrap().to_string(),
+                line: 1,
+                column: 1,
+                symbol: None,
+            },
+            &DefinitionOptions::default(),
+        )
+        .await
+        .expect("Error getting definition");
+    assert!(report.definitions.is_none(), "{report:?}");
+    assert!(report.note.is_none(), "{report:?}");
+}
+
 #[tokio::test]
 async fn test_multiple_usages_same_analyzer() {
     let analyzer = get_shared_analyzer().await;
@@ -442,6 +804,171 @@ async fn test_get_completions_basic() {
     }
 }
 
+#[tokio::test]
+async fn test_get_completions_with_limit() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    let completions = analyzer
+        .get_completions_with_options(
+            &CursorCoordinates {
+                file_path: sample_path.to_str().unwrap().to_string(),
+                line: 31,
+                column: 18,
+                symbol: None,
+            },
+            &CompletionOptions {
+                limit: Some(3),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting completions")
+        .unwrap_or_default();
+    assert!(completions.len() <= 3, "{} completions", completions.len());
+}
+
+#[tokio::test]
+async fn test_get_completions_struct_literal_fields() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let directory_path = get_sample_file_path().with_file_name("directory.rs");
+    let cursor = CursorCoordinates {
+        file_path: directory_path.to_str().unwrap().to_string(),
+        line: 12,
+        column: 14, // After `name,` in `Person { name, ..person.clone() }`
+        symbol: None,
+    };
+
+    let completions = analyzer
+        .get_completions(&cursor)
+        .await
+        .expect("Error getting completions")
+        .expect("Expected completions in the struct literal");
+    let fields: Vec<(&str, Option<&str>)> = completions
+        .iter()
+        .filter(|completion| completion.kind.as_deref() == Some("Field"))
+        .map(|completion| (completion.name.as_str(), completion.signature.as_deref()))
+        .collect();
+    println!("Field completions: {fields:?}");
+
+    // Only the fields not set yet, with their types
+    assert!(fields.contains(&("age", Some("u32"))), "{fields:?}");
+    assert!(
+        fields.contains(&("email", Some("Option<String>"))),
+        "{fields:?}"
+    );
+    assert!(
+        !fields.iter().any(|(name, _)| *name == "name"),
+        "{fields:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_get_completions_match_arm_variants() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    // End of the `None` arm pattern of the match on `Option<Result<String, &str>>`
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line: 53,
+        column: 17,
+        symbol: None,
+    };
+
+    let completions = analyzer
+        .get_completions(&cursor)
+        .await
+        .expect("Error getting completions")
+        .expect("Expected completions in the match arm pattern");
+    let variants: Vec<&str> = completions
+        .iter()
+        .filter(|completion| completion.kind.as_deref() == Some("Variant"))
+        .map(|completion| completion.name.as_str())
+        .collect();
+    println!("Variant completions: {variants:?}");
+
+    for variant in ["Some", "None", "Ok", "Err"] {
+        assert!(
+            variants.iter().any(|name| name.starts_with(variant)),
+            "{variant} missing from {variants:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_get_completions_sorted_by_relevance() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line: 32,
+        column: 20,
+        symbol: None,
+    };
+
+    let completions = analyzer
+        .get_completions_with_options(
+            &cursor,
+            &CompletionOptions {
+                include_scores: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting completions")
+        .unwrap_or_default();
+    let scores: Vec<u32> = completions
+        .iter()
+        .map(|completion| completion.relevance.expect("Score should be included"))
+        .collect();
+    assert!(
+        scores.windows(2).all(|pair| pair[0] >= pair[1]),
+        "Scores should be descending: {scores:?}"
+    );
+
+    let unsorted = analyzer
+        .get_completions_with_options(
+            &cursor,
+            &CompletionOptions {
+                unsorted: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting completions")
+        .unwrap_or_default();
+    assert_eq!(unsorted.len(), completions.len());
+    assert!(
+        unsorted
+            .iter()
+            .all(|completion| completion.relevance.is_none())
+    );
+
+    // A small limit keeps the most relevant items, not rust-analyzer's first ones
+    let limited = analyzer
+        .get_completions_with_options(
+            &cursor,
+            &CompletionOptions {
+                include_scores: true,
+                limit: Some(3),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting completions")
+        .unwrap_or_default();
+    let limited_scores: Vec<u32> = limited
+        .iter()
+        .map(|completion| completion.relevance.expect("Score should be included"))
+        .collect();
+    assert_eq!(limited_scores, scores[..limited_scores.len()]);
+    assert_eq!(limited.len(), scores.len().min(3));
+}
+
 #[tokio::test]
 async fn test_get_completions_method_chaining() {
     let analyzer = get_shared_analyzer().await;
@@ -589,14 +1116,131 @@ async fn test_view_inlay_hints_with_line_range() {
 }
 
 #[tokio::test]
-async fn test_find_references() {
+async fn test_get_inlay_hints_structured() {
     let analyzer = get_shared_analyzer().await;
     let mut analyzer = analyzer.lock().await;
     let sample_path = get_sample_file_path();
 
-    // Test finding references to the Person struct definition
-    let references = analyzer
-        .find_references(&CursorCoordinates {
+    let hints = analyzer
+        .get_inlay_hints(sample_path.to_str().unwrap(), Some(30), Some(45))
+        .await
+        .expect("Error getting inlay hints");
+
+    println!("{hints:#?}");
+    assert!(hints.iter().all(|hint| (30..=45).contains(&hint.line)));
+    assert!(
+        hints
+            .windows(2)
+            .all(|pair| (pair[0].line, pair[0].column) <= (pair[1].line, pair[1].column)),
+        "Hints should be in file order"
+    );
+
+    // `let person = ...` gets a type hint after the binding
+    let person = hints
+        .iter()
+        .find(|hint| hint.line == 33 && hint.kind == "type")
+        .expect("Should have a type hint for person");
+    assert_eq!(person.text, "Person");
+    assert_eq!(person.position, "after");
+    assert_eq!(person.column, 15);
+
+    // `Person::new("Alice".to_string(), 25)` gets parameter hints before the arguments
+    let name = hints
+        .iter()
+        .find(|hint| hint.line == 33 && hint.kind == "parameter")
+        .expect("Should have a parameter hint for name");
+    assert_eq!(name.text, "name");
+    assert_eq!(name.position, "before");
+
+    // The source is left untouched
+    let source = std::fs::read_to_string(&sample_path).expect("Failed to read sample");
+    assert!(source.contains("    let person = Person::new("));
+}
+
+#[tokio::test]
+async fn test_get_qualified_path() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let cursor = |line, column| CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line,
+        column,
+        symbol: None,
+    };
+
+    // `HashMap` in `let mut people: HashMap<String, Person>`
+    let path = analyzer
+        .get_qualified_path(&cursor(31, 21))
+        .await
+        .expect("Error getting qualified path")
+        .expect("No path for HashMap");
+    // The public re-export, not `std::collections::hash::map::HashMap`
+    assert_eq!(path, "std::collections::HashMap");
+
+    // `Person` in `Person::new(...)`, defined in the same crate
+    let path = analyzer
+        .get_qualified_path(&cursor(33, 18))
+        .await
+        .expect("Error getting qualified path")
+        .expect("No path for Person");
+    assert!(
+        path.starts_with("crate::") && path.ends_with("::Person"),
+        "{path}"
+    );
+
+    // The local `person` has no path
+    let path = analyzer
+        .get_qualified_path(&cursor(33, 9))
+        .await
+        .expect("Error getting qualified path");
+    assert_eq!(path, None);
+}
+
+#[tokio::test]
+async fn test_describe_symbol() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // `Person` in `Person::new(...)` of main
+    let description = analyzer
+        .describe_symbol(&CursorCoordinates {
+            file_path: sample_path.to_str().unwrap().to_string(),
+            line: 33,
+            column: 18,
+            symbol: None,
+        })
+        .await
+        .expect("Error describing symbol")
+        .expect("No description for Person");
+
+    println!("{description}");
+    assert_eq!(description.definitions.len(), 1);
+    assert_eq!(description.definitions[0].name, "Person");
+    assert!(
+        description
+            .type_hint
+            .as_ref()
+            .is_some_and(|type_hint| type_hint.symbol.contains("struct Person")),
+        "Should include the hover of Person"
+    );
+    assert!(
+        description.reference_count > 1,
+        "Person is used several times"
+    );
+    assert!(description.to_string().contains("References: "));
+}
+
+#[tokio::test]
+async fn test_find_references() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // Test finding references to the Person struct definition
+    let references = analyzer
+        .find_references(&CursorCoordinates {
             file_path: sample_path.to_str().unwrap().to_string(),
             line: 5, // Person struct definition
             column: 12,
@@ -681,6 +1325,57 @@ async fn test_find_references() {
     assert_eq!(references, sorted_refs, "References should be sorted");
 }
 
+#[tokio::test]
+async fn test_rename_info_spans_files() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // Renaming `Person`, also used by `src/directory.rs`. Only computing the
+    // rename leaves the shared sample project untouched.
+    let rename_result = analyzer
+        .get_rename_info(
+            &CursorCoordinates {
+                file_path: sample_path.to_str().unwrap().to_string(),
+                line: 5,
+                column: 12,
+                symbol: None,
+            },
+            "Human",
+        )
+        .await
+        .expect("Error computing rename")
+        .expect("Person should be renamable");
+    println!("{rename_result}");
+
+    let changed_files: Vec<&str> = rename_result
+        .file_changes
+        .iter()
+        .map(|change| change.file_path.as_str())
+        .collect();
+    assert_eq!(changed_files.len(), 2, "{changed_files:?}");
+    assert!(
+        changed_files
+            .iter()
+            .any(|path| path.ends_with("src/main.rs"))
+    );
+    let directory = rename_result
+        .file_changes
+        .iter()
+        .find(|change| change.file_path.ends_with("src/directory.rs"))
+        .unwrap_or_else(|| panic!("directory.rs not renamed in {changed_files:?}"));
+    // The import, both uses in the signature of `youngest` and three in `renamed`
+    assert_eq!(directory.edits.len(), 6, "{:?}", directory.edits);
+    assert!(directory.edits.iter().all(|edit| edit.new_text == "Human"));
+    assert!(
+        rename_result
+            .line_previews
+            .iter()
+            .any(|line| line.file_path.ends_with("src/directory.rs")
+                && line.after == "use crate::Human;")
+    );
+}
+
 #[tokio::test]
 async fn test_find_references_variable() {
     let analyzer = get_shared_analyzer().await;
@@ -753,6 +1448,124 @@ async fn test_find_references_variable() {
     );
 }
 
+#[tokio::test]
+async fn test_find_references_scoped() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let people = CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line: 31, // people variable declaration
+        column: 13,
+        symbol: None,
+    };
+    let file_scope = ReferenceOptions {
+        scope: ReferenceScope::File,
+        ..Default::default()
+    };
+
+    // A local can't be referenced from another file, so even a workspace-wide
+    // search only scans its file
+    let scope = analyzer
+        .reference_scope(&people, ReferenceScope::Workspace)
+        .await
+        .expect("Error getting reference scope");
+    assert_eq!(scope, ReferenceScope::File);
+
+    // A local is only referenced in its file, so scoping to it finds the same
+    let scoped = analyzer
+        .find_references_with_options(&people, &file_scope)
+        .await
+        .expect("Error finding references")
+        .expect("Should find references to people variable");
+    let everywhere = analyzer
+        .find_references(&people)
+        .await
+        .expect("Error finding references")
+        .expect("Should find references to people variable");
+    assert_eq!(scoped, everywhere);
+
+    // `Person` is also used in `src/directory.rs`, which the file scope skips
+    let person = CursorCoordinates {
+        line: 5,
+        column: 12,
+        ..people
+    };
+    let scope = analyzer
+        .reference_scope(&person, ReferenceScope::Workspace)
+        .await
+        .expect("Error getting reference scope");
+    assert_eq!(scope, ReferenceScope::Workspace);
+    let scoped = analyzer
+        .find_references_with_options(&person, &file_scope)
+        .await
+        .expect("Error finding references")
+        .expect("Should find references to Person");
+    assert!(
+        scoped
+            .iter()
+            .all(|reference| reference.file_path.ends_with("src/main.rs")),
+        "{scoped:?}"
+    );
+    let crate_wide = analyzer
+        .find_references_with_options(
+            &person,
+            &ReferenceOptions {
+                scope: ReferenceScope::Crate,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error finding references")
+        .expect("Should find references to Person");
+    assert!(
+        crate_wide
+            .iter()
+            .any(|reference| reference.file_path.ends_with("src/directory.rs")),
+        "{crate_wide:?}"
+    );
+
+    assert!("module".parse::<ReferenceScope>().is_err());
+}
+
+#[tokio::test]
+async fn test_find_references_categorizes_writes() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    let references = analyzer
+        .find_references(&CursorCoordinates {
+            file_path: sample_path.to_str().unwrap().to_string(),
+            line: 88, // `let mut count = 0;`
+            column: 13,
+            symbol: Some("count".to_string()),
+        })
+        .await
+        .expect("Error finding references")
+        .expect("Should find references to count");
+
+    for reference in &references {
+        println!("  - {}", reference);
+    }
+
+    let increment = references
+        .iter()
+        .find(|r| r.content.contains("count += 1"))
+        .expect("Should find the increment");
+    assert!(increment.category.write, "`count += 1` should be a write");
+
+    let read = references
+        .iter()
+        .find(|r| !r.is_definition && r.content.trim() == "count")
+        .expect("Should find the returned value");
+    assert!(read.category.read, "Returning `count` should be a read");
+    assert!(
+        !read.category.write,
+        "Returning `count` should not be a write"
+    );
+}
+
 #[tokio::test]
 async fn test_symbol_resolution() {
     let analyzer = get_shared_analyzer().await;
@@ -792,3 +1605,824 @@ async fn test_symbol_resolution() {
     assert_eq!(type_info.line, 31, "Line number should be found");
     assert_eq!(type_info.column, 13, "Column number should be found");
 }
+
+#[tokio::test]
+async fn test_get_workspace_diagnostics() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let sample_root = sample_path.parent().unwrap().parent().unwrap();
+
+    let diagnostics = analyzer
+        .get_workspace_diagnostics()
+        .await
+        .expect("Error getting workspace diagnostics");
+
+    println!("Found {} workspace diagnostics:", diagnostics.len());
+    for diagnostic in &diagnostics {
+        println!("  - {}", diagnostic);
+    }
+
+    // Only workspace sources should be reported, never sysroot or dependency files
+    for diagnostic in &diagnostics {
+        assert!(
+            diagnostic
+                .file_path
+                .starts_with(sample_root.to_str().unwrap()),
+            "Diagnostic outside of the workspace: {}",
+            diagnostic
+        );
+        assert!(diagnostic.line > 0, "Line should be positive");
+        assert!(diagnostic.column > 0, "Column should be positive");
+    }
+
+    // Diagnostics should be sorted by severity, then file path
+    let mut sorted = diagnostics.clone();
+    sorted.sort_by(|a, b| {
+        a.severity
+            .cmp(&b.severity)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+            .then_with(|| a.line.cmp(&b.line))
+            .then_with(|| a.column.cmp(&b.column))
+    });
+    assert_eq!(diagnostics, sorted, "Diagnostics should be sorted");
+}
+
+#[tokio::test]
+async fn test_get_file_diagnostics() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path().to_string_lossy().to_string();
+
+    let diagnostics = analyzer
+        .get_file_diagnostics(&sample_path)
+        .await
+        .expect("Error getting file diagnostics");
+
+    // Only the requested file should be reported, in position order
+    for diagnostic in &diagnostics {
+        assert_eq!(
+            diagnostic.file_path, sample_path,
+            "Diagnostic from another file: {}",
+            diagnostic
+        );
+    }
+    let mut sorted = diagnostics.clone();
+    sorted.sort_by(|a, b| a.line.cmp(&b.line).then_with(|| a.column.cmp(&b.column)));
+    assert_eq!(diagnostics, sorted, "Diagnostics should be sorted");
+}
+
+#[tokio::test]
+async fn test_get_memory_usage() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+
+    let usage = analyzer
+        .get_memory_usage()
+        .await
+        .expect("Error getting memory usage");
+    println!("{}", usage);
+
+    assert!(usage.workspace_file_count > 0, "Expected workspace files");
+    // The VFS also holds the sysroot and dependencies
+    assert!(usage.vfs_file_count >= usage.workspace_file_count);
+    assert!(usage.to_string().starts_with("Allocated: "), "{usage}");
+}
+
+#[tokio::test]
+async fn test_get_semantic_tokens() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    let tokens = analyzer
+        .get_semantic_tokens(sample_path.to_str().unwrap())
+        .await
+        .expect("Error getting semantic tokens");
+
+    assert!(!tokens.is_empty(), "Should find semantic tokens");
+
+    // `pub struct Person` on line 5
+    let person = tokens
+        .iter()
+        .find(|token| token.line == 5 && token.text == "Person")
+        .expect("Should find the Person struct name");
+    assert_eq!(person.tag, "struct");
+    assert!(person.modifiers.iter().any(|m| m == "declaration"));
+
+    // `let mut people` on line 31
+    let people = tokens
+        .iter()
+        .find(|token| token.line == 31 && token.text == "people")
+        .expect("Should find the people variable");
+    assert!(people.modifiers.iter().any(|m| m == "mutable"));
+
+    for token in &tokens {
+        assert!(
+            token.end_line > token.line || token.end_column > token.column,
+            "Token {} should not be empty",
+            token
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_get_syntax_tree() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let file_path = sample_path.to_str().unwrap();
+
+    let tree = analyzer
+        .get_syntax_tree(file_path, None)
+        .await
+        .expect("Error getting syntax tree");
+    assert!(tree.starts_with("SOURCE_FILE@"), "{tree}");
+    assert!(tree.contains("FN@"));
+
+    // The `Person` struct on lines 5-9
+    let tree = analyzer
+        .get_syntax_tree(file_path, Some((5, 9)))
+        .await
+        .expect("Error getting syntax tree");
+    println!("{tree}");
+    assert!(tree.starts_with("STRUCT@"), "{tree}");
+    assert!(tree.contains("IDENT@") && tree.contains("\"Person\""));
+    assert!(!tree.contains("IMPL@"));
+
+    assert!(
+        analyzer
+            .get_syntax_tree(file_path, Some((10_000, 10_001)))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_get_related_tests() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    let tests = analyzer
+        .get_related_tests(&CursorCoordinates {
+            file_path: sample_path.to_str().unwrap().to_string(),
+            line: 87, // `pub fn count_adults`
+            column: 8,
+            symbol: Some("count_adults".to_string()),
+        })
+        .await
+        .expect("Error getting related tests");
+
+    for test in &tests {
+        println!("  - {}", test);
+    }
+
+    assert!(
+        tests.iter().any(|test| test.name == "test_count_adults"),
+        "Should find test_count_adults among {} related tests",
+        tests.len()
+    );
+}
+
+#[tokio::test]
+async fn test_get_runnables_in_test() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    let runnables = analyzer
+        .get_runnables(&CursorCoordinates {
+            file_path: sample_path.to_str().unwrap().to_string(),
+            line: 103, // inside `fn test_count_adults`
+            column: 9,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting runnables");
+
+    for runnable in &runnables {
+        println!("  - {}", runnable);
+    }
+
+    let test = runnables
+        .iter()
+        .find(|runnable| runnable.label.starts_with("test "))
+        .expect("Should find the enclosing test");
+    assert_eq!(
+        test.cargo_args,
+        vec!["test", "--", "tests::test_count_adults", "--exact"]
+    );
+    assert_eq!(test.cwd, ".");
+
+    // The enclosing test module is runnable as well
+    assert!(
+        runnables
+            .iter()
+            .any(|runnable| runnable.label == "test-mod tests")
+    );
+}
+
+/// Names of the workspace symbols matching `query`
+async fn workspace_symbol_names(query: &str, options: WorkspaceSymbolsOptions) -> Vec<String> {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let page = analyzer
+        .get_workspace_symbols(query, &options)
+        .await
+        .expect("Error searching workspace symbols");
+    for symbol in &page.symbols {
+        println!("  - {}", symbol);
+    }
+    page.symbols.into_iter().map(|symbol| symbol.name).collect()
+}
+
+#[tokio::test]
+async fn test_get_workspace_symbols_substring() {
+    let names = workspace_symbol_names("person", WorkspaceSymbolsOptions::default()).await;
+    assert!(names.contains(&"Person".to_string()));
+
+    let names = workspace_symbol_names(
+        "person",
+        WorkspaceSymbolsOptions {
+            kind: Some("struct".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(names, vec!["Person"]);
+}
+
+#[tokio::test]
+async fn test_get_workspace_symbols_match_modes() {
+    let prefix = workspace_symbol_names(
+        "calc",
+        WorkspaceSymbolsOptions {
+            mode: SymbolMatchMode::Prefix,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(prefix.contains(&"calculate_average_age".to_string()));
+    assert!(prefix.iter().all(|name| name.starts_with("calc")));
+
+    let regex = workspace_symbol_names(
+        "^count_",
+        WorkspaceSymbolsOptions {
+            mode: SymbolMatchMode::Regex,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(regex.contains(&"count_adults".to_string()));
+    assert!(!regex.contains(&"test_count_adults".to_string()));
+
+    let fuzzy = workspace_symbol_names(
+        "cntadl",
+        WorkspaceSymbolsOptions {
+            mode: SymbolMatchMode::Fuzzy,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(fuzzy.contains(&"count_adults".to_string()));
+
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let error = analyzer
+        .get_workspace_symbols(
+            "count_(",
+            &WorkspaceSymbolsOptions {
+                mode: SymbolMatchMode::Regex,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect_err("Expected an error for an invalid regex");
+    assert_eq!(ErrorKind::of(&error), Some(ErrorKind::InvalidParams));
+}
+
+#[tokio::test]
+async fn test_get_workspace_symbols_pagination() {
+    let all = workspace_symbol_names("a", WorkspaceSymbolsOptions::default()).await;
+    assert!(all.len() > 2, "Expected several symbols containing 'a'");
+
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let page = analyzer
+        .get_workspace_symbols(
+            "a",
+            &WorkspaceSymbolsOptions {
+                offset: 1,
+                limit: Some(2),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error searching workspace symbols");
+
+    assert_eq!(page.total, all.len());
+    assert_eq!(
+        page.header(),
+        format!("Showing symbols 2-3 of {}", all.len())
+    );
+    let names: Vec<_> = page
+        .symbols
+        .iter()
+        .map(|symbol| symbol.name.clone())
+        .collect();
+    assert_eq!(names, all[1..3]);
+}
+
+#[tokio::test]
+async fn test_get_workspace_symbols_by_kind_only() {
+    let structs = workspace_symbol_names(
+        "",
+        WorkspaceSymbolsOptions {
+            kind: Some("struct".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(structs.contains(&"Person".to_string()));
+
+    let functions = workspace_symbol_names(
+        "*",
+        WorkspaceSymbolsOptions {
+            kind: Some("function".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(functions.contains(&"count_adults".to_string()));
+    assert!(!functions.contains(&"Person".to_string()));
+
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let error = analyzer
+        .get_workspace_symbols("", &WorkspaceSymbolsOptions::default())
+        .await
+        .expect_err("An empty query without a kind should fail");
+    assert!(error.to_string().contains("kind filter"), "{error}");
+}
+
+#[tokio::test]
+async fn test_get_workspace_info() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+
+    let info = analyzer
+        .get_workspace_info()
+        .await
+        .expect("Error getting workspace info");
+    println!("{info}");
+
+    let sample = info
+        .crates
+        .first()
+        .expect("Expected at least one crate in the graph");
+    assert_eq!(sample.name, "sample");
+    assert_eq!(sample.edition, "2024");
+    assert!(sample.is_workspace_member);
+    assert!(
+        sample
+            .root_file
+            .as_deref()
+            .is_some_and(|root| root.ends_with("src/main.rs"))
+    );
+    assert_eq!(info.workspace_file_count, 2);
+    assert!(info.vfs_file_count >= info.workspace_file_count);
+}
+
+#[tokio::test]
+async fn test_get_item_attributes() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let cursor = CursorCoordinates {
+        file_path: sample_path.to_str().unwrap().to_string(),
+        line: 5,
+        column: 12, // `Person`
+        symbol: None,
+    };
+
+    let attributes = analyzer
+        .get_item_attributes(&cursor)
+        .await
+        .expect("Error getting item attributes")
+        .expect("Expected the Person struct");
+    assert_eq!(attributes, vec!["#[derive(Debug, Clone)]"]);
+
+    // A field has no attributes of its own
+    let attributes = analyzer
+        .get_item_attributes(&CursorCoordinates {
+            line: 6,
+            column: 9, // `name`
+            ..cursor
+        })
+        .await
+        .expect("Error getting item attributes")
+        .expect("Expected the name field");
+    assert!(
+        attributes.is_empty(),
+        "Unexpected attributes: {attributes:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_get_enclosing_scope() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let file_path = get_sample_file_path().to_string_lossy().to_string();
+    let cursor = |line, column| CursorCoordinates {
+        file_path: file_path.clone(),
+        line,
+        column,
+        symbol: None,
+    };
+
+    // Inside a method body
+    let scope = analyzer
+        .get_enclosing_scope(&cursor(21, 9))
+        .await
+        .expect("Error getting enclosing scope")
+        .expect("No enclosing scope found");
+    println!("{scope:#}");
+    assert_eq!(scope.name, "with_email");
+    assert!(matches!(scope.kind, Some(SymbolKind::Function)));
+    assert_eq!((scope.line, scope.end_line), (20, 23));
+    assert_eq!(scope.container.as_deref(), Some("impl Person"));
+    assert_eq!(
+        scope.content,
+        "pub fn with_email(mut self, email: String) -> Self"
+    );
+
+    // Closures are skipped in favour of the enclosing function
+    let scope = analyzer
+        .get_enclosing_scope(&cursor(42, 50))
+        .await
+        .expect("Error getting enclosing scope")
+        .expect("No enclosing scope found");
+    assert_eq!(scope.name, "main");
+    assert!(scope.container.is_none());
+
+    // Top level of the file
+    let scope = analyzer
+        .get_enclosing_scope(&cursor(3, 1))
+        .await
+        .expect("Error getting enclosing scope");
+    assert!(scope.is_none(), "Unexpected scope: {scope:?}");
+}
+
+#[tokio::test]
+async fn test_get_crate_info() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+
+    let crates = analyzer
+        .get_crate_info("sample")
+        .await
+        .expect("Error getting crate info");
+    println!("{:#}", crates[0]);
+    assert_eq!(crates.len(), 1);
+    assert_eq!(crates[0].version.as_deref(), Some("0.0.1"));
+    assert_eq!(crates[0].edition, "2024");
+    assert!(crates[0].features.is_empty());
+    assert!(crates[0].is_workspace_member);
+
+    let error = analyzer
+        .get_crate_info("no-such-crate")
+        .await
+        .expect_err("Unknown crates should be an error");
+    assert!(error.to_string().contains("no-such-crate"), "{error}");
+}
+
+#[tokio::test]
+async fn test_get_struct_fields() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // On the `Person` of `Person::new(...)` in `main`
+    let fields = analyzer
+        .get_struct_fields(&CursorCoordinates {
+            file_path: sample_path.to_str().unwrap().to_string(),
+            line: 33,
+            column: 18,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting struct fields")
+        .expect("No struct found");
+    let rendered: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+    assert_eq!(
+        rendered,
+        [
+            "pub name: String",
+            "pub age: u32",
+            "pub email: Option<String>"
+        ]
+    );
+    assert_eq!(fields[1].line, 7);
+
+    let by_path = analyzer
+        .get_struct_fields_by_path("sample::Person")
+        .await
+        .expect("Error getting struct fields")
+        .expect("No struct found by path");
+    assert_eq!(by_path, fields);
+}
+
+#[tokio::test]
+async fn test_get_call_return_type() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+    let file_path = sample_path.to_str().unwrap().to_string();
+
+    // `new`, `to_string` and `collect` on the lines
+    // `let person = Person::new("Alice".to_string(), 25)...` and
+    // `let doubled: Vec<i32> = numbers.iter().map(|x| x * 2).collect();`
+    for (line, column, expected) in [(33, 26, "Person"), (33, 38, "String"), (42, 59, "Vec<i32>")] {
+        let ty = analyzer
+            .get_call_return_type(&CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol: None,
+            })
+            .await
+            .expect("Error getting call return type")
+            .expect("No call found");
+        assert_eq!(ty, expected, "at {line}:{column}");
+    }
+
+    // Only the call of `map` is resolved, not the end of the chain
+    let ty = analyzer
+        .get_call_return_type(&CursorCoordinates {
+            file_path: file_path.clone(),
+            line: 42,
+            column: 44,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting call return type")
+        .expect("No call found");
+    assert!(ty.starts_with("Map<"), "Unexpected type: {ty}");
+
+    // `numbers` is the receiver, not a call
+    let ty = analyzer
+        .get_call_return_type(&CursorCoordinates {
+            file_path,
+            line: 42,
+            column: 29,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting call return type");
+    assert_eq!(ty, None);
+}
+
+#[tokio::test]
+async fn test_diff_signature() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let sample_path = get_sample_file_path();
+
+    // On `new` in `Person::new("Alice".to_string(), 25)`
+    let diff = analyzer
+        .diff_signature(
+            &CursorCoordinates {
+                file_path: sample_path.to_str().unwrap().to_string(),
+                line: 33,
+                column: 26,
+                symbol: None,
+            },
+            "pub fn new(name: String) -> Self {}",
+        )
+        .await
+        .expect("Error diffing signature")
+        .expect("No definition found");
+    assert_eq!(diff.name, "new");
+    assert_eq!(diff.changes.len(), 1, "{diff}");
+    assert_eq!(
+        diff.changes[0].to_string(),
+        "[breaking] added parameter 2 (age): age: u32"
+    );
+}
+
+#[tokio::test]
+async fn test_count_references() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let file_path = get_sample_file_path().to_str().unwrap().to_string();
+    let cursor = |line, column| CursorCoordinates {
+        file_path: file_path.clone(),
+        line,
+        column,
+        symbol: None,
+    };
+
+    // `calculate_average_age` is called once from `main`
+    let count = analyzer
+        .count_references(&cursor(58, 4), ReferenceScope::Workspace, false)
+        .await
+        .expect("Error counting references")
+        .expect("No symbol found");
+    assert_eq!(count.to_string(), "calculate_average_age: 1 reference(s)");
+    assert!(count.is_used && count.complete);
+
+    // `swap_people` is never called
+    let count = analyzer
+        .count_references(&cursor(109, 8), ReferenceScope::Workspace, true)
+        .await
+        .expect("Error counting references")
+        .expect("No symbol found");
+    assert_eq!(count.count, 0);
+    assert!(!count.is_used && count.complete);
+
+    // `Person` is used many times, but the search stops at the first use
+    let count = analyzer
+        .count_references(&cursor(5, 12), ReferenceScope::File, true)
+        .await
+        .expect("Error counting references")
+        .expect("No symbol found");
+    assert_eq!(count.count, 1);
+    assert!(count.is_used && !count.complete);
+}
+
+#[tokio::test]
+async fn test_find_unused_items() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let file_path = get_sample_file_path().to_str().unwrap().to_string();
+
+    let items = analyzer
+        .find_unused_items(&file_path)
+        .await
+        .expect("Error finding unused items");
+    assert!(items.iter().all(|item| item.file_path == file_path));
+
+    // `swap_people` is never called, but being `pub` it may be intentional
+    let swap_people = items
+        .iter()
+        .find(|item| item.name == "swap_people")
+        .expect("swap_people not reported");
+    assert_eq!(swap_people.kind, UnusedKind::Function);
+    assert_eq!((swap_people.line, swap_people.column), (109, 8));
+    assert!(swap_people.is_pub && !swap_people.fixable);
+
+    // Used items, `main` and tests aren't reported
+    for name in [
+        "calculate_average_age",
+        "Person",
+        "main",
+        "test_count_adults",
+    ] {
+        assert!(
+            items.iter().all(|item| item.name != name),
+            "{name} reported as unused"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_get_definition_external_source() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let file_path = get_sample_file_path().to_str().unwrap().to_string();
+    let options = DefinitionOptions {
+        include_external_source: true,
+        ..Default::default()
+    };
+
+    // `HashMap` in `use std::collections::HashMap;` is defined in the sysroot
+    let cursor = CursorCoordinates {
+        file_path: file_path.clone(),
+        line: 2,
+        column: 23,
+        symbol: None,
+    };
+    let definitions = analyzer
+        .get_definition_with_options(&cursor, &options)
+        .await
+        .expect("Error getting definition")
+        .expect("Expected to find definition for HashMap");
+    let definition = &definitions[0];
+    assert!(definition.is_external);
+    let source = definition.source.as_ref().expect("No source inlined");
+    assert!(source.text.contains(&definition.content));
+    // Only the item and a few lines around it, not the whole file
+    assert!(source.start_line <= definition.line && definition.end_line <= source.end_line);
+    assert!(source.end_line - source.start_line < 500);
+    assert!(!source.truncated);
+    assert!(definition.to_string().contains(" (external)"));
+
+    // `Person::new` is defined in the workspace, so its source isn't inlined
+    let cursor = CursorCoordinates {
+        file_path,
+        line: 33,
+        column: 26,
+        symbol: None,
+    };
+    let definitions = analyzer
+        .get_definition_with_options(&cursor, &options)
+        .await
+        .expect("Error getting definition")
+        .expect("Expected to find definition for Person::new");
+    assert!(!definitions[0].is_external);
+    assert!(definitions[0].source.is_none());
+}
+
+#[tokio::test]
+async fn test_get_file_edition() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+    let directory_path = get_sample_file_path().with_file_name("directory.rs");
+
+    let edition = analyzer
+        .get_file_edition(directory_path.to_str().unwrap())
+        .await
+        .expect("Error getting file edition");
+    assert_eq!(edition, "2024");
+}
+
+#[tokio::test]
+async fn test_find_trait_impls() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+
+    let traits = analyzer
+        .find_trait_impls("Greet")
+        .await
+        .expect("Error finding trait impls");
+    for trait_impls in &traits {
+        println!("{trait_impls}");
+    }
+    assert_eq!(traits.len(), 1, "Only one trait is named Greet");
+    assert!(traits[0].trait_definition.module.ends_with("Greet"));
+
+    let impls = &traits[0].impls;
+    assert_eq!(impls.len(), 2);
+    assert!(
+        impls
+            .iter()
+            .any(|definition| definition.content.contains("impl Greet for Person"))
+    );
+    assert!(
+        impls
+            .iter()
+            .any(|definition| definition.content.contains("impl Greet for Vec<Person>"))
+    );
+
+    let traits = analyzer
+        .find_trait_impls("nowhere::Greet")
+        .await
+        .expect("Error finding trait impls");
+    assert!(
+        traits.is_empty(),
+        "The path doesn't match the trait's module"
+    );
+}
+
+#[tokio::test]
+async fn test_get_public_api() {
+    let analyzer = get_shared_analyzer().await;
+    let mut analyzer = analyzer.lock().await;
+
+    let public_api = analyzer
+        .get_public_api()
+        .await
+        .expect("Error indexing the public API");
+    for item in &public_api.items {
+        println!("{item}");
+    }
+    assert!(!public_api.truncated);
+    let paths: Vec<&str> = public_api
+        .items
+        .iter()
+        .map(|item| item.path.as_str())
+        .collect();
+    assert!(paths.contains(&"sample::Person"), "{paths:?}");
+    assert!(paths.contains(&"sample::Person::new"), "{paths:?}");
+    assert!(paths.contains(&"sample::Person::age"), "{paths:?}");
+    // Trait methods are public with their trait
+    assert!(paths.contains(&"sample::Greet::greet"), "{paths:?}");
+    // Private items are left out
+    assert!(
+        !paths
+            .iter()
+            .any(|path| path.ends_with("calculate_average_age")),
+        "{paths:?}"
+    );
+    // So are `pub` items of the private `directory` module
+    assert!(!paths.contains(&"sample::directory::youngest"), "{paths:?}");
+    assert!(paths.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    // Served from the cache while no files change
+    let cached = analyzer
+        .get_public_api()
+        .await
+        .expect("Error indexing the public API");
+    assert_eq!(cached, public_api);
+}
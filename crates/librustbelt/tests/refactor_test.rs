@@ -0,0 +1,1400 @@
+use std::path::PathBuf;
+
+use librustbelt::RustAnalyzerish;
+use librustbelt::builder::{RustAnalyzerishBuilder, WorkspaceOptions};
+use librustbelt::entities::{
+    ApplyOptions, CompletionOptions, CursorCoordinates, DefinitionOptions, DiagnosticSeverity,
+    PositionedAssists, VariantKind,
+};
+
+/// Write a throwaway cargo project with the given `src/main.rs`
+fn write_scratch_project(name: &str, main_rs: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rustbelt-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).expect("Failed to create scratch project");
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2024\"\n"),
+    )
+    .expect("Failed to write Cargo.toml");
+    let main_path = dir.join("src/main.rs");
+    std::fs::write(&main_path, main_rs).expect("Failed to write main.rs");
+    main_path
+}
+
+fn build_analyzer(file_path: &PathBuf) -> RustAnalyzerish {
+    let options = WorkspaceOptions {
+        no_sysroot: true,
+        no_proc_macros: true,
+        ..Default::default()
+    };
+    RustAnalyzerishBuilder::from_file_with_options(file_path, &options)
+        .expect("Failed to create builder for scratch project")
+        .build()
+        .expect("Failed to build analyzer for scratch project")
+}
+
+const MOVE_ITEM_SOURCE: &str = "mod a {
+    pub fn helper() {}
+}
+
+mod b {}
+
+fn main() {
+    a::helper();
+}
+";
+
+#[tokio::test]
+async fn test_move_item_info() {
+    let main_path = write_scratch_project("move-item", MOVE_ITEM_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 2,
+        column: 12,
+        symbol: None,
+    };
+    let result = analyzer
+        .get_move_item_info(&cursor, "b")
+        .await
+        .expect("Error computing move");
+
+    assert_eq!(result.item_name, "helper");
+    assert_eq!(result.from_module, "crate::a");
+    assert_eq!(result.to_module, "crate::b");
+    assert_eq!(result.file_changes.len(), 1);
+    assert!(
+        result.warnings.is_empty(),
+        "Warnings: {:?}",
+        result.warnings
+    );
+
+    let edits = &result.file_changes[0].edits;
+    assert!(
+        edits
+            .iter()
+            .any(|edit| edit.line == 5 && edit.new_text.contains("pub fn helper() {}")),
+        "Item not inserted into `b`: {edits:?}"
+    );
+    assert!(
+        edits
+            .iter()
+            .any(|edit| edit.line == 8 && edit.new_text == "crate::b"),
+        "Call site not requalified: {edits:?}"
+    );
+
+    // Computing the move leaves the file untouched
+    let content = std::fs::read_to_string(&main_path).expect("Failed to read main.rs");
+    assert_eq!(content, MOVE_ITEM_SOURCE);
+}
+
+const MOVE_ITEM_SIBLINGS_SOURCE: &str = "mod a {
+    fn private_helper() -> u32 {
+        1
+    }
+
+    pub fn shared_helper() -> u32 {
+        2
+    }
+
+    pub fn uses_private() -> u32 {
+        private_helper()
+    }
+
+    pub fn uses_shared() -> u32 {
+        shared_helper()
+    }
+}
+
+mod b {}
+
+fn main() {
+    a::uses_private();
+    a::uses_shared();
+}
+";
+
+#[tokio::test]
+async fn test_move_item_imports_siblings() {
+    let main_path = write_scratch_project("move-item-siblings", MOVE_ITEM_SIBLINGS_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = |line| CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line,
+        column: 12,
+        symbol: None,
+    };
+
+    // A public sibling is imported next to the moved item
+    let result = analyzer
+        .get_move_item_info(&cursor(14), "b")
+        .await
+        .expect("Error computing move");
+    assert_eq!(result.item_name, "uses_shared");
+    let edits = &result.file_changes[0].edits;
+    assert!(
+        edits.iter().any(|edit| edit
+            .new_text
+            .contains("use crate::a::shared_helper;\npub fn uses_shared()")),
+        "Sibling not imported into `b`: {edits:?}"
+    );
+
+    // A private sibling isn't visible from `b`, so the move is refused
+    let error = analyzer
+        .get_move_item_info(&cursor(10), "b")
+        .await
+        .expect_err("Moving an item using a private sibling should fail");
+    assert!(
+        error.to_string().contains("private_helper"),
+        "Unexpected error: {error}"
+    );
+}
+
+#[tokio::test]
+async fn test_move_item_to_missing_module() {
+    let main_path = write_scratch_project("move-item-missing", MOVE_ITEM_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 2,
+        column: 12,
+        symbol: None,
+    };
+    let error = analyzer
+        .get_move_item_info(&cursor, "crate::nowhere")
+        .await
+        .expect_err("Moving into a missing module should fail");
+    assert!(
+        error.to_string().contains("does not exist"),
+        "Unexpected error: {error}"
+    );
+}
+
+const EXTRACT_FUNCTION_SOURCE: &str = "fn main() {
+    let a = 1;
+    let b = a + 1;
+    let _ = b;
+}
+";
+
+#[tokio::test]
+async fn test_extract_function() {
+    let main_path = write_scratch_project("extract-function", EXTRACT_FUNCTION_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let file_path = main_path.to_string_lossy().to_string();
+
+    let source_change = analyzer
+        .extract_function(&file_path, 2, 3, "compute")
+        .await
+        .expect("Error extracting function")
+        .expect("Extract function not applicable");
+    assert_eq!(source_change.file_changes.len(), 1);
+
+    let content = std::fs::read_to_string(&main_path).expect("Failed to read main.rs");
+    assert!(
+        content.contains("fn compute("),
+        "Unexpected content:\n{content}"
+    );
+    assert!(
+        content.contains("compute()"),
+        "Unexpected content:\n{content}"
+    );
+    assert!(
+        !content.contains("fun_name"),
+        "Unexpected content:\n{content}"
+    );
+}
+
+#[tokio::test]
+async fn test_extract_function_rejects_partial_statements() {
+    let main_path = write_scratch_project("extract-function-partial", EXTRACT_FUNCTION_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let file_path = main_path.to_string_lossy().to_string();
+
+    let error = analyzer
+        .extract_function(&file_path, 3, 5, "compute")
+        .await
+        .expect_err("Lines ending on the closing brace should be rejected");
+    assert!(
+        error.to_string().contains("complete statements"),
+        "Unexpected error: {error}"
+    );
+
+    let content = std::fs::read_to_string(&main_path).expect("Failed to read main.rs");
+    assert_eq!(content, EXTRACT_FUNCTION_SOURCE);
+}
+
+#[tokio::test]
+async fn test_organize_imports_removes_unused() {
+    let source = "mod shapes {
+    pub struct Circle;
+    pub struct Square;
+}
+
+use shapes::Square;
+use shapes::Circle;
+
+fn main() {
+    let _ = Circle;
+}
+";
+    let main_path = write_scratch_project("organize-imports", source);
+    let mut analyzer = build_analyzer(&main_path);
+    let file_path = main_path.to_string_lossy().to_string();
+
+    let organized = analyzer
+        .organize_imports_with_options(&file_path, true)
+        .await
+        .expect("Error organizing imports");
+    assert!(organized.contains("use shapes::Circle;"), "{organized}");
+    assert!(!organized.contains("use shapes::Square"), "{organized}");
+
+    // The file is only rewritten by the CLI and MCP `write` flag
+    let content = std::fs::read_to_string(&main_path).expect("Failed to read main.rs");
+    assert_eq!(content, source);
+}
+
+const IMPORT_SOURCE: &str = "mod shapes {
+    pub struct Circle;
+}
+
+fn main() {
+    let _ = Circle;
+}
+";
+
+#[tokio::test]
+async fn test_import_suggestions_and_apply() {
+    let main_path = write_scratch_project("apply-import", IMPORT_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 6,
+        column: 13,
+        symbol: None,
+    };
+
+    let suggestions = analyzer
+        .get_import_suggestions(&cursor)
+        .await
+        .expect("Error getting import suggestions");
+    let suggestion = suggestions
+        .iter()
+        .find(|path| path.ends_with("shapes::Circle"))
+        .unwrap_or_else(|| panic!("Missing shapes::Circle in {suggestions:?}"))
+        .clone();
+
+    let source_change = analyzer
+        .apply_import(&cursor, &suggestion)
+        .await
+        .expect("Error applying import")
+        .expect("Import suggestion not applicable");
+    assert_eq!(source_change.file_changes.len(), 1);
+
+    let content = std::fs::read_to_string(&main_path).expect("Failed to read main.rs");
+    assert!(
+        content.contains(&format!("use {suggestion};")),
+        "Unexpected content:\n{content}"
+    );
+}
+
+#[tokio::test]
+async fn test_apply_import_rejects_unknown_path() {
+    let main_path = write_scratch_project("apply-import-unknown", IMPORT_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 6,
+        column: 13,
+        symbol: None,
+    };
+
+    let result = analyzer
+        .apply_import(&cursor, "nowhere::Circle")
+        .await
+        .expect("Error applying import");
+    assert!(result.is_none());
+}
+
+#[tokio::test]
+async fn test_rename_symbol_returns_diff() {
+    let main_path = write_scratch_project("rename-diff", MOVE_ITEM_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 2,
+        column: 12,
+        symbol: None,
+    };
+
+    let rename_result = analyzer
+        .rename_symbol_with_options(
+            &cursor,
+            "assist",
+            &ApplyOptions {
+                return_diff: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error renaming symbol")
+        .expect("Symbol cannot be renamed");
+
+    let diff = rename_result.file_changes[0]
+        .diff
+        .as_deref()
+        .expect("No diff returned");
+    assert!(diff.contains("-    pub fn helper() {}\n"), "{diff}");
+    assert!(diff.contains("+    pub fn assist() {}\n"), "{diff}");
+    assert!(diff.contains("+    a::assist();\n"), "{diff}");
+
+    let preview = rename_result.preview();
+    assert!(
+        rename_result
+            .line_previews
+            .iter()
+            .any(|line| line.line == 8 && line.after == "    a::assist();"),
+        "{preview}"
+    );
+    assert!(preview.contains("  -     pub fn helper() {}"), "{preview}");
+}
+
+#[tokio::test]
+async fn test_apply_assist_dry_run() {
+    let main_path = write_scratch_project("assist-dry-run", EXTRACT_FUNCTION_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 2,
+        column: 9,
+        symbol: None,
+    };
+
+    let source_change = analyzer
+        .apply_assist_with_options(
+            &cursor,
+            "add_explicit_type",
+            &ApplyOptions {
+                return_diff: true,
+                dry_run: true,
+            },
+        )
+        .await
+        .expect("Error applying assist")
+        .expect("Assist not available");
+    let diff = source_change.file_changes[0]
+        .diff
+        .as_deref()
+        .expect("No diff returned");
+    assert!(diff.contains("+    let a: i32 = 1;\n"), "{diff}");
+
+    // Nothing is written in a dry run
+    let content = std::fs::read_to_string(&main_path).expect("Failed to read main.rs");
+    assert_eq!(content, EXTRACT_FUNCTION_SOURCE);
+}
+
+#[tokio::test]
+async fn test_get_file_assists() {
+    let main_path = write_scratch_project("file-assists", EXTRACT_FUNCTION_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let file_path = main_path.to_string_lossy().to_string();
+    let has_explicit_type_on = |positions: &[PositionedAssists], line| {
+        positions.iter().any(|position| {
+            position.line == line
+                && position
+                    .assists
+                    .iter()
+                    .any(|assist| assist.id == "add_explicit_type")
+        })
+    };
+
+    let file_assists = analyzer
+        .get_file_assists(&file_path, None)
+        .await
+        .expect("Error getting file assists");
+    assert!(!file_assists.truncated);
+    let positions = file_assists.positions;
+    assert!(has_explicit_type_on(&positions, 2), "{positions:?}");
+    assert!(has_explicit_type_on(&positions, 3), "{positions:?}");
+    // Each assist is listed once, at the first position offering it
+    let mut seen = std::collections::HashSet::new();
+    for assist in positions.iter().flat_map(|position| &position.assists) {
+        assert!(
+            seen.insert((&assist.id, &assist.target)),
+            "{} listed twice",
+            assist.id
+        );
+    }
+
+    let positions = analyzer
+        .get_file_assists(&file_path, Some((3, 3)))
+        .await
+        .expect("Error getting file assists")
+        .positions;
+    assert!(positions.iter().all(|position| position.line == 3));
+    assert!(has_explicit_type_on(&positions, 3), "{positions:?}");
+}
+
+#[tokio::test]
+async fn test_get_file_assists_truncated() {
+    // Every binding offers at least `add_explicit_type`, past the cap of 200
+    let bindings: String = (0..250)
+        .map(|i| format!("    let value{i} = {i};\n"))
+        .collect();
+    let source = format!("fn main() {{\n{bindings}}}\n");
+    let main_path = write_scratch_project("file-assists-truncated", &source);
+    let mut analyzer = build_analyzer(&main_path);
+
+    let file_assists = analyzer
+        .get_file_assists(&main_path.to_string_lossy(), None)
+        .await
+        .expect("Error getting file assists");
+    assert!(file_assists.truncated);
+    assert_eq!(file_assists.count(), 200);
+
+    let file_assists = analyzer
+        .get_file_assists(&main_path.to_string_lossy(), Some((2, 3)))
+        .await
+        .expect("Error getting file assists");
+    assert!(!file_assists.truncated);
+}
+
+#[tokio::test]
+async fn test_completions_with_import_edits() {
+    let source = "mod shapes {
+    pub struct Circle;
+}
+
+fn main() {
+    let _ = Circ;
+}
+";
+    let main_path = write_scratch_project("completion-imports", source);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 6,
+        column: 17,
+        symbol: None,
+    };
+
+    let completions = analyzer
+        .get_completions_with_options(
+            &cursor,
+            &CompletionOptions {
+                include_import_edits: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting completions")
+        .expect("No completions found");
+    let circle = completions
+        .iter()
+        .find(|completion| completion.name == "Circle" && !completion.imports.is_empty())
+        .unwrap_or_else(|| panic!("No importable Circle in {completions:?}"));
+
+    let import = &circle.imports[0];
+    assert!(import.path.ends_with("shapes::Circle"), "{}", import.path);
+    assert_eq!(import.text_edit.file_path, cursor.file_path);
+    assert!(
+        import
+            .text_edit
+            .edits
+            .iter()
+            .any(|edit| edit.new_text.contains(&format!("use {};", import.path))),
+        "Unexpected edits: {:?}",
+        import.text_edit.edits
+    );
+}
+
+#[tokio::test]
+async fn test_completions_only_in_scope() {
+    let source = "mod shapes {
+    pub trait Area {
+        fn area(&self) -> u32;
+    }
+    impl Area for u32 {
+        fn area(&self) -> u32 {
+            *self
+        }
+    }
+}
+
+fn main() {
+    let side = 3u32;
+    let _ = side.are;
+}
+";
+    let main_path = write_scratch_project("completion-in-scope", source);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 14,
+        column: 21,
+        symbol: None,
+    };
+    let is_area = |name: &str| name.starts_with("area");
+
+    let completions = analyzer
+        .get_completions_with_options(
+            &cursor,
+            &CompletionOptions {
+                include_import_edits: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting completions")
+        .expect("No completions found");
+    assert!(
+        completions
+            .iter()
+            .any(|completion| is_area(&completion.name) && completion.required_import.is_some()),
+        "No area method needing an import in {completions:?}"
+    );
+
+    let in_scope = analyzer
+        .get_completions_with_options(
+            &cursor,
+            &CompletionOptions {
+                include_import_edits: true,
+                only_in_scope: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting completions")
+        .unwrap_or_default();
+    assert!(
+        in_scope
+            .iter()
+            .all(|completion| completion.required_import.is_none() && !is_area(&completion.name)),
+        "Completions needing an import in {in_scope:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_completions_with_snippets() {
+    let source = "fn area(radius: i32) -> i32 {
+    radius * radius * 3
+}
+
+fn main() {
+    let _ = are;
+}
+";
+    let main_path = write_scratch_project("completion-snippets", source);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 6,
+        column: 16,
+        symbol: None,
+    };
+
+    let plain = analyzer
+        .get_completions(&cursor)
+        .await
+        .expect("Error getting completions")
+        .expect("No completions found");
+    assert!(plain.iter().all(|completion| completion.snippet.is_none()));
+
+    let completions = analyzer
+        .get_completions_with_options(
+            &cursor,
+            &CompletionOptions {
+                include_snippets: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting completions")
+        .expect("No completions found");
+    let area = completions
+        .iter()
+        .find(|completion| completion.name.starts_with("area"))
+        .unwrap_or_else(|| panic!("No area completion in {completions:?}"));
+    let snippet = area.snippet.as_deref().expect("No snippet for area");
+    assert!(snippet.contains("${1:radius}"), "{snippet}");
+}
+
+#[tokio::test]
+async fn test_completions_exclude_deprecated() {
+    let source = "#[deprecated]
+fn area_old(radius: i32) -> i32 {
+    radius * radius * 3
+}
+
+fn area(radius: i32) -> i32 {
+    radius * radius * 3
+}
+
+fn main() {
+    let _ = are;
+}
+";
+    let main_path = write_scratch_project("completion-deprecated", source);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 11,
+        column: 16,
+        symbol: None,
+    };
+
+    let all = analyzer
+        .get_completions(&cursor)
+        .await
+        .expect("Error getting completions")
+        .expect("No completions found");
+    assert!(
+        all.iter()
+            .any(|completion| completion.name.starts_with("area_old") && completion.deprecated),
+        "No deprecated area_old in {all:?}"
+    );
+
+    let completions = analyzer
+        .get_completions_with_options(
+            &cursor,
+            &CompletionOptions {
+                exclude_deprecated: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error getting completions")
+        .expect("No completions found");
+    assert!(completions.iter().all(|completion| !completion.deprecated));
+    assert!(
+        completions
+            .iter()
+            .any(|completion| completion.name.starts_with("area")),
+        "No area completion in {completions:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_workspace_relative_paths() {
+    let main_path = write_scratch_project("relative-paths", MOVE_ITEM_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+
+    let resolved = analyzer.resolve_path("src/main.rs");
+    assert_eq!(
+        std::fs::canonicalize(&resolved).unwrap(),
+        std::fs::canonicalize(&main_path).unwrap()
+    );
+    let absolute = main_path.to_string_lossy().to_string();
+    assert_eq!(analyzer.resolve_path(&absolute), absolute);
+
+    let cursor = CursorCoordinates {
+        file_path: "src/main.rs".to_string(),
+        line: 2,
+        column: 12,
+        symbol: None,
+    };
+    let scope = analyzer
+        .get_enclosing_scope(&cursor)
+        .await
+        .expect("Error resolving a workspace-relative path")
+        .expect("No enclosing scope found");
+    assert_eq!(scope.name, "helper");
+}
+
+#[tokio::test]
+async fn test_rename_symbol_reports_refusal() {
+    let main_path = write_scratch_project("rename-refused", MOVE_ITEM_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let original = std::fs::read_to_string(&main_path).expect("Failed to read main.rs");
+
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 2,
+        column: 12,
+        symbol: None,
+    };
+    // Keywords are rejected before asking rust-analyzer
+    let error = analyzer
+        .rename_symbol(&cursor, "fn")
+        .await
+        .expect_err("Renaming to a keyword should be refused");
+    assert!(error.to_string().contains("is a keyword"), "{error}");
+
+    // A function can't take a label name, which only rust-analyzer knows
+    let error = analyzer
+        .rename_symbol(&cursor, "'label")
+        .await
+        .expect_err("Renaming a function to a label should be refused");
+    assert!(error.to_string().starts_with("Cannot rename:"), "{error}");
+    assert_eq!(
+        std::fs::read_to_string(&main_path).expect("Failed to read main.rs"),
+        original
+    );
+
+    // A closing brace is not a symbol, which is not an error
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 3,
+        column: 1,
+        symbol: None,
+    };
+    let result = analyzer
+        .rename_symbol(&cursor, "other")
+        .await
+        .expect("Error renaming symbol");
+    assert!(result.is_none());
+}
+
+#[tokio::test]
+async fn test_rename_module_moves_file() {
+    let main_path = write_scratch_project(
+        "rename-module",
+        "mod shapes;\n\nfn main() {\n    shapes::area();\n}\n",
+    );
+    let src = main_path.parent().unwrap().to_path_buf();
+    std::fs::write(src.join("shapes.rs"), "pub fn area() {}\n").expect("Failed to write shapes.rs");
+    let _ = std::fs::remove_file(src.join("figures.rs"));
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 1,
+        column: 5,
+        symbol: None,
+    };
+
+    // A dry run reports the move without touching the file
+    let result = analyzer
+        .rename_symbol_with_options(
+            &cursor,
+            "figures",
+            &ApplyOptions {
+                dry_run: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Error renaming module")
+        .expect("Module cannot be renamed");
+    assert_eq!(result.file_renames.len(), 1);
+    assert!(result.file_renames[0].from.ends_with("src/shapes.rs"));
+    assert!(result.file_renames[0].to.ends_with("src/figures.rs"));
+    assert!(src.join("shapes.rs").exists());
+
+    analyzer
+        .rename_symbol(&cursor, "figures")
+        .await
+        .expect("Error renaming module")
+        .expect("Module cannot be renamed");
+    assert!(!src.join("shapes.rs").exists());
+    assert_eq!(
+        std::fs::read_to_string(src.join("figures.rs")).expect("figures.rs was not created"),
+        "pub fn area() {}\n"
+    );
+    let main_rs = std::fs::read_to_string(&main_path).expect("Failed to read main.rs");
+    assert!(main_rs.contains("mod figures;"), "{main_rs}");
+    assert!(main_rs.contains("figures::area();"), "{main_rs}");
+}
+
+const CLOSURE_SOURCE: &str = "fn apply(f: impl Fn(i32) -> i32, value: i32) -> i32 {
+    f(value)
+}
+
+fn main() {
+    let double = |x| {
+        x * 2
+    };
+    let result = apply(double, 21);
+}
+";
+
+#[tokio::test]
+async fn test_view_inlay_hints_range_ends_at_closure() {
+    let main_path = write_scratch_project("inlay-range", CLOSURE_SOURCE);
+    let mut analyzer = build_analyzer(&main_path);
+    let file_path = main_path.to_string_lossy().to_string();
+
+    // The range ends right before a line with hints of its own
+    let closure = analyzer
+        .view_inlay_hints(&file_path, Some(6), Some(8))
+        .await
+        .expect("Error viewing inlay hints");
+    let lines: Vec<&str> = closure.lines().collect();
+    assert_eq!(lines.len(), 3, "{closure}");
+    assert!(lines[0].starts_with("    let double"), "{closure}");
+    assert_eq!(lines[1], "        x * 2");
+    assert_eq!(lines[2], "    };");
+    assert!(!closure.contains("result"), "{closure}");
+
+    // The next line keeps its own hints
+    let call = analyzer
+        .view_inlay_hints(&file_path, Some(9), Some(9))
+        .await
+        .expect("Error viewing inlay hints");
+    assert!(call.starts_with("    let result: i32 = apply("), "{call}");
+    assert!(!call.contains('\n'), "{call}");
+
+    // Every selected line matches the same line of the full file
+    let full = analyzer
+        .view_inlay_hints(&file_path, None, None)
+        .await
+        .expect("Error viewing inlay hints");
+    assert!(full.contains(&format!("{closure}\n{call}\n")), "{full}");
+}
+
+const CFG_TEST_SOURCE: &str = "fn main() {}
+
+#[cfg(test)]
+fn helper() -> u32 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn uses_helper() {
+        let value = super::helper();
+    }
+}
+";
+
+#[tokio::test]
+async fn test_include_tests_resolves_cfg_test_items() {
+    let main_path = write_scratch_project("cfg-test", CFG_TEST_SOURCE);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 12,
+        column: 28,
+        symbol: None,
+    };
+
+    for include_tests in [false, true] {
+        let options = WorkspaceOptions {
+            no_sysroot: true,
+            no_proc_macros: true,
+            include_tests,
+            ..Default::default()
+        };
+        let mut analyzer = RustAnalyzerishBuilder::from_file_with_options(&main_path, &options)
+            .expect("Failed to create builder for scratch project")
+            .build()
+            .expect("Failed to build analyzer for scratch project");
+
+        let resolved = analyzer
+            .get_definition(&cursor)
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|definitions| {
+                definitions
+                    .iter()
+                    .any(|definition| definition.line == 4 && definition.name == "helper")
+            });
+        assert_eq!(
+            resolved, include_tests,
+            "helper should only resolve with include_tests={include_tests}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_on_type_format_indents_method_chain() {
+    let source = "fn main() {
+    let _ = Vec::<i32>::new()
+    .len();
+}
+";
+    let main_path = write_scratch_project("on-type-format", source);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 3,
+        column: 5,
+        symbol: None,
+    };
+
+    let edits = analyzer
+        .on_type_format(&cursor, '.')
+        .await
+        .expect("Error formatting on type")
+        .expect("Expected the dot to be indented");
+    println!("{edits:?}");
+    assert!(edits.iter().all(|edit| edit.end_line == 3));
+
+    let error = analyzer
+        .on_type_format(&cursor, '=')
+        .await
+        .expect_err("The cursor is not on an '='");
+    assert!(error.to_string().contains("Expected '='"), "{error}");
+
+    // A column past the end of the file is an error rather than a panic
+    let past_end = CursorCoordinates {
+        line: 4,
+        column: 80,
+        ..cursor
+    };
+    analyzer
+        .on_type_format(&past_end, '.')
+        .await
+        .expect_err("The column is past the end of the file");
+}
+
+#[tokio::test]
+async fn test_get_definition_follows_reexports() {
+    let source = "mod inner {
+    pub struct Foo;
+}
+
+pub use inner::Foo as Bar;
+
+fn main() {
+    let _bar = Bar;
+}
+";
+    let main_path = write_scratch_project("follow-reexports", source);
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 8,
+        column: 16,
+        symbol: None,
+    };
+    let options = DefinitionOptions {
+        follow_reexports: true,
+        ..Default::default()
+    };
+
+    let definitions = analyzer
+        .get_definition_with_options(&cursor, &options)
+        .await
+        .expect("Error getting definition")
+        .expect("Expected a definition for Bar");
+    for definition in &definitions {
+        println!("{definition}");
+    }
+    let original = definitions.last().unwrap();
+    assert_eq!(original.name, "Foo");
+    assert_eq!(original.line, 2);
+    assert!(!original.is_reexport);
+    assert!(
+        definitions
+            .iter()
+            .filter(|definition| definition.line == 5)
+            .all(|definition| definition.is_reexport)
+    );
+}
+
+#[tokio::test]
+async fn test_public_api_reachability() {
+    let source = "pub mod shapes {
+    pub enum Shape {
+        Circle,
+        Square,
+    }
+
+    pub trait Area {
+        fn area(&self) -> f64;
+    }
+
+    pub(crate) fn internal() {}
+}
+
+mod hidden {
+    pub fn unreachable() {}
+}
+
+pub fn main() {}
+";
+    let main_path = write_scratch_project("public_api", source);
+    let mut analyzer = build_analyzer(&main_path);
+
+    let public_api = analyzer
+        .get_public_api()
+        .await
+        .expect("Error indexing the public API");
+    assert!(!public_api.truncated);
+    let paths: Vec<&str> = public_api
+        .items
+        .iter()
+        .map(|item| item.path.as_str())
+        .collect();
+    for path in [
+        "public_api::shapes::Shape",
+        "public_api::shapes::Shape::Circle",
+        "public_api::shapes::Shape::Square",
+        "public_api::shapes::Area",
+        "public_api::shapes::Area::area",
+        "public_api::main",
+    ] {
+        assert!(paths.contains(&path), "{path} missing from {paths:?}");
+    }
+    // `pub(crate)` items and `pub` items of private modules can't be named
+    // from other crates
+    assert!(
+        !paths.iter().any(|path| path.ends_with("internal")),
+        "{paths:?}"
+    );
+    assert!(
+        !paths.iter().any(|path| path.ends_with("unreachable")),
+        "{paths:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_unload_workspace() {
+    let main_path = write_scratch_project("unload-workspace", "fn main() {}\n");
+    let mut analyzer = build_analyzer(&main_path);
+    let root = main_path.parent().unwrap().parent().unwrap();
+
+    let unloaded = analyzer
+        .unload_workspace()
+        .expect("Expected a project root");
+    assert!(
+        std::path::Path::new(&unloaded).ends_with(root.file_name().unwrap()),
+        "Unexpected root {unloaded}"
+    );
+    assert_eq!(analyzer.status().workspace_file_count, 0);
+
+    // Nothing is left to query
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 1,
+        column: 4,
+        symbol: None,
+    };
+    assert!(analyzer.get_definition(&cursor).await.is_err());
+    assert_eq!(analyzer.unload_workspace(), None);
+}
+
+#[tokio::test]
+async fn test_bom_prefixed_file() {
+    let main_path = write_scratch_project(
+        "bom-prefixed",
+        "\u{feff}struct Point;\n\nfn main() {\n    let _point = Point;\n}\n",
+    );
+    let mut analyzer = build_analyzer(&main_path);
+    let file_path = main_path.to_string_lossy().to_string();
+
+    // The BOM doesn't count as a column of the first line
+    let definitions = analyzer
+        .get_definition(&CursorCoordinates {
+            file_path: file_path.clone(),
+            line: 4,
+            column: 18,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting definition")
+        .expect("No definition found");
+    assert_eq!((definitions[0].line, definitions[0].column), (1, 8));
+
+    analyzer
+        .rename_symbol(
+            &CursorCoordinates {
+                file_path,
+                line: 1,
+                column: 8,
+                symbol: None,
+            },
+            "Spot",
+        )
+        .await
+        .expect("Error renaming symbol")
+        .expect("Symbol cannot be renamed");
+    let content = std::fs::read_to_string(&main_path).unwrap();
+    assert_eq!(
+        content,
+        "\u{feff}struct Spot;\n\nfn main() {\n    let _point = Spot;\n}\n"
+    );
+}
+
+#[tokio::test]
+async fn test_get_declaration_of_trait_method() {
+    let main_path = write_scratch_project(
+        "declaration",
+        "trait Greet {\n    fn greet(&self);\n}\n\nstruct Person;\n\nimpl Greet for Person {\n    fn greet(&self) {}\n}\n\nfn main() {\n    Person.greet();\n}\n",
+    );
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 12,
+        column: 12,
+        symbol: None,
+    };
+
+    // The declaration is the method in the trait, the definition the one in the impl
+    let declarations = analyzer
+        .get_declaration(&cursor)
+        .await
+        .expect("Error getting declaration")
+        .expect("No declaration found");
+    assert_eq!(declarations.len(), 1);
+    assert_eq!(declarations[0].line, 2);
+
+    let definitions = analyzer
+        .get_definition(&cursor)
+        .await
+        .expect("Error getting definition")
+        .expect("No definition found");
+    assert_eq!(definitions[0].line, 8);
+}
+
+#[tokio::test]
+async fn test_get_enum_variants() {
+    let main_path = write_scratch_project(
+        "enum-variants",
+        "enum Message {\n    Quit = 1,\n    Write(String, Option<u8>),\n    Move { x: i32, y: i32 },\n}\n\nfn main() {\n    let _message = Message::Quit;\n}\n",
+    );
+    let mut analyzer = build_analyzer(&main_path);
+    let file_path = main_path.to_string_lossy().to_string();
+
+    // On a use of a variant, as on the enum's name
+    for (line, column) in [(8, 29), (1, 6)] {
+        let variants = analyzer
+            .get_enum_variants(&CursorCoordinates {
+                file_path: file_path.clone(),
+                line,
+                column,
+                symbol: None,
+            })
+            .await
+            .expect("Error getting enum variants")
+            .expect("No enum found");
+        let rendered: Vec<String> = variants.iter().map(|variant| variant.to_string()).collect();
+        assert_eq!(
+            rendered,
+            [
+                "Quit = 1",
+                "Write(String, Option<u8>)",
+                "Move { x: i32, y: i32 }"
+            ]
+        );
+        assert_eq!(variants[2].kind, VariantKind::Record);
+        assert_eq!(variants[2].line, 4);
+        assert_eq!(variants[1].fields[1].name, "1");
+    }
+
+    let not_an_enum = analyzer
+        .get_enum_variants(&CursorCoordinates {
+            file_path,
+            line: 7,
+            column: 4,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting enum variants");
+    assert!(not_an_enum.is_none());
+
+    // By path instead of position
+    for path in ["Message", "enum_variants::Message"] {
+        let variants = analyzer
+            .get_enum_variants_by_path(path)
+            .await
+            .expect("Error getting enum variants")
+            .expect("No enum found by path");
+        assert_eq!(variants.len(), 3, "{path}");
+    }
+    assert!(
+        analyzer
+            .get_enum_variants_by_path("other::Message")
+            .await
+            .expect("Error getting enum variants")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_get_struct_fields_visibility() {
+    let main_path = write_scratch_project(
+        "struct-fields",
+        "struct Pair(pub(crate) u8, String);\n\nfn main() {\n    let _pair = Pair(1, String::new());\n}\n",
+    );
+    let mut analyzer = build_analyzer(&main_path);
+
+    let fields = analyzer
+        .get_struct_fields(&CursorCoordinates {
+            file_path: main_path.to_string_lossy().to_string(),
+            line: 4,
+            column: 17,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting struct fields")
+        .expect("No struct found");
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields[0].visibility.as_deref(), Some("pub(crate)"));
+    assert_eq!(fields[1].visibility, None);
+    assert_eq!(fields[1].to_string(), "1: String");
+}
+
+#[tokio::test]
+async fn test_build_script_generated_code() {
+    let main_path = write_scratch_project(
+        "build-script",
+        "include!(concat!(env!(\"OUT_DIR\"), \"/generated.rs\"));\n\nfn main() {\n    let _answer = generated_answer();\n}\n",
+    );
+    std::fs::write(
+        main_path.parent().unwrap().parent().unwrap().join("build.rs"),
+        "fn main() {\n    let out_dir = std::env::var(\"OUT_DIR\").unwrap();\n    std::fs::write(\n        std::path::Path::new(&out_dir).join(\"generated.rs\"),\n        \"pub fn generated_answer() -> u32 {\\n    42\\n}\\n\",\n    )\n    .unwrap();\n}\n",
+    )
+    .expect("Failed to write build.rs");
+    let mut analyzer = build_analyzer(&main_path);
+    let cursor = CursorCoordinates {
+        file_path: main_path.to_string_lossy().to_string(),
+        line: 4,
+        column: 19,
+        symbol: None,
+    };
+
+    // The definition is in the OUT_DIR of the build script
+    let definitions = analyzer
+        .get_definition(&cursor)
+        .await
+        .expect("Error getting definition")
+        .expect("No definition found");
+    assert_eq!(definitions.len(), 1);
+    let generated = &definitions[0].file_path;
+    assert!(generated.ends_with("/out/generated.rs"), "{generated}");
+    assert_eq!(definitions[0].line, 1);
+
+    // Generated files count as workspace files, and references reach into them
+    assert!(analyzer.workspace_file_paths().contains(generated));
+    let references = analyzer
+        .find_references(&cursor)
+        .await
+        .expect("Error finding references")
+        .expect("No references found");
+    assert!(
+        references
+            .iter()
+            .any(|reference| reference.is_definition && reference.file_path == *generated),
+        "{references:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_workspace_config_file_overrides_inlay_hints() {
+    let main_path = write_scratch_project(
+        "config-file",
+        "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn main() {\n    let sum = add(1, 2);\n}\n",
+    );
+    let file_path = main_path.to_string_lossy().to_string();
+
+    let mut analyzer = build_analyzer(&main_path);
+    let hints = analyzer
+        .get_inlay_hints(&file_path, None, None)
+        .await
+        .expect("Error getting inlay hints");
+    assert!(
+        hints.iter().any(|hint| hint.kind == "parameter"),
+        "{hints:?}"
+    );
+
+    // A `rustbelt.json` at the workspace root is picked up on the next load
+    std::fs::write(
+        main_path
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("rustbelt.json"),
+        r#"{ "inlay_hints": { "parameter_hints": false } }"#,
+    )
+    .expect("Failed to write rustbelt.json");
+    let mut analyzer = build_analyzer(&main_path);
+    assert!(!analyzer.config().inlay_hints.parameter_hints);
+    let hints = analyzer
+        .get_inlay_hints(&file_path, None, None)
+        .await
+        .expect("Error getting inlay hints");
+    assert!(
+        hints.iter().all(|hint| hint.kind != "parameter"),
+        "{hints:?}"
+    );
+    assert!(hints.iter().any(|hint| hint.kind == "type"), "{hints:?}");
+}
+
+#[tokio::test]
+async fn test_file_diagnostics_report_errors() {
+    let main_path = write_scratch_project(
+        "diagnostics",
+        "fn double(x: u32) -> u32 {\n    x * 2\n}\n\nfn main() {\n    double();\n}\n",
+    );
+    let mut analyzer = build_analyzer(&main_path);
+    let file_path = main_path.to_string_lossy().to_string();
+
+    // Calling `double` without its argument is an error on line 6
+    let diagnostics = analyzer
+        .get_file_diagnostics(&file_path)
+        .await
+        .expect("Error getting file diagnostics");
+    assert!(
+        diagnostics.iter().any(
+            |diagnostic| diagnostic.severity == DiagnosticSeverity::Error && diagnostic.line == 6
+        ),
+        "{diagnostics:?}"
+    );
+
+    let workspace_diagnostics = analyzer
+        .get_workspace_diagnostics()
+        .await
+        .expect("Error getting workspace diagnostics");
+    assert!(
+        workspace_diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.file_path == file_path && diagnostic.line == 6),
+        "{workspace_diagnostics:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_get_completions_for_missing_match_arms() {
+    let main_path = write_scratch_project(
+        "match-arms",
+        "enum Shape {\n    Circle,\n    Square,\n    Triangle,\n}\n\nfn sides(shape: Shape) -> u32 {\n    match shape {\n        Shape::Circle => 0,\n        Shape::\n    }\n}\n\nfn main() {\n    sides(Shape::Square);\n}\n",
+    );
+    let mut analyzer = build_analyzer(&main_path);
+    let file_path = main_path.to_string_lossy().to_string();
+
+    // After the `Shape::` of an arm still to write
+    let completions = analyzer
+        .get_completions(&CursorCoordinates {
+            file_path: file_path.clone(),
+            line: 10,
+            column: 16,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting completions")
+        .expect("Expected completions in the match arm pattern");
+    let variants: Vec<&str> = completions
+        .iter()
+        .filter(|completion| completion.kind.as_deref() == Some("Variant"))
+        .map(|completion| completion.name.as_str())
+        .collect();
+    for variant in ["Square", "Triangle"] {
+        assert!(
+            variants.contains(&variant),
+            "{variant} missing from {variants:?}"
+        );
+    }
+
+    // On the `match` keyword, the unhandled variants can be filled in
+    let assists = analyzer
+        .get_assists(&CursorCoordinates {
+            file_path,
+            line: 8,
+            column: 5,
+            symbol: None,
+        })
+        .await
+        .expect("Error getting assists")
+        .unwrap_or_default();
+    assert!(
+        assists
+            .iter()
+            .any(|assist| assist.id == "add_missing_match_arms"),
+        "{assists:?}"
+    );
+}
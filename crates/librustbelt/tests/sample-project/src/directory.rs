@@ -0,0 +1,15 @@
+//! Second file of the sample project, referencing items of `main.rs`
+use crate::Person;
+
+/// Find the youngest person of a list
+pub fn youngest(people: &[Person]) -> Option<&Person> {
+    people.iter().min_by_key(|person| person.age)
+}
+
+/// A copy of a person under another name
+pub fn renamed(person: &Person, name: String) -> Person {
+    Person {
+        name,
+        ..person.clone()
+    }
+}
@@ -82,3 +82,50 @@ where
 // fn sum_numbers(a: i32, b: i32) -> i32 {
 //     a.
 // }
+
+// Mutated local for reference category tests
+pub fn count_adults(people: &[Person]) -> usize {
+    let mut count = 0;
+    for person in people {
+        if person.is_adult() {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_adults() {
+        let people = vec![Person::new("Bob".to_string(), 30)];
+        assert_eq!(count_adults(&people), 1);
+    }
+}
+
+// Type mentioning `Person` twice for canonical type deduplication tests
+pub fn swap_people(pair: (Person, Person)) -> (Person, Person) {
+    (pair.1, pair.0)
+}
+
+// Trait with several implementors for trait impl search tests
+pub trait Greet {
+    fn greet(&self) -> String;
+}
+
+impl Greet for Person {
+    fn greet(&self) -> String {
+        format!("Hello, {}", self.name)
+    }
+}
+
+impl Greet for Vec<Person> {
+    fn greet(&self) -> String {
+        format!("Hello, {} people", self.len())
+    }
+}
+
+// Second file referencing `Person` for multi-file rename tests
+mod directory;
@@ -0,0 +1,542 @@
+use librustbelt::ErrorKind;
+use librustbelt::RustAnalyzerUtils;
+use librustbelt::entities::{ApplyOptions, FileChange, TextEdit};
+use ra_ap_ide::{LineIndex, TextRange, TextSize};
+use ra_ap_ide_db::imports::insert_use::ImportGranularity;
+use ra_ap_ide_db::source_change::{Snippet, SnippetEdit};
+use ra_ap_ide_db::text_edit::TextEditBuilder;
+use ra_ap_syntax::Edition;
+
+const TEXT: &str = "line 1\nline 2\nline 3\nline 4\nline 5";
+
+#[test]
+fn test_get_lines_around_middle() {
+    assert_eq!(
+        RustAnalyzerUtils::get_lines_around(TEXT, 2, 1).as_deref(),
+        Some("line 2\nline 3\nline 4")
+    );
+}
+
+#[test]
+fn test_get_lines_around_clamps_at_file_boundaries() {
+    assert_eq!(
+        RustAnalyzerUtils::get_lines_around(TEXT, 0, 2).as_deref(),
+        Some("line 1\nline 2\nline 3")
+    );
+    assert_eq!(
+        RustAnalyzerUtils::get_lines_around(TEXT, 4, 2).as_deref(),
+        Some("line 3\nline 4\nline 5")
+    );
+    assert_eq!(
+        RustAnalyzerUtils::get_lines_around(TEXT, 2, u32::MAX as usize).as_deref(),
+        Some(TEXT)
+    );
+}
+
+#[test]
+fn test_get_lines_around_without_context() {
+    assert_eq!(
+        RustAnalyzerUtils::get_lines_around(TEXT, 3, 0).as_deref(),
+        Some("line 4")
+    );
+    assert_eq!(RustAnalyzerUtils::get_lines_around(TEXT, 5, 1), None);
+}
+
+#[test]
+fn test_is_fuzzy_match() {
+    assert!(RustAnalyzerUtils::is_fuzzy_match("count_adults", "cntadl"));
+    assert!(RustAnalyzerUtils::is_fuzzy_match("count_adults", ""));
+    assert!(!RustAnalyzerUtils::is_fuzzy_match("count_adults", "adultc"));
+}
+
+#[test]
+fn test_is_valid_identifier() {
+    assert!(RustAnalyzerUtils::is_valid_identifier("compute_total"));
+    assert!(RustAnalyzerUtils::is_valid_identifier("_helper2"));
+    assert!(!RustAnalyzerUtils::is_valid_identifier(""));
+    assert!(!RustAnalyzerUtils::is_valid_identifier("_"));
+    assert!(!RustAnalyzerUtils::is_valid_identifier("2fast"));
+    assert!(!RustAnalyzerUtils::is_valid_identifier("foo-bar"));
+}
+
+#[test]
+fn test_validate_rename_name() {
+    for name in ["total", "_unused", "r#type", "union", "_", "'a", "'outer"] {
+        assert!(
+            RustAnalyzerUtils::validate_rename_name(name).is_ok(),
+            "{name} should be valid"
+        );
+    }
+
+    let error = |name| {
+        RustAnalyzerUtils::validate_rename_name(name)
+            .expect_err(name)
+            .to_string()
+    };
+    assert_eq!(error(""), "The new name is empty");
+    assert!(error("fn").contains("is a keyword"));
+    assert!(error("self").contains("is a keyword"));
+    assert!(error("2fast").contains("not a valid identifier"));
+    assert!(error("two words").contains("not a valid identifier"));
+    assert!(error("r#self").contains("not a valid raw identifier"));
+    assert!(error("'2").contains("not a valid lifetime or label name"));
+    assert!(error("'fn").contains("not a valid lifetime or label name"));
+    assert!(error("'static").contains("is reserved"));
+}
+
+#[test]
+fn test_organize_imports_merges_and_groups() {
+    let text = "use crate::shapes::Circle;
+use std::fmt;
+use serde::Serialize;
+use std::collections::HashMap;
+
+fn main() {}
+";
+    let organized =
+        RustAnalyzerUtils::organize_imports(text, Edition::CURRENT, ImportGranularity::Crate);
+
+    assert_eq!(organized.matches("use std").count(), 1, "{organized}");
+    let std_import = organized
+        .find("use std::{")
+        .expect("std imports not merged");
+    let serde_import = organized.find("use serde::Serialize;").unwrap();
+    let crate_import = organized.find("use crate::shapes::Circle;").unwrap();
+    assert!(std_import < serde_import && serde_import < crate_import);
+    assert!(organized.contains(";\n\nuse serde"), "{organized}");
+    assert!(organized.ends_with("\n\nfn main() {}\n"), "{organized}");
+}
+
+#[test]
+fn test_organize_imports_keeps_imports_after_comments() {
+    let text = "use std::fmt;
+// Needed for the derive below
+use serde::Serialize;
+";
+    assert_eq!(
+        RustAnalyzerUtils::organize_imports(text, Edition::CURRENT, ImportGranularity::Crate),
+        text
+    );
+}
+
+#[test]
+fn test_organize_imports_groups_reexports_separately() {
+    let text = "pub use crate::shapes::Circle;
+use std::fmt;
+pub use serde::Serialize;
+use crate::config::Config;
+";
+    assert_eq!(
+        RustAnalyzerUtils::organize_imports(text, Edition::CURRENT, ImportGranularity::Crate),
+        "use std::fmt;
+
+use crate::config::Config;
+
+pub use serde::Serialize;
+
+pub use crate::shapes::Circle;
+"
+    );
+}
+
+#[test]
+fn test_organize_imports_parses_with_edition() {
+    // `async` is a plain identifier in Rust 2015
+    let text = "use async::Runtime;
+use std::fmt;
+";
+    assert_eq!(
+        RustAnalyzerUtils::organize_imports(text, Edition::Edition2015, ImportGranularity::Crate),
+        "use std::fmt;
+
+use async::Runtime;
+"
+    );
+}
+
+#[test]
+fn test_import_path_from_label() {
+    assert_eq!(
+        RustAnalyzerUtils::import_path_from_label("Import `std::collections::HashMap`").as_deref(),
+        Some("std::collections::HashMap")
+    );
+    assert_eq!(
+        RustAnalyzerUtils::import_path_from_label("Import `fmt::Write` as `_`").as_deref(),
+        Some("fmt::Write")
+    );
+    assert_eq!(RustAnalyzerUtils::import_path_from_label("Import"), None);
+}
+
+#[test]
+fn test_unified_diff() {
+    let diff = RustAnalyzerUtils::unified_diff(
+        "src/main.rs",
+        "fn main() {\n    old();\n}\n",
+        "fn main() {\n    new();\n}\n",
+    );
+    assert!(
+        diff.starts_with("--- src/main.rs\n+++ src/main.rs\n"),
+        "{diff}"
+    );
+    assert!(diff.contains("@@ -1,3 +1,3 @@"), "{diff}");
+    assert!(diff.contains("\n-    old();\n+    new();\n"), "{diff}");
+}
+
+#[test]
+fn test_convert_text_edit_drops_snippets() {
+    let text = "fn main() {\n    let total = 1;\n}\n";
+    let line_index = LineIndex::new(text);
+    let mut builder = TextEditBuilder::default();
+    builder.replace(
+        TextRange::new(TextSize::new(20), TextSize::new(25)),
+        "sum".to_string(),
+    );
+    let text_edit = builder.finish();
+    let snippet_edit = SnippetEdit::new(vec![
+        Snippet::Tabstop(TextSize::new(23)),
+        Snippet::Placeholder(TextRange::new(TextSize::new(20), TextSize::new(23))),
+    ]);
+
+    let edits = RustAnalyzerUtils::convert_text_edit(&line_index, &text_edit, Some(&snippet_edit));
+    assert_eq!(edits.len(), 1);
+    assert_eq!(
+        (
+            edits[0].line,
+            edits[0].column,
+            edits[0].end_line,
+            edits[0].end_column
+        ),
+        (2, 9, 2, 14)
+    );
+    assert_eq!(edits[0].new_text, "sum");
+}
+
+#[test]
+fn test_line_previews() {
+    let text = "fn total() -> u32 {\n    1\n}\n\nfn main() {\n    let _ = total() + total();\n}\n";
+    let edit = |line, column, end_column| TextEdit {
+        line,
+        column,
+        end_line: line,
+        end_column,
+        new_text: "sum".to_string(),
+    };
+    let edits = [edit(6, 23, 28), edit(1, 4, 9), edit(6, 13, 18)];
+
+    let previews = RustAnalyzerUtils::line_previews("src/main.rs", text, &edits);
+    assert_eq!(previews.len(), 2);
+    assert_eq!(previews[0].line, 1);
+    assert_eq!(previews[0].before, "fn total() -> u32 {");
+    assert_eq!(previews[0].after, "fn sum() -> u32 {");
+    assert_eq!(previews[1].line, 6);
+    assert_eq!(previews[1].after, "    let _ = sum() + sum();");
+    assert_eq!(
+        previews[1].to_string(),
+        "src/main.rs:6\n  -     let _ = total() + total();\n  +     let _ = sum() + sum();"
+    );
+}
+
+#[test]
+fn test_checked_offset() {
+    let text = "let é = 1;\nx\n";
+    let line_index = LineIndex::new(text);
+    let offset = |line, column| RustAnalyzerUtils::checked_offset(text, &line_index, line, column);
+
+    assert_eq!(offset(1, 1).unwrap(), TextSize::from(0));
+    assert_eq!(offset(2, 2).unwrap(), TextSize::from(13));
+    // Just past the last character of a line, and the empty last line
+    assert_eq!(offset(1, 12).unwrap(), TextSize::from(11));
+    assert_eq!(offset(3, 1).unwrap(), TextSize::from(14));
+
+    for (line, column, message) in [
+        (0, 1, "1-based"),
+        (1, 13, "past the end of line 1"),
+        (1, 6, "multi-byte character"),
+        (4, 1, "past the end of the file"),
+    ] {
+        let error = offset(line, column).expect_err("Position should be rejected");
+        assert!(
+            error.to_string().contains(message),
+            "{line}:{column}: {error}"
+        );
+    }
+}
+
+#[test]
+fn test_preserve_final_newline() {
+    let preserve = |original: &str, modified: &str| {
+        let mut modified = modified.to_string();
+        RustAnalyzerUtils::preserve_final_newline(original, &mut modified);
+        modified
+    };
+    assert_eq!(preserve("a\n", "b"), "b\n");
+    assert_eq!(preserve("a\r\n", "b"), "b\r\n");
+    assert_eq!(preserve("a", "b\n"), "b");
+    assert_eq!(preserve("a", "b\r\n"), "b");
+    assert_eq!(preserve("a\n", "b\n"), "b\n");
+    assert_eq!(preserve("", "b\n"), "b\n");
+}
+
+#[test]
+fn test_is_fully_resolved() {
+    let hover = |code: &str| {
+        format!("```rust\n{code}\n```\n\n---\n\nDocs mentioning {{unknown}} and Vec<_>")
+    };
+    assert!(RustAnalyzerUtils::is_fully_resolved(&hover(
+        "let numbers: Vec<i32>"
+    )));
+    assert!(RustAnalyzerUtils::is_fully_resolved(&hover(
+        "fn ignore(_: u8, _unused: Option<u8>)"
+    )));
+    assert!(!RustAnalyzerUtils::is_fully_resolved(&hover(
+        "let value: {unknown}"
+    )));
+    assert!(!RustAnalyzerUtils::is_fully_resolved(&hover(
+        "let items: Vec<_>"
+    )));
+    assert!(!RustAnalyzerUtils::is_fully_resolved(&hover(
+        "let pair: (_, u8)"
+    )));
+}
+
+#[test]
+fn test_panic_message() {
+    let literal = std::panic::catch_unwind(|| panic!("literal message")).unwrap_err();
+    assert_eq!(
+        RustAnalyzerUtils::panic_message(literal.as_ref()),
+        "literal message"
+    );
+
+    let line = 42;
+    let formatted = std::panic::catch_unwind(|| panic!("failed at line {line}")).unwrap_err();
+    assert_eq!(
+        RustAnalyzerUtils::panic_message(formatted.as_ref()),
+        "failed at line 42"
+    );
+
+    let other = std::panic::catch_unwind(|| std::panic::panic_any(42)).unwrap_err();
+    assert_eq!(
+        RustAnalyzerUtils::panic_message(other.as_ref()),
+        "<non-string panic payload>"
+    );
+}
+
+#[tokio::test]
+async fn test_apply_file_changes_preserves_final_newline() {
+    let edit = |line, column, end_line, end_column, new_text: &str| TextEdit {
+        line,
+        column,
+        end_line,
+        end_column,
+        new_text: new_text.to_string(),
+    };
+
+    // Replacing the last line drops the final newline of one file and adds
+    // one to the other
+    for (name, original, edit, expected) in [
+        (
+            "with-newline",
+            "fn a() {}\nfn b() {}\n",
+            edit(2, 1, 3, 1, "fn c() {}"),
+            "fn a() {}\nfn c() {}\n",
+        ),
+        (
+            "without-newline",
+            "fn a() {}\nfn b() {}",
+            edit(2, 1, 2, 10, "fn c() {}\n"),
+            "fn a() {}\nfn c() {}",
+        ),
+    ] {
+        let path = std::env::temp_dir().join(format!(
+            "rustbelt-final-newline-{name}-{}.rs",
+            std::process::id()
+        ));
+        std::fs::write(&path, original).expect("Failed to write scratch file");
+
+        let mut changes = [FileChange {
+            file_path: path.to_string_lossy().to_string(),
+            edits: vec![edit],
+            diff: None,
+        }];
+        RustAnalyzerUtils::apply_file_changes(&mut changes, &ApplyOptions::default())
+            .await
+            .expect("Error applying edits");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected, "{name}");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_apply_file_changes_is_atomic() {
+    let path = std::env::temp_dir().join(format!("rustbelt-apply-edits-{}.rs", std::process::id()));
+    std::fs::write(&path, TEXT).expect("Failed to write scratch file");
+    let edit = |line, column, end_line, end_column, new_text: &str| TextEdit {
+        line,
+        column,
+        end_line,
+        end_column,
+        new_text: new_text.to_string(),
+    };
+
+    // One invalid edit rejects the whole change
+    for (edits, kind) in [
+        (
+            vec![edit(1, 1, 1, 5, "row"), edit(2, 1, 2, 99, "row")],
+            Some(ErrorKind::InvalidPosition),
+        ),
+        (vec![edit(1, 1, 1, 5, "row"), edit(1, 3, 1, 6, "row")], None),
+    ] {
+        let mut changes = [FileChange {
+            file_path: path.to_string_lossy().to_string(),
+            edits,
+            diff: None,
+        }];
+        let error = RustAnalyzerUtils::apply_file_changes(&mut changes, &ApplyOptions::default())
+            .await
+            .expect_err("Invalid edits should be rejected");
+        assert_eq!(ErrorKind::of(&error), kind, "{error}");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), TEXT);
+    }
+
+    let mut changes = [FileChange {
+        file_path: path.to_string_lossy().to_string(),
+        edits: vec![edit(2, 1, 2, 5, "row"), edit(1, 1, 1, 5, "row")],
+        diff: None,
+    }];
+    RustAnalyzerUtils::apply_file_changes(&mut changes, &ApplyOptions::default())
+        .await
+        .expect("Error applying edits");
+    assert!(
+        std::fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("row 1\nrow 2\nline 3")
+    );
+}
+
+#[test]
+fn test_diff_signatures_of_functions() {
+    let diff = RustAnalyzerUtils::diff_signatures(
+        "/// Connect to a server\npub fn connect(host: &str, port: u16) -> Result<(), Error> {}",
+        "pub async fn connect(host: &str, port: u32, timeout: Duration) -> Result<Conn, Error> {\n    todo!()\n}",
+    )
+    .expect("Failed to diff signatures");
+    assert!(diff.is_breaking());
+    assert_eq!(
+        diff.to_string(),
+        "connect: 4 change(s), 4 breaking\n  \
+         [breaking] added qualifier: async\n  \
+         [breaking] changed parameter 2 (port): port: u16 -> port: u32\n  \
+         [breaking] added parameter 3 (timeout): timeout: Duration\n  \
+         [breaking] changed return type: Result<(), Error> -> Result<Conn, Error>"
+    );
+
+    // Formatting, bodies and parameter patterns don't affect callers
+    let diff = RustAnalyzerUtils::diff_signatures(
+        "pub fn f(a: Vec<u8>)->u8{0}",
+        "pub fn f(\n    _a: Vec< u8 >,\n) -> u8 {\n    1\n}",
+    )
+    .expect("Failed to diff signatures");
+    assert!(!diff.is_breaking());
+    assert_eq!(
+        diff.changes
+            .iter()
+            .map(|change| change.to_string())
+            .collect::<Vec<_>>(),
+        ["changed parameter 1 name: a: Vec<u8> -> _a: Vec< u8 >"]
+    );
+
+    let diff = RustAnalyzerUtils::diff_signatures("fn old() {}", "fn new() {}")
+        .expect("Failed to diff signatures");
+    assert_eq!(diff.name, "new");
+    assert_eq!(
+        diff.changes[0].to_string(),
+        "[breaking] changed name: old -> new"
+    );
+}
+
+#[test]
+fn test_diff_signatures_finds_the_baseline_item_by_name() {
+    // A skeleton holds many items, the one named like the current item is used
+    let baseline = "pub mod net {\n    pub fn listen(port: u16) {}\n    pub fn connect(host: &str) {}\n}\n\
+                    pub trait Store {\n    fn connect(&self);\n}";
+    let diff =
+        RustAnalyzerUtils::diff_signatures(baseline, "pub fn connect(host: &str, port: u16) {}")
+            .expect("Failed to diff signatures");
+    assert_eq!(
+        diff.to_string(),
+        "connect: 1 change(s), 1 breaking\n  \
+         [breaking] added parameter 2 (port): port: u16"
+    );
+
+    let error = RustAnalyzerUtils::diff_signatures(baseline, "pub fn close() {}")
+        .expect_err("close isn't in the baseline");
+    assert!(
+        error
+            .to_string()
+            .contains("No function or trait named close"),
+        "{error}"
+    );
+}
+
+#[test]
+fn test_diff_signatures_of_traits() {
+    let diff = RustAnalyzerUtils::diff_signatures(
+        "pub trait Store {\n    type Key;\n    fn get(&self, key: &Self::Key) -> Option<String>;\n    fn len(&self) -> usize { 0 }\n}",
+        "pub trait Store: Send {\n    type Key;\n    fn get(&self, key: &Self::Key) -> Option<String>;\n    fn len(&self) -> usize;\n    /// Whether the store is empty\n    fn is_empty(&self) -> bool { self.len() == 0 }\n    fn clear(&mut self);\n}",
+    )
+    .expect("Failed to diff signatures");
+    assert_eq!(
+        diff.to_string(),
+        "Store: 4 change(s), 3 breaking\n  \
+         [breaking] added supertraits: Send\n  \
+         [breaking] removed default implementation of method len\n  \
+         added method is_empty: fn is_empty(&self) -> bool\n  \
+         [breaking] added method clear: fn clear(&mut self)"
+    );
+
+    let error = RustAnalyzerUtils::diff_signatures("fn f() {}", "trait T {}")
+        .expect_err("A function and a trait can't be compared");
+    assert!(
+        error.to_string().contains("function with a trait"),
+        "{error}"
+    );
+}
+
+#[test]
+fn test_splice_line_range_with_multi_line_closure_type() {
+    let text = "fn main() {\n    let double = |x| {\n        x * 2\n    };\n    let result = apply(double, 21);\n}\n";
+    let line_index = LineIndex::new(text);
+    let after = |name: &str| TextSize::from((text.find(name).unwrap() + name.len()) as u32);
+    let insertions = || {
+        vec![
+            (
+                after("let double"),
+                ": impl Fn(\n    i32,\n) -> i32".to_string(),
+            ),
+            (after("let result"), ": i32".to_string()),
+        ]
+    };
+
+    // The closure's hint adds lines, which must not pull in the next line
+    let closure =
+        RustAnalyzerUtils::splice_line_range(text, &line_index, insertions(), Some((2, 4)))
+            .expect("Failed to splice lines");
+    assert_eq!(
+        closure,
+        "    let double: impl Fn(\n    i32,\n) -> i32 = |x| {\n        x * 2\n    };"
+    );
+
+    // The line after the range keeps its own hint
+    let call = RustAnalyzerUtils::splice_line_range(text, &line_index, insertions(), Some((5, 5)))
+        .expect("Failed to splice lines");
+    assert_eq!(call, "    let result: i32 = apply(double, 21);");
+
+    let full = RustAnalyzerUtils::splice_line_range(text, &line_index, insertions(), None)
+        .expect("Failed to splice lines");
+    assert!(full.contains(&format!("{closure}\n{call}\n")), "{full}");
+
+    assert!(
+        RustAnalyzerUtils::splice_line_range(text, &line_index, insertions(), Some((7, 8)))
+            .is_err()
+    );
+}
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use anyhow::Result;
 use vergen_gix::{BuildBuilder, Emitter, GixBuilder};
 
@@ -7,5 +9,23 @@ fn main() -> Result<()> {
     Emitter::default()
         .add_instructions(&build)?
         .add_instructions(&gix)?
-        .emit()
+        .emit()?;
+
+    // Record the rust-analyzer crates version compiled in, for bug reports
+    let lock_file = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_file.display());
+    let version = std::fs::read_to_string(&lock_file)
+        .ok()
+        .and_then(|lock| ra_ap_version(&lock))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=RA_AP_VERSION={version}");
+    Ok(())
+}
+
+/// Version of the `ra_ap_ide` package in a Cargo.lock
+fn ra_ap_version(lock: &str) -> Option<String> {
+    let mut lines = lock.lines();
+    lines.find(|line| line.trim() == "name = \"ra_ap_ide\"")?;
+    let version = lines.next()?.trim().strip_prefix("version = ")?;
+    Some(version.trim_matches('"').to_string())
 }
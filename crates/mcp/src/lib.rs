@@ -4,13 +4,27 @@
 //! Protocol (MCP). It exposes IDE capabilities like type hints,
 //! go-to-definition, and more as MCP tools.
 
+pub mod skeleton;
+
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 
 use libruskel::Ruskel;
-use librustbelt::{RustAnalyzerish, builder::RustAnalyzerishBuilder, entities::CursorCoordinates};
-use serde::{Deserialize, Serialize};
-use tenx_mcp::{Result, ServerCtx, mcp_server, schema::*, schemars, tool};
+use librustbelt::{
+    ErrorKind, RustAnalyzerUtils, RustAnalyzerish,
+    builder::{LoadProgress, ProgressCallback, RustAnalyzerishBuilder, WorkspaceOptions},
+    entities::{
+        ApplyOptions, CompletionOptions, CursorCoordinates, DefinitionOptions, FileChange,
+        ReferenceOptions, ReferenceScope, SymbolMatchMode, TextEdit, TypeHintOptions,
+        WorkspaceSymbolsOptions,
+    },
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tenx_mcp::{
+    Arguments, Error, Result, ServerConn, ServerCtx, mcp_server, schema::*, schemars, tool,
+};
 use tokio::sync::Mutex;
 use tracing::info;
 
@@ -23,11 +37,34 @@ pub const VERSION: &str = concat!(
     ")"
 );
 
+/// Version of the `ra_ap_*` rust-analyzer crates compiled in
+pub const RA_AP_VERSION: &str = env!("RA_AP_VERSION");
+
+/// Build details for bug reports: versions, features and build profile
+pub fn build_info() -> String {
+    let features = librustbelt::enabled_features();
+    format!(
+        "rustbelt {}\nrust-analyzer crates (ra_ap_*): {}\nlibrustbelt features: {}\nBuild profile: {}",
+        VERSION,
+        RA_AP_VERSION,
+        if features.is_empty() {
+            "none".to_string()
+        } else {
+            features.join(", ")
+        },
+        if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        }
+    )
+}
+
 /// Parameters for the rename_symbol tool
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct RenameParams {
     // TODO Do not nest CursorCoordinates here until tenx-mcp properly reports schema
-    /// Absolute path to the Rust source file
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
     pub file_path: String,
     /// Line number (1-based)
     pub line: u32,
@@ -40,6 +77,265 @@ pub struct RenameParams {
     pub symbol: Option<String>,
     /// New name for the symbol
     pub new_name: String,
+    /// Include a unified diff of every changed file in the result
+    #[serde(default)]
+    pub return_diff: bool,
+    /// Show each changed line before and after the rename instead of edit ranges
+    #[serde(default)]
+    pub preview: bool,
+}
+
+/// Parameters for the move_item tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MoveItemParams {
+    // TODO Do not nest CursorCoordinates here until tenx-mcp properly reports schema
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Line number (1-based)
+    pub line: u32,
+    /// Column number (1-based)
+    pub column: u32,
+    /// Optional symbol to find near the given coordinates.
+    /// If provided, will search for this symbol within a tolerance box
+    /// of +/- 5 lines/columns around the given coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Existing module to move the item into, e.g. `crate::shapes`
+    pub target_module: String,
+}
+
+/// Parameters for the get_type_hint tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TypeHintParams {
+    // TODO Do not nest CursorCoordinates here until tenx-mcp properly reports schema
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Line number (1-based)
+    pub line: u32,
+    /// Column number (1-based)
+    pub column: u32,
+    /// Optional symbol to find near the given coordinates.
+    /// If provided, will search for this symbol within a tolerance box
+    /// of +/- 5 lines/columns around the given coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Include the size, alignment, field offsets and niches of the type
+    #[serde(default)]
+    pub include_layout: bool,
+    /// Include whether dropping the type runs any code (drop glue)
+    #[serde(default)]
+    pub show_drop_glue: bool,
+}
+
+/// Parameters for the get_completions tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CompletionsParams {
+    // TODO Do not nest CursorCoordinates here until tenx-mcp properly reports schema
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Line number (1-based)
+    pub line: u32,
+    /// Column number (1-based)
+    pub column: u32,
+    /// Optional symbol to find near the given coordinates.
+    /// If provided, will search for this symbol within a tolerance box
+    /// of +/- 5 lines/columns around the given coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Also complete items that aren't imported yet, with the exact edit adding each import
+    #[serde(default)]
+    pub include_import_edits: bool,
+    /// Also return snippet bodies with tabstops such as `$0` and `${1:arg}`.
+    /// Leave off unless you expand LSP-style snippets
+    #[serde(default)]
+    pub include_snippets: bool,
+    /// Maximum number of completions (default 200, at most 2000). Use a small
+    /// limit for narrow contexts; larger limits take longer and cost more tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Keep rust-analyzer's order instead of sorting by relevance (for debugging)
+    #[serde(default)]
+    pub unsorted: bool,
+    /// Show each completion's relevance score, higher is more relevant
+    #[serde(default)]
+    pub include_scores: bool,
+    /// Leave out deprecated items
+    #[serde(default)]
+    pub exclude_deprecated: bool,
+    /// Leave out items requiring an unstable feature (names in scope only)
+    #[serde(default)]
+    pub exclude_unstable: bool,
+    /// With include_import_edits, leave out items that need an import, such as
+    /// methods of traits not in scope, so every suggestion compiles as-is
+    #[serde(default)]
+    pub only_in_scope: bool,
+}
+
+/// Parameters for the get_definition, get_type_definition, get_declaration and
+/// get_related_tests tools
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DefinitionParams {
+    // TODO Do not nest CursorCoordinates here until tenx-mcp properly reports schema
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Line number (1-based)
+    pub line: u32,
+    /// Column number (1-based)
+    pub column: u32,
+    /// Optional symbol to find near the given coordinates.
+    /// If provided, will search for this symbol within a tolerance box
+    /// of +/- 5 lines/columns around the given coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Include the end position, symbol kind and module path of each definition
+    #[serde(default)]
+    pub verbose: bool,
+    /// Nest each definition in the header of its enclosing impl, trait or type,
+    /// e.g. to learn a method's self type (get_definition only)
+    #[serde(default)]
+    pub include_container: bool,
+    /// Follow a definition that is a re-export, e.g. `pub use inner::Foo as Bar`,
+    /// to the original item, returning both (get_definition only)
+    #[serde(default)]
+    pub follow_reexports: bool,
+    /// Trim each definition to its signature, e.g. just the `fn ...` line(s),
+    /// dropping attributes, doc comments and the body (get_definition only)
+    #[serde(default)]
+    pub signature_only: bool,
+    /// When nothing is found at the position, e.g. on whitespace, retry on the
+    /// nearest identifier of the line (get_definition only)
+    #[serde(default)]
+    pub fuzzy: bool,
+    /// Append the source lines of definitions outside of the workspace, e.g.
+    /// in the cargo registry or the sysroot, for callers that can't read those
+    /// paths (get_definition only)
+    #[serde(default)]
+    pub include_external_source: bool,
+}
+
+/// Parameters for the get_enum_variants and get_struct_fields tools, which take
+/// either a position or the path of the type
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ItemParams {
+    /// Path to a Rust source file, absolute or relative to the loaded workspace
+    /// root; the file of the position, or any file of the workspace with `item_path`
+    pub file_path: String,
+    /// Line number (1-based), required without `item_path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    /// Column number (1-based), required without `item_path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    /// Optional symbol to find near the given coordinates.
+    /// If provided, will search for this symbol within a tolerance box
+    /// of +/- 5 lines/columns around the given coordinates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Path of the type instead of a position, e.g. "shapes::Shape" or "Shape"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_path: Option<String>,
+}
+
+impl ItemParams {
+    /// The cursor at the given position, or an error message if it's incomplete
+    fn cursor(&self) -> std::result::Result<CursorCoordinates, String> {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => Ok(CursorCoordinates {
+                file_path: self.file_path.clone(),
+                line,
+                column,
+                symbol: self.symbol.clone(),
+            }),
+            _ => Err("Pass both line and column, or item_path".to_string()),
+        }
+    }
+}
+
+/// Parameters for tools operating on a whole file
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct FileParams {
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+}
+
+/// Parameters for the organize_imports tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct OrganizeImportsParams {
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Remove unused imports before sorting
+    #[serde(default)]
+    pub remove_unused: bool,
+    /// Write the result back to the file
+    #[serde(default)]
+    pub write: bool,
+}
+
+/// Parameters for the status tool, which takes none
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct StatusParams {}
+
+/// Parameters for the memory_usage tool, which takes none
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MemoryUsageParams {}
+
+/// Parameters for the unload_workspace tool, which takes none
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UnloadWorkspaceParams {}
+
+/// Parameters for the server_info tool, which takes none
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ServerInfoParams {}
+
+/// Parameters for workspace-wide tools
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WorkspaceParams {
+    /// Absolute path to the workspace directory or any file inside it
+    pub workspace_path: String,
+}
+
+/// Number of workspace symbols returned when the caller doesn't set a limit
+const DEFAULT_SYMBOL_LIMIT: usize = 50;
+
+/// Parameters for the get_crate_info tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CrateInfoParams {
+    /// Absolute path to the workspace directory or any file inside it
+    pub workspace_path: String,
+    /// Crate name, e.g. "serde"; dashes and underscores are interchangeable
+    pub crate_name: String,
+}
+
+/// Parameters for the find_trait_impls tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TraitImplsParams {
+    /// Absolute path to the workspace directory or any file inside it
+    pub workspace_path: String,
+    /// Trait name, optionally qualified, e.g. "Display" or "fmt::Display"
+    pub trait_name: String,
+}
+
+/// Parameters for the get_workspace_symbols tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WorkspaceSymbolsParams {
+    /// Absolute path to the workspace directory or any file inside it
+    pub workspace_path: String,
+    /// Name to search for, interpreted according to `mode`. Leave empty or use
+    /// "*" with a `kind` to list every symbol of that kind
+    #[serde(default)]
+    pub query: String,
+    /// How the query is matched: "substring" (default), "prefix", "fuzzy" or "regex"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Only return symbols of this kind, e.g. "struct", "function" or "trait"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Number of matches to skip, for paging through results (default 0)
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of matches to return (default 50)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
 }
 
 /// Parameters for the ruskel tool
@@ -59,12 +355,90 @@ pub struct RuskelParams {
     /// Include private items in the skeleton
     #[serde(default)]
     pub private: bool,
+    /// Return only the declaration and docs of the item named by the last
+    /// segment of `target`, stripping sibling items and module scaffolding
+    #[serde(default)]
+    pub item_only: bool,
+    /// Optional case-insensitive regex (plain substrings work too) used to keep
+    /// only the items whose name or signature matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// Use the latest published version even when the loaded workspace
+    /// resolves the crate to another one
+    #[serde(default)]
+    pub latest: bool,
+    /// Existing directory that relative local path targets, e.g. `./crate`,
+    /// are resolved against instead of the server's working directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_path: Option<String>,
 }
 
 /// Parameters for the view_inlay_hints tool
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ViewInlayHintsParams {
-    /// Absolute path to the Rust source file
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Optional starting line number (1-based, inclusive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u32>,
+    /// Optional ending line number (1-based, inclusive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+    /// Return the hints as a JSON list of positions instead of spliced into the source
+    #[serde(default)]
+    pub structured: bool,
+}
+
+/// Parameters for the get_file_assists tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct FileAssistsParams {
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Optional starting line number (1-based, inclusive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u32>,
+    /// Optional ending line number (1-based, inclusive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+}
+
+/// Parameters for the diff_signature tool
+///
+/// Pass the baseline as `baseline` or `baseline_target`, and the current
+/// version as a position or as `target`.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DiffSignatureParams {
+    /// Previous version of the function or trait as Rust source, e.g. the
+    /// output of the ruskel tool with `item_only=true` for an older release
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<String>,
+    /// Ruskel target naming the previous version instead, e.g.
+    /// `serde@1.0.100::de::Deserialize`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline_target: Option<String>,
+    /// Path to the Rust source file of the current version, absolute or
+    /// relative to the loaded workspace root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    /// Line number (1-based) of the item or a use of it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    /// Column number (1-based) of the item or a use of it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    /// Optional symbol name to search for near the coordinates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Ruskel target naming the current version instead of a position, e.g.
+    /// `serde@1.0.200::de::Deserialize`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+/// Parameters for the get_syntax_tree tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SyntaxTreeParams {
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
     pub file_path: String,
     /// Optional starting line number (1-based, inclusive)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -74,11 +448,30 @@ pub struct ViewInlayHintsParams {
     pub end_line: Option<u32>,
 }
 
+/// Parameters for the on_type_format tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct OnTypeFormatParams {
+    // TODO Do not nest CursorCoordinates here until tenx-mcp properly reports schema
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Line number (1-based) of the typed character
+    pub line: u32,
+    /// Column number (1-based) of the typed character
+    pub column: u32,
+    /// Optional symbol to find near the given coordinates.
+    /// If provided, will search for this symbol within a tolerance box
+    /// of +/- 5 lines/columns around the given coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// The character just typed, one of `.`, `=`, `<`, `>`, `{`, `(` or `|`
+    pub typed_char: char,
+}
+
 /// Parameters for the apply_assist tool
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ApplyAssistParams {
     // TODO Do not nest CursorCoordinates here until tenx-mcp properly reports schema
-    /// Absolute path to the Rust source file
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
     pub file_path: String,
     /// Line number (1-based)
     pub line: u32,
@@ -91,56 +484,522 @@ pub struct ApplyAssistParams {
     pub symbol: Option<String>,
     /// ID of the assist to apply
     pub assist_id: String,
+    /// Include a unified diff of every changed file in the result
+    #[serde(default)]
+    pub return_diff: bool,
+    /// Compute the changes without writing them to disk, to preview them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Parameters for the apply_edits tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ApplyEditsParams {
+    /// Path to the file to edit, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Edits to apply, each replacing the text between `line`:`column` and
+    /// `end_line`:`end_column` (1-based, end exclusive) with `new_text`.
+    /// Positions refer to the file before any edit is applied
+    pub edits: Vec<TextEdit>,
+    /// Include a unified diff of the change in the result
+    #[serde(default)]
+    pub return_diff: bool,
+    /// Validate the edits and compute the change without writing it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Parameters for the apply_import tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ApplyImportParams {
+    // TODO Do not nest CursorCoordinates here until tenx-mcp properly reports schema
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Line number (1-based)
+    pub line: u32,
+    /// Column number (1-based)
+    pub column: u32,
+    /// Optional symbol to find near the given coordinates.
+    /// If provided, will search for this symbol within a tolerance box
+    /// of +/- 5 lines/columns around the given coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Path to import, as returned by get_import_suggestions
+    pub import_path: String,
+}
+
+/// Parameters for the extract_function tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ExtractFunctionParams {
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// First line of the statements to extract (1-based)
+    pub start_line: u32,
+    /// Last line of the statements to extract (1-based, inclusive)
+    pub end_line: u32,
+    /// Name of the new function
+    pub new_name: String,
+}
+
+/// Parameters for the find_references tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct FindReferencesParams {
+    // TODO Do not nest CursorCoordinates here until tenx-mcp properly reports schema
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Line number (1-based)
+    pub line: u32,
+    /// Column number (1-based)
+    pub column: u32,
+    /// Optional symbol to find near the given coordinates.
+    /// If provided, will search for this symbol within a tolerance box
+    /// of +/- 5 lines/columns around the given coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Number of source lines to include before and after each reference (default 0)
+    #[serde(default)]
+    pub context_lines: u32,
+    /// Where to search: `file` (the file of the cursor, fast for locals),
+    /// `crate` or `workspace` (default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+/// Parameters for the count_references tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CountReferencesParams {
+    /// Path to the Rust source file, absolute or relative to the loaded workspace root
+    pub file_path: String,
+    /// Line number (1-based)
+    pub line: u32,
+    /// Column number (1-based)
+    pub column: u32,
+    /// Optional symbol name to search for near the coordinates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Where to search: `file`, `crate` or `workspace` (default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Stop at the first reference, to only check whether the symbol is used
+    #[serde(default)]
+    pub stop_at_first: bool,
+}
+
+/// Parameters for the find_unused_items tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UnusedItemsParams {
+    /// Absolute path to a Rust source file, or to a directory such as the
+    /// workspace root to check every file under it
+    pub path: String,
+}
+
+/// Parameters for the batch tool
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct BatchParams {
+    // Operations are untyped to keep the schema flat, see the CursorCoordinates TODO
+    /// Operations to run in order, each an object `{ "tool": "<tool name>", "params": { ... } }`
+    /// where `params` are the parameters of that tool
+    pub operations: Vec<serde_json::Value>,
+}
+
+/// A single operation of a batch call
+#[derive(Debug, Deserialize)]
+struct BatchOperation {
+    tool: String,
+    #[serde(default)]
+    params: serde_json::Value,
+}
+
+/// Forward workspace loading progress to the client as MCP progress notifications
+///
+/// MCP only allows progress against the `_meta.progressToken` the client
+/// attached to its request, so there is no notifier when it gave none.
+/// Notifications are best-effort: failing to send one never fails the load.
+fn load_progress_notifier(ctx: &ServerCtx) -> Option<ProgressCallback> {
+    let progress_token = ctx.progress_token()?;
+    let ctx = ctx.clone();
+    // Progress must increase with every notification while the stages don't
+    // share a scale, so count notifications and describe the stage in the message
+    let count = AtomicU64::new(0);
+    Some(Arc::new(move |progress: LoadProgress| {
+        let _ = ctx.notify(ServerNotification::Progress {
+            progress_token: progress_token.clone(),
+            progress: count.fetch_add(1, Ordering::Relaxed) as f64,
+            total: None,
+            message: Some(progress.to_string()),
+        });
+    }))
 }
 
 /// Rust-Analyzer MCP server connection
 #[derive(Debug, Clone)]
 pub struct Rustbelt {
     analyzer: Arc<Mutex<Option<RustAnalyzerish>>>,
+    options: WorkspaceOptions,
 }
 
 impl Rustbelt {
-    fn new() -> Self {
+    fn new(options: WorkspaceOptions) -> Self {
         Self {
             analyzer: Arc::new(Mutex::new(None)),
+            options,
         }
     }
 
     /// Initialize the analyzer if it hasn't been created yet
-    async fn ensure_analyzer<P: AsRef<Path>>(&self, file_path: P) -> Result<()> {
+    ///
+    /// Loading can take a while on big workspaces, so its stages are sent to
+    /// the client as progress notifications when it asked for them. Returns
+    /// the failed tool result to reply with when the file has no workspace or
+    /// the workspace fails to load.
+    async fn ensure_analyzer<P: AsRef<Path>>(
+        &self,
+        ctx: &ServerCtx,
+        file_path: P,
+    ) -> Result<Option<CallToolResult>> {
         let mut analyzer_guard = self.analyzer.lock().await;
         if analyzer_guard.is_none() {
-            // Create a default analyzer for the current folder
-            let analyzer = RustAnalyzerishBuilder::from_file(file_path)
-                .expect("Failed to find root workspace from given file")
-                .build()
-                .expect("Failed to create analyzer with current directory");
+            // Create a default analyzer for the file's workspace
+            let mut builder =
+                match RustAnalyzerishBuilder::from_file_with_options(file_path, &self.options) {
+                    Ok(builder) => builder,
+                    Err(e) => {
+                        return Ok(Some(error_result(ErrorCode::FileNotFound, e.to_string())));
+                    }
+                };
+            if let Some(progress) = load_progress_notifier(ctx) {
+                builder = builder.with_progress(progress);
+            }
+            let analyzer = match builder.build() {
+                Ok(analyzer) => analyzer,
+                Err(e) => return Ok(Some(analyzer_error("Error loading the workspace", e))),
+            };
 
             *analyzer_guard = Some(analyzer);
         }
-        Ok(())
+        Ok(None)
     }
-}
 
-#[mcp_server]
-impl Rustbelt {
-    /// Generate a Rust code skeleton for a crate, showing its public API structure
-    /// returns a single Rust source file that lists the
-    /// *public API (or optionally private items) of any crate or module path, with all
-    /// bodies stripped*. Useful for large‑language models that need to look up item
-    /// names, signatures, derives, feature‑gated cfgs, and doc‑comments while writing
-    /// or reviewing Rust code.
-    ///
-    /// ### When a model should call this tool
-    /// 1. It needs a function/trait/struct signature it can't recall.
-    /// 2. The user asks for examples or docs from a crate.
-    /// 3. The model wants to verify what features gate a symbol.
+    /// Pin a ruskel target to the version of the crate the workspace resolves
     ///
-    /// ### Target syntax examples
+    /// Paths, targets with an explicit version and crates the workspace
+    /// doesn't depend on or only gets from a path or git source are returned
+    /// unchanged. Until a workspace is loaded every target is returned
+    /// unchanged, so ruskel falls back to the latest release. Returns the
+    /// pinned version too.
+    async fn workspace_ruskel_target(&self, target: &str) -> (String, Option<String>) {
+        let Some(name) = skeleton::target_crate_name(target) else {
+            return (target.to_string(), None);
+        };
+        let mut analyzer_guard = self.analyzer.lock().await;
+        let Some(analyzer) = analyzer_guard.as_mut() else {
+            return (target.to_string(), None);
+        };
+
+        // Path and git dependencies aren't published at their version, and a
+        // dependency resolved to several versions uses the highest
+        let crates = analyzer.get_crate_info(name).await.unwrap_or_default();
+        let version = skeleton::highest_version(
+            crates
+                .iter()
+                .filter(|krate| {
+                    !krate.is_workspace_member
+                        && krate
+                            .root_file
+                            .as_deref()
+                            .is_some_and(skeleton::is_registry_source)
+                })
+                .filter_map(|krate| krate.version.as_deref()),
+        )
+        .map(str::to_string);
+        match version {
+            Some(version) => {
+                info!("Pinning ruskel target {} to version {}", target, version);
+                (
+                    skeleton::pin_target_version(target, &version),
+                    Some(version),
+                )
+            }
+            None => (target.to_string(), None),
+        }
+    }
+
+    /// Run a single batch operation by dispatching to the named tool
+    ///
+    /// Invalid operations are reported as an error result rather than failing
+    /// the whole batch.
+    async fn run_batch_operation(
+        &self,
+        ctx: &ServerCtx,
+        operation: BatchOperation,
+    ) -> Result<CallToolResult> {
+        let BatchOperation { tool, params } = operation;
+        match tool.as_str() {
+            "ruskel" => match batch_params(&tool, params) {
+                Ok(params) => self.ruskel(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_type_hint" => match batch_params(&tool, params) {
+                Ok(params) => self.get_type_hint(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_definition" => match batch_params(&tool, params) {
+                Ok(params) => self.get_definition(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_type_definition" => match batch_params(&tool, params) {
+                Ok(params) => self.get_type_definition(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_declaration" => match batch_params(&tool, params) {
+                Ok(params) => self.get_declaration(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_related_tests" => match batch_params(&tool, params) {
+                Ok(params) => self.get_related_tests(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_qualified_path" => match batch_params(&tool, params) {
+                Ok(cursor) => self.get_qualified_path(ctx, cursor).await,
+                Err(result) => Ok(result),
+            },
+            "describe_symbol" => match batch_params(&tool, params) {
+                Ok(cursor) => self.describe_symbol(ctx, cursor).await,
+                Err(result) => Ok(result),
+            },
+            "get_enclosing_scope" => match batch_params(&tool, params) {
+                Ok(cursor) => self.get_enclosing_scope(ctx, cursor).await,
+                Err(result) => Ok(result),
+            },
+            "get_item_attributes" => match batch_params(&tool, params) {
+                Ok(cursor) => self.get_item_attributes(ctx, cursor).await,
+                Err(result) => Ok(result),
+            },
+            "get_enum_variants" => match batch_params(&tool, params) {
+                Ok(params) => self.get_enum_variants(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_struct_fields" => match batch_params(&tool, params) {
+                Ok(params) => self.get_struct_fields(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_call_return_type" => match batch_params(&tool, params) {
+                Ok(cursor) => self.get_call_return_type(ctx, cursor).await,
+                Err(result) => Ok(result),
+            },
+            "diff_signature" => match batch_params(&tool, params) {
+                Ok(params) => self.diff_signature(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "count_references" => match batch_params(&tool, params) {
+                Ok(params) => self.count_references(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "find_unused_items" => match batch_params(&tool, params) {
+                Ok(params) => self.find_unused_items(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_runnables" => match batch_params(&tool, params) {
+                Ok(cursor) => self.get_runnables(ctx, cursor).await,
+                Err(result) => Ok(result),
+            },
+            "get_completions" => match batch_params(&tool, params) {
+                Ok(params) => self.get_completions(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "rename_symbol" => match batch_params(&tool, params) {
+                Ok(params) => self.rename_symbol(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "move_item" => match batch_params(&tool, params) {
+                Ok(params) => self.move_item(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "view_inlay_hints" => match batch_params(&tool, params) {
+                Ok(params) => self.view_inlay_hints(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "find_references" => match batch_params(&tool, params) {
+                Ok(params) => self.find_references(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "organize_imports" => match batch_params(&tool, params) {
+                Ok(params) => self.organize_imports(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_semantic_tokens" => match batch_params(&tool, params) {
+                Ok(params) => self.get_semantic_tokens(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_syntax_tree" => match batch_params(&tool, params) {
+                Ok(params) => self.get_syntax_tree(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "on_type_format" => match batch_params(&tool, params) {
+                Ok(params) => self.on_type_format(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_workspace_diagnostics" => match batch_params(&tool, params) {
+                Ok(params) => self.get_workspace_diagnostics(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "status" => self.status(ctx, StatusParams::default()).await,
+            "server_info" => self.server_info(ctx, ServerInfoParams::default()).await,
+            "memory_usage" => self.memory_usage(ctx, MemoryUsageParams::default()).await,
+            "unload_workspace" => {
+                self.unload_workspace(ctx, UnloadWorkspaceParams::default())
+                    .await
+            }
+            "get_file_edition" => match batch_params(&tool, params) {
+                Ok(params) => self.get_file_edition(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_crate_info" => match batch_params(&tool, params) {
+                Ok(params) => self.get_crate_info(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "find_trait_impls" => match batch_params(&tool, params) {
+                Ok(params) => self.find_trait_impls(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_workspace_symbols" => match batch_params(&tool, params) {
+                Ok(params) => self.get_workspace_symbols(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_assists" => match batch_params(&tool, params) {
+                Ok(cursor) => self.get_assists(ctx, cursor).await,
+                Err(result) => Ok(result),
+            },
+            "get_file_assists" => match batch_params(&tool, params) {
+                Ok(params) => self.get_file_assists(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "apply_assist" => match batch_params(&tool, params) {
+                Ok(params) => self.apply_assist(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "apply_edits" => match batch_params(&tool, params) {
+                Ok(params) => self.apply_edits(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "get_import_suggestions" => match batch_params(&tool, params) {
+                Ok(cursor) => self.get_import_suggestions(ctx, cursor).await,
+                Err(result) => Ok(result),
+            },
+            "apply_import" => match batch_params(&tool, params) {
+                Ok(params) => self.apply_import(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "extract_function" => match batch_params(&tool, params) {
+                Ok(params) => self.extract_function(ctx, params).await,
+                Err(result) => Ok(result),
+            },
+            "batch" => Ok(error_result(
+                ErrorCode::InvalidParams,
+                "Nested batch operations are not supported".to_string(),
+            )),
+            _ => Ok(error_result(
+                ErrorCode::UnknownTool,
+                format!("Unknown tool: {tool}"),
+            )),
+        }
+    }
+}
+
+/// Deserialize the parameters of a batch operation, or describe why they are invalid
+fn batch_params<T: DeserializeOwned>(
+    tool: &str,
+    params: serde_json::Value,
+) -> std::result::Result<T, CallToolResult> {
+    serde_json::from_value(params).map_err(|e| {
+        error_result(
+            ErrorCode::InvalidParams,
+            format!("Invalid parameters for {tool}: {e}"),
+        )
+    })
+}
+
+/// Machine-readable reason a tool call failed
+///
+/// Failed tool results carry it next to the human-readable text, as the
+/// structured content `{"error": {"code": "...", "message": "..."}}`, so
+/// clients can branch on the code without parsing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The file is not part of the loaded workspace
+    FileNotFound,
+    /// The line or column is outside of the file
+    InvalidPosition,
+    /// A parameter is missing or has an invalid value
+    InvalidParams,
+    /// No tool has this name
+    UnknownTool,
+    /// ruskel failed to render the skeleton of the target
+    SkeletonFailed,
+    /// rust-analyzer failed to answer the query
+    AnalysisFailed,
+}
+
+impl ErrorCode {
+    /// Classify an analyzer error by its [`ErrorKind`]
+    ///
+    /// Errors without a kind are failures of the analysis itself.
+    pub fn classify(error: &anyhow::Error) -> Self {
+        match ErrorKind::of(error) {
+            Some(ErrorKind::FileNotFound) => Self::FileNotFound,
+            Some(ErrorKind::InvalidPosition) => Self::InvalidPosition,
+            Some(ErrorKind::InvalidParams) => Self::InvalidParams,
+            None => Self::AnalysisFailed,
+        }
+    }
+}
+
+/// Failed tool result with `message` as text and the error code as structured content
+fn error_result(code: ErrorCode, message: String) -> CallToolResult {
+    let structured = serde_json::json!({ "error": { "code": code, "message": message } });
+    CallToolResult::new()
+        .with_text_content(message)
+        .with_structured_content(structured)
+        .is_error(true)
+}
+
+/// Render the declaration of the item named by a ruskel target, with default features
+fn render_target_item(target: &str) -> anyhow::Result<String> {
+    let skeleton = Ruskel::new()
+        .render(target, false, false, Vec::new(), false)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    let name = target.rsplit_once("::").map_or(target, |(_, name)| name);
+    skeleton::extract_item(&skeleton, target)
+        .ok_or_else(|| anyhow::anyhow!("Item '{}' not found in the skeleton of {}", name, target))
+}
+
+/// Failed tool result for an analyzer error, prefixed with what was being done
+fn analyzer_error(context: &str, error: anyhow::Error) -> CallToolResult {
+    error_result(ErrorCode::classify(&error), format!("{context}: {error}"))
+}
+
+#[mcp_server]
+impl Rustbelt {
+    /// Generate a Rust code skeleton for a crate, showing its public API structure
+    /// returns a single Rust source file that lists the
+    /// *public API (or optionally private items) of any crate or module path, with all
+    /// bodies stripped*. Useful for large‑language models that need to look up item
+    /// names, signatures, derives, feature‑gated cfgs, and doc‑comments while writing
+    /// or reviewing Rust code.
+    ///
+    /// ### When a model should call this tool
+    /// 1. It needs a function/trait/struct signature it can't recall.
+    /// 2. The user asks for examples or docs from a crate.
+    /// 3. The model wants to verify what features gate a symbol.
+    ///
+    /// ### Target syntax examples
     /// - `serde`               →  latest serde on crates.io
     /// - `serde@1.0.160`      →  specific published version
     /// - `serde::de::Deserialize` →  narrow output to one module/type for small contexts
     /// - `/path/to/crate` or `/path/to/crate::submod` →  local workspace paths
+    /// - `./crate` or `crates/foo::submod` →  relative local paths, resolved against
+    ///   `base_path` when given, otherwise against the server's working directory
     ///
     /// ### Output format
     /// Plain UTF‑8 text containing valid Rust code, with implementation omitted.
@@ -149,88 +1008,1678 @@ impl Rustbelt {
     /// - Request deep module paths (e.g. `tokio::sync::mpsc`) to keep the reply below
     ///   your token budget.
     /// - Pass `all_features=true` or `features=[…]` when a symbol is behind a feature gate.
+    /// - Pass `filter` (e.g. `filter="spawn"`) to return only the matching item signatures,
+    ///   each grouped under its containing module path.
+    /// - Pass `item_only=true` with a target such as `tokio::task::spawn` to get just that
+    ///   item's signature and docs.
+    ///
+    /// Once a workspace is loaded, dependencies are rendered at the version it
+    /// resolves, so the skeleton matches the code being edited. Pass `latest=true`
+    /// to get the latest release instead.
     #[tool]
     async fn ruskel(&self, _ctx: &ServerCtx, params: RuskelParams) -> Result<CallToolResult> {
         let ruskel = Ruskel::new();
+        let target = match &params.base_path {
+            Some(base_path) if !Path::new(base_path).is_dir() => {
+                return Ok(error_result(
+                    ErrorCode::InvalidParams,
+                    format!("Base path {base_path} is not an existing directory"),
+                ));
+            }
+            Some(base_path) => skeleton::resolve_target_path(&params.target, Path::new(base_path)),
+            None => params.target.clone(),
+        };
+        let (target, pinned_version) = if params.latest {
+            (target, None)
+        } else {
+            self.workspace_ruskel_target(&target).await
+        };
+        let version_note = pinned_version
+            .map(|version| format!("// Version {version}, as resolved by the workspace\n"))
+            .unwrap_or_default();
 
         match ruskel.render(
-            &params.target,
+            &target,
             params.no_default_features,
             params.all_features,
             params.features.to_vec(),
             params.private,
         ) {
-            Ok(skeleton) => Ok(CallToolResult::new()
-                .with_text_content(skeleton)
+            Ok(skeleton) => {
+                let skeleton = if params.item_only {
+                    skeleton::extract_target_item(&params.target, &skeleton)
+                } else {
+                    skeleton
+                };
+                match params.filter {
+                    Some(filter) => match skeleton::filter_items(&skeleton, &filter) {
+                        Ok(filtered) => Ok(CallToolResult::new()
+                            .with_text_content(format!("{version_note}{filtered}"))
+                            .is_error(false)),
+                        Err(e) => Ok(error_result(
+                            ErrorCode::InvalidParams,
+                            format!("Error filtering skeleton: {e}"),
+                        )),
+                    },
+                    None => Ok(CallToolResult::new()
+                        .with_text_content(format!("{version_note}{skeleton}"))
+                        .is_error(false)),
+                }
+            }
+            Err(e) => {
+                let mut result_text = format!("Error generating skeleton: {e}");
+                let hints = skeleton::error_hints(&e.to_string(), params.all_features);
+                if !hints.is_empty() {
+                    result_text.push_str("\n\nHints:");
+                    for hint in hints {
+                        result_text.push_str(&format!("\n- {hint}"));
+                    }
+                }
+
+                Ok(error_result(ErrorCode::SkeletonFailed, result_text))
+            }
+        }
+    }
+
+    /// Get type information for a symbol at a specific position in Rust code
+    ///
+    /// Provides detailed type information including variable types, function signatures,
+    /// struct/enum definitions, and generic parameters. Use this when you need to understand
+    /// the type of a symbol for code analysis, refactoring, or generating type-aware code.
+    ///
+    /// Returns human-readable type information or indicates if no type data is available.
+    /// With `include_layout=true` the size, alignment, field offsets and niches are
+    /// included too, which is useful for performance-sensitive code. With
+    /// `show_drop_glue=true` it also tells whether dropping the value runs any code,
+    /// to reason about RAII and drop order.
+    #[tool]
+    async fn get_type_hint(
+        &self,
+        ctx: &ServerCtx,
+        params: TypeHintParams,
+    ) -> Result<CallToolResult> {
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_type_hint_with_options(
+                &cursor,
+                &TypeHintOptions {
+                    include_layout: params.include_layout,
+                    show_drop_glue: params.show_drop_glue,
+                },
+            )
+            .await
+        {
+            Ok(Some(type_info)) => Ok(CallToolResult::new()
+                .with_text_content(type_info.to_string())
+                .is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No type information available at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting type hint", e)),
+        }
+    }
+
+    /// Get definition location for a symbol at a specific position in Rust code
+    ///
+    /// Finds where symbols are defined - functions, types, variables, modules, macros,
+    /// and more. Essential for code navigation and understanding symbol relationships.
+    ///
+    /// Returns definition locations as "file_path:line_number:column_number" format,
+    /// or indicates if no definitions are found. With `verbose=true` the location is
+    /// rendered as "file_path:line:column-end_line:end_column" followed by the symbol
+    /// kind and module path. With `include_container=true` a method or field is
+    /// wrapped in the header of its enclosing item, e.g. `impl Person { ... }`.
+    /// Definitions outside of the workspace, in dependencies or the standard
+    /// library, are marked "(external)"; with `include_external_source=true`
+    /// the item's source lines follow, with some context and capped in length,
+    /// for when the path can't be opened.
+    #[tool]
+    async fn get_definition(
+        &self,
+        ctx: &ServerCtx,
+        params: DefinitionParams,
+    ) -> Result<CallToolResult> {
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_definition_report(
+                &cursor,
+                &DefinitionOptions {
+                    include_container: params.include_container,
+                    follow_reexports: params.follow_reexports,
+                    signature_only: params.signature_only,
+                    fuzzy: params.fuzzy,
+                    include_external_source: params.include_external_source,
+                },
+            )
+            .await
+        {
+            Ok(report) => {
+                let mut result_text = match report.definitions {
+                    Some(definitions) => definitions
+                        .iter()
+                        .map(|def| {
+                            if params.verbose {
+                                format!("{def:#}")
+                            } else {
+                                def.to_string()
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    None => "No definitions found at this position".to_string(),
+                };
+                if let Some(note) = report.note {
+                    result_text.push_str(&format!("\nNote: {note}"));
+                }
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Err(e) => Ok(analyzer_error("Error getting definitions", e)),
+        }
+    }
+
+    /// Get the definition of the type of a symbol at a specific position in Rust code
+    ///
+    /// Navigates to where the type of the expression under the cursor is defined,
+    /// e.g. on `let x = foo()` it jumps to the definition of `x`'s type. This is more
+    /// actionable than the canonical type paths returned by get_type_hint.
+    ///
+    /// Returns type definition locations as "file_path:line_number:column_number" format,
+    /// or indicates if no type definitions are found. Accepts the same `verbose` flag
+    /// as get_definition.
+    #[tool]
+    async fn get_type_definition(
+        &self,
+        ctx: &ServerCtx,
+        params: DefinitionParams,
+    ) -> Result<CallToolResult> {
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_type_definition(&cursor)
+            .await
+        {
+            Ok(Some(definitions)) => {
+                let result_text = definitions
+                    .iter()
+                    .map(|def| {
+                        if params.verbose {
+                            format!("{def:#}")
+                        } else {
+                            def.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No type definitions found at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting type definitions", e)),
+        }
+    }
+
+    /// Get the declaration of a symbol at a specific position in Rust code
+    ///
+    /// The declaration differs from the definition for items of `extern` blocks
+    /// and trait items. On a call of a trait method, get_declaration returns the
+    /// method in the trait while get_definition returns the method of the impl.
+    /// For other symbols the declaration is the definition.
+    ///
+    /// Returns declaration locations in the same format as get_definition, including
+    /// the `verbose` flag, or indicates if no declarations are found.
+    #[tool]
+    async fn get_declaration(
+        &self,
+        ctx: &ServerCtx,
+        params: DefinitionParams,
+    ) -> Result<CallToolResult> {
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_declaration(&cursor)
+            .await
+        {
+            Ok(Some(declarations)) => {
+                let result_text = declarations
+                    .iter()
+                    .map(|def| {
+                        if params.verbose {
+                            format!("{def:#}")
+                        } else {
+                            def.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No declarations found at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting declarations", e)),
+        }
+    }
+
+    /// Find the tests exercising the item at a specific position in Rust code
+    ///
+    /// Place the cursor on a function, method or type to find the test functions
+    /// that use it, so you know which tests to run after changing it without
+    /// grepping for its name.
+    ///
+    /// Returns test locations in the same format as get_definition, including the
+    /// `verbose` flag, or indicates if no related tests are found.
+    #[tool]
+    async fn get_related_tests(
+        &self,
+        ctx: &ServerCtx,
+        params: DefinitionParams,
+    ) -> Result<CallToolResult> {
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_related_tests(&cursor)
+            .await
+        {
+            Ok(tests) if tests.is_empty() => Ok(CallToolResult::new()
+                .with_text_content("No related tests found at this position")
+                .is_error(false)),
+            Ok(tests) => {
+                let result_text = tests
+                    .iter()
+                    .map(|test| {
+                        if params.verbose {
+                            format!("{test:#}")
+                        } else {
+                            test.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Err(e) => Ok(analyzer_error("Error getting related tests", e)),
+        }
+    }
+
+    /// Get the fully qualified path of the symbol at a specific position
+    ///
+    /// Answers "what do I import" more cheaply than get_definition, which also
+    /// navigates to and extracts the definition.
+    ///
+    /// Returns the path to import the item with from the file, preferring public
+    /// re-exports, e.g. `std::collections::HashMap`; items of the same crate start
+    /// with `crate::`. An item that can't be imported from the file gets the path
+    /// where it's defined, starting with its crate name.
+    #[tool]
+    async fn get_qualified_path(
+        &self,
+        ctx: &ServerCtx,
+        cursor: CursorCoordinates,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_qualified_path(&cursor)
+            .await
+        {
+            Ok(Some(path)) => Ok(CallToolResult::new()
+                .with_text_content(path)
+                .is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No item with a path at this position (locals have none)")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting qualified path", e)),
+        }
+    }
+
+    /// Describe a symbol at a specific position in one call
+    ///
+    /// Combines get_definition, get_type_hint and find_references for a quick
+    /// overview of an unfamiliar symbol, saving round trips.
+    ///
+    /// Returns the definition locations with their kind and module, the hover
+    /// documentation and types (or a note when there is none), and the number
+    /// of usages across the workspace.
+    #[tool]
+    async fn describe_symbol(
+        &self,
+        ctx: &ServerCtx,
+        cursor: CursorCoordinates,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .describe_symbol(&cursor)
+            .await
+        {
+            Ok(Some(description)) => Ok(CallToolResult::new()
+                .with_text_content(description.to_string())
+                .is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No symbol found at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error describing symbol", e)),
+        }
+    }
+
+    /// Get the function, impl, trait or module enclosing a specific position
+    ///
+    /// Answers "what function am I in" to orient within a large file, from
+    /// anywhere inside the scope including its body. Closures are skipped in
+    /// favour of the function containing them.
+    ///
+    /// Returns the scope's full range as "file_path:line:column-end_line:end_column",
+    /// its kind and module path, then its header nested in the enclosing impl or
+    /// trait, e.g. `impl Person { pub fn new(name: String) -> Self }`.
+    #[tool]
+    async fn get_enclosing_scope(
+        &self,
+        ctx: &ServerCtx,
+        cursor: CursorCoordinates,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_enclosing_scope(&cursor)
+            .await
+        {
+            Ok(Some(scope)) => Ok(CallToolResult::new()
+                .with_text_content(format!("{scope:#}"))
+                .is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("The position is at the top level of the file")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting enclosing scope", e)),
+        }
+    }
+
+    /// Get the attributes and derives on the item at a specific position
+    ///
+    /// Answers "does this type derive Clone" without reading the whole item.
+    /// The item is the innermost item, struct field or enum variant around the
+    /// cursor, so point at its name or anywhere inside it.
+    ///
+    /// Returns one attribute per line as written, e.g. `#[derive(Debug, Clone)]`,
+    /// without doc comments.
+    #[tool]
+    async fn get_item_attributes(
+        &self,
+        ctx: &ServerCtx,
+        cursor: CursorCoordinates,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_item_attributes(&cursor)
+            .await
+        {
+            Ok(Some(attributes)) if attributes.is_empty() => Ok(CallToolResult::new()
+                .with_text_content("The item has no attributes")
+                .is_error(false)),
+            Ok(Some(attributes)) => Ok(CallToolResult::new()
+                .with_text_content(attributes.join("\n"))
+                .is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No item at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting item attributes", e)),
+        }
+    }
+
+    /// Get every variant of an enum with its fields, for code generation
+    ///
+    /// Point at the enum's name, a use of it or one of its variants, or pass its
+    /// `item_path` such as "shapes::Shape" instead of a position. Unlike the hover
+    /// text of get_type_hint, which stops after a few variants, every variant is
+    /// listed.
+    ///
+    /// Returns one variant per line as declared, e.g. `Move { x: i32, y: i32 }`,
+    /// `Write(String)` or `Quit = 1`, with field types as written in the enum.
+    #[tool]
+    async fn get_enum_variants(
+        &self,
+        ctx: &ServerCtx,
+        params: ItemParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.file_path).await? {
+            return Ok(error);
+        }
+        let mut analyzer_guard = self.analyzer.lock().await;
+        let analyzer = analyzer_guard.as_mut().unwrap();
+        let result = match &params.item_path {
+            Some(item_path) => analyzer.get_enum_variants_by_path(item_path).await,
+            None => match params.cursor() {
+                Ok(cursor) => analyzer.get_enum_variants(&cursor).await,
+                Err(message) => return Ok(error_result(ErrorCode::InvalidParams, message)),
+            },
+        };
+        match result {
+            Ok(Some(variants)) if variants.is_empty() => Ok(CallToolResult::new()
+                .with_text_content("The enum has no variants")
+                .is_error(false)),
+            Ok(Some(variants)) => Ok(CallToolResult::new()
+                .with_text_content(
+                    variants
+                        .iter()
+                        .map(|variant| variant.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )
+                .is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No enum at this position or path")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting enum variants", e)),
+        }
+    }
+
+    /// Get every field of a struct with its type and visibility
+    ///
+    /// Point at the struct's name, a use of it or one of its fields, or pass its
+    /// `item_path` instead of a position. Unlike the hover text of get_type_hint,
+    /// every field is listed with its visibility, which is what building a struct
+    /// literal needs. Unions work too.
+    ///
+    /// Returns one field per line as declared, e.g. `pub(crate) name: String`, with
+    /// no visibility for private fields. Tuple struct fields are named by index.
+    #[tool]
+    async fn get_struct_fields(
+        &self,
+        ctx: &ServerCtx,
+        params: ItemParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.file_path).await? {
+            return Ok(error);
+        }
+        let mut analyzer_guard = self.analyzer.lock().await;
+        let analyzer = analyzer_guard.as_mut().unwrap();
+        let result = match &params.item_path {
+            Some(item_path) => analyzer.get_struct_fields_by_path(item_path).await,
+            None => match params.cursor() {
+                Ok(cursor) => analyzer.get_struct_fields(&cursor).await,
+                Err(message) => return Ok(error_result(ErrorCode::InvalidParams, message)),
+            },
+        };
+        match result {
+            Ok(Some(fields)) if fields.is_empty() => Ok(CallToolResult::new()
+                .with_text_content("The struct has no fields")
+                .is_error(false)),
+            Ok(Some(fields)) => Ok(CallToolResult::new()
+                .with_text_content(
+                    fields
+                        .iter()
+                        .map(|field| field.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )
+                .is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No struct at this position or path")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting struct fields", e)),
+        }
+    }
+
+    /// Get the type returned by the function or method call at a position
+    ///
+    /// Point at the name of the called function or method. The type is inferred
+    /// for this call, so generics are substituted: a `collect()` into a vector
+    /// returns `Vec<i32>` rather than `B`. In a method chain, point at the method
+    /// whose result you want, e.g. `map` in `iter().map(..).collect()`.
+    #[tool]
+    async fn get_call_return_type(
+        &self,
+        ctx: &ServerCtx,
+        cursor: CursorCoordinates,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_call_return_type(&cursor)
+            .await
+        {
+            Ok(Some(ty)) => Ok(CallToolResult::new().with_text_content(ty).is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No function or method call at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting call return type", e)),
+        }
+    }
+
+    /// Compare a function or trait signature with a previous version
+    ///
+    /// For API review and semver checks: reports added, removed and changed
+    /// parameters, return types, generics, qualifiers and trait items, each
+    /// flagged `[breaking]` when it can break existing callers or implementors.
+    /// Bodies, docs and formatting are ignored.
+    ///
+    /// - The baseline is either Rust source in `baseline`, or a ruskel target in
+    ///   `baseline_target` such as `mycrate@1.2.0::module::function`.
+    /// - The current version is the item at `file_path`, `line` and `column`, or
+    ///   the item named by the ruskel target in `target`.
+    #[tool]
+    async fn diff_signature(
+        &self,
+        ctx: &ServerCtx,
+        params: DiffSignatureParams,
+    ) -> Result<CallToolResult> {
+        let baseline = match (params.baseline, &params.baseline_target) {
+            (Some(baseline), None) => baseline,
+            (None, Some(target)) => match render_target_item(target) {
+                Ok(item) => item,
+                Err(e) => {
+                    return Ok(error_result(
+                        ErrorCode::SkeletonFailed,
+                        format!("Error rendering baseline {target}: {e}"),
+                    ));
+                }
+            },
+            _ => {
+                return Ok(error_result(
+                    ErrorCode::InvalidParams,
+                    "Pass exactly one of baseline and baseline_target".to_string(),
+                ));
+            }
+        };
+
+        let diff = match (params.target, params.file_path, params.line, params.column) {
+            (Some(target), None, None, None) => match render_target_item(&target) {
+                Ok(current) => RustAnalyzerUtils::diff_signatures(&baseline, &current).map(Some),
+                Err(e) => {
+                    return Ok(error_result(
+                        ErrorCode::SkeletonFailed,
+                        format!("Error rendering {target}: {e}"),
+                    ));
+                }
+            },
+            (None, Some(file_path), Some(line), Some(column)) => {
+                let cursor = CursorCoordinates {
+                    file_path,
+                    line,
+                    column,
+                    symbol: params.symbol,
+                };
+                if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+                    return Ok(error);
+                }
+                self.analyzer
+                    .lock()
+                    .await
+                    .as_mut()
+                    .unwrap()
+                    .diff_signature(&cursor, &baseline)
+                    .await
+            }
+            _ => {
+                return Ok(error_result(
+                    ErrorCode::InvalidParams,
+                    "Pass either target, or file_path, line and column".to_string(),
+                ));
+            }
+        };
+
+        match diff {
+            Ok(Some(diff)) => Ok(CallToolResult::new()
+                .with_text_content(diff.to_string())
+                .is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No definition found at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error comparing signatures", e)),
+        }
+    }
+
+    /// Get the cargo commands to run the tests, benchmarks or binary at a position
+    ///
+    /// Lists every runnable item enclosing the cursor - the test function, its test
+    /// module, a benchmark, a doctest or `main` - so you can run exactly the test you
+    /// are looking at. Test names include their module path, so runs are scoped.
+    ///
+    /// Returns one runnable per entry: its label and location, then the command as
+    /// "cd <package dir relative to the workspace> && cargo <args>".
+    #[tool]
+    async fn get_runnables(
+        &self,
+        ctx: &ServerCtx,
+        cursor: CursorCoordinates,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_runnables(&cursor)
+            .await
+        {
+            Ok(runnables) if runnables.is_empty() => Ok(CallToolResult::new()
+                .with_text_content("No runnables found at this position")
+                .is_error(false)),
+            Ok(runnables) => {
+                let result_text = runnables
+                    .iter()
+                    .map(|runnable| runnable.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Err(e) => Ok(analyzer_error("Error getting runnables", e)),
+        }
+    }
+
+    /// Get completion suggestions at a specific position in Rust code
+    ///
+    /// Provides intelligent code completion suggestions including available methods,
+    /// functions, variables, keywords, imports, and more based on the current context.
+    ///
+    /// Returns a list of completion suggestions with types and descriptions. With
+    /// `include_import_edits=true`, items that aren't imported yet are suggested
+    /// too, each with the line/column range and text of the `use` to insert.
+    /// `include_snippets=true` adds snippet bodies with tabstops for callers that
+    /// expand LSP-style snippets.
+    #[tool]
+    async fn get_completions(
+        &self,
+        ctx: &ServerCtx,
+        params: CompletionsParams,
+    ) -> Result<CallToolResult> {
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_completions_with_options(
+                &cursor,
+                &CompletionOptions {
+                    include_import_edits: params.include_import_edits,
+                    include_snippets: params.include_snippets,
+                    limit: params.limit,
+                    unsorted: params.unsorted,
+                    include_scores: params.include_scores,
+                    exclude_deprecated: params.exclude_deprecated,
+                    exclude_unstable: params.exclude_unstable,
+                    only_in_scope: params.only_in_scope,
+                },
+            )
+            .await
+        {
+            Ok(Some(completions)) => {
+                let result_text = completions
+                    .iter()
+                    .map(|comp| comp.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No completions found at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting completions", e)),
+        }
+    }
+
+    /// Rename a symbol across the workspace
+    ///
+    /// Performs intelligent, workspace-wide symbol renaming that preserves code
+    /// correctness and updates all references. Works with functions, types, variables,
+    /// modules, macros, and more.
+    ///
+    /// Returns a summary of all changes made with file paths and line numbers, or
+    /// explains why the rename is not possible. With `return_diff=true` a unified
+    /// diff of every changed file is included for review. With `preview=true`
+    /// each changed line is shown before and after the rename, which is easier
+    /// to read than the edit ranges.
+    #[tool]
+    async fn rename_symbol(&self, ctx: &ServerCtx, params: RenameParams) -> Result<CallToolResult> {
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .rename_symbol_with_options(
+                &cursor,
+                &params.new_name,
+                &ApplyOptions {
+                    return_diff: params.return_diff,
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            Ok(Some(rename_result)) => {
+                let result_text = if params.preview {
+                    let mut preview = rename_result.preview();
+                    for diff in rename_result
+                        .file_changes
+                        .iter()
+                        .filter_map(|change| change.diff.as_ref())
+                    {
+                        preview.push_str(&format!("\n{diff}"));
+                    }
+                    preview
+                } else {
+                    rename_result.to_string()
+                };
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("Symbol cannot be renamed at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error performing rename", e)),
+        }
+    }
+
+    /// Move an item to another module and update its references
+    ///
+    /// Relocates the function, type, trait, constant or static at the given
+    /// position into an existing module of the same crate, rewriting qualified
+    /// paths and imports across the workspace. Private items become
+    /// `pub(crate)` so existing users keep compiling.
+    ///
+    /// Returns all moved and edited files, plus warnings for references that
+    /// need a manual update. Fails if the target module doesn't exist.
+    #[tool]
+    async fn move_item(&self, ctx: &ServerCtx, params: MoveItemParams) -> Result<CallToolResult> {
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .move_item(&cursor, &params.target_module)
+            .await
+        {
+            Ok(move_result) => Ok(CallToolResult::new()
+                .with_text_content(move_result.to_string())
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error moving item", e)),
+        }
+    }
+
+    /// View a Rust file with inlay hints embedded
+    ///
+    /// Enhances code readability by displaying inline type annotations and other
+    /// helpful hints directly within the source code, including inferred types,
+    /// parameter names, return types, and implicit conversions.
+    ///
+    /// If start_line and end_line are provided, only the specified range of lines
+    /// will be returned with inlay hints. Both parameters are 1-based and inclusive.
+    /// If neither parameter is provided, the entire file is processed.
+    ///
+    /// Returns the source file content (full file or specified range) with inlay hints embedded as inline annotations.
+    ///
+    /// With `structured=true` the source is left untouched and a JSON list of
+    /// `{line, column, position, text, kind}` hints is returned instead, where
+    /// `position` is `before` or `after` the 1-based line and column.
+    #[tool]
+    async fn view_inlay_hints(
+        &self,
+        ctx: &ServerCtx,
+        params: ViewInlayHintsParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.file_path).await? {
+            return Ok(error);
+        }
+        if params.structured {
+            return match self
+                .analyzer
+                .lock()
+                .await
+                .as_mut()
+                .unwrap()
+                .get_inlay_hints(&params.file_path, params.start_line, params.end_line)
+                .await
+            {
+                Ok(hints) => Ok(CallToolResult::new()
+                    .with_text_content(
+                        serde_json::to_string_pretty(&hints).unwrap_or_else(|e| e.to_string()),
+                    )
+                    .is_error(false)),
+                Err(e) => Ok(analyzer_error("Error getting inlay hints", e)),
+            };
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .view_inlay_hints(&params.file_path, params.start_line, params.end_line)
+            .await
+        {
+            Ok(annotated_content) => Ok(CallToolResult::new()
+                .with_text_content(annotated_content)
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error viewing inlay hints", e)),
+        }
+    }
+
+    /// Find all references to a symbol at a specific position in Rust code
+    ///
+    /// Searches for all references to a symbol (function, variable, type, etc.)
+    /// throughout the workspace, including both the definition and all usage sites.
+    /// Essential for understanding code dependencies and refactoring operations.
+    ///
+    /// Returns a list of reference locations with file paths, line numbers, and
+    /// contextual information, or indicates if no references are found. Set
+    /// `context_lines` to include surrounding source lines with each reference,
+    /// and `scope` to `file` or `crate` to avoid scanning the whole workspace.
+    #[tool]
+    async fn find_references(
+        &self,
+        ctx: &ServerCtx,
+        params: FindReferencesParams,
+    ) -> Result<CallToolResult> {
+        let scope = match params.scope.as_deref().map(str::parse::<ReferenceScope>) {
+            Some(Ok(scope)) => scope,
+            Some(Err(e)) => {
+                return Ok(error_result(ErrorCode::InvalidParams, e.to_string()));
+            }
+            None => ReferenceScope::default(),
+        };
+        let options = ReferenceOptions {
+            context_lines: params.context_lines,
+            scope,
+        };
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .find_references_with_options(&cursor, &options)
+            .await
+        {
+            Ok(Some(references)) => {
+                let result_text = references
+                    .iter()
+                    .map(|ref_info| ref_info.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No references found at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error finding references", e)),
+        }
+    }
+
+    /// Count the references to a symbol, e.g. to check whether it is dead code
+    ///
+    /// Cheaper than find_references, as only the number of references is
+    /// computed. The definition itself isn't counted. Pass `stop_at_first=true`
+    /// to stop searching at the first reference when you only need to know
+    /// whether the symbol is used.
+    ///
+    /// Returns e.g. `name: 3 reference(s)` or `name: unused`.
+    #[tool]
+    async fn count_references(
+        &self,
+        ctx: &ServerCtx,
+        params: CountReferencesParams,
+    ) -> Result<CallToolResult> {
+        let scope = match params.scope.as_deref().map(str::parse::<ReferenceScope>) {
+            Some(Ok(scope)) => scope,
+            Some(Err(e)) => {
+                return Ok(error_result(ErrorCode::InvalidParams, e.to_string()));
+            }
+            None => ReferenceScope::default(),
+        };
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .count_references(&cursor, scope, params.stop_at_first)
+            .await
+        {
+            Ok(Some(count)) => Ok(CallToolResult::new()
+                .with_text_content(count.to_string())
+                .is_error(false)),
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No symbol found at this position")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error counting references", e)),
+        }
+    }
+
+    /// Get semantic highlight tokens for a Rust source file
+    ///
+    /// Classifies every identifier, keyword and literal the way rust-analyzer's
+    /// semantic highlighting does, so identifiers can be told apart (function,
+    /// method, struct, trait, local, parameter, macro, ...) without re-parsing.
+    ///
+    /// Returns one token per line as "line:column-end_line:end_column tag [modifiers] text",
+    /// where modifiers include e.g. declaration, mutable, unsafe, public or library.
+    #[tool]
+    async fn get_semantic_tokens(
+        &self,
+        ctx: &ServerCtx,
+        params: FileParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_semantic_tokens(&params.file_path)
+            .await
+        {
+            Ok(tokens) => {
+                let result_text = tokens
+                    .iter()
+                    .map(|token| token.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Err(e) => Ok(analyzer_error("Error getting semantic tokens", e)),
+        }
+    }
+
+    /// Get the syntax tree of a Rust source file
+    ///
+    /// Shows the file as rust-analyzer parses it, like its "Show Syntax Tree"
+    /// command: one node or token per line, indented by depth, with its kind,
+    /// text range and the text of tokens, e.g. `IDENT@3..7 "main"`. Useful to
+    /// debug parse errors or inspect the structure of code.
+    ///
+    /// With start_line and/or end_line (1-based, inclusive), only the smallest
+    /// node covering those lines is returned, to keep large files manageable.
+    #[tool]
+    async fn get_syntax_tree(
+        &self,
+        ctx: &ServerCtx,
+        params: SyntaxTreeParams,
+    ) -> Result<CallToolResult> {
+        let range = match (params.start_line, params.end_line) {
+            (None, None) => None,
+            (start, end) => Some((start.unwrap_or(1), end.unwrap_or(u32::MAX))),
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &params.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_syntax_tree(&params.file_path, range)
+            .await
+        {
+            Ok(tree) => Ok(CallToolResult::new()
+                .with_text_content(tree)
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting syntax tree", e)),
+        }
+    }
+
+    /// Get the edits rust-analyzer makes after a character is typed
+    ///
+    /// Mirrors an editor's on-type formatting: after writing `.`, `=`, `<`,
+    /// `>`, `{`, `(` or `|` to the file, point the cursor at that character to
+    /// get the edits keeping the code tidy, e.g. indenting a `.` that continues
+    /// a method chain or adding the `;` after a `let` binding.
+    ///
+    /// Returns the edits as "line:column-end_line:end_column" ranges with their
+    /// new text. Nothing is written to disk.
+    #[tool]
+    async fn on_type_format(
+        &self,
+        ctx: &ServerCtx,
+        params: OnTypeFormatParams,
+    ) -> Result<CallToolResult> {
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .on_type_format(&cursor, params.typed_char)
+            .await
+        {
+            Ok(Some(edits)) => {
+                let result_text = edits
+                    .iter()
+                    .map(|edit| edit.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Ok(None) => Ok(CallToolResult::new()
+                .with_text_content("No formatting edits for this character")
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error formatting on type", e)),
+        }
+    }
+
+    /// Merge and sort the imports at the top of a Rust source file
+    ///
+    /// Merges imports from the same crate into one `use` and sorts them into
+    /// std, external crate and local groups, like rust-analyzer does when it adds
+    /// an import. With `remove_unused=true` unused imports are dropped first.
+    ///
+    /// Returns the rewritten file content. The file is only modified on disk
+    /// with `write=true`.
+    #[tool]
+    async fn organize_imports(
+        &self,
+        ctx: &ServerCtx,
+        params: OrganizeImportsParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.file_path).await? {
+            return Ok(error);
+        }
+        let (file_path, result) = {
+            let mut analyzer_guard = self.analyzer.lock().await;
+            let analyzer = analyzer_guard.as_mut().unwrap();
+            let file_path = analyzer.resolve_path(&params.file_path);
+            let result = analyzer
+                .organize_imports_with_options(&file_path, params.remove_unused)
+                .await;
+            (file_path, result)
+        };
+        let result = match result {
+            Ok(content) if params.write => tokio::fs::write(&file_path, &content)
+                .await
+                .map(|()| content)
+                .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", params.file_path, e)),
+            result => result,
+        };
+
+        match result {
+            Ok(content) => Ok(CallToolResult::new()
+                .with_text_content(content)
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error organizing imports", e)),
+        }
+    }
+
+    /// Get all current errors and warnings across the whole workspace
+    ///
+    /// Aggregates rust-analyzer diagnostics for every source file of the loaded
+    /// workspace, which is useful to triage a codebase. This reuses the already
+    /// primed analysis instead of running `cargo check`, so only diagnostics computed
+    /// natively by rust-analyzer are reported.
+    ///
+    /// Returns a summary count header followed by one diagnostic per line, sorted
+    /// by severity and then file.
+    #[tool]
+    async fn get_workspace_diagnostics(
+        &self,
+        ctx: &ServerCtx,
+        params: WorkspaceParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.workspace_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_workspace_diagnostics()
+            .await
+        {
+            Ok(diagnostics) => {
+                let mut result_text = format!(
+                    "Found {} diagnostic(s): {}",
+                    diagnostics.len(),
+                    RustAnalyzerUtils::diagnostics_summary(&diagnostics)
+                );
+                for diagnostic in &diagnostics {
+                    result_text.push('\n');
+                    result_text.push_str(&diagnostic.to_string());
+                }
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Err(e) => Ok(analyzer_error("Error getting workspace diagnostics", e)),
+        }
+    }
+
+    /// Find unused imports, variables, functions, types and constants
+    ///
+    /// Checks a single file, or every workspace file under a directory. Unused
+    /// imports and variables come from rust-analyzer and are marked fixable,
+    /// e.g. with the `remove_unused_imports` assist. Items are reported when no
+    /// reference to them is found outside of their own definition; unused `pub`
+    /// items are flagged separately as they may be intentional public API.
+    /// `main`, tests and `pub use` re-exports are never reported.
+    ///
+    /// Returns a count header followed by one item per line with its location.
+    #[tool]
+    async fn find_unused_items(
+        &self,
+        ctx: &ServerCtx,
+        params: UnusedItemsParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .find_unused_items(&params.path)
+            .await
+        {
+            Ok(items) => {
+                let public = items.iter().filter(|item| item.is_pub).count();
+                let mut result_text = format!(
+                    "Found {} unused item(s), {} of them public",
+                    items.len(),
+                    public
+                );
+                for item in &items {
+                    result_text.push('\n');
+                    result_text.push_str(&item.to_string());
+                }
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Err(e) => Ok(analyzer_error("Error finding unused items", e)),
+        }
+    }
+
+    /// Check whether the analyzer is ready for queries
+    ///
+    /// The workspace is loaded by the first tool call with a file or workspace
+    /// path, which can take a while on big workspaces. Poll this cheap tool to
+    /// decide when to start heavy queries; it never waits for a load to finish.
+    ///
+    /// Returns whether a workspace is loaded, its root, file counts and whether
+    /// cache priming completed, or that a load or query is in progress.
+    #[tool]
+    async fn status(&self, _ctx: &ServerCtx, _params: StatusParams) -> Result<CallToolResult> {
+        let text = match self.analyzer.try_lock() {
+            Ok(analyzer) => match analyzer.as_ref() {
+                Some(analyzer) => analyzer.status().to_string(),
+                None => "Workspace loaded: no. The first tool call with a file_path or \
+                         workspace_path loads its workspace"
+                    .to_string(),
+            },
+            Err(_) => {
+                "Busy: a workspace is loading or a query is running, retry shortly".to_string()
+            }
+        };
+        Ok(CallToolResult::new()
+            .with_text_content(text)
+            .is_error(false))
+    }
+
+    /// Report the memory used by the loaded workspace
+    ///
+    /// Memory grows over a long session as query results are cached. Use this to
+    /// decide whether to reload the workspace, e.g. with unload_workspace.
+    ///
+    /// Returns the memory allocated by the server process and the number of files
+    /// of the workspace and loaded overall. The number of cached queries isn't
+    /// reported, as the rust-analyzer version used (ra_ap 0.0.304) doesn't expose it.
+    #[tool]
+    async fn memory_usage(
+        &self,
+        _ctx: &ServerCtx,
+        _params: MemoryUsageParams,
+    ) -> Result<CallToolResult> {
+        let mut analyzer_guard = self.analyzer.lock().await;
+        let Some(analyzer) = analyzer_guard.as_mut() else {
+            return Ok(CallToolResult::new()
+                .with_text_content(
+                    "Workspace loaded: no. The first tool call with a file_path or \
+                     workspace_path loads its workspace",
+                )
+                .is_error(false));
+        };
+        match analyzer.get_memory_usage().await {
+            Ok(usage) => Ok(CallToolResult::new()
+                .with_text_content(usage.to_string())
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting memory usage", e)),
+        }
+    }
+
+    /// Unload the current workspace, freeing its memory
+    ///
+    /// Only one workspace is loaded at a time. Call this before working on a
+    /// different workspace, or to release memory without restarting the server.
+    /// The next tool call with a file_path or workspace_path loads its workspace.
+    #[tool]
+    async fn unload_workspace(
+        &self,
+        _ctx: &ServerCtx,
+        _params: UnloadWorkspaceParams,
+    ) -> Result<CallToolResult> {
+        let analyzer = self.analyzer.lock().await.take();
+        let text = match analyzer.map(|mut analyzer| analyzer.unload_workspace()) {
+            Some(root) => format!(
+                "Unloaded workspace {}. The next tool call with a file_path or \
+                 workspace_path loads its workspace",
+                root.as_deref().unwrap_or("<unknown>")
+            ),
+            None => "No workspace is loaded".to_string(),
+        };
+        Ok(CallToolResult::new()
+            .with_text_content(text)
+            .is_error(false))
+    }
+
+    /// Get the version and build details of this rustbelt server
+    ///
+    /// Include the output when reporting a bug, so it can be reproduced against
+    /// the same build.
+    ///
+    /// Returns the rustbelt version with its git sha and build date, the version
+    /// of the rust-analyzer crates compiled in, enabled features and build profile.
+    #[tool]
+    async fn server_info(
+        &self,
+        _ctx: &ServerCtx,
+        _params: ServerInfoParams,
+    ) -> Result<CallToolResult> {
+        Ok(CallToolResult::new()
+            .with_text_content(build_info())
+            .is_error(false))
+    }
+
+    /// Get the resolved version, edition and enabled features of a crate
+    ///
+    /// A lightweight alternative to ruskel for questions like "which version of
+    /// serde am I actually using", read from the loaded crate graph rather than
+    /// `Cargo.lock`. Works for workspace members and dependencies alike.
+    ///
+    /// Returns one entry per matching crate, workspace members first, as a
+    /// dependency can be resolved to several versions.
+    #[tool]
+    async fn get_crate_info(
+        &self,
+        ctx: &ServerCtx,
+        params: CrateInfoParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.workspace_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_crate_info(&params.crate_name)
+            .await
+        {
+            Ok(crates) => Ok(CallToolResult::new()
+                .with_text_content(
+                    crates
+                        .iter()
+                        .map(|krate| format!("{krate:#}"))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting crate info", e)),
+        }
+    }
+
+    /// Get the Rust edition of the crate containing a file
+    ///
+    /// Crates of a workspace can use different editions, which gate syntax and
+    /// features and change name resolution. Check it before writing code that
+    /// depends on the edition, e.g. `async` closures or `gen` blocks.
+    ///
+    /// Returns the edition, e.g. "2021".
+    #[tool]
+    async fn get_file_edition(
+        &self,
+        ctx: &ServerCtx,
+        params: FileParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.file_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .get_file_edition(&params.file_path)
+            .await
+        {
+            Ok(edition) => Ok(CallToolResult::new()
+                .with_text_content(edition)
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting file edition", e)),
+        }
+    }
+
+    /// Find every impl of a trait in the workspace, given the trait's name
+    ///
+    /// Unlike cursor-based tools, this only needs the trait name, optionally
+    /// qualified to disambiguate (e.g. "fmt::Display"). Traits from dependencies
+    /// are found too, but only impls declared in the workspace are listed.
+    ///
+    /// Returns each matching trait with its module path and location, followed by
+    /// its impl blocks. Several traits are returned when the name is ambiguous.
+    #[tool]
+    async fn find_trait_impls(
+        &self,
+        ctx: &ServerCtx,
+        params: TraitImplsParams,
+    ) -> Result<CallToolResult> {
+        if let Some(error) = self.ensure_analyzer(ctx, &params.workspace_path).await? {
+            return Ok(error);
+        }
+        match self
+            .analyzer
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .find_trait_impls(&params.trait_name)
+            .await
+        {
+            Ok(traits) if traits.is_empty() => Ok(CallToolResult::new()
+                .with_text_content(format!("No trait named '{}' found", params.trait_name))
                 .is_error(false)),
-            Err(e) => Ok(CallToolResult::new()
-                .with_text_content(format!("Error generating skeleton: {e}"))
-                .is_error(true)),
+            Ok(traits) => Ok(CallToolResult::new()
+                .with_text_content(
+                    traits
+                        .iter()
+                        .map(|trait_impls| trait_impls.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error finding trait impls", e)),
         }
     }
 
-    /// Get type information for a symbol at a specific position in Rust code
+    /// Search the whole workspace for symbols (types, functions, constants, ...) by name
     ///
-    /// Provides detailed type information including variable types, function signatures,
-    /// struct/enum definitions, and generic parameters. Use this when you need to understand
-    /// the type of a symbol for code analysis, refactoring, or generating type-aware code.
+    /// Useful to locate an item without knowing which file declares it. The query
+    /// is matched case-insensitively as a substring by default; `mode` switches to
+    /// "prefix", "fuzzy" (characters in order, e.g. "cntadl" for "count_adults") or
+    /// "regex". Set `kind` to narrow the results to one symbol kind.
     ///
-    /// Returns human-readable type information or indicates if no type data is available.
+    /// Results are sorted by file then line and paginated with `offset` and
+    /// `limit`. Returns a header with the total match count, followed by one
+    /// symbol per line with its kind, container and location.
     #[tool]
-    async fn get_type_hint(
+    async fn get_workspace_symbols(
         &self,
-        _ctx: &ServerCtx,
-        cursor: CursorCoordinates,
+        ctx: &ServerCtx,
+        params: WorkspaceSymbolsParams,
     ) -> Result<CallToolResult> {
-        self.ensure_analyzer(&cursor.file_path).await?;
+        let mode = match params.mode.as_deref().map(str::parse::<SymbolMatchMode>) {
+            Some(Ok(mode)) => mode,
+            Some(Err(e)) => {
+                return Ok(error_result(ErrorCode::InvalidParams, e.to_string()));
+            }
+            None => SymbolMatchMode::default(),
+        };
+        let options = WorkspaceSymbolsOptions {
+            mode,
+            kind: params.kind,
+            offset: params.offset,
+            limit: Some(params.limit.unwrap_or(DEFAULT_SYMBOL_LIMIT)),
+        };
+
+        if let Some(error) = self.ensure_analyzer(ctx, &params.workspace_path).await? {
+            return Ok(error);
+        }
         match self
             .analyzer
             .lock()
             .await
             .as_mut()
             .unwrap()
-            .get_type_hint(&cursor)
+            .get_workspace_symbols(&params.query, &options)
             .await
         {
-            Ok(Some(type_info)) => Ok(CallToolResult::new()
-                .with_text_content(type_info.to_string())
-                .is_error(false)),
-            Ok(None) => Ok(CallToolResult::new()
-                .with_text_content("No type information available at this position")
+            Ok(page) if page.total == 0 => Ok(CallToolResult::new()
+                .with_text_content(format!("No symbols matching '{}'", params.query))
                 .is_error(false)),
-            Err(e) => Ok(CallToolResult::new()
-                .with_text_content(format!("Error getting type hint: {e}"))
-                .is_error(true)),
+            Ok(page) => {
+                let mut result_text = format!("{}:", page.header());
+                for symbol in &page.symbols {
+                    result_text.push('\n');
+                    result_text.push_str(&symbol.to_string());
+                }
+
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Err(e) => Ok(analyzer_error("Error searching workspace symbols", e)),
         }
     }
 
-    /// Get definition location for a symbol at a specific position in Rust code
+    /// Get available code assists (code actions) at a specific position in Rust code
     ///
-    /// Finds where symbols are defined - functions, types, variables, modules, macros,
-    /// and more. Essential for code navigation and understanding symbol relationships.
+    /// Returns available assists like "extract function", "merge imports", "add missing impl", etc.
+    /// These are context-sensitive refactoring and code transformation options that rust-analyzer
+    /// can apply to improve or modify your code.
     ///
-    /// Returns definition locations as "file_path:line_number:column_number" format,
-    /// or indicates if no definitions are found.
+    /// Returns a list of available assists with their IDs, descriptions, and target ranges.
     #[tool]
-    async fn get_definition(
+    async fn get_assists(
         &self,
-        _ctx: &ServerCtx,
+        ctx: &ServerCtx,
         cursor: CursorCoordinates,
     ) -> Result<CallToolResult> {
-        self.ensure_analyzer(&cursor.file_path).await?;
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
         match self
             .analyzer
             .lock()
             .await
             .as_mut()
             .unwrap()
-            .get_definition(&cursor)
+            .get_assists(&cursor)
             .await
         {
-            Ok(Some(definitions)) => {
-                let result_text = definitions
+            Ok(Some(assists)) => {
+                let result_text = assists
                     .iter()
-                    .map(|def| def.to_string())
+                    .map(|assist| assist.to_string())
                     .collect::<Vec<_>>()
                     .join("\n");
 
@@ -239,69 +2688,83 @@ impl Rustbelt {
                     .is_error(false))
             }
             Ok(None) => Ok(CallToolResult::new()
-                .with_text_content("No definitions found at this position")
+                .with_text_content("No assists available at this position")
                 .is_error(false)),
-            Err(e) => Ok(CallToolResult::new()
-                .with_text_content(format!("Error getting definitions: {e}"))
-                .is_error(true)),
+            Err(e) => Ok(analyzer_error("Error getting assists", e)),
         }
     }
 
-    /// Get completion suggestions at a specific position in Rust code
+    /// Get the code assists (code actions) available across a whole Rust file
     ///
-    /// Provides intelligent code completion suggestions including available methods,
-    /// functions, variables, keywords, imports, and more based on the current context.
+    /// Like get_assists at every position of the file at once, including the quick
+    /// fixes of diagnostics, for a bulk cleanup or to see every refactor a file
+    /// offers. With start_line and/or end_line (1-based, inclusive), only positions
+    /// in those lines are asked. The number of assists is capped, and a truncated
+    /// result says so.
     ///
-    /// Returns a list of completion suggestions with types and descriptions.
+    /// Returns assists grouped under their "line:column" position. An assist is listed
+    /// once, at the first position offering it; pass that position to apply_assist.
     #[tool]
-    async fn get_completions(
+    async fn get_file_assists(
         &self,
-        _ctx: &ServerCtx,
-        cursor: CursorCoordinates,
+        ctx: &ServerCtx,
+        params: FileAssistsParams,
     ) -> Result<CallToolResult> {
-        self.ensure_analyzer(&cursor.file_path).await?;
+        let range = match (params.start_line, params.end_line) {
+            (None, None) => None,
+            (start, end) => Some((start.unwrap_or(1), end.unwrap_or(u32::MAX))),
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &params.file_path).await? {
+            return Ok(error);
+        }
         match self
             .analyzer
             .lock()
             .await
             .as_mut()
             .unwrap()
-            .get_completions(&cursor)
+            .get_file_assists(&params.file_path, range)
             .await
         {
-            Ok(Some(completions)) => {
-                let result_text = completions
+            Ok(file_assists) if file_assists.positions.is_empty() => Ok(CallToolResult::new()
+                .with_text_content("No assists available in this file")
+                .is_error(false)),
+            Ok(file_assists) => {
+                let mut text = file_assists
+                    .positions
                     .iter()
-                    .map(|comp| comp.to_string())
+                    .map(|position| position.to_string())
                     .collect::<Vec<_>>()
                     .join("\n");
-
+                if file_assists.truncated {
+                    text.push_str(
+                        "\n\nTruncated: more assists are available, pass start_line and \
+                         end_line to see them",
+                    );
+                }
                 Ok(CallToolResult::new()
-                    .with_text_content(result_text)
+                    .with_text_content(text)
                     .is_error(false))
             }
-            Ok(None) => Ok(CallToolResult::new()
-                .with_text_content("No completions found at this position")
-                .is_error(false)),
-            Err(e) => Ok(CallToolResult::new()
-                .with_text_content(format!("Error getting completions: {e}"))
-                .is_error(true)),
+            Err(e) => Ok(analyzer_error("Error getting file assists", e)),
         }
     }
 
-    /// Rename a symbol across the workspace
+    /// Apply a specific code assist (code action) at a position in Rust code
     ///
-    /// Performs intelligent, workspace-wide symbol renaming that preserves code
-    /// correctness and updates all references. Works with functions, types, variables,
-    /// modules, macros, and more.
+    /// Takes an assist ID (from get_assists) and applies the corresponding code transformation
+    /// to your source files. This will modify files on disk with the changes suggested by
+    /// the assist.
     ///
-    /// Returns a summary of all changes made with file paths and line numbers, or
-    /// explains why the rename is not possible.
+    /// Common assists include "merge_imports", "extract_function", "add_missing_impl", etc.
+    /// Returns a summary of the changes made to files, with a unified diff of every
+    /// changed file if `return_diff=true`. With `dry_run=true` nothing is written to
+    /// disk, so the changes can be previewed before applying them.
     #[tool]
-    async fn rename_symbol(
+    async fn apply_assist(
         &self,
-        _ctx: &ServerCtx,
-        params: RenameParams,
+        ctx: &ServerCtx,
+        params: ApplyAssistParams,
     ) -> Result<CallToolResult> {
         let cursor = CursorCoordinates {
             file_path: params.file_path,
@@ -309,217 +2772,484 @@ impl Rustbelt {
             column: params.column,
             symbol: params.symbol,
         };
-        self.ensure_analyzer(&cursor.file_path).await?;
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
         match self
             .analyzer
             .lock()
             .await
             .as_mut()
             .unwrap()
-            .rename_symbol(&cursor, &params.new_name)
+            .apply_assist_with_options(
+                &cursor,
+                &params.assist_id,
+                &ApplyOptions {
+                    return_diff: params.return_diff,
+                    dry_run: params.dry_run,
+                },
+            )
             .await
         {
-            Ok(Some(rename_result)) => {
-                let result_text = rename_result.to_string();
+            Ok(Some(source_change)) => {
+                let result_text = if params.dry_run {
+                    format!("Dry run, no files were modified. {source_change}")
+                } else {
+                    source_change.to_string()
+                };
 
                 Ok(CallToolResult::new()
                     .with_text_content(result_text)
                     .is_error(false))
             }
             Ok(None) => Ok(CallToolResult::new()
-                .with_text_content("Symbol cannot be renamed at this position")
+                .with_text_content(format!(
+                    "Assist '{}' not available at this position",
+                    params.assist_id
+                ))
                 .is_error(false)),
-            Err(e) => Ok(CallToolResult::new()
-                .with_text_content(format!("Error performing rename: {e}"))
-                .is_error(true)),
+            Err(e) => Ok(analyzer_error("Error applying assist", e)),
         }
     }
 
-    /// View a Rust file with inlay hints embedded
-    ///
-    /// Enhances code readability by displaying inline type annotations and other
-    /// helpful hints directly within the source code, including inferred types,
-    /// parameter names, return types, and implicit conversions.
+    /// Apply text edits computed elsewhere to a file, atomically
     ///
-    /// If start_line and end_line are provided, only the specified range of lines
-    /// will be returned with inlay hints. Both parameters are 1-based and inclusive.
-    /// If neither parameter is provided, the entire file is processed.
+    /// Safer than string slicing: positions are converted with a UTF-8 aware line
+    /// index, and every edit is validated before anything is written, so a
+    /// position past the end of a line or file, inside a multi-byte character or
+    /// an overlapping pair of edits fails the whole call with a clear error.
     ///
-    /// Returns the source file content (full file or specified range) with inlay hints embedded as inline annotations.
+    /// Returns the applied edits, with a unified diff when `return_diff=true`.
     #[tool]
-    async fn view_inlay_hints(
+    async fn apply_edits(
         &self,
         _ctx: &ServerCtx,
-        params: ViewInlayHintsParams,
+        params: ApplyEditsParams,
     ) -> Result<CallToolResult> {
-        self.ensure_analyzer(&params.file_path).await?;
-        match self
-            .analyzer
-            .lock()
-            .await
-            .as_mut()
-            .unwrap()
-            .view_inlay_hints(&params.file_path, params.start_line, params.end_line)
-            .await
-        {
-            Ok(annotated_content) => Ok(CallToolResult::new()
-                .with_text_content(annotated_content)
-                .is_error(false)),
-            Err(e) => Ok(CallToolResult::new()
-                .with_text_content(format!("Error viewing inlay hints: {e}"))
-                .is_error(true)),
+        let file_path = match self.analyzer.lock().await.as_ref() {
+            Some(analyzer) => analyzer.resolve_path(&params.file_path),
+            None => params.file_path,
+        };
+        let mut file_changes = [FileChange {
+            file_path,
+            edits: params.edits,
+            diff: None,
+        }];
+        let options = ApplyOptions {
+            return_diff: params.return_diff,
+            dry_run: params.dry_run,
+        };
+        match RustAnalyzerUtils::apply_file_changes(&mut file_changes, &options).await {
+            Ok(()) => {
+                let [file_change] = file_changes;
+                let result_text = if params.dry_run {
+                    format!("Dry run, no files were modified. {file_change}")
+                } else {
+                    format!(
+                        "Applied {} edit(s) to {file_change}",
+                        file_change.edits.len()
+                    )
+                };
+                Ok(CallToolResult::new()
+                    .with_text_content(result_text)
+                    .is_error(false))
+            }
+            Err(e) => Ok(analyzer_error("Error applying edits", e)),
         }
     }
 
-    /// Find all references to a symbol at a specific position in Rust code
+    /// Suggest `use` imports for an unresolved name
     ///
-    /// Searches for all references to a symbol (function, variable, type, etc.)
-    /// throughout the workspace, including both the definition and all usage sites.
-    /// Essential for understanding code dependencies and refactoring operations.
+    /// Given a position on an identifier that doesn't resolve, such as `HashMap`
+    /// without an import, lists the paths it could be imported from, best
+    /// match first. Pass one of them to apply_import to add the `use`.
     ///
-    /// Returns a list of reference locations with file paths, line numbers, and
-    /// contextual information, or indicates if no references are found.
+    /// Returns one import path per line, or a message if there are none.
     #[tool]
-    async fn find_references(
+    async fn get_import_suggestions(
         &self,
-        _ctx: &ServerCtx,
+        ctx: &ServerCtx,
         cursor: CursorCoordinates,
     ) -> Result<CallToolResult> {
-        self.ensure_analyzer(&cursor.file_path).await?;
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
         match self
             .analyzer
             .lock()
             .await
             .as_mut()
             .unwrap()
-            .find_references(&cursor)
+            .get_import_suggestions(&cursor)
             .await
         {
-            Ok(Some(references)) => {
-                let result_text = references
-                    .iter()
-                    .map(|ref_info| ref_info.to_string())
-                    .collect::<Vec<_>>()
-                    .join("\n");
-
-                Ok(CallToolResult::new()
-                    .with_text_content(result_text)
-                    .is_error(false))
-            }
-            Ok(None) => Ok(CallToolResult::new()
-                .with_text_content("No references found at this position")
+            Ok(suggestions) if suggestions.is_empty() => Ok(CallToolResult::new()
+                .with_text_content("No import suggestions at this position")
                 .is_error(false)),
-            Err(e) => Ok(CallToolResult::new()
-                .with_text_content(format!("Error finding references: {e}"))
-                .is_error(true)),
+            Ok(suggestions) => Ok(CallToolResult::new()
+                .with_text_content(suggestions.join("\n"))
+                .is_error(false)),
+            Err(e) => Ok(analyzer_error("Error getting import suggestions", e)),
         }
     }
 
-    /// Get available code assists (code actions) at a specific position in Rust code
+    /// Add a `use` import for an unresolved name
     ///
-    /// Returns available assists like "extract function", "merge imports", "add missing impl", etc.
-    /// These are context-sensitive refactoring and code transformation options that rust-analyzer
-    /// can apply to improve or modify your code.
+    /// Inserts the import of import_path, one of the paths returned by
+    /// get_import_suggestions for the same position, merging it with the existing
+    /// imports. This will modify files on disk.
     ///
-    /// Returns a list of available assists with their IDs, descriptions, and target ranges.
+    /// Returns a summary of the changes made to files.
     #[tool]
-    async fn get_assists(
+    async fn apply_import(
         &self,
-        _ctx: &ServerCtx,
-        cursor: CursorCoordinates,
+        ctx: &ServerCtx,
+        params: ApplyImportParams,
     ) -> Result<CallToolResult> {
-        self.ensure_analyzer(&cursor.file_path).await?;
+        let cursor = CursorCoordinates {
+            file_path: params.file_path,
+            line: params.line,
+            column: params.column,
+            symbol: params.symbol,
+        };
+        if let Some(error) = self.ensure_analyzer(ctx, &cursor.file_path).await? {
+            return Ok(error);
+        }
         match self
             .analyzer
             .lock()
             .await
             .as_mut()
             .unwrap()
-            .get_assists(&cursor)
+            .apply_import(&cursor, &params.import_path)
             .await
         {
-            Ok(Some(assists)) => {
-                let result_text = assists
-                    .iter()
-                    .map(|assist| assist.to_string())
-                    .collect::<Vec<_>>()
-                    .join("\n");
-
-                Ok(CallToolResult::new()
-                    .with_text_content(result_text)
-                    .is_error(false))
-            }
+            Ok(Some(source_change)) => Ok(CallToolResult::new()
+                .with_text_content(source_change.to_string())
+                .is_error(false)),
             Ok(None) => Ok(CallToolResult::new()
-                .with_text_content("No assists available at this position")
+                .with_text_content(format!(
+                    "'{}' is not an import suggestion at this position",
+                    params.import_path
+                ))
                 .is_error(false)),
-            Err(e) => Ok(CallToolResult::new()
-                .with_text_content(format!("Error getting assists: {e}"))
-                .is_error(true)),
+            Err(e) => Ok(analyzer_error("Error applying import", e)),
         }
     }
 
-    /// Apply a specific code assist (code action) at a position in Rust code
+    /// Extract a range of statements into a new function
     ///
-    /// Takes an assist ID (from get_assists) and applies the corresponding code transformation
-    /// to your source files. This will modify files on disk with the changes suggested by
-    /// the assist.
+    /// Moves the statements on lines start_line to end_line (1-based, inclusive)
+    /// into a new function named new_name, passing the variables they use as
+    /// parameters and returning the ones used afterwards. The lines must cover
+    /// complete statements of a single block. This will modify files on disk.
     ///
-    /// Common assists include "merge_imports", "extract_function", "add_missing_impl", etc.
     /// Returns a summary of the changes made to files.
     #[tool]
-    async fn apply_assist(
+    async fn extract_function(
         &self,
-        _ctx: &ServerCtx,
-        params: ApplyAssistParams,
+        ctx: &ServerCtx,
+        params: ExtractFunctionParams,
     ) -> Result<CallToolResult> {
-        let cursor = CursorCoordinates {
-            file_path: params.file_path,
-            line: params.line,
-            column: params.column,
-            symbol: params.symbol,
-        };
-        self.ensure_analyzer(&cursor.file_path).await?;
+        if let Some(error) = self.ensure_analyzer(ctx, &params.file_path).await? {
+            return Ok(error);
+        }
         match self
             .analyzer
             .lock()
             .await
             .as_mut()
             .unwrap()
-            .apply_assist(&cursor, &params.assist_id)
+            .extract_function(
+                &params.file_path,
+                params.start_line,
+                params.end_line,
+                &params.new_name,
+            )
             .await
         {
-            Ok(Some(source_change)) => {
-                let result_text = source_change.to_string();
-
-                Ok(CallToolResult::new()
-                    .with_text_content(result_text)
-                    .is_error(false))
-            }
+            Ok(Some(source_change)) => Ok(CallToolResult::new()
+                .with_text_content(source_change.to_string())
+                .is_error(false)),
             Ok(None) => Ok(CallToolResult::new()
                 .with_text_content(format!(
-                    "Assist '{}' not available at this position",
-                    params.assist_id
+                    "Lines {}-{} cannot be extracted into a function",
+                    params.start_line, params.end_line
                 ))
                 .is_error(false)),
-            Err(e) => Ok(CallToolResult::new()
-                .with_text_content(format!("Error applying assist: {e}"))
-                .is_error(true)),
+            Err(e) => Ok(analyzer_error("Error extracting function", e)),
+        }
+    }
+
+    /// Run several tool calls in one request against the same loaded workspace
+    ///
+    /// Agents often need a type hint, definition and references for the same
+    /// position. Batching them saves a protocol round trip per call. Operations run
+    /// in order and a failing operation doesn't abort the rest.
+    ///
+    /// Returns, for every operation in order, a status line "[N] tool: ok" or
+    /// "[N] tool: error" followed by that tool's output.
+    #[tool]
+    async fn batch(&self, ctx: &ServerCtx, params: BatchParams) -> Result<CallToolResult> {
+        let mut result = CallToolResult::new().is_error(false);
+        for (index, operation) in params.operations.into_iter().enumerate() {
+            let operation: BatchOperation = match serde_json::from_value(operation) {
+                Ok(operation) => operation,
+                Err(e) => {
+                    result = result.with_text_content(format!(
+                        "[{}] error: Invalid batch operation: {e}",
+                        index + 1
+                    ));
+                    continue;
+                }
+            };
+
+            let tool = operation.tool.clone();
+            let (status, content) = match self.run_batch_operation(ctx, operation).await {
+                Ok(output) if output.is_error.unwrap_or(false) => ("error", output.content),
+                Ok(output) => ("ok", output.content),
+                Err(e) => (
+                    "error",
+                    CallToolResult::new()
+                        .with_text_content(e.to_string())
+                        .content,
+                ),
+            };
+            result = result.with_text_content(format!("[{}] {tool}: {status}", index + 1));
+            result.content.extend(content);
+        }
+        Ok(result)
+    }
+}
+
+/// URI of the resource indexing the public items of the loaded workspace
+pub const PUBLIC_API_URI: &str = "rustbelt://workspace/public-api";
+
+/// Connection serving the [`Rustbelt`] tools and its resources
+///
+/// `#[mcp_server]` generates the tool methods of `ServerConn` only, so they are
+/// forwarded to it while resources are handled here.
+struct RustbeltConn {
+    rustbelt: Rustbelt,
+    /// Requests in flight and the last one finished, for the idle timeout
+    activity: Arc<Activity>,
+}
+
+/// Request activity of a connection, watched by the idle timeout
+#[derive(Debug)]
+struct Activity {
+    /// When the last request finished, or the connection was opened
+    last: std::sync::Mutex<Instant>,
+    /// Requests started and not finished yet
+    in_flight: AtomicUsize,
+}
+
+impl Activity {
+    fn new() -> Self {
+        Self {
+            last: std::sync::Mutex::new(Instant::now()),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Count a request in flight until the returned guard is dropped
+    ///
+    /// The guard also ends the request when its future is cancelled or
+    /// panics, so a lost request never pins the workspace.
+    fn start(&self) -> ActivityGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        ActivityGuard(self)
+    }
+
+    /// How long no request has been running, `None` while one is in flight
+    fn idle_for(&self) -> Option<Duration> {
+        if self.in_flight.load(Ordering::SeqCst) > 0 {
+            return None;
+        }
+        Some(self.last.lock().unwrap().elapsed())
+    }
+}
+
+/// Request in flight, see [`Activity::start`]
+struct ActivityGuard<'a>(&'a Activity);
+
+impl Drop for ActivityGuard<'_> {
+    fn drop(&mut self) {
+        // Restart the timeout before the request stops counting, so the
+        // watchdog never sees it finished with a stale timestamp
+        *self.0.last.lock().unwrap() = Instant::now();
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl RustbeltConn {
+    fn new(options: WorkspaceOptions, idle_timeout: Option<Duration>) -> Self {
+        let conn = Self {
+            rustbelt: Rustbelt::new(options),
+            activity: Arc::new(Activity::new()),
+        };
+        if let Some(timeout) = idle_timeout {
+            spawn_idle_watchdog(
+                Arc::downgrade(&conn.rustbelt.analyzer),
+                Arc::downgrade(&conn.activity),
+                timeout,
+            );
+        }
+        conn
+    }
+
+    /// Public API map of the loaded workspace, one item per line
+    async fn public_api(&self) -> String {
+        let mut analyzer_guard = self.rustbelt.analyzer.lock().await;
+        let Some(analyzer) = analyzer_guard.as_mut() else {
+            return "No workspace loaded yet: call any tool with a file path of the workspace first"
+                .to_string();
+        };
+        match analyzer.get_public_api().await {
+            Ok(public_api) => {
+                let mut text = format!("{} public item(s):\n", public_api.items.len());
+                for item in &public_api.items {
+                    text.push_str(&format!("{item}\n"));
+                }
+                if public_api.truncated {
+                    text.push_str("Truncated: the workspace has more symbols than were searched\n");
+                }
+                text
+            }
+            Err(e) => format!("Error indexing the public API: {e}"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ServerConn for RustbeltConn {
+    async fn initialize(
+        &self,
+        context: &ServerCtx,
+        protocol_version: String,
+        capabilities: ClientCapabilities,
+        client_info: Implementation,
+    ) -> Result<InitializeResult> {
+        let result = self
+            .rustbelt
+            .initialize(context, protocol_version, capabilities, client_info)
+            .await?;
+        Ok(result.with_resources(false, false))
+    }
+
+    async fn list_tools(
+        &self,
+        context: &ServerCtx,
+        cursor: Option<Cursor>,
+    ) -> Result<ListToolsResult> {
+        self.rustbelt.list_tools(context, cursor).await
+    }
+
+    async fn call_tool(
+        &self,
+        context: &ServerCtx,
+        name: String,
+        arguments: Option<Arguments>,
+    ) -> Result<CallToolResult> {
+        let _request = self.activity.start();
+        self.rustbelt.call_tool(context, name, arguments).await
+    }
+
+    async fn list_resources(
+        &self,
+        _context: &ServerCtx,
+        _cursor: Option<Cursor>,
+    ) -> Result<ListResourcesResult> {
+        Ok(ListResourcesResult::new().with_resource(
+            Resource::new(PUBLIC_API_URI, "public-api")
+                .with_description(
+                    "Compact index of the public items of the loaded workspace: path, kind and \
+                     file:line of each. Load it once instead of searching repeatedly.",
+                )
+                .with_mime_type("text/plain"),
+        ))
+    }
+
+    async fn read_resource(&self, _context: &ServerCtx, uri: String) -> Result<ReadResourceResult> {
+        if uri != PUBLIC_API_URI {
+            return Err(Error::InvalidParams(format!("Unknown resource: {uri}")));
         }
+        let text = {
+            let _request = self.activity.start();
+            self.public_api().await
+        };
+        Ok(
+            ReadResourceResult::new().with_content(ResourceContents::Text(
+                TextResourceContents::new(uri, text).with_mime_type("text/plain"),
+            )),
+        )
     }
 }
 
-pub async fn serve_stdio() -> Result<()> {
+/// Drop the loaded workspace once no request ran for `timeout`
+///
+/// This frees the memory held by the analyzer of an idle connection, and the
+/// next tool call loads the workspace again. The workspace is never dropped
+/// while a request is in flight, however long it runs. The task ends with
+/// the connection, as it only holds weak references to it.
+fn spawn_idle_watchdog(
+    analyzer: Weak<Mutex<Option<RustAnalyzerish>>>,
+    activity: Weak<Activity>,
+    timeout: Duration,
+) {
+    // `None` once the connection is gone, `Some(None)` while busy
+    let idle = move || activity.upgrade().map(|activity| activity.idle_for());
+    tokio::spawn(async move {
+        while let Some(idle_for) = idle() {
+            let Some(elapsed) = idle_for else {
+                tokio::time::sleep(timeout).await;
+                continue;
+            };
+            if elapsed < timeout {
+                tokio::time::sleep(timeout - elapsed).await;
+                continue;
+            }
+            let Some(analyzer) = analyzer.upgrade() else {
+                return;
+            };
+            // A request may have started while waiting for the lock
+            let mut analyzer_guard = analyzer.lock().await;
+            let still_idle = idle().flatten().is_some_and(|elapsed| elapsed >= timeout);
+            if still_idle && analyzer_guard.take().is_some() {
+                info!(
+                    "Dropped the loaded workspace after {}s without requests",
+                    timeout.as_secs()
+                );
+            }
+            drop(analyzer_guard);
+            tokio::time::sleep(timeout).await;
+        }
+    });
+}
+
+/// Serve MCP over stdio
+///
+/// With `idle_timeout`, the loaded workspace is dropped after that long
+/// without requests to free memory, and loaded again on the next tool call.
+pub async fn serve_stdio(options: WorkspaceOptions, idle_timeout: Option<Duration>) -> Result<()> {
     tenx_mcp::Server::default()
-        .with_connection(Rustbelt::new)
+        .with_connection(move || RustbeltConn::new(options.clone(), idle_timeout))
         .serve_stdio()
         .await
 }
 
-pub async fn serve_tcp(addr: String) -> Result<()> {
+/// Serve MCP over TCP, with a separate workspace per connection
+///
+/// See [`serve_stdio`] for `idle_timeout`.
+pub async fn serve_tcp(
+    addr: String,
+    options: WorkspaceOptions,
+    idle_timeout: Option<Duration>,
+) -> Result<()> {
     info!("Starting Rustbelt MCP server on {}", addr);
 
     tenx_mcp::Server::default()
-        .with_connection(Rustbelt::new)
+        .with_connection(move || RustbeltConn::new(options.clone(), idle_timeout))
         .serve_tcp(addr)
         .await
 }
@@ -0,0 +1,502 @@
+//! Post-processing of rendered ruskel skeletons
+//!
+//! Skeletons for large crates can be huge. This module splits a rendered
+//! skeleton into its individual items so callers can narrow the output down
+//! before returning it to an LLM.
+
+use std::path::Path;
+
+use anyhow::Result;
+use regex::RegexBuilder;
+
+/// A single item extracted from a rendered skeleton
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkeletonItem {
+    /// Containing module path, e.g. `["tokio", "task"]`
+    pub module_path: Vec<String>,
+    /// Header of the enclosing `impl` or `trait` block, if any
+    pub container: Option<String>,
+    /// Doc comments and attributes preceding the item
+    pub docs: Vec<String>,
+    /// Declaration lines of the item, up to and including its (empty) body
+    pub declaration: Vec<String>,
+}
+
+impl SkeletonItem {
+    /// Render the item with its docs, dedented to column zero
+    pub fn render(&self) -> String {
+        let lines: Vec<&String> = self.docs.iter().chain(self.declaration.iter()).collect();
+        let indent = lines
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        lines
+            .iter()
+            .map(|line| line.get(indent..).unwrap_or(line.trim_start()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Human readable location of the item, e.g. `tokio::runtime > impl Runtime`
+    pub fn location(&self) -> String {
+        let path = self.module_path.join("::");
+        match &self.container {
+            Some(container) if path.is_empty() => container.clone(),
+            Some(container) => format!("{path} > {container}"),
+            None => path,
+        }
+    }
+}
+
+/// A block that contains other items
+enum Container {
+    Module(String),
+    Block(String),
+}
+
+/// Split a rendered skeleton into its items
+///
+/// Modules, `impl` and `trait` blocks are descended into rather than returned
+/// as items, so every returned item is a leaf declaration (function, struct,
+/// enum, type alias, constant, ...) tagged with its containing module path.
+pub fn parse_items(skeleton: &str) -> Vec<SkeletonItem> {
+    let mut items = Vec::new();
+    let mut containers: Vec<Container> = Vec::new();
+    let mut docs: Vec<String> = Vec::new();
+    let mut lines = skeleton.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with("//!") {
+            continue;
+        }
+        if trimmed.starts_with("///") || trimmed.starts_with("#[") {
+            docs.push(line.to_string());
+            continue;
+        }
+        if trimmed == "}" {
+            containers.pop();
+            docs.clear();
+            continue;
+        }
+
+        let declaration = strip_visibility(trimmed);
+        if let Some(name) = declaration
+            .strip_prefix("mod ")
+            .and_then(|rest| rest.strip_suffix('{'))
+        {
+            containers.push(Container::Module(name.trim().to_string()));
+            docs.clear();
+            continue;
+        }
+        if is_block_header(declaration) && trimmed.ends_with('{') {
+            let header = trimmed.trim_end_matches('{').trim_end().to_string();
+            containers.push(Container::Block(header));
+            docs.clear();
+            continue;
+        }
+
+        // Collect the declaration until its braces are balanced and it ends
+        let mut declaration_lines = vec![line.to_string()];
+        let mut depth = brace_delta(line);
+        while !(depth <= 0 && is_item_end(declaration_lines.last().unwrap())) {
+            let Some(next) = lines.next() else {
+                break;
+            };
+            depth += brace_delta(next);
+            declaration_lines.push(next.to_string());
+        }
+
+        items.push(SkeletonItem {
+            module_path: containers
+                .iter()
+                .filter_map(|container| match container {
+                    Container::Module(name) => Some(name.clone()),
+                    Container::Block(_) => None,
+                })
+                .collect(),
+            container: containers
+                .iter()
+                .rev()
+                .find_map(|container| match container {
+                    Container::Block(header) => Some(header.clone()),
+                    Container::Module(_) => None,
+                }),
+            docs: std::mem::take(&mut docs),
+            declaration: declaration_lines,
+        });
+    }
+
+    items
+}
+
+/// Keep only the items whose name or signature matches `filter`
+///
+/// The filter is a case-insensitive regular expression, so plain substrings
+/// such as `spawn` work as-is. Matching items are grouped under a comment
+/// naming their containing module path.
+pub fn filter_items(skeleton: &str, filter: &str) -> Result<String> {
+    let regex = RegexBuilder::new(filter)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Invalid filter '{}': {}", filter, e))?;
+
+    let matching: Vec<SkeletonItem> = parse_items(skeleton)
+        .into_iter()
+        .filter(|item| regex.is_match(&item.declaration.join("\n")))
+        .collect();
+
+    if matching.is_empty() {
+        return Ok(format!("No items matching '{filter}'"));
+    }
+
+    let mut output = String::new();
+    let mut current_location = None;
+    for item in &matching {
+        let location = item.location();
+        if current_location.as_ref() != Some(&location) {
+            if current_location.is_some() {
+                output.push('\n');
+            }
+            output.push_str(&format!("// {location}\n"));
+            current_location = Some(location);
+        }
+        output.push_str(&item.render());
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Extract the item named by the last path segment of a ruskel target
+///
+/// For a target such as `serde::de::Deserialize`, returns only the
+/// `Deserialize` declaration with its docs and attributes, stripping sibling
+/// items and module scaffolding. Structs, enums and traits keep their full
+/// block. Returns the skeleton unchanged, with a leading note, when the target
+/// names a crate or module rather than an item.
+pub fn extract_target_item(target: &str, skeleton: &str) -> String {
+    let Some((_, name)) = target.rsplit_once("::") else {
+        return skeleton.to_string();
+    };
+    match extract_item(skeleton, target) {
+        Some(item) => item,
+        None => format!("// Item '{name}' not found, returning the full skeleton\n{skeleton}"),
+    }
+}
+
+/// Crate name of a ruskel target naming a published crate, e.g. `serde` for
+/// `serde::de::Deserialize`
+///
+/// Returns `None` for local paths such as `/path/to/crate` or `./crate` and for
+/// targets already pinned to a version, e.g. `serde@1.0.160`.
+pub fn target_crate_name(target: &str) -> Option<&str> {
+    let name = target.split("::").next()?;
+    let is_crate_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    is_crate_name.then_some(name)
+}
+
+/// Pin a ruskel target naming a published crate to `version`
+///
+/// For example `serde::de` pinned to `1.0.160` becomes `serde@1.0.160::de`.
+/// Targets [`target_crate_name`] rejects are returned unchanged.
+pub fn pin_target_version(target: &str, version: &str) -> String {
+    match target_crate_name(target) {
+        Some(name) => format!("{name}@{version}{}", &target[name.len()..]),
+        None => target.to_string(),
+    }
+}
+
+/// Whether a crate root file was fetched from a registry, as opposed to a path
+/// or git dependency
+///
+/// Registry crates are unpacked under `$CARGO_HOME/registry/src`, so only those
+/// can be pinned to a published version.
+pub fn is_registry_source(root_file: &str) -> bool {
+    let components: Vec<_> = Path::new(root_file)
+        .components()
+        .map(|component| component.as_os_str())
+        .collect();
+    components
+        .windows(2)
+        .any(|pair| pair[0] == "registry" && pair[1] == "src")
+}
+
+/// Highest of the semver versions a dependency resolves to
+///
+/// Versions that aren't valid semver are ignored.
+pub fn highest_version<'a>(versions: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    versions
+        .into_iter()
+        .filter_map(|version| Some((semver::Version::parse(version).ok()?, version)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, version)| version)
+}
+
+/// Resolve the local path of a ruskel target against `base_path`
+///
+/// Relative paths such as `./crate` or `crates/foo::module` are joined to
+/// `base_path`, keeping the module suffix. Absolute paths, published crate
+/// names and pinned versions are returned unchanged.
+pub fn resolve_target_path(target: &str, base_path: &Path) -> String {
+    let (path, module) = match target.find("::") {
+        Some(index) => target.split_at(index),
+        None => (target, ""),
+    };
+    let is_relative_path = (path.starts_with('.') || path.contains('/') || path.contains('\\'))
+        && !Path::new(path).is_absolute();
+    if !is_relative_path {
+        return target.to_string();
+    }
+    let resolved = match path.trim_start_matches("./") {
+        "" | "." => base_path.to_path_buf(),
+        relative => base_path.join(relative),
+    };
+    format!("{}{module}", resolved.display())
+}
+
+/// Extract the item at `path`, with its docs and attributes
+///
+/// The path is a ruskel target such as `tokio::task::spawn`: the segments after
+/// the crate must match the item's module path, then the type of its `impl`
+/// or trait block if any, then its name, so `tokio::runtime::Runtime::spawn`
+/// doesn't return `tokio::task::spawn`. The crate segment itself is ignored,
+/// as it may be a local path or carry a version. Modules are not items.
+pub fn extract_item(skeleton: &str, path: &str) -> Option<String> {
+    let wanted: Vec<&str> = path.split("::").skip(1).collect();
+    parse_items(skeleton)
+        .into_iter()
+        .find(|item| {
+            let Some(name) = item
+                .declaration
+                .first()
+                .and_then(|line| declared_name(line.trim()))
+            else {
+                return false;
+            };
+            // Below the module named after the crate, when the skeleton has one
+            let modules = item.module_path.iter().skip(1).map(String::as_str);
+            let container = item.container.as_deref().and_then(container_type_name);
+            modules
+                .chain(container)
+                .chain(std::iter::once(name))
+                .eq(wanted.iter().copied())
+        })
+        .map(|item| item.render())
+}
+
+/// Type named by an `impl` or trait block header, e.g. `Runtime` for
+/// `impl<T> Display for Runtime<T>` or `Store` for `pub trait Store: Send`
+fn container_type_name(header: &str) -> Option<&str> {
+    let header = strip_visibility(header.trim());
+    let header = header.strip_prefix("unsafe ").unwrap_or(header);
+    let rest = if let Some(rest) = header.strip_prefix("impl") {
+        // The last `for` separates the trait from the implementing type
+        let rest = skip_generics(rest);
+        rest.rsplit_once(" for ").map_or(rest, |(_, ty)| ty)
+    } else {
+        header
+            .strip_prefix("auto ")
+            .unwrap_or(header)
+            .strip_prefix("trait ")?
+    };
+    let ty = rest.trim_start().trim_start_matches('&').trim_start();
+    // The last segment of a path type, e.g. `Runtime` for `crate::rt::Runtime`
+    let ty = identifier_prefix_path(ty);
+    Some(ty).filter(|ty| !ty.is_empty())
+}
+
+/// `rest` without leading generic parameters, e.g. `<T> Foo<T>` -> ` Foo<T>`
+fn skip_generics(rest: &str) -> &str {
+    if !rest.starts_with('<') {
+        return rest;
+    }
+    let mut depth = 0;
+    for (index, c) in rest.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &rest[index + 1..];
+                }
+            }
+            _ => {}
+        }
+    }
+    rest
+}
+
+/// Last segment of the leading path of `ty`, e.g. `Runtime` for `rt::Runtime<T>`
+fn identifier_prefix_path(ty: &str) -> &str {
+    let end = ty
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(ty.len());
+    let path = &ty[..end];
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// Name declared by an item line, e.g. `spawn` for `pub async fn spawn<F>(...)`
+///
+/// Returns `None` for modules, impl blocks and lines that don't start an item.
+fn declared_name(line: &str) -> Option<&str> {
+    let mut tokens = strip_visibility(line).split_whitespace().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            "fn" | "struct" | "enum" | "union" | "trait" | "type" | "macro_rules!" => {
+                return tokens.next().map(identifier_prefix);
+            }
+            "static" => {
+                let next = tokens.next()?;
+                let next = if next == "mut" { tokens.next()? } else { next };
+                return Some(identifier_prefix(next));
+            }
+            "const" if !matches!(tokens.peek(), Some(&("fn" | "unsafe" | "async" | "extern"))) => {
+                return tokens.next().map(identifier_prefix);
+            }
+            "const" | "async" | "unsafe" | "default" | "auto" | "extern" => continue,
+            _ if token.starts_with('"') => continue, // ABI string of `extern "C" fn`
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Leading identifier characters of a token, e.g. `spawn` for `spawn<F>(future:`
+fn identifier_prefix(token: &str) -> &str {
+    let end = token
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(token.len());
+    &token[..end]
+}
+
+/// Best-effort hints for common ruskel failures
+///
+/// Inspects the error message for known failure modes (missing feature,
+/// unknown crate, version mismatch, unknown path) and returns actionable
+/// suggestions. Never replaces the original error, which callers should keep
+/// showing alongside the hints.
+pub fn error_hints(error: &str, all_features: bool) -> Vec<String> {
+    let message = error.to_lowercase();
+    let mut hints = Vec::new();
+
+    if message.contains("feature") {
+        if all_features {
+            hints.push(
+                "Check the feature names; the requested feature may not exist in this version"
+                    .to_string(),
+            );
+        } else {
+            hints.push(
+                "The item may be behind a feature gate: try all_features=true or features=[...]"
+                    .to_string(),
+            );
+        }
+    }
+
+    if message.contains("no matching package")
+        || message.contains("could not find")
+        || message.contains("not found in registry")
+        || message.contains("failed to find")
+    {
+        hints.push("Check the crate name spelling, e.g. `serde_json` vs `serde-json`".to_string());
+        if let Some(suggestion) = suggested_name(error) {
+            hints.push(format!("Did you mean `{suggestion}`?"));
+        }
+    }
+
+    if message.contains("failed to select a version")
+        || message.contains("candidate versions found")
+        || (message.contains("version")
+            && (message.contains("mismatch") || message.contains("requirement")))
+    {
+        hints.push(
+            "No published version matches: drop the `@version` suffix to use the latest release"
+                .to_string(),
+        );
+    }
+
+    if (message.contains("path") && message.contains("not found"))
+        || message.contains("unresolved")
+        || message.contains("no item")
+    {
+        hints.push(
+            "Try a shorter module path, or private=true if the item is not public".to_string(),
+        );
+    }
+
+    hints
+}
+
+/// Extract a name suggested by cargo, e.g. from "perhaps you meant: serde"
+fn suggested_name(error: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets, so they still index `error`
+    let lower = error.to_ascii_lowercase();
+    ["perhaps you meant:", "did you mean"]
+        .iter()
+        .find_map(|marker| {
+            let start = lower.find(marker)? + marker.len();
+            error[start..]
+                .split_whitespace()
+                .next()
+                .map(|name| {
+                    name.trim_matches(|c: char| "`'\"?,".contains(c))
+                        .to_string()
+                })
+                .filter(|name| !name.is_empty())
+        })
+}
+
+/// Remove a leading visibility modifier such as `pub` or `pub(crate)`
+fn strip_visibility(declaration: &str) -> &str {
+    let Some(rest) = declaration.strip_prefix("pub") else {
+        return declaration;
+    };
+    let rest = if rest.starts_with('(') {
+        match rest.find(')') {
+            Some(end) => &rest[end + 1..],
+            None => rest,
+        }
+    } else {
+        rest
+    };
+    rest.trim_start()
+}
+
+/// Whether a declaration opens a block whose members are items themselves
+fn is_block_header(declaration: &str) -> bool {
+    let declaration = declaration.strip_prefix("unsafe ").unwrap_or(declaration);
+    declaration.starts_with("impl")
+        || declaration.starts_with("trait ")
+        || declaration.starts_with("auto trait ")
+        || declaration.starts_with("extern ")
+}
+
+/// Whether a line terminates an item declaration
+fn is_item_end(line: &str) -> bool {
+    let trimmed = line.trim_end();
+    trimmed.ends_with('}') || trimmed.ends_with(';')
+}
+
+/// Net change in brace depth on a line, ignoring braces inside string literals
+fn brace_delta(line: &str) -> i32 {
+    let mut delta = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in line.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' if !in_string => delta += 1,
+            '}' if !in_string => delta -= 1,
+            _ => {}
+        }
+    }
+    delta
+}
@@ -18,6 +18,13 @@ use tokio::{
 
 /// Helper to create a test MCP client connected to the rustbelt server process
 async fn create_test_client() -> Result<(Client<()>, tokio::process::Child)> {
+    create_test_client_with_args(&[]).await
+}
+
+/// Like [`create_test_client`], passing extra arguments to `rustbelt serve`
+async fn create_test_client_with_args(
+    serve_args: &[&str],
+) -> Result<(Client<()>, tokio::process::Child)> {
     // Get the workspace root - this is the current project directory
     let manifest_dir = env!("CARGO_MANIFEST_DIR");
     let workspace_root = std::path::Path::new(manifest_dir)
@@ -54,7 +61,7 @@ async fn create_test_client() -> Result<(Client<()>, tokio::process::Child)> {
     let mut client = Client::new("test-client".to_string(), "1.0.0".to_string());
 
     let mut cmd = TokioCommand::new(binary_path);
-    cmd.arg("serve");
+    cmd.arg("serve").args(serve_args);
 
     let child = client.connect_process(cmd).await?;
 
@@ -111,17 +118,51 @@ async fn test_mcp_server_list_tools() {
         .expect("Failed to list tools");
 
     // Verify response
-    assert_eq!(result.tools.len(), 9);
+    assert_eq!(result.tools.len(), 43);
     let tool_names: Vec<&str> = result.tools.iter().map(|t| t.name.as_str()).collect();
     assert!(tool_names.contains(&"get_type_hint"));
     assert!(tool_names.contains(&"get_definition"));
+    assert!(tool_names.contains(&"get_type_definition"));
+    assert!(tool_names.contains(&"get_declaration"));
+    assert!(tool_names.contains(&"get_related_tests"));
+    assert!(tool_names.contains(&"get_runnables"));
     assert!(tool_names.contains(&"get_completions"));
     assert!(tool_names.contains(&"ruskel"));
     assert!(tool_names.contains(&"rename_symbol"));
+    assert!(tool_names.contains(&"move_item"));
     assert!(tool_names.contains(&"view_inlay_hints"));
     assert!(tool_names.contains(&"find_references"));
+    assert!(tool_names.contains(&"get_semantic_tokens"));
+    assert!(tool_names.contains(&"get_syntax_tree"));
+    assert!(tool_names.contains(&"on_type_format"));
+    assert!(tool_names.contains(&"organize_imports"));
+    assert!(tool_names.contains(&"get_workspace_diagnostics"));
+    assert!(tool_names.contains(&"get_workspace_symbols"));
+    assert!(tool_names.contains(&"find_trait_impls"));
+    assert!(tool_names.contains(&"get_crate_info"));
+    assert!(tool_names.contains(&"get_file_edition"));
+    assert!(tool_names.contains(&"get_enclosing_scope"));
+    assert!(tool_names.contains(&"get_item_attributes"));
+    assert!(tool_names.contains(&"get_enum_variants"));
+    assert!(tool_names.contains(&"get_struct_fields"));
+    assert!(tool_names.contains(&"get_call_return_type"));
+    assert!(tool_names.contains(&"diff_signature"));
+    assert!(tool_names.contains(&"count_references"));
+    assert!(tool_names.contains(&"find_unused_items"));
+    assert!(tool_names.contains(&"apply_edits"));
+    assert!(tool_names.contains(&"status"));
+    assert!(tool_names.contains(&"server_info"));
+    assert!(tool_names.contains(&"memory_usage"));
+    assert!(tool_names.contains(&"unload_workspace"));
+    assert!(tool_names.contains(&"describe_symbol"));
+    assert!(tool_names.contains(&"get_qualified_path"));
     assert!(tool_names.contains(&"get_assists"));
+    assert!(tool_names.contains(&"get_file_assists"));
     assert!(tool_names.contains(&"apply_assist"));
+    assert!(tool_names.contains(&"extract_function"));
+    assert!(tool_names.contains(&"get_import_suggestions"));
+    assert!(tool_names.contains(&"apply_import"));
+    assert!(tool_names.contains(&"batch"));
 
     // Clean up
     let _ = child.kill().await;
@@ -363,3 +404,300 @@ async fn test_mcp_get_completions_tool() {
     // Clean up
     let _ = child.kill().await;
 }
+
+#[tokio::test]
+async fn test_mcp_error_code() {
+    let (mut client, mut child) = create_test_client()
+        .await
+        .expect("Failed to create test client");
+
+    let _init_result = initialize_client(&mut client)
+        .await
+        .expect("Failed to initialize");
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let workspace_root = std::path::Path::new(manifest_dir)
+        .parent() // crates
+        .unwrap()
+        .parent() // workspace root
+        .unwrap();
+    let sample_file = workspace_root.join("crates/librustbelt/tests/sample-project/src/main.rs");
+
+    // A line past the end of the file
+    let arguments = HashMap::from([
+        (
+            "file_path".to_string(),
+            Value::from(sample_file.to_string_lossy()),
+        ),
+        ("line".to_string(), Value::from(10_000)),
+        ("column".to_string(), Value::from(1)),
+    ]);
+
+    let result = timeout(
+        Duration::from_secs(30),
+        client.call_tool("get_type_hint", Some(arguments.into())),
+    )
+    .await
+    .expect("Timeout during get_type_hint call")
+    .expect("Failed to call get_type_hint tool");
+
+    assert!(
+        result.is_error.unwrap_or(false),
+        "get_type_hint should error"
+    );
+    let result = serde_json::to_value(&result).expect("Failed to serialize result");
+    assert_eq!(
+        result["structuredContent"]["error"]["code"], "invalid_position",
+        "{result}"
+    );
+
+    // Clean up
+    let _ = child.kill().await;
+}
+
+#[tokio::test]
+async fn test_mcp_file_outside_workspace() {
+    let (mut client, mut child) = create_test_client()
+        .await
+        .expect("Failed to create test client");
+
+    let _init_result = initialize_client(&mut client)
+        .await
+        .expect("Failed to initialize");
+
+    let scratch_dir =
+        std::env::temp_dir().join(format!("rustbelt-mcp-outside-{}", std::process::id()));
+    std::fs::create_dir_all(&scratch_dir).expect("Failed to create scratch directory");
+    let scratch_file = scratch_dir.join("scratch.rs");
+    std::fs::write(&scratch_file, "fn main() {}\n").expect("Failed to write scratch file");
+
+    let arguments = HashMap::from([
+        (
+            "file_path".to_string(),
+            Value::from(scratch_file.to_string_lossy()),
+        ),
+        ("line".to_string(), Value::from(1)),
+        ("column".to_string(), Value::from(4)),
+    ]);
+    let result = timeout(
+        Duration::from_secs(30),
+        client.call_tool("get_type_hint", Some(arguments.clone().into())),
+    )
+    .await
+    .expect("Timeout during get_type_hint call")
+    .expect("Failed to call get_type_hint tool");
+
+    // The server replies with an error instead of panicking, and keeps serving
+    assert!(
+        result.is_error.unwrap_or(false),
+        "get_type_hint should error"
+    );
+    let result = serde_json::to_value(&result).expect("Failed to serialize result");
+    assert_eq!(
+        result["structuredContent"]["error"]["code"], "file_not_found",
+        "{result}"
+    );
+    let retried = timeout(
+        Duration::from_secs(30),
+        client.call_tool("get_type_hint", Some(arguments.into())),
+    )
+    .await
+    .expect("Timeout during the second get_type_hint call")
+    .expect("Server should still answer after a failed load");
+    assert!(retried.is_error.unwrap_or(false));
+
+    std::fs::remove_dir_all(&scratch_dir).ok();
+
+    // Clean up
+    let _ = child.kill().await;
+}
+
+#[tokio::test]
+async fn test_mcp_server_batch_tool() {
+    let (mut client, mut child) = create_test_client()
+        .await
+        .expect("Failed to create test client");
+
+    let _init_result = initialize_client(&mut client)
+        .await
+        .expect("Failed to initialize");
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let workspace_root = std::path::Path::new(manifest_dir)
+        .parent() // crates
+        .unwrap()
+        .parent() // workspace root
+        .unwrap();
+    let sample_file = workspace_root.join("crates/librustbelt/tests/sample-project/src/main.rs");
+    let cursor = serde_json::json!({
+        "file_path": sample_file.to_string_lossy(),
+        "line": 33,
+        "column": 9,
+    });
+
+    // A failing operation in the middle must not abort the others
+    let arguments = HashMap::from([(
+        "operations".to_string(),
+        serde_json::json!([
+            { "tool": "get_type_hint", "params": cursor },
+            { "tool": "non_existent_tool", "params": {} },
+            { "tool": "find_references", "params": cursor },
+        ]),
+    )]);
+
+    let result = timeout(
+        Duration::from_secs(60),
+        client.call_tool("batch", Some(arguments.into())),
+    )
+    .await
+    .expect("Timeout during batch call")
+    .expect("Failed to call batch tool");
+
+    assert!(!result.is_error.unwrap_or(false));
+    let output = serde_json::to_string(&result.content).expect("Failed to serialize content");
+    assert!(output.contains("[1] get_type_hint: ok"), "{output}");
+    assert!(output.contains("[2] non_existent_tool: error"), "{output}");
+    assert!(output.contains("[3] find_references: ok"), "{output}");
+
+    // Clean up
+    let _ = child.kill().await;
+}
+
+#[tokio::test]
+async fn test_mcp_status_before_loading() {
+    let (mut client, mut child) = create_test_client()
+        .await
+        .expect("Failed to create test client");
+
+    let _init_result = initialize_client(&mut client)
+        .await
+        .expect("Failed to initialize");
+
+    let arguments: HashMap<String, Value> = HashMap::new();
+    let result = timeout(
+        Duration::from_secs(10),
+        client.call_tool("status", Some(arguments.into())),
+    )
+    .await
+    .expect("Timeout during status call")
+    .expect("Failed to call status tool");
+
+    assert!(!result.is_error.unwrap_or(false), "status should not error");
+    println!("Status result: {:?}", result.content);
+    assert!(
+        format!("{:?}", result.content).contains("Workspace loaded: no"),
+        "Unexpected status: {:?}",
+        result.content
+    );
+
+    // Clean up
+    let _ = child.kill().await;
+}
+
+#[tokio::test]
+async fn test_mcp_public_api_resource() {
+    let (mut client, mut child) = create_test_client()
+        .await
+        .expect("Failed to create test client");
+
+    let _init_result = initialize_client(&mut client)
+        .await
+        .expect("Failed to initialize");
+
+    let result = timeout(Duration::from_secs(10), client.list_resources(None))
+        .await
+        .expect("Timeout listing resources")
+        .expect("Failed to list resources");
+    let uris: Vec<&str> = result
+        .resources
+        .iter()
+        .map(|resource| resource.uri.as_str())
+        .collect();
+    assert_eq!(uris, vec!["rustbelt://workspace/public-api"]);
+
+    // Nothing is indexed until a tool loads a workspace
+    let result = timeout(
+        Duration::from_secs(10),
+        client.read_resource("rustbelt://workspace/public-api"),
+    )
+    .await
+    .expect("Timeout reading resource")
+    .expect("Failed to read resource");
+    assert!(
+        format!("{:?}", result.contents).contains("No workspace loaded yet"),
+        "Unexpected contents: {:?}",
+        result.contents
+    );
+
+    let result = client.read_resource("rustbelt://unknown").await;
+    assert!(result.is_err());
+
+    // Clean up
+    let _ = child.kill().await;
+}
+
+/// Text of the `status` tool
+async fn workspace_status(client: &mut Client<()>) -> String {
+    let arguments: HashMap<String, Value> = HashMap::new();
+    let result = timeout(
+        Duration::from_secs(10),
+        client.call_tool("status", Some(arguments.into())),
+    )
+    .await
+    .expect("Timeout during status call")
+    .expect("Failed to call status tool");
+    format!("{:?}", result.content)
+}
+
+#[tokio::test]
+async fn test_mcp_idle_timeout_drops_workspace() {
+    let (mut client, mut child) = create_test_client_with_args(&["--idle-timeout", "2"])
+        .await
+        .expect("Failed to create test client");
+
+    let _init_result = initialize_client(&mut client)
+        .await
+        .expect("Failed to initialize");
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let workspace_root = std::path::Path::new(manifest_dir)
+        .parent() // crates
+        .unwrap()
+        .parent() // workspace root
+        .unwrap();
+    let sample_file = workspace_root.join("crates/librustbelt/tests/sample-project/src/main.rs");
+
+    // Loading the workspace takes longer than the timeout, and must survive it
+    let arguments = HashMap::from([
+        (
+            "file_path".to_string(),
+            Value::from(sample_file.to_string_lossy()),
+        ),
+        ("line".to_string(), Value::from(31)),
+        ("column".to_string(), Value::from(18)),
+    ]);
+    let result = timeout(
+        Duration::from_secs(60),
+        client.call_tool("get_completions", Some(arguments.into())),
+    )
+    .await
+    .expect("Timeout during get_completions call")
+    .expect("Failed to call get_completions tool");
+    assert!(!result.is_error.unwrap_or(false), "get_completions errored");
+
+    let status = workspace_status(&mut client).await;
+    assert!(
+        status.contains("Workspace loaded: yes"),
+        "Workspace dropped right after a request: {status}"
+    );
+
+    sleep(Duration::from_secs(5)).await;
+    let status = workspace_status(&mut client).await;
+    assert!(
+        status.contains("Workspace loaded: no"),
+        "Workspace kept past the idle timeout: {status}"
+    );
+
+    // Clean up
+    let _ = child.kill().await;
+}
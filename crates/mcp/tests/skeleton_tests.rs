@@ -0,0 +1,278 @@
+//! Tests for ruskel skeleton post-processing
+
+use std::path::Path;
+
+use rustbelt_server::skeleton::{
+    error_hints, extract_item, extract_target_item, filter_items, parse_items, pin_target_version,
+    resolve_target_path, target_crate_name,
+};
+
+const SKELETON: &str = r#"pub mod tokio {
+    //! Tokio docs
+    pub mod task {
+        /// Spawns a new asynchronous task.
+        pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+        where
+            F: Future + Send + 'static,
+        {
+        }
+
+        pub fn yield_now() {}
+    }
+
+    pub mod runtime {
+        #[derive(Debug)]
+        pub struct Runtime {
+            pub name: String,
+        }
+
+        impl Runtime {
+            /// Spawns a future onto the runtime.
+            pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output> {}
+
+            pub fn block_on<F: Future>(&self, future: F) -> F::Output {}
+        }
+    }
+}
+"#;
+
+#[test]
+fn test_parse_items_tracks_module_paths() {
+    let items = parse_items(SKELETON);
+
+    let names: Vec<String> = items.iter().map(|item| item.location()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "tokio::task",
+            "tokio::task",
+            "tokio::runtime",
+            "tokio::runtime > impl Runtime",
+            "tokio::runtime > impl Runtime",
+        ]
+    );
+
+    // Multi-line declarations are kept whole, with their docs
+    assert_eq!(items[0].docs.len(), 1);
+    assert_eq!(items[0].declaration.len(), 5);
+    assert_eq!(items[2].declaration.len(), 3);
+}
+
+#[test]
+fn test_filter_items_returns_matching_signatures() {
+    let filtered = filter_items(SKELETON, "spawn").expect("Filter should be valid");
+    println!("{filtered}");
+
+    assert!(
+        filtered.contains("// tokio::task\n/// Spawns a new asynchronous task.\npub fn spawn<F>")
+    );
+    assert!(filtered.contains("// tokio::runtime > impl Runtime\n"));
+    assert!(filtered.contains("pub fn spawn<F>(&self, future: F)"));
+    assert!(!filtered.contains("yield_now"));
+    assert!(!filtered.contains("block_on"));
+    assert!(!filtered.contains("struct Runtime"));
+}
+
+#[test]
+fn test_filter_items_supports_regex() {
+    let filtered =
+        filter_items(SKELETON, r"fn (yield_now|block_on)").expect("Filter should be valid");
+
+    assert!(filtered.contains("pub fn yield_now() {}"));
+    assert!(filtered.contains("pub fn block_on<F: Future>"));
+    assert!(!filtered.contains("spawn"));
+}
+
+#[test]
+fn test_filter_items_no_match() {
+    let filtered = filter_items(SKELETON, "nonexistent").expect("Filter should be valid");
+    assert_eq!(filtered, "No items matching 'nonexistent'");
+
+    assert!(filter_items(SKELETON, "(unclosed").is_err());
+}
+
+#[test]
+fn test_extract_item_returns_docs_and_signature() {
+    let item = extract_item(SKELETON, "tokio::task::spawn").expect("Should find spawn");
+
+    assert_eq!(
+        item,
+        "/// Spawns a new asynchronous task.\n\
+         pub fn spawn<F>(future: F) -> JoinHandle<F::Output>\n\
+         where\n    \
+             F: Future + Send + 'static,\n\
+         {\n\
+         }"
+    );
+}
+
+#[test]
+fn test_extract_item_keeps_struct_block() {
+    let item = extract_item(SKELETON, "tokio::runtime::Runtime").expect("Should find Runtime");
+
+    assert_eq!(
+        item,
+        "#[derive(Debug)]\npub struct Runtime {\n    pub name: String,\n}"
+    );
+    assert!(
+        extract_item(SKELETON, "tokio::runtime").is_none(),
+        "Modules are not items"
+    );
+}
+
+#[test]
+fn test_extract_item_matches_the_full_path() {
+    // Both `spawn`s are found by their own path only
+    let item = extract_item(SKELETON, "tokio::runtime::Runtime::spawn")
+        .expect("Should find Runtime::spawn");
+    assert_eq!(
+        item,
+        "/// Spawns a future onto the runtime.\n\
+         pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output> {}"
+    );
+    let item = extract_item(SKELETON, "tokio::task::spawn").expect("Should find task::spawn");
+    assert!(item.contains("pub fn spawn<F>(future: F)"), "{item}");
+
+    // The crate segment may be a local path
+    assert!(extract_item(SKELETON, "./tokio::task::yield_now").is_some());
+
+    assert!(extract_item(SKELETON, "tokio::spawn").is_none());
+    assert!(extract_item(SKELETON, "tokio::runtime::yield_now").is_none());
+}
+
+#[test]
+fn test_extract_target_item_falls_back_to_skeleton() {
+    let item = extract_target_item("tokio::task::yield_now", SKELETON);
+    assert_eq!(item, "pub fn yield_now() {}");
+
+    // A bare crate target returns the skeleton untouched
+    assert_eq!(extract_target_item("tokio", SKELETON), SKELETON);
+
+    let missing = extract_target_item("tokio::missing", SKELETON);
+    assert!(missing.starts_with("// Item 'missing' not found"));
+    assert!(missing.ends_with(SKELETON));
+}
+
+#[test]
+fn test_error_hints_for_missing_feature() {
+    let hints = error_hints(
+        "Could not resolve `tokio::fs`: requires feature `fs`",
+        false,
+    );
+    assert!(hints.iter().any(|hint| hint.contains("all_features=true")));
+
+    // Suggesting all_features is pointless when it is already enabled
+    let hints = error_hints("package does not have feature `fs`", true);
+    assert!(hints.iter().all(|hint| !hint.contains("all_features=true")));
+    assert!(!hints.is_empty());
+}
+
+#[test]
+fn test_error_hints_for_unknown_crate() {
+    let hints = error_hints(
+        "no matching package named `serde-jsn` found, perhaps you meant: serde_json",
+        false,
+    );
+    assert!(hints.iter().any(|hint| hint.contains("spelling")));
+    assert!(
+        hints
+            .iter()
+            .any(|hint| hint == "Did you mean `serde_json`?")
+    );
+
+    // Characters changing length when lowercased don't shift the suggestion
+    let hints = error_hints(
+        "no matching package named `İİİ` found, perhaps you meant: serde_json",
+        false,
+    );
+    assert!(
+        hints
+            .iter()
+            .any(|hint| hint == "Did you mean `serde_json`?"),
+        "{hints:?}"
+    );
+}
+
+#[test]
+fn test_error_hints_for_version_mismatch() {
+    let hints = error_hints(
+        "failed to select a version for the requirement `serde = \"=99.0.0\"`",
+        false,
+    );
+    assert!(hints.iter().any(|hint| hint.contains("@version")));
+}
+
+#[test]
+fn test_error_hints_unknown_error() {
+    assert!(error_hints("something unexpected happened", false).is_empty());
+}
+
+#[test]
+fn test_target_crate_name() {
+    assert_eq!(target_crate_name("serde"), Some("serde"));
+    assert_eq!(target_crate_name("serde_json::Value"), Some("serde_json"));
+    assert_eq!(target_crate_name("tokio-util::codec"), Some("tokio-util"));
+    assert_eq!(target_crate_name("serde@1.0.160"), None);
+    assert_eq!(target_crate_name("/path/to/crate"), None);
+    assert_eq!(target_crate_name("./crate::module"), None);
+}
+
+#[test]
+fn test_pin_target_version() {
+    assert_eq!(pin_target_version("serde", "1.0.160"), "serde@1.0.160");
+    assert_eq!(
+        pin_target_version("serde::de::Deserialize", "1.0.160"),
+        "serde@1.0.160::de::Deserialize"
+    );
+    assert_eq!(pin_target_version("serde@1.0.1", "1.0.160"), "serde@1.0.1");
+}
+
+#[test]
+fn test_resolve_target_path() {
+    let base = Path::new("/work/project");
+    assert_eq!(resolve_target_path(".", base), "/work/project");
+    assert_eq!(
+        resolve_target_path("./crates/core::shapes", base),
+        "/work/project/crates/core::shapes"
+    );
+    assert_eq!(
+        resolve_target_path("../sibling", base),
+        "/work/project/../sibling"
+    );
+    assert_eq!(
+        resolve_target_path("crates/core", base),
+        "/work/project/crates/core"
+    );
+    assert_eq!(
+        resolve_target_path("/path/to/crate", base),
+        "/path/to/crate"
+    );
+    assert_eq!(resolve_target_path("serde::de", base), "serde::de");
+    assert_eq!(resolve_target_path("serde@1.0.160", base), "serde@1.0.160");
+}
+
+#[test]
+fn test_highest_version_compares_semver() {
+    assert_eq!(
+        highest_version(["0.9.0", "0.10.0", "0.2.1"]),
+        Some("0.10.0")
+    );
+    assert_eq!(
+        highest_version(["1.0.0-rc.1", "1.0.0", "not-a-version"]),
+        Some("1.0.0")
+    );
+    assert_eq!(highest_version(["not-a-version"]), None);
+}
+
+#[test]
+fn test_is_registry_source() {
+    assert!(is_registry_source(
+        "/home/user/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/serde-1.0.219/src/lib.rs"
+    ));
+    assert!(!is_registry_source(
+        "/home/user/.cargo/git/checkouts/ruskel-0123456789abcdef/57ad247/src/lib.rs"
+    ));
+    assert!(!is_registry_source(
+        "/home/user/project/vendor/helper/src/lib.rs"
+    ));
+}